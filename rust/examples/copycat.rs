@@ -9,10 +9,11 @@
  * Authors: see CONTRIBUTORS.md
  */
 
+use std::collections::VecDeque;
 use std::fs;
 use std::path::Path;
-use std::sync::mpsc::{channel, Receiver, TryRecvError};
 use std::sync::{Arc, RwLock};
+use std::time::Duration;
 
 use clap::{Arg, Command};
 use flexi_logger::{
@@ -24,19 +25,21 @@ use libits::client::application::pipeline;
 use libits::client::configuration::Configuration;
 use libits::exchange::sequence_number::SequenceNumber;
 use libits::exchange::Exchange;
-use libits::now;
 use libits::transport::mqtt::geo_topic::GeoTopic;
 use libits::transport::packet::Packet;
 use log::{debug, info, warn};
-use timer::MessageTimer;
+use tokio_util::sync::CancellationToken;
 
 #[cfg(feature = "telemetry")]
 use libits::transport::telemetry::init_tracer;
 
+/// Delay before a received item is copied
+const COPY_DELAY: Duration = Duration::from_secs(3);
+
 pub struct CopyCat {
     configuration: Arc<Configuration>,
-    item_receiver: Receiver<Packet<GeoTopic, Exchange>>,
-    timer: MessageTimer<Packet<GeoTopic, Exchange>>,
+    /// Received items with the timestamp from which they can be copied, oldest first
+    delayed_items: VecDeque<(u64, Packet<GeoTopic, Exchange>)>,
 }
 
 #[derive(Default)]
@@ -51,12 +54,9 @@ impl Analyzer<GeoTopic, NoContext> for CopyCat {
     where
         Self: Sized,
     {
-        let (tx, item_receiver) = channel();
-        let timer = timer::MessageTimer::new(tx);
         Self {
             configuration,
-            item_receiver,
-            timer,
+            delayed_items: VecDeque::new(),
         }
     }
 
@@ -88,49 +88,43 @@ impl Analyzer<GeoTopic, NoContext> for CopyCat {
                         packet.payload.source_uuid
                     );
 
-                    let guard = self
-                        .timer
-                        .schedule_with_delay(chrono::Duration::seconds(3), clone);
-                    guard.ignore();
+                    let due_time =
+                        self.configuration.clock.now_millis() + COPY_DELAY.as_millis() as u64;
+                    self.delayed_items.push_back((due_time, clone));
                     debug!("scheduling done");
                 }
 
-                // 2- create the copy cat items for each removed delayed item
+                // 2- create the copy cat items for each delayed item which is due
+                let now = self.configuration.clock.now_millis();
                 let mut data_found = 0;
-                while data_found >= 0 {
-                    match self.item_receiver.try_recv() {
-                        Ok(item) => {
-                            data_found += 1;
-
-                            //assumed clone, we create a new item
-                            let mut own_exchange = item.payload.clone();
-                            info!(
-                                "we treat the scheduled item {} {} from {}",
-                                data_found,
-                                &mobile_message.id(),
-                                item.payload.source_uuid
-                            );
-                            let timestamp = now();
-
-                            own_exchange.appropriate(&self.configuration, timestamp);
-
-                            let mut own_topic = item.topic.clone();
-                            own_topic.appropriate(&self.configuration);
-                            item_to_publish.push(Packet::new(own_topic, own_exchange));
-
-                            debug!("item scheduled published");
-                        }
-                        Err(e) => match e {
-                            TryRecvError::Empty => {
-                                debug!("delayed channel empty, we stop");
-                                data_found = -1;
-                            }
-                            TryRecvError::Disconnected => {
-                                warn!("delayed channel disconnected, we stop");
-                                data_found = -1;
-                            }
-                        },
+                while let Some((due_time, _)) = self.delayed_items.front() {
+                    if *due_time > now {
+                        debug!("no more due delayed item, we stop");
+                        break;
+                    }
+                    let (_, item) = self.delayed_items.pop_front().unwrap();
+                    if item.payload.is_expired(now) {
+                        debug!("scheduled item expired, we skip it");
+                        continue;
                     }
+                    data_found += 1;
+
+                    //assumed clone, we create a new item
+                    let mut own_exchange = item.payload.clone();
+                    info!(
+                        "we treat the scheduled item {} {} from {}",
+                        data_found,
+                        &mobile_message.id(),
+                        item.payload.source_uuid
+                    );
+
+                    own_exchange.appropriate(&self.configuration, now);
+
+                    let mut own_topic = item.topic.clone();
+                    own_topic.appropriate(&self.configuration);
+                    item_to_publish.push(Packet::new(own_topic, own_exchange));
+
+                    debug!("item scheduled published");
                 }
             }
             Err(e) => warn!("{}", e),
@@ -239,11 +233,22 @@ async fn main() {
     #[cfg(feature = "telemetry")]
     init_tracer(&configuration.telemetry, "copycat").expect("Failed to init telemetry");
 
+    let shutdown = CancellationToken::new();
+    let ctrl_c_shutdown = shutdown.clone();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            info!("Interruption received, shutting down...");
+            ctrl_c_shutdown.cancel();
+        }
+    });
+
     pipeline::run::<CopyCat, NoContext, GeoTopic>(
         Arc::new(configuration),
         Arc::new(RwLock::new(context)),
         Arc::new(RwLock::new(SequenceNumber::new(u16::MAX.into()))),
         &topics,
+        shutdown,
+        None,
     )
     .await;
 
@@ -252,15 +257,79 @@ async fn main() {
 
 #[cfg(test)]
 mod tests {
-    use std::sync::mpsc::channel;
+    use std::sync::{Arc, RwLock};
+    use std::time::Duration;
+
+    use ini::Ini;
+    use libits::client::application::analyzer::Analyzer;
+    use libits::client::application::create_cam;
+    use libits::client::configuration::Configuration;
+    use libits::clock::MockClock;
+    use libits::exchange::message::Message;
+    use libits::exchange::sequence_number::SequenceNumber;
+    use libits::exchange::Exchange;
+    use libits::mobility::position::position_from_degrees;
+    use libits::transport::mqtt::geo_topic::GeoTopic;
+    use libits::transport::packet::Packet;
+
+    use crate::{CopyCat, NoContext, COPY_DELAY};
+
+    const CONFIGURATION: &str = r#"
+[station]
+id="copycat"
+type="mec_application"
+
+[mqtt]
+host="localhost"
+port=1883
+client_id="copycat"
+
+[geo]
+prefix=default
+suffix=v2x
+
+[node]
+responsibility_enabled=false
+
+[telemetry]
+host=localhost
+port=4318
+"#;
+
+    fn copycat(clock: Arc<MockClock>) -> CopyCat {
+        let mut configuration =
+            Configuration::try_from(Ini::load_from_str(CONFIGURATION).unwrap()).unwrap();
+        configuration.set_clock(clock);
+
+        CopyCat::new(
+            Arc::new(configuration),
+            Arc::new(RwLock::new(NoContext::default())),
+            Arc::new(RwLock::new(SequenceNumber::new(u16::MAX.into()))),
+        )
+    }
+
+    fn moving_cam_packet() -> Packet<GeoTopic, Exchange> {
+        let position = position_from_degrees(48.6250, 2.2412, 150.);
+        let cam = create_cam(42, 5, position, 10., 0.);
+        let topic = GeoTopic::from("default/outQueue/v2x/cam/car_42/1/2/0/2");
+        let exchange = Exchange::new("car_42".to_string(), 0, Vec::new(), Message::CAM(cam));
+
+        Packet::new(topic, *exchange)
+    }
 
     #[test]
-    fn test_timer_schedule_with_delay() {
-        let (tx, rx) = channel();
-        let timer = timer::MessageTimer::new(tx);
-        let _guard = timer.schedule_with_delay(chrono::Duration::seconds(3), 3);
+    fn copies_are_scheduled_using_the_configuration_clock() {
+        let clock = Arc::new(MockClock::new(1_000_000));
+        let mut copycat = copycat(clock.clone());
+
+        assert!(copycat.analyze(moving_cam_packet()).is_empty());
+
+        clock.advance(COPY_DELAY - Duration::from_millis(1));
+        assert!(copycat.analyze(moving_cam_packet()).is_empty());
 
-        rx.recv().unwrap();
-        println!("This code has been executed after 3 seconds");
+        clock.advance(Duration::from_millis(1));
+        let copies = copycat.analyze(moving_cam_packet());
+        assert_eq!(copies.len(), 1);
+        assert_eq!(copies[0].payload.timestamp, 1_000_000 + 3_000);
     }
 }
@@ -0,0 +1,125 @@
+/*
+ * Software Name : libits-client
+ * SPDX-FileCopyrightText: Copyright (c) Orange SA
+ * SPDX-License-Identifier: MIT
+ *
+ * This software is distributed under the MIT license,
+ * see the "LICENSE.txt" file for more details or https://opensource.org/license/MIT/
+ *
+ * Authors: see CONTRIBUTORS.md
+ */
+
+use std::path::Path;
+use std::time::Duration;
+
+use clap::{Arg, Command};
+use flexi_logger::{with_thread, Logger, WriteMode};
+use ini::Ini;
+use libits::client::application::create_cam;
+use libits::client::configuration::Configuration;
+use libits::exchange::message::Message;
+use libits::exchange::Exchange;
+use libits::mobility::position::{haversine_destination, position_from_degrees};
+use libits::mobility::quadtree::quadkey::Quadkey;
+use libits::now;
+use libits::transport::mqtt::geo_topic::GeoTopic;
+use libits::transport::mqtt::mqtt_client::MqttClient;
+use libits::transport::packet::Packet;
+use log::{error, info};
+
+/// Passenger car station type
+const STATION_TYPE: u8 = 5;
+/// Publishing period, 10 Hz
+const PERIOD: Duration = Duration::from_millis(100);
+
+#[tokio::main(flavor = "multi_thread")]
+async fn main() {
+    let matches = Command::new("ITS Emitter client")
+        .version("0.1.0")
+        .about("Emitter example publishes the CAMs of a virtual vehicle driving along a straight track")
+        .arg(
+            Arg::new("config-file-path")
+                .short('c')
+                .long("config")
+                .value_name("CONFIG_FILE_PATH")
+                .default_value("examples/config.ini")
+                .help("Path to the configuration file"),
+        )
+        .arg(
+            Arg::new("station-id")
+                .short('s')
+                .long("station-id")
+                .value_name("STATION_ID")
+                .default_value("42")
+                .value_parser(clap::value_parser!(u32))
+                .help("Station id of the virtual vehicle"),
+        )
+        .arg(
+            Arg::new("count")
+                .short('n')
+                .long("count")
+                .value_name("COUNT")
+                .default_value("600")
+                .value_parser(clap::value_parser!(u32))
+                .help("Number of CAMs to publish"),
+        )
+        .get_matches();
+
+    let configuration = Configuration::try_from(
+        Ini::load_from_file(Path::new(
+            matches.get_one::<String>("config-file-path").unwrap(),
+        ))
+        .expect("Failed to load config file as Ini"),
+    )
+    .expect("Failed to create Configuration from loaded Ini");
+
+    let _logger = Logger::try_with_env_or_str("info")
+        .expect("Logger initialization failed")
+        .log_to_stdout()
+        .write_mode(WriteMode::Async)
+        .format_for_files(with_thread)
+        .start()
+        .expect("Logger starting failed");
+
+    let station_id = *matches.get_one::<u32>("station-id").unwrap();
+    let count = *matches.get_one::<u32>("count").unwrap();
+    let component_name = configuration.component_name(None);
+
+    let (client, mut event_loop) = MqttClient::new(&configuration.mqtt_options);
+    tokio::spawn(async move {
+        loop {
+            if let Err(error) = event_loop.poll().await {
+                error!("Connection error received: {:?}", error);
+                tokio::time::sleep(Duration::from_secs(1)).await;
+            }
+        }
+    });
+
+    // 50 km/h heading east
+    let speed = 50. / 3.6;
+    let heading = 90_f64.to_radians();
+    let mut position = position_from_degrees(48.6250, 2.2412, 150.);
+
+    let mut interval = tokio::time::interval(PERIOD);
+    for _ in 0..count {
+        interval.tick().await;
+
+        let cam = create_cam(station_id, STATION_TYPE, position, speed, heading);
+        let topic = GeoTopic::cam(
+            &configuration.geo,
+            &component_name,
+            &Quadkey::from(&position).as_reduced(22),
+        );
+        let exchange = Exchange::new(component_name.clone(), now(), Vec::new(), Message::CAM(cam));
+
+        if let Err(e) = client.publish(Packet::new(topic, *exchange)).await {
+            error!("Failed to publish CAM: {}", e);
+        }
+
+        position = haversine_destination(&position, heading, speed * PERIOD.as_secs_f64());
+    }
+
+    info!("{} CAMs published, emitter example exiting", count);
+    // let the event loop flush the last publishes
+    tokio::time::sleep(Duration::from_secs(1)).await;
+}
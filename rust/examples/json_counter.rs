@@ -65,7 +65,7 @@ async fn main() {
         )
         .get_matches();
 
-    let mut configuration = Configuration::try_from(
+    let configuration = Configuration::try_from(
         Ini::load_from_file(Path::new(
             matches.get_one::<String>("config-file-path").unwrap(),
         ))
@@ -107,10 +107,6 @@ async fn main() {
         Err(error) => panic!("Logger initialization failed with {:?}", error),
     };
 
-    configuration
-        .mqtt_options
-        .set_max_packet_size(Some(256_000));
-
     let (mut client, mut event_loop) = MqttClient::new(&configuration.mqtt_options);
     let mut router = MqttRouter::default();
 
@@ -138,7 +134,10 @@ async fn main() {
         },
     );
 
-    client.subscribe(&["#".to_string()]).await;
+    client
+        .subscribe(&["#".to_string()])
+        .await
+        .expect("Failed to subscribe");
 
     let mut total: u128 = 0;
     let mut json: u128 = 0;
@@ -154,7 +153,7 @@ async fn main() {
                 }
                 total += 1;
 
-                if total % 1000 == 0 {
+                if total % 1000 == 1 {
                     println!("Received {} messages including {} as JSON", total, json);
                 }
             }
@@ -17,13 +17,14 @@ use crate::exchange::etsi::decentralized_environmental_notification_message::{
     DecentralizedEnvironmentalNotificationMessage, RelevanceDistance, RelevanceTrafficDirection,
 };
 use crate::exchange::etsi::reference_position::ReferencePosition;
-use crate::exchange::etsi::{etsi_now, heading_to_etsi, speed_to_etsi, timestamp_to_etsi};
+use crate::exchange::etsi::{heading_to_etsi, speed_to_etsi, timestamp_to_etsi};
 use crate::exchange::sequence_number::SequenceNumber;
 use crate::exchange::PathElement;
 use crate::mobility::mobile::Mobile;
 use crate::mobility::position::Position;
 
 pub mod analyzer;
+pub mod denm_repeater;
 pub mod pipeline;
 
 /// Creates a [CAM][1] message from minimal required information
@@ -70,7 +71,9 @@ pub fn create_denm(
 ) -> DecentralizedEnvironmentalNotificationMessage {
     if let Some(node_configuration) = &configuration.node {
         let read_lock = node_configuration.read().unwrap();
-        let station_id = read_lock.station_id(None);
+        let station_id = read_lock
+            .station_id(None)
+            .expect("the instance station id always fits in a u32");
         drop(read_lock);
 
         let (relevance_distance, relevance_traffic_direction, event_speed, event_heading) =
@@ -91,12 +94,12 @@ pub fn create_denm(
                 }
             };
 
-        DecentralizedEnvironmentalNotificationMessage::new(
+        let mut denm = DecentralizedEnvironmentalNotificationMessage::new(
             mobile.id(),
             station_id,
             ReferencePosition::from(mobile.position()),
             sequence_number.get_next() as u16,
-            timestamp_to_etsi(detection_time),
+            timestamp_to_etsi(detection_time).unwrap_or_default(),
             cause,
             subcause,
             relevance_distance,
@@ -105,24 +108,32 @@ pub fn create_denm(
             event_heading,
             Some(10),
             Some(200),
-        )
+        );
+        denm.management_container.reference_time =
+            timestamp_to_etsi(configuration.clock.now_millis()).unwrap_or_default();
+        denm
     } else {
         todo!("Ego DENM creation not managed yet")
     }
 }
 
-/// Creates an updated copy of the provided DENM
+/// Creates an updated copy of the provided DENM, referenced at the configuration's [clock][1] time
 ///
 /// FIXME check for appropriation
+///
+/// [1]: Configuration::clock
 pub fn update_denm(
     detection_time: u64,
+    configuration: &Configuration,
     denm: &DecentralizedEnvironmentalNotificationMessage,
     mobile: &dyn Mobile,
 ) -> DecentralizedEnvironmentalNotificationMessage {
     let mut copy = denm.clone();
 
-    copy.management_container.detection_time = timestamp_to_etsi(detection_time);
-    copy.management_container.reference_time = etsi_now();
+    copy.management_container.detection_time =
+        timestamp_to_etsi(detection_time).unwrap_or_default();
+    copy.management_container.reference_time =
+        timestamp_to_etsi(configuration.clock.now_millis()).unwrap_or_default();
     copy.management_container.event_position = ReferencePosition::from(mobile.position());
 
     copy
@@ -104,3 +104,156 @@ pub trait Analyzer<T: Topic, C> {
 
     fn analyze(&mut self, packet: Packet<T, Exchange>) -> Vec<Packet<T, Exchange>>;
 }
+
+/// [Analyzer] chaining two others, the second one analyzing each packet the first one returns
+///
+/// Allows to compose behaviours (e.g. deduplication, fusion, relevance) rather than merging them
+/// into a single analyzer; longer chains are nested, e.g. `Chain<A, Chain<B, C>>`
+/// Both analyzers are created with the same configuration, context and sequence number, e.g. by
+/// [run][1] called with `Chain<Deduplication, Relevance>` as analyzer
+///
+/// [1]: crate::client::application::pipeline::run
+pub struct Chain<A, B> {
+    first: A,
+    second: B,
+}
+
+impl<T, C, A, B> Analyzer<T, C> for Chain<A, B>
+where
+    T: Topic,
+    A: Analyzer<T, C>,
+    B: Analyzer<T, C>,
+{
+    fn new(
+        configuration: Arc<Configuration>,
+        context: Arc<RwLock<C>>,
+        sequence_number: Arc<RwLock<SequenceNumber>>,
+    ) -> Self
+    where
+        Self: Sized,
+    {
+        Self {
+            first: A::new(
+                configuration.clone(),
+                context.clone(),
+                sequence_number.clone(),
+            ),
+            second: B::new(configuration, context, sequence_number),
+        }
+    }
+
+    fn analyze(&mut self, packet: Packet<T, Exchange>) -> Vec<Packet<T, Exchange>> {
+        self.first
+            .analyze(packet)
+            .into_iter()
+            .flat_map(|packet| self.second.analyze(packet))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, RwLock};
+
+    use ini::Ini;
+
+    use crate::client::application::analyzer::{Analyzer, Chain};
+    use crate::client::configuration::Configuration;
+    use crate::exchange::etsi::cooperative_awareness_message::CooperativeAwarenessMessage;
+    use crate::exchange::message::Message;
+    use crate::exchange::sequence_number::SequenceNumber;
+    use crate::exchange::Exchange;
+    use crate::transport::mqtt::topic::tests::RoutedTopic;
+    use crate::transport::packet::Packet;
+
+    const CONFIGURATION: &str = r#"
+[station]
+id="chain"
+type="mec_application"
+
+[mqtt]
+host="localhost"
+port=1883
+client_id="chain"
+
+[geo]
+prefix=default
+suffix=v2x
+
+[node]
+responsibility_enabled=false
+
+[telemetry]
+host=localhost
+port=4318
+"#;
+
+    /// Keeps the messages of the station 42 only
+    struct Filter;
+    impl Analyzer<RoutedTopic, ()> for Filter {
+        fn new(_: Arc<Configuration>, _: Arc<RwLock<()>>, _: Arc<RwLock<SequenceNumber>>) -> Self {
+            Self
+        }
+
+        fn analyze(
+            &mut self,
+            packet: Packet<RoutedTopic, Exchange>,
+        ) -> Vec<Packet<RoutedTopic, Exchange>> {
+            if packet.payload.message.station_id() == Some(42) {
+                vec![packet]
+            } else {
+                Vec::new()
+            }
+        }
+    }
+
+    /// Sends the messages again as its own
+    struct Relay {
+        configuration: Arc<Configuration>,
+    }
+    impl Analyzer<RoutedTopic, ()> for Relay {
+        fn new(
+            configuration: Arc<Configuration>,
+            _: Arc<RwLock<()>>,
+            _: Arc<RwLock<SequenceNumber>>,
+        ) -> Self {
+            Self { configuration }
+        }
+
+        fn analyze(
+            &mut self,
+            mut packet: Packet<RoutedTopic, Exchange>,
+        ) -> Vec<Packet<RoutedTopic, Exchange>> {
+            packet.payload.source_uuid = self.configuration.component_name(None);
+            vec![packet]
+        }
+    }
+
+    fn cam_packet(station_id: u32) -> Packet<RoutedTopic, Exchange> {
+        let cam = CooperativeAwarenessMessage {
+            station_id,
+            ..Default::default()
+        };
+        let exchange = Exchange::new("car".to_string(), 0, Vec::new(), Message::CAM(cam));
+
+        Packet::new(RoutedTopic(String::from("cam")), *exchange)
+    }
+
+    #[test]
+    fn second_stage_analyzes_first_stage_output() {
+        let configuration =
+            Configuration::try_from(Ini::load_from_str(CONFIGURATION).unwrap()).unwrap();
+        let mut chain = Chain::<Filter, Relay>::new(
+            Arc::new(configuration),
+            Arc::new(RwLock::new(())),
+            Arc::new(RwLock::new(SequenceNumber::new(u16::MAX.into()))),
+        );
+
+        assert!(chain.analyze(cam_packet(43)).is_empty());
+
+        let output = chain.analyze(cam_packet(42));
+        assert_eq!(output.len(), 1);
+        assert_eq!(output[0].payload.message.station_id(), Some(42));
+        assert_ne!(output[0].payload.source_uuid, "car");
+    }
+}
@@ -0,0 +1,271 @@
+/*
+ * Software Name : libits-client
+ * SPDX-FileCopyrightText: Copyright (c) Orange SA
+ * SPDX-License-Identifier: MIT
+ *
+ * This software is distributed under the MIT license,
+ * see the "LICENSE.txt" file for more details or https://opensource.org/license/MIT/
+ *
+ * Authors: see CONTRIBUTORS.md
+ */
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::time::{sleep_until, Instant};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, warn};
+
+use crate::clock::{Clock, SystemClock};
+use crate::exchange::etsi::action_id::ActionId;
+use crate::exchange::etsi::decentralized_environmental_notification_message::DecentralizedEnvironmentalNotificationMessage;
+use crate::exchange::etsi::timestamp_to_etsi;
+use crate::exchange::mortal::Mortal;
+
+/// Repeats a [DENM][1] at a fixed interval for a given duration
+///
+/// Repetitions stop at the end of the repetition duration, when the DENM's validity expires,
+/// or when the repetition is [terminated][2], whichever comes first
+///
+/// [1]: DecentralizedEnvironmentalNotificationMessage
+/// [2]: DenmRepeater::terminator
+pub struct DenmRepeater {
+    denm: DecentralizedEnvironmentalNotificationMessage,
+    repetition_interval: Duration,
+    repetition_duration: Duration,
+    termination: CancellationToken,
+    clock: Arc<dyn Clock>,
+}
+
+impl DenmRepeater {
+    pub fn new(
+        denm: DecentralizedEnvironmentalNotificationMessage,
+        repetition_interval: Duration,
+        repetition_duration: Duration,
+    ) -> Self {
+        Self {
+            denm,
+            repetition_interval,
+            repetition_duration,
+            termination: CancellationToken::new(),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Sets the clock the repetitions start from, e.g. the configuration's [clock][1]
+    ///
+    /// [1]: crate::client::configuration::Configuration::clock
+    pub fn with_clock(self, clock: Arc<dyn Clock>) -> Self {
+        Self { clock, ..self }
+    }
+
+    /// Returns the action id of the repeated DENM, e.g. to find the repetition to terminate
+    /// when the event is updated or terminated
+    pub fn action_id(&self) -> ActionId {
+        self.denm.action_id()
+    }
+
+    /// Returns the token to cancel to stop the repetition
+    pub fn terminator(&self) -> CancellationToken {
+        self.termination.clone()
+    }
+
+    /// Returns the ETSI timestamps, in milliseconds, of the repetitions starting at `start`
+    ///
+    /// The first repetition is at `start`, the following ones every repetition interval as long
+    /// as they are before the end of the repetition duration and the DENM's validity
+    ///
+    /// The timestamps are computed as the iterator is consumed
+    pub fn schedule(&self, start: u64) -> impl Iterator<Item = u64> {
+        let interval = u64::try_from(self.repetition_interval.as_millis()).unwrap_or(u64::MAX);
+        let end = if interval == 0 {
+            warn!("DENM repetition interval cannot be null, no repetition scheduled");
+            start
+        } else {
+            let duration = u64::try_from(self.repetition_duration.as_millis()).unwrap_or(u64::MAX);
+            start.saturating_add(duration).min(self.denm.timeout())
+        };
+        (start..end).step_by(usize::try_from(interval.max(1)).unwrap_or(usize::MAX))
+    }
+
+    /// Sends the DENM with its ETSI timestamp at each scheduled repetition
+    ///
+    /// Returns once the schedule is exhausted, the repetition is terminated
+    /// or the receiver is dropped
+    pub async fn run(
+        self,
+        sender: UnboundedSender<(u64, DecentralizedEnvironmentalNotificationMessage)>,
+    ) {
+        let start = timestamp_to_etsi(self.clock.now_millis()).unwrap_or_default();
+        let start_instant = Instant::now();
+
+        for timestamp in self.schedule(start) {
+            tokio::select! {
+                _ = self.termination.cancelled() => {
+                    debug!("DENM repetition terminated");
+                    return;
+                }
+                _ = sleep_until(start_instant + Duration::from_millis(timestamp - start)) => {
+                    if sender.send((timestamp, self.denm.clone())).is_err() {
+                        debug!("DENM repetition receiver dropped");
+                        return;
+                    }
+                }
+            }
+        }
+        debug!("DENM repetition finished");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+    use tokio::time::Instant;
+
+    use crate::client::application::denm_repeater::DenmRepeater;
+    use crate::clock::MockClock;
+    use crate::exchange::etsi::decentralized_environmental_notification_message::DecentralizedEnvironmentalNotificationMessage;
+    use crate::exchange::etsi::reference_position::ReferencePosition;
+    use crate::exchange::etsi::timestamp_to_etsi;
+
+    fn denm(
+        reference_time: u64,
+        validity_duration: u32,
+    ) -> DecentralizedEnvironmentalNotificationMessage {
+        let mut denm = DecentralizedEnvironmentalNotificationMessage::new_stationary_vehicle(
+            42,
+            42,
+            ReferencePosition::default(),
+            1,
+            reference_time,
+            None,
+        );
+        denm.management_container.reference_time = reference_time;
+        denm.management_container.validity_duration = Some(validity_duration);
+        denm
+    }
+
+    macro_rules! test_schedule_count {
+        ($test_name:ident, $interval:expr, $duration:expr, $validity:expr, $expected:expr) => {
+            #[test]
+            fn $test_name() {
+                let repeater = DenmRepeater::new(
+                    denm(1_000_000, $validity),
+                    Duration::from_millis($interval),
+                    Duration::from_millis($duration),
+                );
+
+                let schedule: Vec<u64> = repeater.schedule(1_000_000).collect();
+
+                assert_eq!(schedule.len(), $expected);
+                assert_eq!(schedule.first(), Some(&1_000_000));
+            }
+        };
+    }
+    test_schedule_count!(one_second_every_100ms, 100, 1_000, 600, 10);
+    test_schedule_count!(uneven_duration, 300, 1_000, 600, 4);
+    test_schedule_count!(interval_longer_than_duration, 2_000, 1_000, 600, 1);
+    test_schedule_count!(validity_shorter_than_duration, 1_000, 10_000, 3, 3);
+
+    #[test]
+    fn null_interval_schedules_nothing() {
+        let repeater =
+            DenmRepeater::new(denm(1_000_000, 600), Duration::ZERO, Duration::from_secs(1));
+
+        assert_eq!(repeater.schedule(1_000_000).next(), None);
+    }
+
+    #[test]
+    fn expired_denm_schedules_nothing() {
+        let repeater = DenmRepeater::new(
+            denm(1_000_000, 10),
+            Duration::from_millis(100),
+            Duration::from_secs(1),
+        );
+
+        assert_eq!(repeater.schedule(1_000_000 + 10_000).next(), None);
+    }
+
+    #[test]
+    fn huge_duration_does_not_overflow() {
+        let repeater = DenmRepeater::new(
+            denm(1_000_000, 600),
+            Duration::from_millis(100),
+            Duration::MAX,
+        );
+
+        assert_eq!(repeater.schedule(1_000_000).count(), 6_000);
+        assert_eq!(repeater.schedule(u64::MAX - 10).next(), None);
+    }
+
+    const NOW: u64 = 1_700_000_000_000;
+
+    fn received(
+        receiver: &mut UnboundedReceiver<(u64, DecentralizedEnvironmentalNotificationMessage)>,
+    ) -> Vec<u64> {
+        let mut timestamps = Vec::new();
+        while let Ok((timestamp, _)) = receiver.try_recv() {
+            timestamps.push(timestamp);
+        }
+        timestamps
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn run_sends_every_repetition() {
+        let (sender, mut receiver) = unbounded_channel();
+        let start = timestamp_to_etsi(NOW).unwrap();
+        let repeater = DenmRepeater::new(
+            denm(start, 600),
+            Duration::from_millis(20),
+            Duration::from_millis(100),
+        )
+        .with_clock(Arc::new(MockClock::new(NOW)));
+        let started_at = Instant::now();
+
+        repeater.run(sender).await;
+
+        assert_eq!(
+            received(&mut receiver),
+            vec![start, start + 20, start + 40, start + 60, start + 80]
+        );
+        assert_eq!(started_at.elapsed(), Duration::from_millis(80));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn repetitions_start_from_the_clock() {
+        let (sender, mut receiver) = unbounded_channel();
+        let repeater = DenmRepeater::new(
+            denm(timestamp_to_etsi(NOW).unwrap() - 20_000, 10),
+            Duration::from_millis(20),
+            Duration::from_secs(10),
+        )
+        .with_clock(Arc::new(MockClock::new(NOW)));
+
+        repeater.run(sender).await;
+
+        assert!(received(&mut receiver).is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn terminated_repetition_stops() {
+        let (sender, mut receiver) = unbounded_channel();
+        let repeater = DenmRepeater::new(
+            denm(timestamp_to_etsi(NOW).unwrap(), 600),
+            Duration::from_millis(20),
+            Duration::from_secs(10),
+        )
+        .with_clock(Arc::new(MockClock::new(NOW)));
+        let terminator = repeater.terminator();
+
+        let handle = tokio::spawn(repeater.run(sender));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        terminator.cancel();
+        handle.await.unwrap();
+
+        assert_eq!(received(&mut receiver).len(), 3);
+    }
+}
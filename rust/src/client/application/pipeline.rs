@@ -10,38 +10,70 @@
  */
 
 use crate::client::application::analyzer::Analyzer;
+use crate::client::configuration::node_configuration::NodeConfiguration;
+use crate::client::configuration::pipeline_configuration::PipelineConfiguration;
 use crate::client::configuration::Configuration;
 use crate::exchange::cause::Cause;
+use crate::exchange::etsi::denm_priority::Priority;
+use crate::exchange::message::content::Content;
 use crate::exchange::message::information::Information;
 use crate::exchange::sequence_number::SequenceNumber;
 use crate::exchange::Exchange;
+use crate::mobility::geofence::Polygon;
+use crate::mobility::quadtree::quadkey::Quadkey;
 use crate::monitor::trace_exchange;
+use crate::transport::compression::{gunzip, is_gzip_encoded};
+use crate::transport::dedup::MessageDeduplicator;
+#[cfg(feature = "metrics")]
+use crate::transport::metrics::PrometheusServer;
+#[cfg(feature = "metrics")]
+use crate::transport::mqtt::connection_state::ConnectionTracker;
+#[cfg(feature = "geo_routing")]
+use crate::transport::mqtt::geo_topic::topic_template::TopicTemplate;
 use crate::transport::mqtt::mqtt_client::{listen, MqttClient};
 use crate::transport::mqtt::mqtt_router;
 use crate::transport::mqtt::mqtt_router::BoxedReception;
+use crate::transport::mqtt::parse_error_reporter::ParseErrorReporter;
+use crate::transport::mqtt::router_stats::RouterStats;
 use crate::transport::mqtt::topic::Topic;
 use crate::transport::packet::Packet;
-use crate::transport::payload::Payload;
-use crossbeam_channel::{unbounded, Receiver};
-use log::{debug, error, info, trace, warn};
-use rumqttc::v5::mqttbytes::v5::PublishProperties;
+use crate::transport::payload::{parse_strict, DeserializeError, Payload};
+use crate::transport::pipe::{pipe, PipeSender};
+use crate::transport::serialization::Serialization;
+use crossbeam_channel::{select, unbounded, Receiver, RecvTimeoutError};
+use rumqttc::v5::mqttbytes::v5::Publish;
 use rumqttc::v5::{Event, EventLoop};
 use serde::de::DeserializeOwned;
+use std::fmt::Display;
 use std::sync::{Arc, RwLock};
 use std::thread;
 use std::thread::JoinHandle;
-use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, info_span, trace, warn, Span};
+
+/// Function called with the topic, the raw payload and the error of each received payload which
+/// fails to be parsed, e.g. to forward it to a dead letter topic
+pub type ParseErrorCallback = Arc<dyn Fn(&str, &[u8], &DeserializeError) + Send + Sync>;
+
+/// Room left in a packet for its fixed header and properties when guessing whether a truncated
+/// payload was cut at the `max_packet_size` limit
+const PACKET_OVERHEAD: usize = 256;
+
+/// Last level of the information topic of the broker the client is connected to
+const BROKER_INFO_SUFFIX: &str = "/broker";
 
 /// Struct holding the result of the output exchanges filter thread initialization
 ///
 /// Holding:
-/// - one [exchange][1] channel receiver for exchange sending monitoring
-/// - one [exchange][1]/cause channel receiver for exchange MQTT publishing
+/// - one [exchange][1] channel receiver for urgent exchange MQTT publishing
+/// - one [exchange][1] channel receiver for routine exchange MQTT publishing
+/// - one [exchange][1]/cause channel receiver for exchange sending monitoring
 /// - the [join handle][2] to manage the tread's termination
 ///
 /// [1]: Exchange
 /// [2]: JoinHandle
 type FilterPipes<T> = (
+    Receiver<Packet<T, Exchange>>,
     Receiver<Packet<T, Exchange>>,
     Receiver<(Packet<T, Exchange>, Option<Cause>)>,
     JoinHandle<()>,
@@ -65,11 +97,26 @@ type DispatchPipes<T> = (
     JoinHandle<()>,
 );
 
+/// Runs the analysis pipeline until the shutdown token is cancelled or the connection is lost
+///
+/// On shutdown, the pipeline stops accepting incoming messages, lets the analysers process the
+/// ones already received, publishes their results and then disconnects from the broker before
+/// returning
+///
+/// Payloads failing to be parsed are counted in the router's [stats][1] and handed to the
+/// optional `on_parse_error` callback
+///
+/// Several analyzers can be run one after the other by [chaining][2] them
+///
+/// [1]: crate::transport::mqtt::router_stats::RouterStats
+/// [2]: crate::client::application::analyzer::Chain
 pub async fn run<A, C, T>(
     configuration: Arc<Configuration>,
     context: Arc<RwLock<C>>,
     sequence_number: Arc<RwLock<SequenceNumber>>,
     subscription_list: &[T],
+    shutdown: CancellationToken,
+    on_parse_error: Option<ParseErrorCallback>,
 ) where
     A: Analyzer<T, C>,
     T: Topic + 'static,
@@ -91,11 +138,32 @@ pub async fn run<A, C, T>(
     info!("Analysis thread count set to: {}", thread_count);
 
     let (mut mqtt_client, event_loop) = MqttClient::new(&configuration.mqtt_options);
-    mqtt_client_subscribe(subscription_list, &mut mqtt_client).await;
+    mqtt_client.set_payload_compression(configuration.pipeline.compress_payloads);
+    mqtt_client.set_serialization(configuration.pipeline.serialization);
+    mqtt_client.set_resend_queue_capacity(configuration.pipeline.resend_queue_capacity);
+    #[cfg(feature = "geo_routing")]
+    mqtt_client.set_clock(configuration.clock.clone());
+    mqtt_client_subscribe(subscription_list, &configuration, &mut mqtt_client).await;
+
+    let stats = Arc::new(RouterStats::default());
+    #[cfg(feature = "metrics")]
+    start_metrics_server(
+        &configuration,
+        stats.clone(),
+        mqtt_client.connection_tracker(),
+        shutdown.clone(),
+    );
 
-    let (event_receiver, mqtt_client_listen_handle) = mqtt_client_listen_thread(event_loop);
+    let (event_receiver, mqtt_client_listen_handle) =
+        mqtt_client_listen_thread(&configuration, &mqtt_client, event_loop, shutdown);
     let (item_receiver, monitoring_receiver, information_receiver, mqtt_router_dispatch_handle) =
-        mqtt_router_dispatch_thread(subscription_list.to_vec(), event_receiver);
+        mqtt_router_dispatch_thread(
+            &configuration,
+            stats,
+            subscription_list.to_vec(),
+            event_receiver,
+            on_parse_error,
+        );
 
     let monitor_reception_handle = monitor_thread(
         "received_on".to_string(),
@@ -105,7 +173,7 @@ pub async fn run<A, C, T>(
 
     let analysis_pool = threadpool::ThreadPool::with_name("Analysis".to_string(), thread_count);
 
-    let (analyser_sender, analyser_receiver) = unbounded();
+    let (analyser_sender, analyser_receiver) = configured_pipe(&configuration);
     for _ in 0..thread_count {
         let rx = item_receiver.clone();
         let tx = analyser_sender.clone();
@@ -131,8 +199,10 @@ pub async fn run<A, C, T>(
             }
         });
     }
+    // NOTE: only analysers must hold a sender so that the filter stops once they are done
+    drop(analyser_sender);
 
-    let (publish_item_receiver, publish_monitoring_receiver, filter_handle) =
+    let (urgent_item_receiver, publish_item_receiver, publish_monitoring_receiver, filter_handle) =
         filter_thread::<T>(configuration.clone(), analyser_receiver);
 
     let reader_configure_handle =
@@ -144,7 +214,15 @@ pub async fn run<A, C, T>(
         publish_monitoring_receiver,
     );
 
-    mqtt_client_publish(publish_item_receiver, &mut mqtt_client).await;
+    mqtt_client_publish(
+        urgent_item_receiver,
+        publish_item_receiver,
+        &mut mqtt_client,
+    )
+    .await;
+    if let Err(e) = mqtt_client.disconnect().await {
+        warn!("Failed to disconnect: {}", e);
+    }
 
     debug!("mqtt_client_listen_handler joining...");
     mqtt_client_listen_handle.await.unwrap();
@@ -162,52 +240,302 @@ pub async fn run<A, C, T>(
     monitor_publish_handle.join().unwrap();
 
     warn!("loop done");
-    tokio::time::sleep(Duration::from_secs(5)).await;
+}
+
+/// Creates the channel handing the exchanges to the [consume] caller
+///
+/// The configuration parsing refuses a null capacity but the field can still be set by hand, and
+/// `async_channel::bounded` panics on 0: such a capacity is raised to 1
+fn exchange_channel<T>(
+    capacity: Option<usize>,
+) -> (async_channel::Sender<T>, async_channel::Receiver<T>) {
+    match capacity {
+        Some(capacity) => async_channel::bounded(capacity.max(1)),
+        None => async_channel::unbounded(),
+    }
+}
+
+/// Subscribes to the topics and returns the stream of the received [exchanges][1]
+///
+/// This is the lightweight alternative to [run] for applications which only consume messages:
+/// no [Analyzer] is required, the parsed exchanges are directly handed to the caller
+/// The stream ends once the shutdown token is cancelled or the connection is lost
+///
+/// Example:
+/// ```no_run
+/// # use libits::client::application::pipeline::consume;
+/// # use libits::client::configuration::Configuration;
+/// # use libits::transport::mqtt::topic::Topic;
+/// # use tokio_util::sync::CancellationToken;
+/// # async fn example<T: Topic + 'static>(configuration: Configuration, topics: Vec<T>) {
+/// let exchanges = consume(&configuration, &topics, CancellationToken::new()).await;
+/// while let Ok(packet) = exchanges.recv().await {
+///     println!("{} received on {}", packet.payload.type_field, packet.topic);
+/// }
+/// # }
+/// ```
+///
+/// [1]: Exchange
+pub async fn consume<T>(
+    configuration: &Configuration,
+    subscription_list: &[T],
+    shutdown: CancellationToken,
+) -> async_channel::Receiver<Packet<T, Exchange>>
+where
+    T: Topic + 'static,
+{
+    let (mut mqtt_client, event_loop) = MqttClient::new(&configuration.mqtt_options);
+    mqtt_client.set_payload_compression(configuration.pipeline.compress_payloads);
+    mqtt_client.set_serialization(configuration.pipeline.serialization);
+    mqtt_client.set_resend_queue_capacity(configuration.pipeline.resend_queue_capacity);
+    #[cfg(feature = "geo_routing")]
+    mqtt_client.set_clock(configuration.clock.clone());
+    mqtt_client_subscribe(subscription_list, configuration, &mut mqtt_client).await;
+
+    let (event_sender, event_receiver) = configured_pipe(configuration);
+    let (exchange_sender, exchange_receiver) =
+        exchange_channel(configuration.pipeline.channel_capacity);
+
+    let settings = RouterSettings::from(configuration);
+    #[cfg(feature = "metrics")]
+    start_metrics_server(
+        configuration,
+        settings.stats.clone(),
+        mqtt_client.connection_tracker(),
+        shutdown.clone(),
+    );
+
+    let listen_shutdown = shutdown.clone();
+    let connection = mqtt_client.connection_tracker();
+    tokio::task::spawn(async move {
+        listen(event_loop, event_sender, listen_shutdown, connection).await;
+    });
+    tokio::task::spawn(async move {
+        shutdown.cancelled().await;
+        if let Err(e) = mqtt_client.disconnect().await {
+            warn!("Failed to disconnect: {}", e);
+        }
+    });
+
+    let topic_list = subscription_list.to_vec();
+    thread::Builder::new()
+        .name("mqtt-router-consumer".into())
+        .spawn(move || consumer_dispatch(settings, topic_list, event_receiver, exchange_sender))
+        .unwrap();
+
+    exchange_receiver
+}
+
+/// Reception settings of the routers, taken from the configuration to be moved to their threads
+///
+/// They are shared with the routes to parse the received payloads, see [deserialize]
+#[derive(Default)]
+struct RouterSettings {
+    max_payload_size: Option<usize>,
+    max_packet_size: Option<usize>,
+    strict_parsing: bool,
+    #[cfg(feature = "geo_routing")]
+    topic_template: Option<TopicTemplate>,
+    stats: Arc<RouterStats>,
+    on_parse_error: Option<ParseErrorCallback>,
+    /// Parse errors are only logged at debug level, the router's reporter summarizing them
+    summarize_parse_errors: bool,
+}
+
+impl RouterSettings {
+    fn router(&self) -> mqtt_router::MqttRouter {
+        let mut router = mqtt_router::MqttRouter::default();
+        router.set_stats(self.stats.clone());
+        router.set_max_payload_size(self.max_payload_size);
+        #[cfg(feature = "geo_routing")]
+        router.set_topic_template(self.topic_template.clone());
+        router
+    }
+}
+
+impl From<&Configuration> for RouterSettings {
+    fn from(configuration: &Configuration) -> Self {
+        Self {
+            max_payload_size: configuration.pipeline.max_payload_size,
+            max_packet_size: configuration
+                .mqtt_options
+                .max_packet_size()
+                .map(|size| size as usize),
+            strict_parsing: configuration.pipeline.strict_parsing,
+            #[cfg(feature = "geo_routing")]
+            topic_template: configuration.geo.topic_template.clone(),
+            stats: Arc::default(),
+            on_parse_error: None,
+            summarize_parse_errors: false,
+        }
+    }
+}
+
+/// Routes the received events and forwards the parsed exchanges until one of the channels closes
+fn consumer_dispatch<T>(
+    settings: RouterSettings,
+    topic_list: Vec<T>,
+    event_receiver: Receiver<Event>,
+    exchange_sender: async_channel::Sender<Packet<T, Exchange>>,
+) where
+    T: Topic + 'static,
+{
+    trace!("mqtt router consumer closure entering...");
+    let mut router = settings.router();
+    let settings = Arc::new(settings);
+    for topic in topic_list {
+        let settings = settings.clone();
+        router.add_route(topic, move |publish| {
+            deserialize::<Exchange>(publish, &settings)
+        });
+    }
+
+    for event in event_receiver {
+        if let Some((topic, (reception, properties))) = router.handle_event(event) {
+            if let Ok(exchange) = reception.downcast::<Exchange>() {
+                let packet = Packet {
+                    properties,
+                    ..Packet::new(topic, *exchange)
+                };
+                if exchange_sender.send_blocking(packet).is_err() {
+                    info!("exchange stream dropped, consumer stops");
+                    break;
+                }
+            }
+        }
+    }
+    trace!("mqtt router consumer closure finished");
 }
 
 fn filter_thread<T>(
-    _configuration: Arc<Configuration>,
+    configuration: Arc<Configuration>,
     exchange_receiver: Receiver<(Packet<T, Exchange>, Option<Cause>)>,
 ) -> FilterPipes<T>
 where
     T: Topic + 'static,
 {
     info!("starting filtering...");
+    let (urgent_sender, urgent_receiver) = unbounded();
     let (publish_sender, publish_receiver) = unbounded();
     let (monitoring_sender, monitoring_receiver) = unbounded();
     let handle = thread::Builder::new()
         .name("filter".into())
         .spawn(move || {
             trace!("filter closure entering...");
-            for tuple in exchange_receiver {
+            'filter: for tuple in exchange_receiver {
                 let item = tuple.0;
                 let cause = tuple.1;
+                let _span = exchange_span("sent", &item.topic, &item.payload).entered();
 
-                // FIXME Topic does not hold geo_extension anymore
-                //assumed clone, we just send the GeoExtension
-                // if configuration.is_in_region_of_responsibility(item.topic.geo_extension.clone()) {
-                //assumed clone, we send to 2 channels
-                match publish_sender.send(item.clone()) {
-                    Ok(()) => trace!("publish sent"),
-                    Err(error) => {
-                        error!("stopped to send publish: {}", error);
-                        break;
-                    }
+                if !is_in_geofence(&configuration, &item.payload) {
+                    debug!("item outside of the geofence, not published");
+                    continue;
                 }
-                match monitoring_sender.send((item, cause)) {
-                    Ok(()) => trace!("monitoring sent"),
-                    Err(error) => {
-                        error!("stopped to send monitoring: {}", error);
-                        break;
+
+                let priority = configuration
+                    .pipeline
+                    .denm_priorities
+                    .priority(&item.payload);
+                let item = Packet {
+                    qos: priority.qos(item.qos),
+                    ..item
+                };
+                let sender = match priority {
+                    Priority::Urgent => &urgent_sender,
+                    Priority::Routine => &publish_sender,
+                };
+                let items = match item.payload.message.covering_area() {
+                    Some(area) => covering_packets(&configuration.pipeline, item, &area),
+                    None => vec![item],
+                };
+                for item in items {
+                    //assumed clone, we send to 2 channels
+                    match sender.send(item.clone()) {
+                        Ok(()) => trace!("publish sent"),
+                        Err(error) => {
+                            error!("stopped to send publish: {}", error);
+                            break 'filter;
+                        }
+                    }
+                    match monitoring_sender.send((item, cause.clone())) {
+                        Ok(()) => trace!("monitoring sent"),
+                        Err(error) => {
+                            error!("stopped to send monitoring: {}", error);
+                            break 'filter;
+                        }
                     }
                 }
-                // }
                 trace!("filter closure finished");
             }
         })
         .unwrap();
     info!("filter started");
-    (publish_receiver, monitoring_receiver, handle)
+    (
+        urgent_receiver,
+        publish_receiver,
+        monitoring_receiver,
+        handle,
+    )
+}
+
+/// Returns the copies of the packet to publish on each tile covered by the area
+///
+/// Fan-out is enabled by the `max_covering_tiles` [pipeline option][1]: the packet is copied on
+/// each tile overlapping the area at the depth of its topic's quadkey, as long as the area's
+/// bounding box spans at most this number of tiles. Without this option, if the topic has no
+/// quadkey or if the area spans more tiles, the packet is returned as is
+///
+/// Analyzers can return the result of this function for messages relevant to a whole area
+///
+/// [1]: PipelineConfiguration
+pub fn covering_packets<T: Topic>(
+    configuration: &PipelineConfiguration,
+    packet: Packet<T, Exchange>,
+    area: &Polygon,
+) -> Vec<Packet<T, Exchange>> {
+    let Some(max_covering_tiles) = configuration.max_covering_tiles else {
+        return vec![packet];
+    };
+    let depth = match packet.topic.geo_extension() {
+        Some(quadkey) if !quadkey.tiles.is_empty() => quadkey.tiles.len(),
+        _ => return vec![packet],
+    };
+
+    let Some(tiles) = u8::try_from(depth)
+        .ok()
+        .and_then(|zoom| Quadkey::covering_tiles(area, zoom, max_covering_tiles))
+    else {
+        warn!(
+            "cannot cover an area with at most {} tiles at depth {}, published as is",
+            max_covering_tiles, depth
+        );
+        return vec![packet];
+    };
+
+    tiles
+        .into_iter()
+        .filter_map(|tile| {
+            let topic = packet.topic.with_geo_extension(tile)?;
+            Some(Packet {
+                topic,
+                ..packet.clone()
+            })
+        })
+        .collect()
+}
+
+/// Returns false if the exchange is a mobile message positioned outside the node's geofence
+fn is_in_geofence(configuration: &Configuration, exchange: &Exchange) -> bool {
+    let Some(node_configuration) = configuration.node.as_ref() else {
+        return true;
+    };
+    match exchange.message.content().as_mobile() {
+        Ok(mobile) => node_configuration
+            .read()
+            .unwrap()
+            .is_in_geofence(&mobile.position()),
+        Err(_) => true,
+    }
 }
 
 fn monitor_thread<T>(
@@ -247,6 +575,7 @@ where
                             packet.topic.as_route(),
                             packet.payload.source_uuid
                         ),
+                        configuration.clock.now_millis(),
                     );
                 } else {
                     info!("Cannot trace exchange, missing gateway component name in node configuration");
@@ -258,14 +587,42 @@ where
     handle
 }
 
+/// Serves the metrics until the shutdown if a bind address is configured
+#[cfg(feature = "metrics")]
+fn start_metrics_server(
+    configuration: &Configuration,
+    stats: Arc<RouterStats>,
+    connection: Arc<ConnectionTracker>,
+    shutdown: CancellationToken,
+) {
+    let Some(bind_address) = configuration.pipeline.metrics_bind_address.clone() else {
+        return;
+    };
+    let server = PrometheusServer::new(stats).with_connection(connection);
+    tokio::spawn(async move {
+        tokio::select! {
+            result = server.serve(bind_address.as_str()) => {
+                if let Err(e) = result {
+                    error!("Failed to serve metrics on {}: {}", bind_address, e);
+                }
+            }
+            _ = shutdown.cancelled() => (),
+        }
+    });
+}
+
 fn mqtt_client_listen_thread(
+    configuration: &Configuration,
+    mqtt_client: &MqttClient,
     event_loop: EventLoop,
+    shutdown: CancellationToken,
 ) -> (Receiver<Event>, tokio::task::JoinHandle<()>) {
     info!("Starting MQTT listening thread...");
-    let (event_sender, event_receiver) = unbounded();
+    let (event_sender, event_receiver) = configured_pipe(configuration);
+    let connection = mqtt_client.connection_tracker();
     let handle = tokio::task::spawn(async move {
         trace!("mqtt client listening closure entering...");
-        listen(event_loop, event_sender).await;
+        listen(event_loop, event_sender, shutdown, connection).await;
         trace!("mqtt client listening closure finished");
     });
     info!("MQTT listening thread started!");
@@ -290,13 +647,13 @@ where
                     packet.topic, packet.payload
                 );
 
-                configuration
+                let mut node_configuration = configuration
                     .node
                     .as_ref()
                     .expect("Node app requires node configuration")
                     .write()
-                    .unwrap()
-                    .update(packet.payload);
+                    .unwrap();
+                update_node_configuration(&mut node_configuration, packet);
             }
             trace!("reader configuration closure finished");
         })
@@ -305,25 +662,87 @@ where
     handle
 }
 
-async fn mqtt_client_subscribe<T: Topic>(topic_list: &[T], client: &mut MqttClient) {
-    info!("mqtt client subscribing starting...");
-    let mut topic_subscription_list = topic_list.iter().map(|t| t.to_string()).collect::<Vec<_>>();
-
-    for topic in topic_subscription_list.iter_mut() {
-        match topic {
-            info_topic if info_topic.contains(Information::TYPE) => {
-                info_topic.push_str("/broker");
-            }
-            topic => topic.push_str("/+/#"),
-        }
+/// Updates the node configuration from the broker's information, or records the information of
+/// a neighbouring instance
+fn update_node_configuration<T: Topic>(
+    node_configuration: &mut NodeConfiguration,
+    packet: Packet<T, Information>,
+) {
+    if packet.topic.to_string().ends_with(BROKER_INFO_SUFFIX) {
+        node_configuration.update(packet.payload);
+    } else {
+        node_configuration.update_neighbour(packet.payload);
     }
+}
+
+async fn mqtt_client_subscribe<T: Topic>(
+    topic_list: &[T],
+    configuration: &Configuration,
+    client: &mut MqttClient,
+) {
+    info!("mqtt client subscribing starting...");
+    let topic_subscription_list = subscription_topics(topic_list, configuration);
 
     // NOTE: we share the topic list with the dispatcher
-    client.subscribe(&topic_subscription_list).await;
+    if let Err(e) = client.subscribe(&topic_subscription_list).await {
+        error!("Failed to subscribe: {}", e);
+    }
     info!("mqtt client subscribing finished");
 }
 
+/// Returns the topic filters to subscribe to, restricted to the configured tiles of each
+/// message type, and to the broker's information unless all are [subscribed to][1]
+///
+/// The uuid and geo levels are placed following the [topic template][2] if any, after the
+/// topic otherwise
+///
+/// [1]: PipelineConfiguration::subscribe_all_info
+/// [2]: crate::client::configuration::geo_configuration::GeoConfiguration::topic_template
+fn subscription_topics<T: Topic>(topic_list: &[T], configuration: &Configuration) -> Vec<String> {
+    let pipeline = &configuration.pipeline;
+    let info_uuid = if pipeline.subscribe_all_info {
+        "+"
+    } else {
+        BROKER_INFO_SUFFIX.trim_start_matches('/')
+    };
+
+    topic_list
+        .iter()
+        .map(|topic| {
+            let topic = topic.to_string();
+            #[cfg(feature = "geo_routing")]
+            if let Some(template) = configuration.geo.topic_template.as_ref() {
+                match template.parse(&topic) {
+                    Ok(route) if route.message_type() == Information::TYPE => {
+                        return template.subscription(&route, info_uuid, "");
+                    }
+                    Ok(route) => {
+                        let geo = pipeline
+                            .subscription_filters
+                            .geo_filter(&route.message_type());
+                        return template.subscription(&route, "+", &geo);
+                    }
+                    Err(e) => warn!("Topic {} does not follow the template: {}", topic, e),
+                }
+            }
+
+            if topic.contains(Information::TYPE) {
+                format!("{}/{}", topic, info_uuid)
+            } else {
+                let message_type = topic.rsplit('/').next().unwrap_or_default();
+                format!(
+                    "{}/+/{}",
+                    topic,
+                    pipeline.subscription_filters.geo_filter(message_type)
+                )
+            }
+        })
+        .collect()
+}
+
+/// Publishes the packets, the urgent ones before any routine one waiting to be published
 async fn mqtt_client_publish<T, P>(
+    urgent_item_receiver: Receiver<Packet<T, P>>,
     publish_item_receiver: Receiver<Packet<T, P>>,
     client: &mut MqttClient,
 ) where
@@ -331,24 +750,56 @@ async fn mqtt_client_publish<T, P>(
     P: Payload,
 {
     info!("Starting MQTT publishing thread...");
-    for item in publish_item_receiver {
+    while let Some(item) = next_by_priority(&urgent_item_receiver, &publish_item_receiver) {
         debug!("Packet to publish...");
-        client.publish(item).await;
-        debug!("Packet published!");
+        match client.publish(item).await {
+            Ok(()) => debug!("Packet published!"),
+            Err(e) => error!("Failed to publish packet: {}", e),
+        }
     }
     info!("MQTT publishing thread stopping");
 }
 
+/// Returns the next urgent item if any, the next routine one otherwise
+///
+/// Blocks until an item is received, returns None once both channels are disconnected and empty
+fn next_by_priority<I>(urgent: &Receiver<I>, routine: &Receiver<I>) -> Option<I> {
+    if let Ok(item) = urgent.try_recv() {
+        return Some(item);
+    }
+    select! {
+        recv(urgent) -> item => item.ok().or_else(|| routine.recv().ok()),
+        recv(routine) -> item => item.ok().or_else(|| urgent.recv().ok()),
+    }
+}
+
 fn mqtt_router_dispatch_thread<T>(
+    configuration: &Configuration,
+    stats: Arc<RouterStats>,
     topic_list: Vec<T>,
     event_receiver: Receiver<Event>,
+    on_parse_error: Option<ParseErrorCallback>,
     // FIXME manage a Box into the Exchange to use a unique object Trait instead
 ) -> DispatchPipes<T>
 where
     T: Topic + 'static,
 {
     info!("starting mqtt router dispatching...");
-    let (exchange_sender, exchange_receiver) = unbounded();
+    let report_interval = configuration.pipeline.parse_error_report_interval;
+    let parse_error_reporter = report_interval
+        .map(|interval| ParseErrorReporter::new(interval, configuration.clock.clone()));
+    let settings = RouterSettings {
+        stats,
+        on_parse_error,
+        summarize_parse_errors: parse_error_reporter.is_some(),
+        ..RouterSettings::from(configuration)
+    };
+    let mut deduplicator = configuration
+        .pipeline
+        .dedup_capacity
+        .map(|capacity| MessageDeduplicator::new(capacity, configuration.pipeline.dedup_ttl));
+    let clock = configuration.clock.clone();
+    let (exchange_sender, exchange_receiver) = configured_pipe(configuration);
     let (monitoring_sender, monitoring_receiver) = unbounded();
     let (information_sender, information_receiver) = unbounded();
 
@@ -357,27 +808,60 @@ where
         .spawn(move || {
             trace!("mqtt router dispatching closure entering...");
             //initialize the router
-            let router = &mut mqtt_router::MqttRouter::default();
+            let router = &mut settings.router();
+            if let Some(reporter) = parse_error_reporter {
+                router.set_parse_error_reporter(reporter);
+            }
 
+            let stats = router.stats();
+            let settings = Arc::new(settings);
             for topic in topic_list.iter() {
+                let settings = settings.clone();
                 match topic {
                     info_topic if info_topic.to_string().contains(Information::TYPE) => {
-                        router.add_route(info_topic.clone(), deserialize::<Information>);
+                        router.add_route(info_topic.clone(), move |publish| {
+                            deserialize::<Information>(publish, &settings)
+                        });
                     }
-                    _ => router.add_route(topic.clone(), deserialize::<Exchange>),
+                    _ => router.add_route(topic.clone(), move |publish| {
+                        deserialize::<Exchange>(publish, &settings)
+                    }),
                 }
             }
 
-            for event in event_receiver {
+            loop {
+                // without traffic, the parse errors summary is logged once the interval elapsed
+                let event = match report_interval {
+                    Some(interval) => match event_receiver.recv_timeout(interval) {
+                        Ok(event) => event,
+                        Err(RecvTimeoutError::Timeout) => {
+                            router.report_parse_errors();
+                            continue;
+                        }
+                        Err(RecvTimeoutError::Disconnected) => break,
+                    },
+                    None => match event_receiver.recv() {
+                        Ok(event) => event,
+                        Err(_) => break,
+                    },
+                };
                 match router.handle_event(event) {
                     Some((topic, (reception, properties))) => {
                         // TODO use the From Trait
                         if reception.is::<Exchange>() {
                             if let Ok(exchange) = reception.downcast::<Exchange>() {
+                                if let Some(deduplicator) = deduplicator.as_mut() {
+                                    if deduplicator.is_duplicate(&exchange, clock.now_millis()) {
+                                        trace!("duplicate {} dropped", exchange.type_field);
+                                        stats.count_duplicate();
+                                        continue;
+                                    }
+                                }
+                                let _span = exchange_span("received", &topic, &exchange).entered();
+                                stats.count_message_type(&exchange.type_field);
                                 let item = Packet {
-                                    topic,
-                                    payload: *exchange,
                                     properties,
+                                    ..Packet::new(topic, *exchange)
                                 };
                                 //assumed clone, we send to 2 channels
                                 match monitoring_sender.send((item.clone(), None)) {
@@ -396,11 +880,8 @@ where
                                 }
                             }
                         } else if let Ok(information) = reception.downcast::<Information>() {
-                            match information_sender.send(Packet {
-                                topic,
-                                payload: *information,
-                                properties: PublishProperties::default(),
-                            }) {
+                            stats.count_message_type(Information::TYPE);
+                            match information_sender.send(Packet::new(topic, *information)) {
                                 Ok(()) => trace!("mqtt information sent"),
                                 Err(error) => {
                                     error!("stopped to send mqtt information: {}", error);
@@ -424,23 +905,816 @@ where
     )
 }
 
-fn deserialize<T>(publish: rumqttc::v5::mqttbytes::v5::Publish) -> Option<BoxedReception>
+/// Creates the span carrying the structured fields of an exchange going through the pipeline
+///
+/// `direction` is either `received` or `sent`
+fn exchange_span(direction: &'static str, topic: &impl Display, exchange: &Exchange) -> Span {
+    info_span!(
+        "exchange",
+        direction,
+        message_type = %exchange.type_field,
+        station_id = exchange.message.station_id(),
+        topic = %topic,
+    )
+}
+
+/// Creates a [pipe] sized and configured according to the pipeline configuration
+fn configured_pipe<T>(configuration: &Configuration) -> (PipeSender<T>, Receiver<T>) {
+    pipe(
+        configuration.pipeline.channel_capacity,
+        configuration.pipeline.channel_policy,
+    )
+}
+
+/// Parses the payload, failing on unknown fields when `strict_parsing` is set (see [parse_strict])
+///
+/// Compressed payloads are refused once their decompressed size goes beyond `max_payload_size`
+///
+/// On failure, the optional `on_parse_error` callback receives the topic, the raw payload and the
+/// error
+fn deserialize<T>(publish: Publish, settings: &RouterSettings) -> Option<BoxedReception>
 where
     T: DeserializeOwned + Payload + 'static + Send,
 {
-    // Incoming publish from the broker
-    match String::from_utf8(publish.payload.to_vec()) {
+    match try_deserialize::<T>(&publish, settings.strict_parsing, settings.max_payload_size) {
         Ok(message) => {
-            let message_str = message.as_str();
-            match serde_json::from_str::<T>(message_str) {
-                Ok(message) => {
-                    trace!("message parsed");
-                    return Some((Box::new(message), publish.properties.unwrap_or_default()));
-                }
-                Err(e) => warn!("parse error({}) on: {}", e, message_str),
+            trace!("message parsed");
+            Some((Box::new(message), publish.properties.unwrap_or_default()))
+        }
+        Err(e) => {
+            // parse errors are summarized by the router's reporter if any
+            if !settings.summarize_parse_errors {
+                warn!("parse error({}) on: {:?}", e, publish.payload);
+            } else {
+                debug!("parse error({}) on: {:?}", e, publish.payload);
+            }
+            if e.is_truncation() {
+                settings.stats.count_truncation();
+                warn!(
+                    "Truncated payload of {} bytes on topic '{}'{}",
+                    publish.payload.len(),
+                    String::from_utf8_lossy(&publish.topic),
+                    if packet_size_limit_hit(&publish, settings.max_packet_size) {
+                        ", the max_packet_size limit was likely hit"
+                    } else {
+                        ""
+                    }
+                );
             }
+            if let Some(callback) = &settings.on_parse_error {
+                callback(
+                    &String::from_utf8_lossy(&publish.topic),
+                    &publish.payload,
+                    &e,
+                );
+            }
+            None
         }
-        Err(e) => warn!("format error: {}", e),
     }
-    None
+}
+
+/// Returns true if the publish is within [PACKET_OVERHEAD] bytes of the packet size limit
+fn packet_size_limit_hit(publish: &Publish, max_packet_size: Option<usize>) -> bool {
+    max_packet_size
+        .is_some_and(|max| publish.topic.len() + publish.payload.len() + PACKET_OVERHEAD >= max)
+}
+
+fn try_deserialize<T>(
+    publish: &Publish,
+    strict: bool,
+    max_payload_size: Option<usize>,
+) -> Result<T, DeserializeError>
+where
+    T: DeserializeOwned + Payload,
+{
+    let gzip_encoded = publish
+        .properties
+        .as_ref()
+        .is_some_and(|properties| is_gzip_encoded(&properties.user_properties));
+    let payload = if gzip_encoded {
+        gunzip(&publish.payload, max_payload_size)?
+    } else {
+        publish.payload.to_vec()
+    };
+
+    let serialization = Serialization::from_content_type(
+        publish
+            .properties
+            .as_ref()
+            .and_then(|properties| properties.content_type.as_deref()),
+    );
+    if serialization != Serialization::Json {
+        return if strict {
+            let value = serialization.deserialize::<serde_json::Value>(&payload)?;
+            Ok(parse_strict::<T>(&serde_json::to_vec(&value)?)?)
+        } else {
+            Ok(serialization.deserialize::<T>(&payload)?)
+        };
+    }
+
+    let message = String::from_utf8(payload)?;
+
+    if strict {
+        Ok(parse_strict::<T>(message.as_bytes())?)
+    } else {
+        Ok(serde_json::from_str::<T>(&message)?)
+    }
+}
+
+#[cfg(all(test, feature = "geo_routing"))]
+mod tests {
+    use crate::client::application::pipeline::{
+        consumer_dispatch, covering_packets, deserialize, exchange_channel, exchange_span,
+        filter_thread, mqtt_client_publish, next_by_priority, packet_size_limit_hit,
+        subscription_topics, update_node_configuration, ParseErrorCallback, RouterSettings,
+    };
+    use crate::client::configuration::node_configuration::NodeConfiguration;
+    use crate::client::configuration::pipeline_configuration::PipelineConfiguration;
+    use crate::client::configuration::Configuration;
+    use crate::exchange::etsi::collective_perception_message::{
+        CollectivePerceptionMessage, ManagementContainer,
+    };
+    use crate::exchange::etsi::decentralized_environmental_notification_message::{
+        DecentralizedEnvironmentalNotificationMessage, EventType, SituationContainer,
+    };
+    use crate::exchange::etsi::perceived_object::PerceivedObject;
+    use crate::exchange::etsi::reference_position::ReferencePosition;
+    use crate::exchange::message::content::Content;
+    use crate::exchange::message::information::Information;
+    use crate::exchange::message::Message;
+    use crate::exchange::Exchange;
+    use crate::mobility::geofence::Polygon;
+    use crate::now;
+    use crate::transport::compression::{gzip, CONTENT_ENCODING, GZIP};
+    use crate::transport::mqtt::geo_topic::topic_template::TopicTemplate;
+    use crate::transport::mqtt::geo_topic::GeoTopic;
+    use crate::transport::mqtt::mqtt_client::MqttClient;
+    use crate::transport::mqtt::subscription::SubscriptionFilters;
+    use crate::transport::packet::Packet;
+    use crate::transport::payload::DeserializeError;
+    use crate::transport::serialization::Serialization;
+    use crossbeam_channel::unbounded;
+    use ini::Ini;
+    use rumqttc::v5::mqttbytes::v5::{Publish, PublishProperties};
+    use rumqttc::v5::mqttbytes::QoS;
+    use rumqttc::v5::{AsyncClient, Event, Incoming, Request};
+    use std::collections::HashSet;
+    use std::str::FromStr;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use tracing::info;
+
+    const CAM: &str = r#"{"type":"cam","origin":"self","version":"1.1.3","source_uuid":"car_1","timestamp":1574778515424,"message":{"protocol_version":1,"station_id":42,"generation_delta_time":3,"basic_container":{"reference_position":{"latitude":486263556,"longitude":22492123,"altitude":20000}},"high_frequency_container":{}}}"#;
+
+    fn publish_event(topic: &str, payload: &'static str) -> Event {
+        Event::Incoming(Incoming::Publish(Publish::new(
+            topic,
+            QoS::AtMostOnce,
+            payload,
+            None,
+        )))
+    }
+
+    #[tokio::test]
+    async fn consumer_streams_parsed_exchanges() {
+        let topic = GeoTopic::from_str("default/outQueue/v2x/cam").unwrap();
+        let (event_sender, event_receiver) = unbounded();
+        let (exchange_sender, exchange_receiver) = async_channel::unbounded();
+        let handle = thread::spawn(move || {
+            consumer_dispatch(
+                RouterSettings::default(),
+                vec![topic],
+                event_receiver,
+                exchange_sender,
+            )
+        });
+
+        event_sender
+            .send(publish_event(
+                "default/outQueue/v2x/cam/car_1/1/2/0",
+                "garbage",
+            ))
+            .unwrap();
+        event_sender
+            .send(publish_event("default/outQueue/v2x/cam/car_1/1/2/0", CAM))
+            .unwrap();
+        drop(event_sender);
+
+        let packet = exchange_receiver.recv().await.unwrap();
+        assert_eq!(packet.payload.type_field, "cam");
+        assert_eq!(
+            packet.topic.to_string(),
+            "default/outQueue/v2x/cam/car_1/1/2/0"
+        );
+        assert!(exchange_receiver.recv().await.is_err());
+        handle.join().unwrap();
+    }
+
+    #[tokio::test]
+    async fn user_properties_reach_the_packet() {
+        let topic = GeoTopic::from_str("default/outQueue/v2x/cam").unwrap();
+        let (event_sender, event_receiver) = unbounded();
+        let (exchange_sender, exchange_receiver) = async_channel::unbounded();
+        let handle = thread::spawn(move || {
+            consumer_dispatch(
+                RouterSettings::default(),
+                vec![topic],
+                event_receiver,
+                exchange_sender,
+            )
+        });
+
+        event_sender
+            .send(Event::Incoming(Incoming::Publish(Publish::new(
+                "default/outQueue/v2x/cam/car_1/1/2/0",
+                QoS::AtMostOnce,
+                CAM,
+                Some(PublishProperties {
+                    user_properties: vec![(String::from("rssi"), String::from("-67"))],
+                    ..Default::default()
+                }),
+            ))))
+            .unwrap();
+        drop(event_sender);
+
+        let packet = exchange_receiver.recv().await.unwrap();
+        assert_eq!(
+            packet.user_properties().get("rssi").map(String::as_str),
+            Some("-67")
+        );
+        handle.join().unwrap();
+    }
+
+    fn decoded_cam(serialization: Serialization, strict: bool) -> serde_json::Value {
+        let exchange = serde_json::from_str::<Exchange>(CAM).unwrap();
+        let publish = Publish::new(
+            "default/outQueue/v2x/cam/car_1",
+            QoS::AtMostOnce,
+            serialization.serialize(&exchange).unwrap(),
+            Some(PublishProperties {
+                content_type: Some(serialization.content_type().to_string()),
+                ..Default::default()
+            }),
+        );
+
+        let (reception, _) = deserialize::<Exchange>(
+            publish,
+            &RouterSettings {
+                strict_parsing: strict,
+                ..Default::default()
+            },
+        )
+        .expect("Failed to decode CAM");
+        serde_json::to_value(*reception.downcast::<Exchange>().unwrap()).unwrap()
+    }
+
+    macro_rules! test_cam_round_trip {
+        ($test_name:ident, $serialization:expr, $strict:expr) => {
+            #[test]
+            fn $test_name() {
+                let expected =
+                    serde_json::to_value(serde_json::from_str::<Exchange>(CAM).unwrap()).unwrap();
+
+                assert_eq!(decoded_cam($serialization, $strict), expected);
+            }
+        };
+    }
+    test_cam_round_trip!(cam_round_trips_through_json, Serialization::Json, false);
+    test_cam_round_trip!(cam_round_trips_through_cbor, Serialization::Cbor, false);
+    test_cam_round_trip!(
+        cam_round_trips_through_message_pack,
+        Serialization::MessagePack,
+        false
+    );
+    test_cam_round_trip!(strict_cbor_cam_is_parsed, Serialization::Cbor, true);
+
+    #[test]
+    fn exchange_span_has_structured_fields() {
+        let topic = GeoTopic::from_str("default/outQueue/v2x/cam/car_1/1/2/0").unwrap();
+        let exchange = serde_json::from_str::<Exchange>(CAM).unwrap();
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let writer = output.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_ansi(false)
+            .with_writer(move || LogBuffer(writer.clone()))
+            .finish();
+
+        tracing::subscriber::with_default(subscriber, || {
+            let _span = exchange_span("received", &topic, &exchange).entered();
+            info!("routed");
+        });
+
+        let output = String::from_utf8(output.lock().unwrap().clone()).unwrap();
+        assert!(output.contains(
+            "exchange{direction=\"received\" message_type=cam station_id=42 topic=default/outQueue/v2x/cam/car_1/1/2/0}: "
+        ));
+    }
+
+    /// Collects the formatted events to assert on them
+    struct LogBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for LogBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    const CAM_WITH_EXTRA_FIELD: &str = r#"{"type":"cam","origin":"self","version":"1.1.3","source_uuid":"car_1","timestamp":1574778515424,"vendor_field":42,"message":{"protocol_version":1,"station_id":42,"generation_delta_time":3,"basic_container":{"reference_position":{"latitude":486263556,"longitude":22492123,"altitude":20000}},"high_frequency_container":{}}}"#;
+
+    #[test]
+    fn extra_field_is_accepted_in_lenient_mode() {
+        let publish = Publish::new(
+            "default/outQueue/v2x/cam/car_1",
+            QoS::AtMostOnce,
+            CAM_WITH_EXTRA_FIELD,
+            None,
+        );
+
+        assert!(deserialize::<Exchange>(publish, &RouterSettings::default()).is_some());
+    }
+
+    #[test]
+    fn extra_field_is_refused_in_strict_mode() {
+        let publish = Publish::new(
+            "default/outQueue/v2x/cam/car_1",
+            QoS::AtMostOnce,
+            CAM_WITH_EXTRA_FIELD,
+            None,
+        );
+
+        assert!(deserialize::<Exchange>(
+            publish,
+            &RouterSettings {
+                strict_parsing: true,
+                ..Default::default()
+            }
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn known_fields_are_accepted_in_strict_mode() {
+        let publish = Publish::new("default/outQueue/v2x/cam/car_1", QoS::AtMostOnce, CAM, None);
+
+        assert!(deserialize::<Exchange>(
+            publish,
+            &RouterSettings {
+                strict_parsing: true,
+                ..Default::default()
+            }
+        )
+        .is_some());
+    }
+
+    #[test]
+    fn parse_error_callback_receives_topic_and_payload() {
+        let failures = Arc::new(Mutex::new(Vec::new()));
+        let recorded = failures.clone();
+        let callback: ParseErrorCallback = Arc::new(move |topic, payload, error| {
+            recorded.lock().unwrap().push((
+                topic.to_string(),
+                payload.to_vec(),
+                matches!(error, DeserializeError::Json(_)),
+            ))
+        });
+        let publish = Publish::new(
+            "default/outQueue/v2x/cam/car_1",
+            QoS::AtMostOnce,
+            "garbage",
+            None,
+        );
+
+        assert!(deserialize::<Exchange>(
+            publish,
+            &RouterSettings {
+                on_parse_error: Some(callback),
+                ..Default::default()
+            }
+        )
+        .is_none());
+
+        assert_eq!(
+            *failures.lock().unwrap(),
+            vec![(
+                String::from("default/outQueue/v2x/cam/car_1"),
+                b"garbage".to_vec(),
+                true
+            )]
+        );
+    }
+
+    #[test]
+    fn strict_failure_is_reported_as_such() {
+        let reported = Arc::new(Mutex::new(false));
+        let recorded = reported.clone();
+        let callback: ParseErrorCallback = Arc::new(move |_, _, error| {
+            *recorded.lock().unwrap() = matches!(error, DeserializeError::Strict(_))
+        });
+        let publish = Publish::new(
+            "default/outQueue/v2x/cam/car_1",
+            QoS::AtMostOnce,
+            CAM_WITH_EXTRA_FIELD,
+            None,
+        );
+
+        assert!(deserialize::<Exchange>(
+            publish,
+            &RouterSettings {
+                strict_parsing: true,
+                on_parse_error: Some(callback),
+                ..Default::default()
+            }
+        )
+        .is_none());
+        assert!(*reported.lock().unwrap());
+    }
+
+    const CPM: &str = r#"{"type":"cpm","origin":"self","version":"1.1.3","source_uuid":"uuid1","timestamp":1574778515425,"message":{"protocol_version":1,"station_id":12345,"generation_delta_time":65535,"management_container":{"station_type":5,"reference_position":{"latitude":426263556,"longitude":-82492123,"altitude":800001},"confidence":{"position_confidence_ellipse":{"semi_major_confidence":4095,"semi_minor_confidence":4095,"semi_major_orientation":3601},"altitude":15}}}}"#;
+
+    fn cpm_publish(payload: &str) -> Publish {
+        Publish::new(
+            "default/outQueue/v2x/cpm/uuid1",
+            QoS::AtMostOnce,
+            payload.to_string(),
+            None,
+        )
+    }
+
+    #[test]
+    fn truncated_cpm_is_counted_as_such() {
+        let settings = RouterSettings::default();
+        assert!(deserialize::<Exchange>(cpm_publish(CPM), &settings).is_some());
+
+        let truncated = &CPM[..CPM.len() / 2];
+        assert!(deserialize::<Exchange>(cpm_publish(truncated), &settings).is_none());
+
+        assert_eq!(settings.stats.snapshot().truncated, 1);
+    }
+
+    #[test]
+    fn corrupted_cpm_is_not_counted_as_truncated() {
+        let settings = RouterSettings::default();
+        let corrupted = CPM.replacen("\"station_id\":", "\"station_id\"", 1);
+
+        assert!(deserialize::<Exchange>(cpm_publish(&corrupted), &settings).is_none());
+
+        assert_eq!(settings.stats.snapshot().truncated, 0);
+    }
+
+    #[test]
+    fn decompressed_payload_above_the_limit_is_rejected() {
+        let properties = PublishProperties {
+            user_properties: vec![(CONTENT_ENCODING.to_string(), GZIP.to_string())],
+            ..Default::default()
+        };
+        let compressed = gzip(CPM.as_bytes()).unwrap();
+        let publish = Publish::new(
+            "default/outQueue/v2x/cpm/uuid1",
+            QoS::AtMostOnce,
+            compressed.clone(),
+            Some(properties),
+        );
+
+        assert!(deserialize::<Exchange>(
+            publish.clone(),
+            &RouterSettings {
+                max_payload_size: Some(CPM.len()),
+                ..Default::default()
+            }
+        )
+        .is_some());
+        assert!(deserialize::<Exchange>(
+            publish,
+            &RouterSettings {
+                max_payload_size: Some(compressed.len()),
+                ..Default::default()
+            }
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn truncation_at_packet_size_limit_is_detected() {
+        let truncated = cpm_publish(&CPM[..CPM.len() - 10]);
+        let packet_size = truncated.topic.len() + truncated.payload.len();
+
+        assert!(packet_size_limit_hit(&truncated, Some(packet_size + 10)));
+        assert!(!packet_size_limit_hit(&truncated, Some(packet_size * 2)));
+        assert!(!packet_size_limit_hit(&truncated, None));
+    }
+
+    fn denm(cause: u8) -> Exchange {
+        let denm = DecentralizedEnvironmentalNotificationMessage {
+            situation_container: Some(SituationContainer {
+                event_type: EventType {
+                    cause,
+                    subcause: None,
+                },
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        *Exchange::new(String::from("rsu_1"), 0, Vec::new(), Message::DENM(denm))
+    }
+
+    const CONFIGURATION: &str = r#"
+[station]
+id="pipeline"
+type="mec_application"
+
+[mqtt]
+host="localhost"
+port=1883
+client_id="pipeline"
+
+[geo]
+prefix=default
+suffix=v2x
+
+[node]
+responsibility_enabled=false
+
+[telemetry]
+host=localhost
+port=4318
+"#;
+
+    #[test]
+    fn accident_denm_is_published_before_traffic_condition() {
+        let configuration =
+            Configuration::try_from(Ini::load_from_str(CONFIGURATION).unwrap()).unwrap();
+        let (exchange_sender, exchange_receiver) = unbounded();
+        for cause in [1, 2] {
+            let packet = Packet {
+                qos: QoS::AtMostOnce,
+                ..Packet::new(
+                    GeoTopic::from_str("default/outQueue/v2x/denm/rsu_1/0").unwrap(),
+                    denm(cause),
+                )
+            };
+            exchange_sender.send((packet, None)).unwrap();
+        }
+        drop(exchange_sender);
+
+        let (urgent_receiver, routine_receiver, _monitoring_receiver, handle) =
+            filter_thread(Arc::new(configuration), exchange_receiver);
+        handle.join().unwrap();
+
+        let cause_and_qos = |packet: Option<Packet<GeoTopic, Exchange>>| match packet
+            .map(|packet| (packet.payload.message, packet.qos))
+        {
+            Some((Message::DENM(denm), qos)) => {
+                denm.situation_container.map(|s| (s.event_type.cause, qos))
+            }
+            _ => None,
+        };
+        assert_eq!(
+            cause_and_qos(next_by_priority(&urgent_receiver, &routine_receiver)),
+            Some((2, QoS::AtLeastOnce))
+        );
+        assert_eq!(
+            cause_and_qos(next_by_priority(&urgent_receiver, &routine_receiver)),
+            Some((1, QoS::AtMostOnce))
+        );
+        assert!(next_by_priority(&urgent_receiver, &routine_receiver).is_none());
+    }
+
+    #[tokio::test]
+    async fn urgent_denm_is_published_with_qos_1() {
+        let configuration =
+            Configuration::try_from(Ini::load_from_str(CONFIGURATION).unwrap()).unwrap();
+        let (exchange_sender, exchange_receiver) = unbounded();
+        for cause in [1, 2] {
+            let packet = Packet::new(
+                GeoTopic::from_str("default/outQueue/v2x/denm/rsu_1/0").unwrap(),
+                denm(cause),
+            );
+            exchange_sender.send((packet, None)).unwrap();
+        }
+        drop(exchange_sender);
+        let (urgent_receiver, routine_receiver, _monitoring_receiver, handle) =
+            filter_thread(Arc::new(configuration), exchange_receiver);
+        handle.join().unwrap();
+        let (request_sender, request_receiver) = flume::bounded(10);
+        let mut client = MqttClient::from_client(AsyncClient::from_senders(request_sender));
+
+        mqtt_client_publish(urgent_receiver, routine_receiver, &mut client).await;
+
+        let qos = request_receiver
+            .drain()
+            .map(|request| match request {
+                Request::Publish(publish) => publish.qos,
+                other => panic!("Expected a publish request, got {:?}", other),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(qos, vec![QoS::AtLeastOnce, QoS::ExactlyOnce]);
+    }
+
+    fn area_packet() -> Packet<GeoTopic, Exchange> {
+        Packet::new(
+            GeoTopic::from_str("default/outQueue/v2x/cam/car_1/0").unwrap(),
+            serde_json::from_str::<Exchange>(CAM).unwrap(),
+        )
+    }
+
+    #[test]
+    fn null_exchange_channel_capacity_is_raised_to_one() {
+        let (sender, receiver) = exchange_channel::<u8>(Some(0));
+
+        assert_eq!(Some(1), receiver.capacity());
+        assert!(sender.try_send(1).is_ok());
+        assert!(sender.try_send(2).is_err());
+    }
+
+    #[test]
+    fn exchange_channel_is_unbounded_by_default() {
+        let (_, receiver) = exchange_channel::<u8>(None);
+
+        assert!(receiver.capacity().is_none());
+    }
+
+    fn pipeline_configuration(max_covering_tiles: Option<usize>) -> PipelineConfiguration {
+        PipelineConfiguration {
+            max_covering_tiles,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn area_straddling_four_tiles_is_published_on_four_topics() {
+        let area = Polygon::from_str("1,-1;1,1;-1,1;-1,-1").unwrap();
+
+        let packets = covering_packets(&pipeline_configuration(Some(9)), area_packet(), &area);
+
+        let topics = packets
+            .iter()
+            .map(|packet| packet.topic.to_string())
+            .collect::<HashSet<String>>();
+        assert_eq!(packets.len(), 4);
+        assert_eq!(
+            topics,
+            HashSet::from(
+                ["0", "1", "2", "3"].map(|tile| format!("default/outQueue/v2x/cam/car_1/{}", tile))
+            )
+        );
+    }
+
+    #[test]
+    fn area_spanning_too_many_tiles_is_published_as_is() {
+        let area = Polygon::from_str("1,-1;1,1;-1,1;-1,-1").unwrap();
+
+        let packets = covering_packets(&pipeline_configuration(Some(2)), area_packet(), &area);
+
+        assert_eq!(packets, vec![area_packet()]);
+    }
+
+    #[test]
+    fn perceived_objects_area_is_published_on_each_tile() {
+        let cpm = CollectivePerceptionMessage {
+            management_container: ManagementContainer {
+                station_type: 15,
+                reference_position: ReferencePosition {
+                    latitude: 10,
+                    longitude: 10,
+                    altitude: 0,
+                },
+                ..Default::default()
+            },
+            perceived_object_container: vec![PerceivedObject {
+                x_distance: -500,
+                y_distance: -500,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let packet = Packet::new(
+            GeoTopic::from_str("default/outQueue/v2x/cpm/car_1/0").unwrap(),
+            *Exchange::new(String::from("car_1"), 0, Vec::new(), Message::CPM(cpm)),
+        );
+        let area = packet
+            .payload
+            .message
+            .covering_area()
+            .expect("CPM with objects must have an area");
+
+        let packets = covering_packets(&pipeline_configuration(Some(9)), packet, &area);
+
+        assert_eq!(packets.len(), 4);
+    }
+
+    #[test]
+    fn too_deep_topic_is_published_as_is() {
+        let area = Polygon::from_str("1,-1;1,1;-1,1;-1,-1").unwrap();
+        let packet = Packet::new(
+            GeoTopic::from_str(&format!(
+                "default/outQueue/v2x/cam/car_1/{}",
+                ["0"; 60].join("/")
+            ))
+            .unwrap(),
+            serde_json::from_str::<Exchange>(CAM).unwrap(),
+        );
+
+        let packets = covering_packets(&pipeline_configuration(Some(9)), packet.clone(), &area);
+
+        assert_eq!(packets, vec![packet]);
+    }
+
+    #[test]
+    fn no_fan_out_by_default() {
+        let area = Polygon::from_str("1,-1;1,1;-1,1;-1,-1").unwrap();
+
+        let packets = covering_packets(&pipeline_configuration(None), area_packet(), &area);
+
+        assert_eq!(packets, vec![area_packet()]);
+    }
+
+    #[test]
+    fn subscriptions_are_restricted_to_configured_tiles() {
+        let topics = [
+            "default/outQueue/v2x/cam",
+            "default/outQueue/v2x/denm",
+            "default/outQueue/info",
+        ]
+        .map(GeoTopic::from);
+        let mut configuration =
+            Configuration::try_from(Ini::load_from_str(CONFIGURATION).unwrap()).unwrap();
+        configuration.pipeline.subscription_filters =
+            SubscriptionFilters::from_str("cam:1202:6").unwrap();
+
+        assert_eq!(
+            subscription_topics(&topics, &configuration),
+            vec![
+                "default/outQueue/v2x/cam/+/1/2/0/2/+/+",
+                "default/outQueue/v2x/denm/+/#",
+                "default/outQueue/info/broker",
+            ]
+        );
+    }
+
+    #[test]
+    fn all_info_are_subscribed_to() {
+        let mut configuration =
+            Configuration::try_from(Ini::load_from_str(CONFIGURATION).unwrap()).unwrap();
+        configuration.pipeline.subscribe_all_info = true;
+
+        assert_eq!(
+            subscription_topics(&[GeoTopic::from("default/outQueue/info")], &configuration),
+            vec!["default/outQueue/info/+"]
+        );
+    }
+
+    #[test]
+    fn subscriptions_follow_the_topic_template() {
+        let template = TopicTemplate::from_str("{project}/{type}/{queue}/{uuid}/{geo}").unwrap();
+        let topics = ["default/cam/outQueue", "default/info/outQueue"]
+            .map(|topic| template.parse(topic).unwrap());
+        let mut configuration =
+            Configuration::try_from(Ini::load_from_str(CONFIGURATION).unwrap()).unwrap();
+        configuration.geo.topic_template = Some(template);
+        configuration.pipeline.subscription_filters =
+            SubscriptionFilters::from_str("cam:1202").unwrap();
+
+        assert_eq!(
+            subscription_topics(&topics, &configuration),
+            vec![
+                "default/cam/outQueue/+/1/2/0/2/#",
+                "default/info/outQueue/broker",
+            ]
+        );
+    }
+
+    #[test]
+    fn neighbour_information_is_recorded_apart_from_the_broker_one() {
+        let mut node_configuration = NodeConfiguration::default();
+        for (uuid, instance_id) in [
+            ("broker", "broker_gw_3"),
+            ("node_1", "node_1"),
+            ("node_2", "node_2"),
+        ] {
+            update_node_configuration(
+                &mut node_configuration,
+                Packet::new(
+                    GeoTopic::from(format!("default/outQueue/info/{}", uuid).as_str()),
+                    Information::new(instance_id, "local", 60, now()),
+                ),
+            );
+        }
+
+        assert_eq!(
+            node_configuration.gateway_component_name(),
+            Some("broker_gw_3")
+        );
+        assert_eq!(
+            node_configuration
+                .neighbours()
+                .keys()
+                .map(String::as_str)
+                .collect::<HashSet<_>>(),
+            HashSet::from(["node_1", "node_2"])
+        );
+    }
 }
@@ -17,12 +17,14 @@ use crate::client::configuration::geo_configuration::GeoConfiguration;
 #[cfg(feature = "telemetry")]
 use crate::client::configuration::telemetry_configuration::TelemetryConfiguration;
 use crate::client::configuration::{get_optional_from_section, Configuration, MqttOptionWrapper};
+use crate::clock::SystemClock;
 #[cfg(feature = "mobility")]
 use {
     crate::client::configuration::{
         mobility_configuration::MobilityConfiguration,
         node_configuration::{NodeConfiguration, NODE_SECTION},
         pick_mandatory_section,
+        pipeline_configuration::{PipelineConfiguration, PIPELINE_SECTION},
     },
     std::sync::RwLock,
 };
@@ -34,12 +36,13 @@ use crate::client::configuration::configuration_error::ConfigurationError::{
     BootstrapFailure, MissingMandatoryField,
 };
 use ini::{Ini, Properties};
-use log::{debug, error, info, trace, warn};
 use reqwest::Url;
 use rumqttc::v5::MqttOptions;
 use serde_json::{json, Value};
 use std::collections::HashMap;
 use std::ops::Deref;
+use std::sync::Arc;
+use tracing::{debug, error, info, trace, warn};
 
 mod bootstrap_error;
 
@@ -129,6 +132,12 @@ pub async fn bootstrap(mut ini: Ini) -> Result<Configuration, ConfigurationError
                     Some(properties) => Some(RwLock::new(NodeConfiguration::try_from(properties)?)),
                     None => None,
                 },
+                #[cfg(feature = "mobility")]
+                pipeline: match ini.section(Some(PIPELINE_SECTION)) {
+                    Some(properties) => PipelineConfiguration::try_from(properties)?,
+                    None => PipelineConfiguration::default(),
+                },
+                clock: Arc::new(SystemClock),
                 custom_settings: Some(ini),
             })
         }
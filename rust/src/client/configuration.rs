@@ -16,14 +16,17 @@ use std::any::type_name;
 
 use std::ops::Deref;
 use std::str::FromStr;
+use std::sync::Arc;
 #[cfg(feature = "mobility")]
 use std::sync::RwLock;
+use std::time::Duration;
 
 use crate::client::configuration::configuration_error::ConfigurationError::{
-    FieldNotFound, MissingMandatoryField, MissingMandatorySection, NoCustomSettings, NoPassword,
-    TypeError,
+    FieldNotFound, InvalidValue, MissingMandatoryField, MissingMandatorySection, NoCustomSettings,
+    NoPassword, SecretUnavailable, TypeError,
 };
-use crate::transport::mqtt::configure_transport;
+use crate::clock::{Clock, SystemClock};
+use crate::transport::mqtt::{broker_address, configure_transport};
 
 #[cfg(feature = "telemetry")]
 use crate::client::configuration::telemetry_configuration::{
@@ -32,8 +35,11 @@ use crate::client::configuration::telemetry_configuration::{
 
 #[cfg(feature = "mobility")]
 use crate::client::configuration::{
-    mobility_configuration::{MobilityConfiguration, STATION_SECTION},
+    mobility_configuration::{
+        MobilityConfiguration, INSTANCE_ID_PLACEHOLDER, PREFIX_PLACEHOLDER, STATION_SECTION,
+    },
     node_configuration::{NodeConfiguration, NODE_SECTION},
+    pipeline_configuration::{PipelineConfiguration, PIPELINE_SECTION},
 };
 
 #[cfg(feature = "geo_routing")]
@@ -47,10 +53,16 @@ pub mod geo_configuration;
 pub mod mobility_configuration;
 #[cfg(feature = "mobility")]
 pub mod node_configuration;
+#[cfg(feature = "mobility")]
+pub mod pipeline_configuration;
 #[cfg(feature = "telemetry")]
 pub mod telemetry_configuration;
 
 const MQTT_SECTION: &str = "mqtt";
+const USERNAME_KEYS: SecretKeys = ("username", "username_file", "username_env");
+const PASSWORD_KEYS: SecretKeys = ("password", "password_file", "password_env");
+/// Shortest keep alive accepted by the MQTT client, in seconds
+const MIN_KEEP_ALIVE: u64 = 5;
 
 pub struct Configuration {
     pub mqtt_options: MqttOptions,
@@ -62,21 +74,54 @@ pub struct Configuration {
     pub mobility: MobilityConfiguration,
     #[cfg(feature = "mobility")]
     pub node: Option<RwLock<NodeConfiguration>>,
+    #[cfg(feature = "mobility")]
+    pub pipeline: PipelineConfiguration,
+    /// Time source to use instead of [now][1], the system clock by default
+    ///
+    /// [1]: crate::now
+    pub clock: Arc<dyn Clock>,
     pub(crate) custom_settings: Option<Ini>,
 }
 
 impl Configuration {
+    /// Returns the name identifying this client, e.g. as source of the messages it publishes
+    ///
+    /// The name follows the station's [name format][1], `{prefix}_{instance_id}` by default like
+    /// the Python clients, where the prefix is the MQTT client id and the instance id is derived
+    /// from the node's gateway component name or is the station id without node configuration
+    ///
+    /// The modifier distinguishes several instances running with the same configuration: with a
+    /// node configuration it selects the derived [station id][2], or is appended to the station
+    /// id as a suffix if it is too large for it, otherwise it is appended to the station id as
+    /// a suffix
+    ///
+    /// [1]: MobilityConfiguration::name_format
+    /// [2]: NodeConfiguration::station_id
     #[cfg(feature = "mobility")]
     pub fn component_name(&self, modifier: Option<u32>) -> String {
-        let station_id: String = match &self.node {
-            Some(node_configuration) => node_configuration
-                .read()
-                .unwrap()
-                .station_id(modifier)
-                .to_string(),
-            None => self.mobility.station_id.clone(),
+        let instance_id: String = match &self.node {
+            Some(node_configuration) => {
+                let node_configuration = node_configuration.read().unwrap();
+                node_configuration
+                    .station_id(modifier)
+                    .map(|station_id| station_id.to_string())
+                    .unwrap_or_else(|| {
+                        format!(
+                            "{}_{}",
+                            node_configuration.station_id(None).unwrap_or_default(),
+                            modifier.unwrap_or_default()
+                        )
+                    })
+            }
+            None => match modifier {
+                Some(modifier) => format!("{}_{}", self.mobility.station_id, modifier),
+                None => self.mobility.station_id.clone(),
+            },
         };
-        format!("{}_{}", self.mqtt_options.client_id(), station_id)
+        self.mobility
+            .name_format
+            .replace(PREFIX_PLACEHOLDER, &self.mqtt_options.client_id())
+            .replace(INSTANCE_ID_PLACEHOLDER, &instance_id)
     }
 
     #[cfg(feature = "mobility")]
@@ -84,6 +129,10 @@ impl Configuration {
         self.node = Some(RwLock::new(node_configuration));
     }
 
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+
     pub fn set_mqtt_credentials(&mut self, username: &str, password: &str) {
         self.mqtt_options.set_credentials(username, password);
     }
@@ -93,8 +142,8 @@ impl Configuration {
         section: Option<&'static str>,
         key: &'static str,
     ) -> Result<T, ConfigurationError> {
-        if self.custom_settings.is_some() {
-            match get_optional_field(section, key, self.custom_settings.as_ref().unwrap()) {
+        if let Some(custom_settings) = self.custom_settings.as_ref() {
+            match get_optional_field(section, key, custom_settings) {
                 Ok(result) => {
                     if let Some(value) = result {
                         Ok(value)
@@ -128,30 +177,59 @@ impl TryFrom<&Properties> for MqttOptionWrapper {
 
     fn try_from(properties: &Properties) -> Result<Self, Self::Error> {
         let section = (MQTT_SECTION, properties);
+        let use_tls = get_optional_from_section::<bool>("use_tls", properties)
+            .unwrap_or_default()
+            .unwrap_or_default();
+        let use_websocket = get_optional_from_section::<bool>("use_websocket", properties)
+            .unwrap_or_default()
+            .unwrap_or_default();
+
+        let tls_server_name =
+            get_optional_from_section::<String>("tls_server_name", properties).unwrap_or_default();
+
         let mut mqtt_options = MqttOptions::new(
             get_mandatory_from_section::<String>("client_id", section)?,
-            get_mandatory_from_section::<String>("host", section)?,
+            broker_address(
+                get_mandatory_from_section::<String>("host", section)?,
+                use_tls,
+                use_websocket,
+                tls_server_name.as_deref(),
+            ),
             get_mandatory_from_section::<u16>("port", section)?,
         );
 
-        if let Ok(Some(username)) = get_optional_from_section::<String>("username", section.1) {
-            if let Ok(Some(password)) = get_optional_from_section::<String>("password", section.1) {
+        if let Some(username) = get_secret(USERNAME_KEYS, section.1)? {
+            if let Some(password) = get_secret(PASSWORD_KEYS, section.1)? {
                 mqtt_options.set_credentials(username, password);
             } else {
                 return Err(NoPassword);
             }
         }
 
-        // TODO manage other optional
+        if let Some(max_packet_size) =
+            get_optional_from_section::<u32>("max_packet_size", section.1)?
+        {
+            mqtt_options.set_max_packet_size(Some(max_packet_size));
+        }
 
-        let use_tls = get_optional_from_section::<bool>("use_tls", properties)
-            .unwrap_or_default()
-            .unwrap_or_default();
-        let use_websocket = get_optional_from_section::<bool>("use_websocket", properties)
-            .unwrap_or_default()
-            .unwrap_or_default();
+        if let Some(keep_alive) = get_optional_from_section::<u64>("keep_alive", section.1)? {
+            if keep_alive < MIN_KEEP_ALIVE {
+                return Err(InvalidValue(
+                    "keep_alive",
+                    format!("must be at least {} seconds", MIN_KEEP_ALIVE),
+                ));
+            }
+            mqtt_options.set_keep_alive(Duration::from_secs(keep_alive));
+        }
 
-        configure_transport(use_tls, use_websocket, &mut mqtt_options);
+        if let Some(clean_session) = get_optional_from_section::<bool>("clean_session", section.1)?
+        {
+            mqtt_options.set_clean_start(clean_session);
+        }
+
+        // TODO manage other optional
+
+        configure_transport(use_tls, use_websocket, tls_server_name, &mut mqtt_options);
 
         Ok(MqttOptionWrapper(mqtt_options))
     }
@@ -190,6 +268,36 @@ pub(crate) fn get_optional_from_section<T: FromStr>(
     }
 }
 
+/// Keys a secret can be read from: the value itself, a file holding it or an environment variable
+/// holding it
+type SecretKeys = (&'static str, &'static str, &'static str);
+
+/// Reads a secret from the first key set among its value, file and environment variable keys
+///
+/// The file content is trimmed of its trailing new line; errors never include the secret
+fn get_secret(
+    keys: SecretKeys,
+    properties: &Properties,
+) -> Result<Option<String>, ConfigurationError> {
+    let (value_key, file_key, env_key) = keys;
+
+    if let Some(value) = get_optional_from_section::<String>(value_key, properties)? {
+        Ok(Some(value))
+    } else if let Some(path) = get_optional_from_section::<String>(file_key, properties)? {
+        match std::fs::read_to_string(&path) {
+            Ok(content) => Ok(Some(content.trim_end_matches(['\r', '\n']).to_string())),
+            Err(e) => Err(SecretUnavailable(file_key, format!("'{}': {}", path, e))),
+        }
+    } else if let Some(variable) = get_optional_from_section::<String>(env_key, properties)? {
+        match std::env::var(&variable) {
+            Ok(value) => Ok(Some(value)),
+            Err(e) => Err(SecretUnavailable(env_key, format!("'{}': {}", variable, e))),
+        }
+    } else {
+        Ok(None)
+    }
+}
+
 pub(crate) fn get_mandatory_field<T: FromStr>(
     section: Option<&'static str>,
     field: &'static str,
@@ -265,6 +373,12 @@ impl TryFrom<Ini> for Configuration {
                 Some(properties) => Some(RwLock::new(NodeConfiguration::try_from(properties)?)),
                 None => None,
             },
+            #[cfg(feature = "mobility")]
+            pipeline: match ini_config.section(Some(PIPELINE_SECTION)) {
+                Some(properties) => PipelineConfiguration::try_from(properties)?,
+                None => PipelineConfiguration::default(),
+            },
+            clock: Arc::new(SystemClock),
             custom_settings: Some(ini_config),
         })
     }
@@ -272,8 +386,14 @@ impl TryFrom<Ini> for Configuration {
 
 #[cfg(test)]
 mod tests {
-    use crate::client::configuration::{get_optional_field, pick_mandatory_section, Configuration};
+    use crate::client::configuration::configuration_error::ConfigurationError;
+    use crate::client::configuration::{
+        get_optional_field, pick_mandatory_section, Configuration, MqttOptionWrapper,
+    };
     use ini::Ini;
+    use rumqttc::v5::MqttOptions;
+    use std::ops::Deref;
+    use std::time::Duration;
 
     #[cfg(feature = "telemetry")]
     use crate::client::configuration::telemetry_configuration;
@@ -325,7 +445,7 @@ port=1883
 client_id="com_myapplication"
 "#;
 
-    #[cfg(feature = "mobility")]
+    #[cfg(feature = "geo_routing")]
     const MINIMAL_GEO_ROUTING_CONFIGURATION: &str = r#"
 [station]
 id="com_myapplication"
@@ -495,4 +615,303 @@ port=5418
         let _ = Configuration::try_from(ini)
             .expect("Failed to create Configuration with minimal mandatory sections and fields");
     }
+
+    #[test]
+    fn mqtt_max_packet_size_is_read() {
+        let ini = Ini::load_from_str(
+            r#"
+[mqtt]
+host="localhost"
+port=1883
+client_id="com_myapplication"
+max_packet_size=256000
+"#,
+        )
+        .expect("Ini creation should not fail");
+
+        let mqtt_options = MqttOptionWrapper::try_from(ini.section(Some("mqtt")).unwrap())
+            .expect("Failed to create MqttOptions");
+
+        assert_eq!(mqtt_options.max_packet_size(), Some(256_000));
+    }
+
+    #[test]
+    fn mqtt_max_packet_size_is_unset_by_default() {
+        let ini = Ini::load_from_str(MINIMAL_FEATURELESS_CONFIGURATION)
+            .expect("Ini creation should not fail");
+
+        let mqtt_options = MqttOptionWrapper::try_from(ini.section(Some("mqtt")).unwrap())
+            .expect("Failed to create MqttOptions");
+
+        assert!(mqtt_options.max_packet_size().is_none());
+    }
+
+    #[test]
+    fn mqtt_keep_alive_and_clean_session_are_read() {
+        let ini = Ini::load_from_str(
+            r#"
+[mqtt]
+host="localhost"
+port=1883
+client_id="com_myapplication"
+keep_alive=10
+clean_session=false
+"#,
+        )
+        .expect("Ini creation should not fail");
+
+        let mqtt_options = MqttOptionWrapper::try_from(ini.section(Some("mqtt")).unwrap())
+            .expect("Failed to create MqttOptions");
+
+        assert_eq!(mqtt_options.keep_alive(), Duration::from_secs(10));
+        assert!(!mqtt_options.clean_start());
+    }
+
+    #[test]
+    fn mqtt_keep_alive_and_clean_session_default_to_client_ones() {
+        let ini = Ini::load_from_str(MINIMAL_FEATURELESS_CONFIGURATION)
+            .expect("Ini creation should not fail");
+        let default = MqttOptions::new("com_myapplication", "localhost", 1883);
+
+        let mqtt_options = MqttOptionWrapper::try_from(ini.section(Some("mqtt")).unwrap())
+            .expect("Failed to create MqttOptions");
+
+        assert_eq!(mqtt_options.keep_alive(), default.keep_alive());
+        assert_eq!(mqtt_options.clean_start(), default.clean_start());
+    }
+
+    #[test]
+    fn mqtt_too_short_keep_alive_is_err() {
+        let ini = Ini::load_from_str(
+            r#"
+[mqtt]
+host="localhost"
+port=1883
+client_id="com_myapplication"
+keep_alive=2
+"#,
+        )
+        .expect("Ini creation should not fail");
+
+        assert!(MqttOptionWrapper::try_from(ini.section(Some("mqtt")).unwrap()).is_err());
+    }
+
+    #[cfg(feature = "mobility")]
+    fn node_instance(gateway_component_name: &str) -> Configuration {
+        let ini = Ini::load_from_str(&EXHAUSTIVE_CUSTOM_INI_CONFIG.replace(
+            "[node]\n",
+            &format!(
+                "[node]\ngateway_component_name={}\n",
+                gateway_component_name
+            ),
+        ))
+        .expect("Ini creation should not fail");
+
+        Configuration::try_from(ini).expect("Failed to create Configuration")
+    }
+
+    #[test]
+    #[cfg(feature = "mobility")]
+    fn node_instances_have_distinct_names() {
+        let first = node_instance("central_com_myapplication_1");
+        let second = node_instance("central_com_myapplication_2");
+
+        assert_eq!(first.component_name(None), "com_myapplication_10001");
+        assert_eq!(second.component_name(None), "com_myapplication_10002");
+        let gateway_name = |configuration: &Configuration| {
+            configuration
+                .node
+                .as_ref()
+                .unwrap()
+                .read()
+                .unwrap()
+                .gateway_component_name()
+                .map(String::from)
+        };
+        assert_ne!(gateway_name(&first), gateway_name(&second));
+    }
+
+    #[test]
+    #[cfg(feature = "mobility")]
+    fn modifier_is_appended_without_node() {
+        let ini = Ini::load_from_str(
+            &EXHAUSTIVE_CUSTOM_INI_CONFIG.replace("[node]\nresponsibility_enabled=true\n", ""),
+        )
+        .expect("Ini creation should not fail");
+        let configuration = Configuration::try_from(ini).expect("Failed to create Configuration");
+
+        assert_eq!(
+            configuration.component_name(None),
+            "com_myapplication_com_myapplication"
+        );
+        assert_eq!(
+            configuration.component_name(Some(1)),
+            "com_myapplication_com_myapplication_1"
+        );
+        assert_ne!(
+            configuration.component_name(Some(1)),
+            configuration.component_name(Some(2))
+        );
+    }
+
+    #[cfg(feature = "mobility")]
+    fn configuration_with_name_format(
+        name_format: &str,
+    ) -> Result<Configuration, ConfigurationError> {
+        let ini = Ini::load_from_str(&EXHAUSTIVE_CUSTOM_INI_CONFIG.replace(
+            "[station]\n",
+            &format!("[station]\nname_format={}\n", name_format),
+        ))
+        .expect("Ini creation should not fail");
+
+        Configuration::try_from(ini)
+    }
+
+    #[test]
+    #[cfg(feature = "mobility")]
+    fn name_format_orders_instance_id_and_prefix() {
+        let configuration = configuration_with_name_format("{instance_id}_{prefix}_edge")
+            .expect("Failed to create Configuration");
+
+        assert_eq!(
+            configuration.component_name(None),
+            "10000_com_myapplication_edge"
+        );
+        assert_eq!(
+            configuration.component_name(Some(3)),
+            "40000_com_myapplication_edge"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "mobility")]
+    fn default_name_format_matches_python_names() {
+        let configuration = node_instance("central_com_myapplication_12");
+
+        assert_eq!(
+            configuration.component_name(None),
+            "com_myapplication_10012"
+        );
+        assert_eq!(
+            configuration.component_name(Some(1)),
+            "com_myapplication_20012"
+        );
+        assert_eq!(
+            configuration.component_name(Some(u32::MAX)),
+            "com_myapplication_10012_4294967295"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "mobility")]
+    fn name_format_without_instance_id_is_err() {
+        assert!(matches!(
+            configuration_with_name_format("{prefix}_edge"),
+            Err(ConfigurationError::InvalidValue("name_format", _))
+        ));
+    }
+
+    fn mqtt_options_from(credentials: &str) -> Result<MqttOptions, ConfigurationError> {
+        let ini = Ini::load_from_str(&format!(
+            "{}{}",
+            MINIMAL_FEATURELESS_CONFIGURATION, credentials
+        ))
+        .expect("Ini creation should not fail");
+
+        MqttOptionWrapper::try_from(ini.section(Some("mqtt")).unwrap())
+            .map(|wrapper| wrapper.deref().clone())
+    }
+
+    #[test]
+    fn mqtt_password_is_read_from_file() {
+        let path = std::env::temp_dir().join(format!("its-client-password-{}", std::process::id()));
+        std::fs::write(&path, "s3cr3t\n").expect("Failed to write password file");
+
+        let mqtt_options = mqtt_options_from(&format!(
+            "username=\"login\"\npassword_file=\"{}\"\n",
+            path.display()
+        ));
+        std::fs::remove_file(&path).expect("Failed to remove password file");
+
+        assert_eq!(
+            mqtt_options.unwrap().credentials(),
+            Some((String::from("login"), String::from("s3cr3t")))
+        );
+    }
+
+    #[test]
+    fn mqtt_password_is_read_from_env() {
+        std::env::set_var("ITS_CLIENT_TEST_MQTT_PASSWORD", "s3cr3t");
+
+        let mqtt_options = mqtt_options_from(
+            "username=\"login\"\npassword_env=\"ITS_CLIENT_TEST_MQTT_PASSWORD\"\n",
+        );
+
+        assert_eq!(
+            mqtt_options.unwrap().credentials(),
+            Some((String::from("login"), String::from("s3cr3t")))
+        );
+    }
+
+    #[test]
+    fn mqtt_password_value_takes_precedence() {
+        let mqtt_options = mqtt_options_from(
+            "username=\"login\"\npassword=\"inline\"\npassword_file=\"/nonexistent\"\n",
+        );
+
+        assert_eq!(
+            mqtt_options.unwrap().credentials(),
+            Some((String::from("login"), String::from("inline")))
+        );
+    }
+
+    #[test]
+    fn unreadable_password_file_is_err() {
+        let result = mqtt_options_from(
+            "username=\"login\"\npassword_file=\"/nonexistent/its-client-password\"\n",
+        );
+
+        match result {
+            Err(ConfigurationError::SecretUnavailable(key, _)) => assert_eq!(key, "password_file"),
+            _ => panic!("Expected the password file to be unavailable"),
+        }
+    }
+
+    #[test]
+    fn missing_env_secret_is_err_without_value() {
+        let error = mqtt_options_from(
+            "username=\"login\"\npassword_env=\"ITS_CLIENT_TEST_UNSET_PASSWORD\"\n",
+        )
+        .unwrap_err();
+
+        assert!(matches!(
+            error,
+            ConfigurationError::SecretUnavailable("password_env", _)
+        ));
+    }
+
+    #[test]
+    fn secret_file_content_is_not_in_error() {
+        let path = std::env::temp_dir().join(format!("its-client-invalid-{}", std::process::id()));
+        std::fs::write(&path, b"s3cr3t\xff").expect("Failed to write password file");
+
+        let error = mqtt_options_from(&format!(
+            "username=\"login\"\npassword_file=\"{}\"\n",
+            path.display()
+        ))
+        .unwrap_err();
+        std::fs::remove_file(&path).expect("Failed to remove password file");
+
+        let message = format!("{} {:?}", error, error);
+        assert!(message.contains(&path.display().to_string()));
+        assert!(!message.contains("s3cr3t"));
+    }
+
+    #[test]
+    fn username_without_password_is_err() {
+        assert!(matches!(
+            mqtt_options_from("username=\"login\"\n"),
+            Err(ConfigurationError::NoPassword)
+        ));
+    }
 }
@@ -19,6 +19,8 @@ pub enum ConfigurationError {
     FieldNotFound(&'static str),
     #[error("Cannot parse '{0}' due to invalid file type")]
     InvalidFileType(String),
+    #[error("Invalid value of field '{0}': {1}")]
+    InvalidValue(&'static str, String),
     #[error("Configuration missing mandatory field {0} in section {1}")]
     MissingMandatoryField(&'static str, &'static str),
     #[error("Configuration missing mandatory section: {0}")]
@@ -31,4 +33,6 @@ pub enum ConfigurationError {
     TypeError(&'static str, &'static str),
     #[error("Username provided with no password")]
     NoPassword,
+    #[error("Could not read secret of field '{0}': {1}")]
+    SecretUnavailable(&'static str, String),
 }
@@ -1,24 +1,41 @@
 use crate::client::configuration::configuration_error::ConfigurationError;
-use crate::client::configuration::get_mandatory_from_section;
+use crate::client::configuration::get_optional_from_section;
+use crate::transport::mqtt::geo_topic::topic_template::TopicTemplate;
 use ini::Properties;
 
 pub(crate) const GEO_SECTION: &str = "geo";
 
+/// Project used as first topic level when no prefix is configured
+pub const DEFAULT_PREFIX: &str = "5GCroCo";
+/// Server used as topic level after the queue when no suffix is configured
+pub const DEFAULT_SUFFIX: &str = "v2x";
+
 /// Configuration of the geo_routing feature
 ///
 /// Contains the information to build [GeoTopic][1]s
 ///
+/// The prefix (project) and the suffix (server) default to [DEFAULT_PREFIX] and
+/// [DEFAULT_SUFFIX] if not set
+///
 /// Example
 /// ```ini
 /// [geo]
 /// prefix=myProject
 /// suffix=my_domain
+/// ; Optional, layout of the topic levels
+/// topic_template={project}/{type}/{queue}/{uuid}/{geo}
 /// ```
 ///
+/// The topics created from this configuration follow its template; received topics are parsed
+/// with it by the [pipeline][2], or by a [router][3] it is set on
+///
 /// [1]: crate::transport::mqtt::geo_topic::GeoTopic
+/// [2]: crate::client::application::pipeline::run
+/// [3]: crate::transport::mqtt::mqtt_router::MqttRouter::set_topic_template
 pub struct GeoConfiguration {
     pub prefix: String,
     pub suffix: String,
+    pub topic_template: Option<TopicTemplate>,
 }
 
 impl TryFrom<&Properties> for GeoConfiguration {
@@ -26,8 +43,50 @@ impl TryFrom<&Properties> for GeoConfiguration {
 
     fn try_from(properties: &Properties) -> Result<Self, Self::Error> {
         Ok(Self {
-            prefix: get_mandatory_from_section::<String>("prefix", ("geo", properties))?,
-            suffix: get_mandatory_from_section::<String>("suffix", ("geo", properties))?,
+            prefix: get_optional_from_section::<String>("prefix", properties)?
+                .unwrap_or_else(|| DEFAULT_PREFIX.to_string()),
+            suffix: get_optional_from_section::<String>("suffix", properties)?
+                .unwrap_or_else(|| DEFAULT_SUFFIX.to_string()),
+            topic_template: get_optional_from_section::<TopicTemplate>(
+                "topic_template",
+                properties,
+            )?,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::client::configuration::geo_configuration::GeoConfiguration;
+    use crate::transport::mqtt::geo_topic::GeoTopic;
+    use ini::Ini;
+
+    fn project_base(ini: &str) -> String {
+        let ini = Ini::load_from_str(ini).expect("Failed to load string as Ini");
+        let configuration = GeoConfiguration::try_from(ini.section(Some("geo")).unwrap())
+            .expect("Failed to create GeoConfiguration from config");
+
+        GeoTopic::info(&configuration, "com_myapplication_1").project_base()
+    }
+
+    #[test]
+    fn configured_project_and_server_are_used() {
+        assert_eq!(
+            project_base("[geo]\nprefix=myProject\nsuffix=my_domain"),
+            "myProject/inQueue/my_domain"
+        );
+    }
+
+    #[test]
+    fn project_and_server_fall_back_to_defaults() {
+        assert_eq!(project_base("[geo]"), "5GCroCo/inQueue/v2x");
+    }
+
+    #[test]
+    fn project_base_follows_the_template() {
+        assert_eq!(
+            project_base("[geo]\nprefix=myProject\ntopic_template={project}/{type}/{queue}/{uuid}"),
+            "myProject/inQueue"
+        );
+    }
+}
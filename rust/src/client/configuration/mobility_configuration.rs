@@ -12,16 +12,35 @@
 use ini::Properties;
 
 use crate::client::configuration::configuration_error::ConfigurationError;
-use crate::client::configuration::get_mandatory_from_section;
+use crate::client::configuration::{get_mandatory_from_section, get_optional_from_section};
 
 pub(crate) const STATION_SECTION: &str = "station";
 
 const STATION_ID_FIELD: &str = "id";
 const STATION_TYPE_FIELD: &str = "type";
+const NAME_FORMAT_FIELD: &str = "name_format";
+
+/// Placeholder of the [name format][1] replaced by the MQTT client id
+///
+/// [1]: MobilityConfiguration::name_format
+pub const PREFIX_PLACEHOLDER: &str = "{prefix}";
+/// Placeholder of the [name format][1] replaced by the instance id
+///
+/// [1]: MobilityConfiguration::name_format
+pub const INSTANCE_ID_PLACEHOLDER: &str = "{instance_id}";
+/// Name format producing the same names as the Python clients
+pub const DEFAULT_NAME_FORMAT: &str = "{prefix}_{instance_id}";
 
 pub struct MobilityConfiguration {
     pub station_id: String,
     pub station_type: String,
+    /// Format of the [component name][1], e.g. `{prefix}_{instance_id}_edge`
+    ///
+    /// `{prefix}` is replaced by the MQTT client id and `{instance_id}` by the node's instance
+    /// id or the station id; anything else, like a suffix, is kept as is
+    ///
+    /// [1]: crate::client::configuration::Configuration::component_name
+    pub name_format: String,
 }
 
 impl TryFrom<&Properties> for MobilityConfiguration {
@@ -37,8 +56,20 @@ impl TryFrom<&Properties> for MobilityConfiguration {
                 STATION_TYPE_FIELD,
                 (STATION_SECTION, properties),
             )?,
+            name_format: get_optional_from_section::<String>(NAME_FORMAT_FIELD, properties)?
+                .unwrap_or_else(|| DEFAULT_NAME_FORMAT.to_string()),
         };
 
+        if !s.name_format.contains(INSTANCE_ID_PLACEHOLDER) {
+            return Err(ConfigurationError::InvalidValue(
+                NAME_FORMAT_FIELD,
+                format!(
+                    "'{}' does not contain {}, instances would not be distinguished",
+                    s.name_format, INSTANCE_ID_PLACEHOLDER
+                ),
+            ));
+        }
+
         Ok(s)
     }
 }
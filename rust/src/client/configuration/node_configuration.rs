@@ -12,30 +12,44 @@
 use crate::client::configuration::configuration_error::ConfigurationError;
 use crate::client::configuration::{get_mandatory_from_section, get_optional_from_section};
 use crate::exchange::message::information::Information;
+use crate::mobility::geofence::Polygon;
+use crate::mobility::position::Position;
 use crate::mobility::quadtree;
 use crate::mobility::quadtree::quadkey::Quadkey;
 use crate::mobility::quadtree::Quadtree;
 use ini::Properties;
-use log::{error, info, warn};
+use std::collections::HashMap;
 use std::str::FromStr;
+use tracing::{error, info, warn};
 
 pub(crate) const NODE_SECTION: &str = "node";
 
+/// Largest instance id station ids can be derived from, see [NodeConfiguration::station_id]
+const MAX_INSTANCE_ID: u32 = 9_999;
+
 /// Configuration of the node the client is hosted on
 ///
 /// This is the case for backend running application that would consume and/or produce messages
 /// from the broker
 /// Clients running in on-board units or road-side units might not use this
 ///
-/// TODO add the information's of the neighbourhood
+/// The information of the neighbouring instances is kept when [subscribing to all of them][1]
+///
+/// [1]: crate::client::configuration::pipeline_configuration::PipelineConfiguration::subscribe_all_info
 //  TODO if you're a central node, remove from your Region Of Responsibility the Regions Of Responsibility of the neighbourhood
 #[derive(Default)]
 pub struct NodeConfiguration {
     pub responsibility_enabled: bool,
     pub thread_count: Option<usize>,
+    /// Area outside of which the pipeline does not publish, see [Polygon::from_str] for the format
+    pub geofence: Option<Polygon>,
     gateway_component_name: String,
+    /// True if the gateway component name is set in the configuration and must not be updated
+    fixed_gateway_component_name: bool,
     instance_id: u32,
     region_of_responsibility: Quadtree,
+    /// Last information received from each neighbouring instance, by instance id
+    neighbours: HashMap<String, Information>,
 }
 
 impl NodeConfiguration {
@@ -47,22 +61,44 @@ impl NodeConfiguration {
         }
     }
 
-    pub fn station_id(&self, modifier: Option<u32>) -> u32 {
-        if let Some(modifier) = modifier {
-            self.instance_id + modifier
-        } else {
-            self.instance_id + 10_000
-        }
+    /// Returns the station id of this instance, derived for the `modifier`-th station if any
+    ///
+    /// The instance id and the modifier are kept in separate decimal fields,
+    /// `(modifier + 1) * 10 000 + instance_id`, so that two instances or two modifiers never
+    /// share a station id; no modifier is the modifier 0, i.e. `10 000 + instance_id`
+    ///
+    /// Returns None if the station id does not fit in a u32
+    pub fn station_id(&self, modifier: Option<u32>) -> Option<u32> {
+        modifier
+            .unwrap_or_default()
+            .checked_add(1)?
+            .checked_mul(MAX_INSTANCE_ID + 1)?
+            .checked_add(self.instance_id)
     }
 
     pub fn is_in_region_of_responsibility(&self, quadkey: &Quadkey) -> bool {
         !self.responsibility_enabled || quadtree::contains(&self.region_of_responsibility, quadkey)
     }
 
+    /// Returns true if no geofence is configured or if the position is inside it
+    pub fn is_in_geofence(&self, position: &Position) -> bool {
+        match &self.geofence {
+            Some(polygon) => polygon.contains(position),
+            None => true,
+        }
+    }
+
     pub fn update(&mut self, information: Information) {
         info!("Updating node configuration...");
-        self.gateway_component_name = information.instance_id;
-        self.instance_id = Self::extract_instance_id(&self.gateway_component_name);
+        if self.fixed_gateway_component_name {
+            info!(
+                "Keeping configured gateway component name '{}' instead of '{}'",
+                self.gateway_component_name, information.instance_id
+            );
+        } else {
+            self.gateway_component_name = information.instance_id;
+            self.instance_id = Self::extract_instance_id(&self.gateway_component_name);
+        }
 
         match information.service_area {
             Some(area) => {
@@ -88,12 +124,34 @@ impl NodeConfiguration {
         info!("Node configuration updated!");
     }
 
+    /// Records the information of a neighbouring instance, replacing the previous one it sent
+    pub fn update_neighbour(&mut self, information: Information) {
+        info!(
+            "Neighbour '{}' information updated",
+            information.instance_id
+        );
+        self.neighbours
+            .insert(information.instance_id.clone(), information);
+    }
+
+    /// Returns the last information received from each neighbouring instance, by instance id
+    pub fn neighbours(&self) -> &HashMap<String, Information> {
+        &self.neighbours
+    }
+
     fn extract_instance_id(gw_component_name: &String) -> u32 {
         match gw_component_name.split('_').collect::<Vec<&str>>().last() {
-            Some(id_as_str) => id_as_str.parse::<u32>().unwrap_or_else(|e| {
-                warn!("Failed to parse id ({}) as u32: {}", id_as_str, e);
-                0
-            }),
+            Some(id_as_str) => match id_as_str.parse::<u32>() {
+                Ok(id) if id <= MAX_INSTANCE_ID => id,
+                Ok(id) => {
+                    warn!("Instance id {} is above {}", id, MAX_INSTANCE_ID);
+                    0
+                }
+                Err(e) => {
+                    warn!("Failed to parse id ({}) as u32: {}", id_as_str, e);
+                    0
+                }
+            },
             None => {
                 error!(
                     "Gateway component name is empty or is malformed ({})",
@@ -117,15 +175,147 @@ impl TryFrom<&Properties> for NodeConfiguration {
             Err(e) => info!("Could not read thread_count: {}", e),
         }
 
+        let gateway_component_name =
+            get_optional_from_section::<String>("gateway_component_name", _properties)?;
+
         let s = Self {
+            instance_id: gateway_component_name
+                .as_ref()
+                .map(Self::extract_instance_id)
+                .unwrap_or_default(),
+            fixed_gateway_component_name: gateway_component_name.is_some(),
+            gateway_component_name: gateway_component_name.unwrap_or_default(),
             responsibility_enabled: get_mandatory_from_section::<bool>(
                 "responsibility_enabled",
                 section,
             )?,
             thread_count,
+            geofence: get_optional_from_section::<Polygon>("geofence", _properties)?,
             ..Default::default()
         };
 
         Ok(s)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::client::configuration::node_configuration::{NodeConfiguration, MAX_INSTANCE_ID};
+    use crate::exchange::message::information::Information;
+    use crate::mobility::position::position_from_degrees;
+    use ini::Ini;
+    use std::collections::HashSet;
+
+    #[test]
+    fn geofence_is_read_from_conf() {
+        let ini = Ini::load_from_str(
+            r#"
+[node]
+responsibility_enabled=true
+geofence=48.60,2.20;48.70,2.20;48.70,2.30;48.60,2.30
+"#,
+        )
+        .expect("Failed to load string as Ini");
+
+        let node_conf = NodeConfiguration::try_from(ini.section(Some("node")).unwrap())
+            .expect("Failed to create NodeConfiguration from config");
+
+        assert!(node_conf.is_in_geofence(&position_from_degrees(48.65, 2.25, 0.)));
+        assert!(!node_conf.is_in_geofence(&position_from_degrees(48.75, 2.25, 0.)));
+    }
+
+    #[test]
+    fn no_geofence_contains_everything() {
+        let ini = Ini::load_from_str("[node]\nresponsibility_enabled=false")
+            .expect("Failed to load string as Ini");
+
+        let node_conf = NodeConfiguration::try_from(ini.section(Some("node")).unwrap())
+            .expect("Failed to create NodeConfiguration from config");
+
+        assert!(node_conf.geofence.is_none());
+        assert!(node_conf.is_in_geofence(&position_from_degrees(0., 0., 0.)));
+    }
+
+    #[test]
+    fn invalid_geofence_is_err() {
+        let ini = Ini::load_from_str("[node]\nresponsibility_enabled=true\ngeofence=48.60,2.20")
+            .expect("Failed to load string as Ini");
+
+        assert!(NodeConfiguration::try_from(ini.section(Some("node")).unwrap()).is_err());
+    }
+
+    #[test]
+    fn gateway_component_name_is_read_from_conf() {
+        let ini = Ini::load_from_str(
+            "[node]\nresponsibility_enabled=false\ngateway_component_name=central_gw_12",
+        )
+        .expect("Failed to load string as Ini");
+
+        let mut node_conf = NodeConfiguration::try_from(ini.section(Some("node")).unwrap())
+            .expect("Failed to create NodeConfiguration from config");
+        let mut information = Information::default();
+        information.instance_id = String::from("broker_gw_3");
+        node_conf.update(information);
+
+        assert_eq!(node_conf.gateway_component_name(), Some("central_gw_12"));
+        assert_eq!(node_conf.station_id(None), Some(10_012));
+    }
+
+    #[test]
+    fn gateway_component_name_is_received_by_default() {
+        let ini = Ini::load_from_str("[node]\nresponsibility_enabled=false")
+            .expect("Failed to load string as Ini");
+
+        let mut node_conf = NodeConfiguration::try_from(ini.section(Some("node")).unwrap())
+            .expect("Failed to create NodeConfiguration from config");
+        assert!(node_conf.gateway_component_name().is_none());
+        let mut information = Information::default();
+        information.instance_id = String::from("broker_gw_3");
+        node_conf.update(information);
+
+        assert_eq!(node_conf.gateway_component_name(), Some("broker_gw_3"));
+        assert_eq!(node_conf.station_id(None), Some(10_003));
+    }
+
+    fn node_configuration(instance_id: u32) -> NodeConfiguration {
+        NodeConfiguration {
+            instance_id,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn station_ids_of_instances_and_modifiers_do_not_collide() {
+        let ids = [2, 3]
+            .into_iter()
+            .map(node_configuration)
+            .flat_map(|node_conf| {
+                [None, Some(1), Some(2), Some(10_000)]
+                    .map(|modifier| node_conf.station_id(modifier))
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(ids.iter().collect::<HashSet<_>>().len(), ids.len());
+        assert_eq!(ids[1], Some(20_002));
+        assert_eq!(ids[3], Some(100_010_002));
+    }
+
+    #[test]
+    fn overflowing_station_id_is_none() {
+        let node_conf = node_configuration(MAX_INSTANCE_ID);
+
+        assert_eq!(node_conf.station_id(Some(429_495)), None);
+        assert_eq!(node_conf.station_id(Some(u32::MAX)), None);
+        assert_eq!(node_conf.station_id(Some(429_493)), Some(4_294_949_999));
+    }
+
+    #[test]
+    fn too_large_instance_id_is_ignored() {
+        let mut node_conf = node_configuration(3);
+        let mut information = Information::default();
+        information.instance_id = String::from("broker_gw_10000");
+        node_conf.update(information);
+
+        assert_eq!(node_conf.station_id(None), Some(10_000));
+    }
+}
@@ -0,0 +1,334 @@
+/*
+ * Software Name : libits-client
+ * SPDX-FileCopyrightText: Copyright (c) Orange SA
+ * SPDX-License-Identifier: MIT
+ *
+ * This software is distributed under the MIT license,
+ * see the "LICENSE.txt" file for more details or https://opensource.org/license/MIT/
+ *
+ * Authors: see CONTRIBUTORS.md
+ */
+
+use ini::Properties;
+use std::time::Duration;
+
+use crate::client::configuration::configuration_error::ConfigurationError;
+use crate::client::configuration::get_optional_from_section;
+use crate::exchange::etsi::denm_priority::DenmPriorities;
+use crate::transport::mqtt::subscription::SubscriptionFilters;
+use crate::transport::pipe::OverflowPolicy;
+use crate::transport::serialization::Serialization;
+
+pub(crate) const PIPELINE_SECTION: &str = "pipeline";
+
+const DEFAULT_DEDUP_TTL: Duration = Duration::from_secs(5);
+
+/// Configuration of the channels linking the [pipeline][1] stages
+///
+/// The whole section is optional; channels are unbounded by default
+///
+/// Ini configuration example:
+/// ```ini
+/// [pipeline]
+/// ; Optional, maximum number of items waiting between two stages, at least 1
+/// channel_capacity=1000
+/// ; Optional, behaviour when a channel is full: 'block' (default) or 'drop_oldest'
+/// channel_policy="drop_oldest"
+/// ; Optional, size in bytes above which received payloads are dropped without being parsed
+/// max_payload_size=256000
+/// ; Optional, gzip the published payloads (default false)
+/// compress_payloads=true
+/// ; Optional, encoding of the published payloads: 'json' (default), 'cbor' or 'msgpack'
+/// serialization="cbor"
+/// ; Optional, period in seconds of the parse errors summary, one warning per error if not set
+/// parse_error_report_interval=60
+/// ; Optional, publishes area messages on each covered tile if their area spans at most this
+/// ; number of tiles
+/// max_covering_tiles=9
+/// ; Optional, refuse received payloads with unknown fields (default false)
+/// strict_parsing=true
+/// ; Optional, drops the messages already received among the last ones, whatever their source,
+/// ; up to this number
+/// dedup_capacity=10000
+/// ; Optional, time in seconds during which a message is remembered (default 5)
+/// dedup_ttl=5
+/// ; Optional, DENM cause codes published first and with QoS 1 (default 2,12,14,95,97,99)
+/// urgent_denm_causes=2,95
+/// ; Optional, tiles to subscribe to per message type as <type>:<tile prefix>[:<depth>], all if not set
+/// subscription_tiles=cam:1202:18,denm:12
+/// ; Optional, receive the information messages of every instance, not only the broker's (default false)
+/// subscribe_all_info=true
+/// ; Optional, QoS 1 and 2 publishes kept while disconnected to be resent on reconnection (default 0, none)
+/// resend_queue_capacity=100
+/// ; Optional, with the metrics feature, serves the Prometheus metrics at this address
+/// metrics_bind_address="0.0.0.0:9100"
+/// ```
+///
+/// [1]: crate::client::application::pipeline
+#[derive(Clone, Debug)]
+pub struct PipelineConfiguration {
+    pub channel_capacity: Option<usize>,
+    pub channel_policy: OverflowPolicy,
+    pub max_payload_size: Option<usize>,
+    pub compress_payloads: bool,
+    /// Encoding of the published payloads, received payloads are decoded according to their
+    /// content type whatever this setting
+    pub serialization: Serialization,
+    pub parse_error_report_interval: Option<Duration>,
+    pub max_covering_tiles: Option<usize>,
+    pub strict_parsing: bool,
+    /// Number of messages remembered to drop duplicates, no deduplication if not set
+    pub dedup_capacity: Option<usize>,
+    pub dedup_ttl: Duration,
+    pub denm_priorities: DenmPriorities,
+    pub subscription_filters: SubscriptionFilters,
+    /// Subscribes to the information messages of the neighbouring instances along with the
+    /// broker's one
+    pub subscribe_all_info: bool,
+    /// Number of publishes kept while disconnected, see [set_resend_queue_capacity][1]
+    ///
+    /// [1]: crate::transport::mqtt::mqtt_client::MqttClient::set_resend_queue_capacity
+    pub resend_queue_capacity: usize,
+    /// Address the [metrics][1] are served at, not served if not set
+    ///
+    /// [1]: crate::transport::metrics::PrometheusServer
+    #[cfg(feature = "metrics")]
+    pub metrics_bind_address: Option<String>,
+}
+
+impl Default for PipelineConfiguration {
+    fn default() -> Self {
+        Self {
+            channel_capacity: None,
+            channel_policy: OverflowPolicy::default(),
+            max_payload_size: None,
+            compress_payloads: false,
+            serialization: Serialization::default(),
+            parse_error_report_interval: None,
+            max_covering_tiles: None,
+            strict_parsing: false,
+            dedup_capacity: None,
+            dedup_ttl: DEFAULT_DEDUP_TTL,
+            denm_priorities: DenmPriorities::default(),
+            subscription_filters: SubscriptionFilters::default(),
+            subscribe_all_info: false,
+            resend_queue_capacity: 0,
+            #[cfg(feature = "metrics")]
+            metrics_bind_address: None,
+        }
+    }
+}
+
+impl TryFrom<&Properties> for PipelineConfiguration {
+    type Error = ConfigurationError;
+
+    fn try_from(properties: &Properties) -> Result<Self, Self::Error> {
+        Ok(Self {
+            channel_capacity: match get_optional_from_section::<usize>(
+                "channel_capacity",
+                properties,
+            )? {
+                Some(0) => {
+                    return Err(ConfigurationError::InvalidValue(
+                        "channel_capacity",
+                        "must be at least 1".to_string(),
+                    ))
+                }
+                capacity => capacity,
+            },
+            channel_policy: get_optional_from_section::<OverflowPolicy>(
+                "channel_policy",
+                properties,
+            )?
+            .unwrap_or_default(),
+            max_payload_size: get_optional_from_section::<usize>("max_payload_size", properties)?,
+            compress_payloads: get_optional_from_section::<bool>("compress_payloads", properties)?
+                .unwrap_or_default(),
+            serialization: get_optional_from_section::<Serialization>("serialization", properties)?
+                .unwrap_or_default(),
+            parse_error_report_interval: get_optional_from_section::<u64>(
+                "parse_error_report_interval",
+                properties,
+            )?
+            .map(Duration::from_secs),
+            max_covering_tiles: get_optional_from_section::<usize>(
+                "max_covering_tiles",
+                properties,
+            )?,
+            strict_parsing: get_optional_from_section::<bool>("strict_parsing", properties)?
+                .unwrap_or_default(),
+            dedup_capacity: get_optional_from_section::<usize>("dedup_capacity", properties)?,
+            dedup_ttl: get_optional_from_section::<u64>("dedup_ttl", properties)?
+                .map(Duration::from_secs)
+                .unwrap_or(DEFAULT_DEDUP_TTL),
+            denm_priorities: get_optional_from_section::<DenmPriorities>(
+                "urgent_denm_causes",
+                properties,
+            )?
+            .unwrap_or_default(),
+            subscription_filters: get_optional_from_section::<SubscriptionFilters>(
+                "subscription_tiles",
+                properties,
+            )?
+            .unwrap_or_default(),
+            subscribe_all_info: get_optional_from_section::<bool>(
+                "subscribe_all_info",
+                properties,
+            )?
+            .unwrap_or_default(),
+            resend_queue_capacity: get_optional_from_section::<usize>(
+                "resend_queue_capacity",
+                properties,
+            )?
+            .unwrap_or_default(),
+            #[cfg(feature = "metrics")]
+            metrics_bind_address: get_optional_from_section::<String>(
+                "metrics_bind_address",
+                properties,
+            )?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::client::configuration::pipeline_configuration::PipelineConfiguration;
+    use crate::exchange::etsi::decentralized_environmental_notification_message::CauseCode;
+    use crate::exchange::etsi::denm_priority::DenmPriorities;
+    use crate::transport::mqtt::subscription::SubscriptionFilters;
+    use crate::transport::pipe::OverflowPolicy;
+    use crate::transport::serialization::Serialization;
+    use ini::Ini;
+    use std::str::FromStr;
+    use std::time::Duration;
+
+    #[test]
+    fn values_are_read_from_conf() {
+        let ini = Ini::load_from_str(
+            r#"
+[pipeline]
+channel_capacity=1
+channel_policy="drop_oldest"
+max_payload_size=256000
+compress_payloads=true
+serialization="cbor"
+parse_error_report_interval=60
+max_covering_tiles=9
+strict_parsing=true
+dedup_capacity=10000
+dedup_ttl=2
+urgent_denm_causes=1,3
+subscription_tiles=cam:1202
+subscribe_all_info=true
+resend_queue_capacity=100
+metrics_bind_address="0.0.0.0:9100"
+"#,
+        )
+        .expect("Failed to load string as Ini");
+
+        let pipeline_conf = PipelineConfiguration::try_from(ini.section(Some("pipeline")).unwrap())
+            .expect("Failed to create PipelineConfiguration from config");
+
+        assert_eq!(Some(1), pipeline_conf.channel_capacity);
+        assert_eq!(OverflowPolicy::DropOldest, pipeline_conf.channel_policy);
+        assert_eq!(Some(256_000), pipeline_conf.max_payload_size);
+        assert!(pipeline_conf.compress_payloads);
+        assert_eq!(Serialization::Cbor, pipeline_conf.serialization);
+        assert_eq!(
+            Some(Duration::from_secs(60)),
+            pipeline_conf.parse_error_report_interval
+        );
+        assert_eq!(Some(9), pipeline_conf.max_covering_tiles);
+        assert!(pipeline_conf.strict_parsing);
+        assert_eq!(Some(10_000), pipeline_conf.dedup_capacity);
+        assert_eq!(Duration::from_secs(2), pipeline_conf.dedup_ttl);
+        assert_eq!(
+            DenmPriorities::new([CauseCode::TrafficCondition, CauseCode::Roadworks]),
+            pipeline_conf.denm_priorities
+        );
+        assert_eq!(
+            SubscriptionFilters::from_str("cam:1202").unwrap(),
+            pipeline_conf.subscription_filters
+        );
+        assert!(pipeline_conf.subscribe_all_info);
+        assert_eq!(100, pipeline_conf.resend_queue_capacity);
+        #[cfg(feature = "metrics")]
+        assert_eq!(
+            Some("0.0.0.0:9100"),
+            pipeline_conf.metrics_bind_address.as_deref()
+        );
+    }
+
+    #[test]
+    fn default_values() {
+        let ini = Ini::load_from_str("[pipeline]").expect("Failed to load string as Ini");
+
+        let pipeline_conf = PipelineConfiguration::try_from(ini.section(Some("pipeline")).unwrap())
+            .expect("Failed to create PipelineConfiguration from config");
+
+        assert!(pipeline_conf.channel_capacity.is_none());
+        assert_eq!(OverflowPolicy::Block, pipeline_conf.channel_policy);
+        assert!(pipeline_conf.max_payload_size.is_none());
+        assert!(!pipeline_conf.compress_payloads);
+        assert_eq!(Serialization::Json, pipeline_conf.serialization);
+        assert!(pipeline_conf.parse_error_report_interval.is_none());
+        assert!(pipeline_conf.max_covering_tiles.is_none());
+        assert!(!pipeline_conf.strict_parsing);
+        assert!(pipeline_conf.dedup_capacity.is_none());
+        assert_eq!(Duration::from_secs(5), pipeline_conf.dedup_ttl);
+        assert_eq!(DenmPriorities::default(), pipeline_conf.denm_priorities);
+        assert_eq!(
+            SubscriptionFilters::default(),
+            pipeline_conf.subscription_filters
+        );
+        assert!(!pipeline_conf.subscribe_all_info);
+        assert_eq!(0, pipeline_conf.resend_queue_capacity);
+        #[cfg(feature = "metrics")]
+        assert!(pipeline_conf.metrics_bind_address.is_none());
+    }
+
+    #[test]
+    fn unknown_policy_is_err() {
+        let ini = Ini::load_from_str(
+            r#"
+[pipeline]
+channel_policy="drop_newest"
+"#,
+        )
+        .expect("Failed to load string as Ini");
+
+        let result = PipelineConfiguration::try_from(ini.section(Some("pipeline")).unwrap());
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn invalid_subscription_tiles_is_err() {
+        let ini = Ini::load_from_str(
+            r#"
+[pipeline]
+subscription_tiles=cam:1204
+"#,
+        )
+        .expect("Failed to load string as Ini");
+
+        let result = PipelineConfiguration::try_from(ini.section(Some("pipeline")).unwrap());
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn null_channel_capacity_is_err() {
+        let ini = Ini::load_from_str(
+            r#"
+[pipeline]
+channel_capacity=0
+"#,
+        )
+        .expect("Failed to load string as Ini");
+
+        let result = PipelineConfiguration::try_from(ini.section(Some("pipeline")).unwrap());
+
+        assert!(result.is_err());
+    }
+}
@@ -1,7 +1,7 @@
 use base64::Engine;
 use ini::Properties;
-use log::warn;
 use std::string::ToString;
+use tracing::warn;
 
 use crate::client::configuration::configuration_error::ConfigurationError;
 use crate::client::configuration::{get_mandatory_from_section, get_optional_from_section};
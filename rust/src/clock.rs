@@ -0,0 +1,95 @@
+/*
+ * Software Name : libits-client
+ * SPDX-FileCopyrightText: Copyright (c) Orange SA
+ * SPDX-License-Identifier: MIT
+ *
+ * This software is distributed under the MIT license,
+ * see the "LICENSE.txt" file for more details or https://opensource.org/license/MIT/
+ *
+ * Authors: see CONTRIBUTORS.md
+ */
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use crate::now;
+
+/// Time source providing the current timestamp
+///
+/// The [Configuration][1] holds the clock the pipeline and the analysers must use instead of
+/// calling [now] directly, so that timestamp related logic can be tested with a [MockClock]
+///
+/// [1]: crate::client::configuration::Configuration
+pub trait Clock: Send + Sync {
+    /// Returns the current UTC timestamp in milliseconds
+    fn now_millis(&self) -> u64;
+}
+
+/// [Clock] reading the system time
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_millis(&self) -> u64 {
+        now()
+    }
+}
+
+/// [Clock] which only moves when explicitly set or advanced
+#[derive(Debug, Default)]
+pub struct MockClock {
+    millis: AtomicU64,
+}
+
+impl MockClock {
+    pub fn new(millis: u64) -> Self {
+        Self {
+            millis: AtomicU64::new(millis),
+        }
+    }
+
+    pub fn set(&self, millis: u64) {
+        self.millis.store(millis, Ordering::Relaxed);
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        self.millis
+            .fetch_add(duration.as_millis() as u64, Ordering::Relaxed);
+    }
+}
+
+impl Clock for MockClock {
+    fn now_millis(&self) -> u64 {
+        self.millis.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::clock::{Clock, MockClock, SystemClock};
+    use crate::now;
+
+    #[test]
+    fn system_clock_follows_system_time() {
+        let before = now();
+
+        let timestamp = SystemClock.now_millis();
+
+        assert!(timestamp >= before);
+        assert!(timestamp <= now());
+    }
+
+    #[test]
+    fn mock_clock_only_moves_on_demand() {
+        let clock = MockClock::new(1_000);
+        assert_eq!(clock.now_millis(), 1_000);
+
+        clock.advance(Duration::from_secs(3));
+        assert_eq!(clock.now_millis(), 4_000);
+
+        clock.set(42);
+        assert_eq!(clock.now_millis(), 42);
+    }
+}
@@ -11,10 +11,14 @@
 
 pub(crate) mod cause;
 pub mod etsi;
+pub mod exchange_error;
 pub mod message;
 pub mod mortal;
+pub mod schema;
 pub mod sequence_number;
 
+pub use schema::schema_version;
+
 use crate::exchange::message::content::Content;
 use crate::exchange::message::Message;
 use crate::mobility::position::Position;
@@ -23,6 +27,19 @@ use crate::transport::payload::Payload;
 use crate::client::configuration::Configuration;
 use serde::{Deserialize, Serialize};
 
+#[cfg(feature = "geo_routing")]
+use {
+    crate::exchange::etsi::collective_perception_message::CollectivePerceptionMessage,
+    crate::exchange::etsi::cooperative_awareness_message::CooperativeAwarenessMessage,
+    crate::exchange::etsi::decentralized_environmental_notification_message::DecentralizedEnvironmentalNotificationMessage,
+    crate::exchange::etsi::map_extended_message::MAPExtendedMessage,
+    crate::exchange::etsi::signal_phase_and_timing_extended_message::SignalPhaseAndTimingExtendedMessage,
+    crate::exchange::exchange_error::ExchangeError, crate::transport::mqtt::geo_topic::GeoTopic,
+};
+
+const ORIGIN: &str = "mec_application";
+const VERSION: &str = "1.1.1";
+
 #[serde_with::skip_serializing_none]
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Exchange {
@@ -85,8 +102,8 @@ impl Exchange {
 
         Box::from(Exchange {
             type_field: content.get_type().to_string(),
-            origin: "mec_application".to_string(),
-            version: "1.1.1".to_string(),
+            origin: ORIGIN.to_string(),
+            version: VERSION.to_string(),
             source_uuid: component,
             path,
             timestamp,
@@ -94,6 +111,74 @@ impl Exchange {
         })
     }
 
+    /// Parses an exchange, choosing the message type from the topic rather than guessing it
+    /// from the payload
+    ///
+    /// Information messages are not exchanges, and are not supported
+    #[cfg(feature = "geo_routing")]
+    pub fn from_topic_and_payload(
+        topic: &GeoTopic,
+        payload: &[u8],
+    ) -> Result<Exchange, ExchangeError> {
+        /// Exchange whose message is kept raw until its type is known
+        #[derive(Deserialize)]
+        struct Envelope {
+            #[serde(rename = "type")]
+            type_field: String,
+            origin: String,
+            version: String,
+            source_uuid: String,
+            timestamp: u64,
+            #[serde(default)]
+            path: Vec<PathElement>,
+            message: serde_json::Value,
+        }
+
+        let envelope = serde_json::from_slice::<Envelope>(payload)?;
+        let message = match topic.message_type().as_str() {
+            "cam" => Message::CAM(serde_json::from_value::<CooperativeAwarenessMessage>(
+                envelope.message,
+            )?),
+            "cpm" => Message::CPM(serde_json::from_value::<CollectivePerceptionMessage>(
+                envelope.message,
+            )?),
+            "denm" => Message::DENM(serde_json::from_value::<
+                DecentralizedEnvironmentalNotificationMessage,
+            >(envelope.message)?),
+            "map" | "mapem" => Message::MAPEM(serde_json::from_value::<MAPExtendedMessage>(
+                envelope.message,
+            )?),
+            "spat" | "spatem" => Message::SPATEM(serde_json::from_value::<
+                SignalPhaseAndTimingExtendedMessage,
+            >(envelope.message)?),
+            message_type => {
+                return Err(ExchangeError::UnsupportedMessageType(
+                    message_type.to_string(),
+                ))
+            }
+        };
+
+        Ok(Exchange {
+            type_field: envelope.type_field,
+            origin: envelope.origin,
+            version: envelope.version,
+            source_uuid: envelope.source_uuid,
+            timestamp: envelope.timestamp,
+            path: envelope.path,
+            message,
+        })
+    }
+
+    /// Returns true if the message is expired at the UNIX timestamp, in milliseconds
+    ///
+    /// Only messages with a validity (e.g. DENM) expire, the other ones (e.g. CAM, CPM) never do
+    pub fn is_expired(&self, now_ms: u64) -> bool {
+        self.message
+            .content()
+            .as_mortal()
+            .is_ok_and(|mortal| mortal.expired_at(now_ms))
+    }
+
     // TODO find a better way to appropriate
     pub fn appropriate(&mut self, configuration: &Configuration, timestamp: u64) {
         self.origin = "mec_application".to_string();
@@ -1507,4 +1592,72 @@ mod tests {
             }
         }
     }
+
+    #[cfg(feature = "geo_routing")]
+    macro_rules! test_from_topic_and_payload {
+        ($test_name:ident, $topic:expr, $payload:expr, $variant:path, $type:expr) => {
+            #[test]
+            fn $test_name() {
+                let topic = crate::transport::mqtt::geo_topic::GeoTopic::from($topic);
+
+                let exchange = Exchange::from_topic_and_payload(&topic, $payload.as_bytes())
+                    .expect("Failed to parse exchange");
+
+                assert!(matches!(exchange.message, $variant(_)));
+                assert_eq!(exchange.message.content().get_type(), $type);
+                assert_eq!(exchange.type_field, $type);
+            }
+        };
+    }
+    #[cfg(feature = "geo_routing")]
+    test_from_topic_and_payload!(
+        cam_from_topic_and_payload,
+        "default/outQueue/v2x/cam/uuid14/1/2/0/2",
+        full_cam(),
+        Message::CAM,
+        "cam"
+    );
+    #[cfg(feature = "geo_routing")]
+    test_from_topic_and_payload!(
+        denm_from_topic_and_payload,
+        "default/outQueue/v2x/denm/uuid14/1/2/0/2",
+        full_denm(),
+        Message::DENM,
+        "denm"
+    );
+    #[cfg(feature = "geo_routing")]
+    test_from_topic_and_payload!(
+        cpm_from_topic_and_payload,
+        "default/outQueue/v2x/cpm/uuid14/1/2/0/2",
+        full_cpm(),
+        Message::CPM,
+        "cpm"
+    );
+
+    #[cfg(feature = "geo_routing")]
+    #[test]
+    fn payload_not_matching_topic_type_is_err() {
+        let topic = crate::transport::mqtt::geo_topic::GeoTopic::from(
+            "default/outQueue/v2x/denm/uuid14/1/2/0/2",
+        );
+
+        let result = Exchange::from_topic_and_payload(&topic, basic_cam().as_bytes());
+
+        assert!(result.is_err());
+    }
+
+    #[cfg(feature = "geo_routing")]
+    #[test]
+    fn unsupported_topic_type_is_err() {
+        let topic = crate::transport::mqtt::geo_topic::GeoTopic::from(
+            "default/outQueue/v2x/ivim/uuid14/1/2/0/2",
+        );
+
+        let result = Exchange::from_topic_and_payload(&topic, full_cam().as_bytes());
+
+        assert!(matches!(
+            result,
+            Err(crate::exchange::exchange_error::ExchangeError::UnsupportedMessageType(_))
+        ));
+    }
 }
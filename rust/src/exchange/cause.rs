@@ -21,6 +21,7 @@ use std::fmt::Formatter;
 /// tell that this DENM was detected using this message
 ///
 /// [1]: crate::monitor::trace_exchange
+#[derive(Clone)]
 pub(crate) struct Cause {
     pub m_type: String,
     pub id: String,
@@ -9,23 +9,44 @@
  * Authors: see CONTRIBUTORS.md
  */
 
-use crate::now;
+use crate::exchange::etsi::angle::Angle;
 use serde::{Deserialize, Serialize};
 
+pub mod action_id;
+pub mod angle;
+pub mod cam_generation;
 pub mod collective_perception_message;
 pub mod cooperative_awareness_message;
+pub mod cpm_reassembler;
 pub mod decentralized_environmental_notification_message;
+pub mod denm_priority;
+pub mod generation_delta_time;
 pub mod map_extended_message;
 pub mod mobile_perceived_object;
 pub mod perceived_object;
+pub mod quantity;
 pub mod reference_position;
+pub mod shape;
 pub mod signal_phase_and_timing_extended_message;
+pub mod track;
 
 const ETSI_TIMESTAMP_OFFSET: u64 = 1072915200000;
+/// UNIX millisecond timestamps from which each leap second inserted since the ITS epoch applies,
+/// along with the cumulated leap second count
+const LEAP_SECONDS: [(u64, u64); 5] = [
+    (1136073600000, 1), // 2006-01-01
+    (1230768000000, 2), // 2009-01-01
+    (1341100800000, 3), // 2012-07-01
+    (1435708800000, 4), // 2015-07-01
+    (1483228800000, 5), // 2017-01-01
+];
+
+const HEADING_UNAVAILABLE: u16 = 3601;
 
 #[serde_with::skip_serializing_none]
 #[derive(Default, Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PositionConfidence {
+    #[serde(alias = "positionConfidenceEllipse")]
     pub position_confidence_ellipse: Option<PositionConfidenceEllipse>,
     pub altitude: Option<u8>,
 }
@@ -33,23 +54,31 @@ pub struct PositionConfidence {
 #[serde_with::skip_serializing_none]
 #[derive(Default, Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PositionConfidenceEllipse {
+    #[serde(alias = "semiMajorConfidence")]
     pub semi_major_confidence: Option<u16>,
+    #[serde(alias = "semiMinorConfidence")]
     pub semi_minor_confidence: Option<u16>,
+    #[serde(alias = "semiMajorOrientation")]
     pub semi_major_orientation: Option<u16>,
 }
 
 #[serde_with::skip_serializing_none]
 #[derive(Default, Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PathHistory {
+    #[serde(alias = "pathPosition")]
     pub path_position: PathPosition,
+    #[serde(alias = "pathDeltaTime")]
     pub path_delta_time: Option<u16>,
 }
 
 #[serde_with::skip_serializing_none]
 #[derive(Default, Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PathPosition {
+    #[serde(alias = "deltaLatitude")]
     pub delta_latitude: Option<i32>,
+    #[serde(alias = "deltaLongitude")]
     pub delta_longitude: Option<i32>,
+    #[serde(alias = "deltaAltitude")]
     pub delta_altitude: Option<i32>,
 }
 
@@ -58,6 +87,16 @@ pub(crate) fn heading_from_etsi(decidegrees: u16) -> f64 {
     (f64::from(decidegrees) / 10.).to_radians()
 }
 
+/// Converts heading from decidegrees to radians
+///
+/// Returns `None` for the unavailable value (3601) and the out of range ones above
+pub(crate) fn heading_from_etsi_opt(decidegrees: u16) -> Option<f64> {
+    if decidegrees > HEADING_UNAVAILABLE {
+        return None;
+    }
+    Angle(decidegrees).to_radians()
+}
+
 /// Converts heading from radians to decidegrees
 pub(crate) fn heading_to_etsi(radians: f64) -> u16 {
     ((radians.to_degrees() * 10_f64) % 3600.) as u16
@@ -89,27 +128,65 @@ pub(crate) fn acceleration_to_etsi(m_per_s_2: f64) -> i16 {
     (m_per_s_2 * 10.) as i16
 }
 
-/// FIXME use this function and remove this clause once mobility message creation is implemented
-///       (cf. [Github issue][1])
+/// Converts heading confidence from decidegrees to radians
 ///
-/// [1]: https://github.com/orgs/Orange-OpenSource/projects/3/views/8?pane=issue&itemId=69693871&issue=Orange-OpenSource%7Cits-client%7C131
-#[allow(unused)]
-pub(crate) fn timestamp_from_etsi(etsi_timestamp: u64) -> u64 {
-    etsi_timestamp + ETSI_TIMESTAMP_OFFSET
+/// Returns `None` for the out of range (126) and unavailable (127) values
+pub(crate) fn heading_confidence_from_etsi(decidegrees: u8) -> Option<f64> {
+    match decidegrees {
+        126.. => None,
+        value => Some((f64::from(value) / 10.).to_radians()),
+    }
+}
+
+/// Converts a UNIX timestamp in milliseconds into an ETSI `TimestampIts`
+///
+/// `TimestampIts` counts the milliseconds elapsed since 2004-01-01T00:00:00.000 UTC in TAI,
+/// i.e. including the leap seconds inserted since then; e.g. 2007-01-01T00:00:00.000 UTC is
+/// 94694401000
+///
+/// Leap seconds are only known up to the last one, inserted at the end of 2016
+///
+/// Returns None if the timestamp precedes the ITS epoch
+pub fn timestamp_to_etsi(unix_timestamp: u64) -> Option<u64> {
+    let leap_seconds = LEAP_SECONDS
+        .iter()
+        .rev()
+        .find(|(from, _)| unix_timestamp >= *from)
+        .map_or(0, |(_, count)| *count);
+    unix_timestamp
+        .checked_sub(ETSI_TIMESTAMP_OFFSET)
+        .map(|etsi_timestamp| etsi_timestamp + leap_seconds * 1000)
 }
 
-pub(crate) fn timestamp_to_etsi(unix_timestamp: u64) -> u64 {
-    unix_timestamp - ETSI_TIMESTAMP_OFFSET
+/// Converts an ETSI `TimestampIts` into a UNIX timestamp in milliseconds
+///
+/// This is the inverse of [timestamp_to_etsi], see it for the leap second assumptions;
+/// a `TimestampIts` falling on an inserted leap second is mapped to the following UTC second
+///
+/// Returns None if the UNIX timestamp does not fit in a u64, e.g. for a received garbage value
+pub fn timestamp_from_etsi(etsi_timestamp: u64) -> Option<u64> {
+    let leap_seconds = LEAP_SECONDS
+        .iter()
+        .rev()
+        .find(|(from, count)| etsi_timestamp >= from - ETSI_TIMESTAMP_OFFSET + count * 1000)
+        .map_or(0, |(_, count)| *count);
+    etsi_timestamp
+        .checked_add(ETSI_TIMESTAMP_OFFSET)?
+        .checked_sub(leap_seconds * 1000)
 }
 
-pub(crate) fn etsi_now() -> u64 {
-    timestamp_to_etsi(now())
+/// Returns the signed difference `a - b` between two generation delta times, in milliseconds
+///
+/// See [generation_delta_time::delta]
+pub fn generation_delta_time_diff(a: u16, b: u16) -> i32 {
+    generation_delta_time::delta(a, b)
 }
 
 #[cfg(test)]
 mod tests {
     use crate::exchange::etsi::{
-        acceleration_from_etsi, acceleration_to_etsi, etsi_now, heading_from_etsi, heading_to_etsi,
+        acceleration_from_etsi, acceleration_to_etsi, generation_delta_time_diff,
+        heading_confidence_from_etsi, heading_from_etsi, heading_from_etsi_opt, heading_to_etsi,
         speed_from_etsi, speed_to_etsi, timestamp_from_etsi, timestamp_to_etsi,
         ETSI_TIMESTAMP_OFFSET,
     };
@@ -223,30 +300,128 @@ mod tests {
         161
     );
 
+    macro_rules! test_opt_from_etsi {
+        ($func:ident, $test_name:ident, $value:expr, $expected:expr) => {
+            #[test]
+            fn $test_name() {
+                let as_si: Option<f64> = $func($value);
+
+                match ($expected, as_si) {
+                    (Some(expected), Some(actual)) => assert!(
+                        (actual - expected).abs() < 1e-11,
+                        "Actual: {} (expected: {})",
+                        actual,
+                        expected
+                    ),
+                    (expected, actual) => assert_eq!(actual, expected),
+                }
+            }
+        };
+    }
+    test_opt_from_etsi!(
+        heading_from_etsi_opt,
+        available_heading_from_etsi_opt,
+        900,
+        Some(PI / 2.)
+    );
+    test_opt_from_etsi!(
+        heading_from_etsi_opt,
+        unavailable_heading_from_etsi_opt,
+        3601,
+        None::<f64>
+    );
+    test_opt_from_etsi!(
+        heading_from_etsi_opt,
+        out_of_range_heading_from_etsi_opt,
+        3700,
+        None::<f64>
+    );
+    test_opt_from_etsi!(
+        heading_confidence_from_etsi,
+        max_heading_confidence_from_etsi,
+        125,
+        Some(12.5_f64.to_radians())
+    );
+    test_opt_from_etsi!(
+        heading_confidence_from_etsi,
+        out_of_range_heading_confidence_from_etsi,
+        126,
+        None::<f64>
+    );
+    test_opt_from_etsi!(
+        heading_confidence_from_etsi,
+        unavailable_heading_confidence_from_etsi,
+        127,
+        None::<f64>
+    );
+
     #[test]
     fn test_timestamp_to_etsi() {
         let now = now();
 
-        let etsi_timestamp = timestamp_to_etsi(now);
+        let etsi_timestamp = timestamp_to_etsi(now).unwrap();
 
-        assert_eq!(now - etsi_timestamp, ETSI_TIMESTAMP_OFFSET);
+        assert_eq!(now + 5000 - etsi_timestamp, ETSI_TIMESTAMP_OFFSET);
     }
 
     #[test]
-    fn test_etsi_now() {
+    fn timestamp_before_its_epoch_is_none() {
+        assert!(timestamp_to_etsi(ETSI_TIMESTAMP_OFFSET - 1).is_none());
+    }
+
+    #[test]
+    fn test_timestamp_from_etsi() {
         let now = now();
 
-        let etsi_now = etsi_now();
+        let unix_timestamp = timestamp_from_etsi(timestamp_to_etsi(now).unwrap());
 
-        assert_eq!(now - etsi_now, ETSI_TIMESTAMP_OFFSET);
+        assert_eq!(unix_timestamp, Some(now));
     }
 
     #[test]
-    fn test_timestamp_from_etsi() {
-        let etsi_now = etsi_now();
+    fn overflowing_etsi_timestamp_is_none() {
+        assert!(timestamp_from_etsi(u64::MAX).is_none());
+    }
 
-        let now = timestamp_from_etsi(etsi_now);
+    macro_rules! test_generation_delta_time_diff {
+        ($test_name:ident, $a:expr, $b:expr, $expected:expr) => {
+            #[test]
+            fn $test_name() {
+                assert_eq!(generation_delta_time_diff($a, $b), $expected);
+            }
+        };
+    }
+    test_generation_delta_time_diff!(same_generation_delta_time, 1000, 1000, 0);
+    test_generation_delta_time_diff!(later_without_wrap, 1100, 1000, 100);
+    test_generation_delta_time_diff!(earlier_without_wrap, 1000, 1100, -100);
+    test_generation_delta_time_diff!(later_across_wrap, 2, 65534, 4);
+    test_generation_delta_time_diff!(earlier_across_wrap, 65534, 2, -4);
+    test_generation_delta_time_diff!(last_before_wrap, 0, 65535, 1);
 
-        assert_eq!(now - etsi_now, ETSI_TIMESTAMP_OFFSET);
+    macro_rules! test_etsi_timestamp {
+        ($test_name:ident, $unix_ms:expr, $etsi_timestamp:expr) => {
+            #[test]
+            fn $test_name() {
+                assert_eq!(timestamp_to_etsi($unix_ms), Some($etsi_timestamp));
+                assert_eq!(timestamp_from_etsi($etsi_timestamp), Some($unix_ms));
+            }
+        };
+    }
+    test_etsi_timestamp!(its_epoch, 1072915200000, 0);
+    test_etsi_timestamp!(one_millisecond_after_its_epoch, 1072915200001, 1);
+    // ETSI TS 102 894-2 TimestampIts example
+    test_etsi_timestamp!(start_of_2007, 1167609600000, 94694401000);
+    test_etsi_timestamp!(
+        last_millisecond_before_first_leap_second,
+        1136073599999,
+        63158399999
+    );
+    test_etsi_timestamp!(start_of_2017, 1483228800000, 410313605000);
+    test_etsi_timestamp!(after_last_leap_second, 1700000000000, 627084805000);
+
+    #[test]
+    fn etsi_timestamp_on_leap_second() {
+        // 2005-12-31T23:59:60 UTC
+        assert_eq!(timestamp_from_etsi(63158400000), Some(1136073600000));
     }
 }
@@ -0,0 +1,82 @@
+/*
+ * Software Name : libits-client
+ * SPDX-FileCopyrightText: Copyright (c) Orange SA
+ * SPDX-License-Identifier: MIT
+ *
+ * This software is distributed under the MIT license,
+ * see the "LICENSE.txt" file for more details or https://opensource.org/license/MIT/
+ *
+ * Authors: see CONTRIBUTORS.md
+ */
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::exchange::sequence_number::SequenceNumber;
+
+/// Identifier of a DENM event, shared by all its updates, repetitions and its termination
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq, Serialize, Deserialize, Hash)]
+pub struct ActionId {
+    #[serde(alias = "originatingStationId")]
+    pub originating_station_id: u32,
+    #[serde(alias = "sequenceNumber")]
+    pub sequence_number: u16,
+}
+
+impl ActionId {
+    pub fn new(originating_station_id: u32, sequence_number: u16) -> Self {
+        Self {
+            originating_station_id,
+            sequence_number,
+        }
+    }
+}
+
+/// Hands out the action ids of the new events, with a sequence number incremented per
+/// originating station
+///
+/// Sequence numbers start at 1 and wrap to 0 after 65535
+#[derive(Debug, Default)]
+pub struct ActionIdGenerator {
+    sequence_numbers: HashMap<u32, SequenceNumber>,
+}
+
+impl ActionIdGenerator {
+    /// Returns the action id of the next event originating from the station
+    pub fn next(&mut self, originating_station_id: u32) -> ActionId {
+        let sequence_number = self
+            .sequence_numbers
+            .entry(originating_station_id)
+            .or_insert_with(|| SequenceNumber::new(u128::from(u16::MAX) + 1))
+            .get_next() as u16;
+
+        ActionId::new(originating_station_id, sequence_number)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::exchange::etsi::action_id::{ActionId, ActionIdGenerator};
+
+    #[test]
+    fn sequence_numbers_are_incremented_per_station() {
+        let mut generator = ActionIdGenerator::default();
+
+        assert_eq!(generator.next(1), ActionId::new(1, 1));
+        assert_eq!(generator.next(1), ActionId::new(1, 2));
+        assert_eq!(generator.next(2), ActionId::new(2, 1));
+        assert_eq!(generator.next(1), ActionId::new(1, 3));
+    }
+
+    #[test]
+    fn sequence_number_wraps() {
+        let mut generator = ActionIdGenerator::default();
+        for _ in 0..u16::MAX - 1 {
+            generator.next(1);
+        }
+
+        assert_eq!(generator.next(1), ActionId::new(1, u16::MAX));
+        assert_eq!(generator.next(1), ActionId::new(1, 0));
+    }
+}
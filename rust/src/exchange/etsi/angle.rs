@@ -0,0 +1,135 @@
+/*
+ * Software Name : libits-client
+ * SPDX-FileCopyrightText: Copyright (c) Orange SA
+ * SPDX-License-Identifier: MIT
+ *
+ * This software is distributed under the MIT license,
+ * see the "LICENSE.txt" file for more details or https://opensource.org/license/MIT/
+ *
+ * Authors: see CONTRIBUTORS.md
+ */
+
+use std::ops::Add;
+
+use serde::{Deserialize, Serialize};
+
+use crate::exchange::etsi::{heading_from_etsi, HEADING_UNAVAILABLE};
+
+/// Decidegrees in a full turn
+const FULL_TURN: u16 = 3600;
+
+/// Angle in tenths of degree, clockwise from north, as ETSI headings
+///
+/// Values wrap every 3600, 3601 standing for an unavailable angle; any other value above is
+/// only [normalized][1] when used
+///
+/// [1]: Angle::normalized
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Angle(pub u16);
+
+impl Angle {
+    pub const UNAVAILABLE: Angle = Angle(HEADING_UNAVAILABLE);
+
+    pub fn is_available(&self) -> bool {
+        self.0 != HEADING_UNAVAILABLE
+    }
+
+    /// Returns the angle in [0, 3600), or unavailable
+    pub fn normalized(&self) -> Angle {
+        if self.is_available() {
+            Angle(self.0 % FULL_TURN)
+        } else {
+            *self
+        }
+    }
+
+    /// Returns the shortest signed arc to turn clockwise from this angle to reach `other`, in
+    /// (-1800, 1800] tenths of degree, `None` if any of them is unavailable
+    pub fn difference(&self, other: &Angle) -> Option<i16> {
+        if !self.is_available() || !other.is_available() {
+            return None;
+        }
+
+        let difference = (i32::from(other.normalized().0) - i32::from(self.normalized().0))
+            .rem_euclid(i32::from(FULL_TURN));
+        let half_turn = i32::from(FULL_TURN / 2);
+        Some(if difference > half_turn {
+            difference - i32::from(FULL_TURN)
+        } else {
+            difference
+        } as i16)
+    }
+
+    /// Returns the angle in radians, `None` if unavailable
+    pub fn to_radians(&self) -> Option<f64> {
+        self.is_available()
+            .then(|| heading_from_etsi(self.normalized().0))
+    }
+}
+
+/// Sums the angles, wrapping around north; the sum is unavailable if any of them is
+impl Add for Angle {
+    type Output = Angle;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        if self.is_available() && rhs.is_available() {
+            Angle((self.normalized().0 + rhs.normalized().0) % FULL_TURN)
+        } else {
+            Angle::UNAVAILABLE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::exchange::etsi::angle::Angle;
+
+    macro_rules! test_difference {
+        ($test_name:ident, $from:expr, $to:expr, $expected:expr) => {
+            #[test]
+            fn $test_name() {
+                assert_eq!(Angle($from).difference(&Angle($to)), $expected);
+            }
+        };
+    }
+    test_difference!(clockwise, 100, 300, Some(200));
+    test_difference!(counterclockwise, 300, 100, Some(-200));
+    test_difference!(clockwise_across_north, 3500, 100, Some(200));
+    test_difference!(counterclockwise_across_north, 100, 3500, Some(-200));
+    test_difference!(half_turn_is_positive, 0, 1800, Some(1800));
+    test_difference!(half_turn_back_is_positive, 1800, 0, Some(1800));
+    test_difference!(full_turn_is_no_difference, 3600, 0, Some(0));
+    test_difference!(unavailable_from, 3601, 100, None);
+    test_difference!(unavailable_to, 100, 3601, None);
+
+    macro_rules! test_normalized {
+        ($test_name:ident, $angle:expr, $expected:expr) => {
+            #[test]
+            fn $test_name() {
+                assert_eq!(Angle($angle).normalized(), Angle($expected));
+            }
+        };
+    }
+    test_normalized!(in_range_is_unchanged, 900, 900);
+    test_normalized!(full_turn_is_north, 3600, 0);
+    test_normalized!(above_full_turn_wraps, 3700, 100);
+    test_normalized!(unavailable_is_unchanged, 3601, 3601);
+
+    #[test]
+    fn sum_wraps_around_north() {
+        assert_eq!(Angle(3500) + Angle(200), Angle(100));
+    }
+
+    #[test]
+    fn sum_with_unavailable_is_unavailable() {
+        assert_eq!(Angle(100) + Angle::UNAVAILABLE, Angle::UNAVAILABLE);
+        assert!(!(Angle::UNAVAILABLE + Angle(100)).is_available());
+    }
+
+    #[test]
+    fn unavailable_has_no_radians() {
+        assert!(Angle::UNAVAILABLE.to_radians().is_none());
+        assert!((Angle(900).to_radians().unwrap() - 90_f64.to_radians()).abs() < 1e-9);
+    }
+}
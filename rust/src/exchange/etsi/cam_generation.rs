@@ -0,0 +1,261 @@
+/*
+ * Software Name : libits-client
+ * SPDX-FileCopyrightText: Copyright (c) Orange SA
+ * SPDX-License-Identifier: MIT
+ *
+ * This software is distributed under the MIT license,
+ * see the "LICENSE.txt" file for more details or https://opensource.org/license/MIT/
+ *
+ * Authors: see CONTRIBUTORS.md
+ */
+
+//! CAM generation frequency management, as defined by ETSI EN 302 637-2
+//!
+//! A CAM is generated at most every 100 ms and at least every second; in between, a new CAM is
+//! generated as soon as the station's dynamics changed significantly since the last one
+
+use std::time::Duration;
+
+use crate::mobility::angle::bearing_difference;
+use crate::mobility::mobile::Mobile;
+use crate::mobility::position::haversine_distance;
+
+/// Heading change triggering a CAM, 4° in radians
+const HEADING_THRESHOLD: f64 = 4. * std::f64::consts::PI / 180.;
+/// Position change triggering a CAM, in meters
+const POSITION_THRESHOLD: f64 = 4.;
+/// Speed change triggering a CAM, in m/s
+const SPEED_THRESHOLD: f64 = 0.5;
+
+/// Condition for which a new CAM has to be generated
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TriggerCondition {
+    /// The heading changed by more than 4°
+    Heading,
+    /// The position changed by more than 4 m
+    Position,
+    /// The speed changed by more than 0.5 m/s
+    Speed,
+    /// No CAM has been generated for the maximum interval
+    MaxInterval,
+}
+
+/// Decides when a new CAM has to be generated from the station's dynamics
+#[derive(Clone, Debug)]
+pub struct CamTrigger {
+    min_interval: Duration,
+    max_interval: Duration,
+}
+
+impl CamTrigger {
+    /// Creates a trigger with custom interval bounds
+    ///
+    /// The minimum interval can be raised above 100 ms by the congestion control (DCC)
+    pub fn new(min_interval: Duration, max_interval: Duration) -> Self {
+        Self {
+            min_interval,
+            max_interval,
+        }
+    }
+
+    /// Returns the condition for which a new CAM has to be generated, None if it does not have to
+    ///
+    /// `previous` is the state sent in the last CAM, `current` the current station state and
+    /// `elapsed` the time since the last CAM was generated; heading and speed changes are only
+    /// checked when both states provide them
+    pub fn check(
+        &self,
+        previous: &dyn Mobile,
+        current: &dyn Mobile,
+        elapsed: Duration,
+    ) -> Option<TriggerCondition> {
+        if elapsed < self.min_interval {
+            return None;
+        }
+
+        if let (Some(previous_heading), Some(current_heading)) =
+            (previous.heading(), current.heading())
+        {
+            if bearing_difference(previous_heading, current_heading).abs() > HEADING_THRESHOLD {
+                return Some(TriggerCondition::Heading);
+            }
+        }
+
+        if haversine_distance(&previous.position(), &current.position()) > POSITION_THRESHOLD {
+            return Some(TriggerCondition::Position);
+        }
+
+        if let (Some(previous_speed), Some(current_speed)) = (previous.speed(), current.speed()) {
+            if (current_speed - previous_speed).abs() > SPEED_THRESHOLD {
+                return Some(TriggerCondition::Speed);
+            }
+        }
+
+        if elapsed >= self.max_interval {
+            return Some(TriggerCondition::MaxInterval);
+        }
+
+        None
+    }
+
+    /// Returns true if a new CAM has to be generated, see [check][1]
+    ///
+    /// [1]: CamTrigger::check
+    pub fn should_generate(
+        &self,
+        previous: &dyn Mobile,
+        current: &dyn Mobile,
+        elapsed: Duration,
+    ) -> bool {
+        self.check(previous, current, elapsed).is_some()
+    }
+}
+
+impl Default for CamTrigger {
+    /// ETSI bounds: from 100 ms (10 Hz) to 1 s (1 Hz)
+    fn default() -> Self {
+        Self::new(Duration::from_millis(100), Duration::from_secs(1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::exchange::etsi::cam_generation::{CamTrigger, TriggerCondition};
+    use crate::mobility::mobile::MobileSnapshot;
+    use crate::mobility::position::{haversine_destination, position_from_degrees};
+
+    fn state() -> MobileSnapshot {
+        MobileSnapshot {
+            id: 42,
+            position: position_from_degrees(48.6263556, 2.2492123, 0.),
+            speed: Some(10.),
+            heading: Some(90_f64.to_radians()),
+            acceleration: None,
+            timestamp: None,
+        }
+    }
+
+    macro_rules! test_check {
+        ($test_name:ident, $current:expr, $elapsed:expr, $expected:expr) => {
+            #[test]
+            fn $test_name() {
+                let trigger = CamTrigger::default();
+
+                let condition = trigger.check(&state(), &$current, Duration::from_millis($elapsed));
+
+                assert_eq!(condition, $expected);
+            }
+        };
+    }
+    test_check!(no_change_no_cam, state(), 500, None);
+    test_check!(
+        heading_change,
+        MobileSnapshot {
+            heading: Some(95_f64.to_radians()),
+            ..state()
+        },
+        100,
+        Some(TriggerCondition::Heading)
+    );
+    test_check!(
+        small_heading_change,
+        MobileSnapshot {
+            heading: Some(93_f64.to_radians()),
+            ..state()
+        },
+        100,
+        None
+    );
+    test_check!(
+        position_change,
+        MobileSnapshot {
+            position: haversine_destination(&state().position, 0., 4.5),
+            ..state()
+        },
+        100,
+        Some(TriggerCondition::Position)
+    );
+    test_check!(
+        small_position_change,
+        MobileSnapshot {
+            position: haversine_destination(&state().position, 0., 3.5),
+            ..state()
+        },
+        100,
+        None
+    );
+    test_check!(
+        speed_change,
+        MobileSnapshot {
+            speed: Some(10.6),
+            ..state()
+        },
+        100,
+        Some(TriggerCondition::Speed)
+    );
+    test_check!(
+        small_speed_change,
+        MobileSnapshot {
+            speed: Some(10.4),
+            ..state()
+        },
+        100,
+        None
+    );
+    test_check!(
+        unknown_speed_is_not_a_change,
+        MobileSnapshot {
+            speed: None,
+            ..state()
+        },
+        100,
+        None
+    );
+    test_check!(
+        max_interval_reached,
+        state(),
+        1_000,
+        Some(TriggerCondition::MaxInterval)
+    );
+    test_check!(
+        changes_before_min_interval_are_ignored,
+        MobileSnapshot {
+            speed: Some(20.),
+            heading: Some(180_f64.to_radians()),
+            ..state()
+        },
+        99,
+        None
+    );
+
+    #[test]
+    fn heading_change_across_north() {
+        let previous = MobileSnapshot {
+            heading: Some(358_f64.to_radians()),
+            ..state()
+        };
+        let current = MobileSnapshot {
+            heading: Some(3_f64.to_radians()),
+            ..state()
+        };
+
+        let condition =
+            CamTrigger::default().check(&previous, &current, Duration::from_millis(100));
+
+        assert_eq!(condition, Some(TriggerCondition::Heading));
+    }
+
+    #[test]
+    fn min_interval_can_be_raised() {
+        let trigger = CamTrigger::new(Duration::from_millis(300), Duration::from_secs(1));
+        let current = MobileSnapshot {
+            speed: Some(20.),
+            ..state()
+        };
+
+        assert!(!trigger.should_generate(&state(), &current, Duration::from_millis(200)));
+        assert!(trigger.should_generate(&state(), &current, Duration::from_millis(300)));
+    }
+}
@@ -11,51 +11,77 @@
 
 use crate::client::configuration::Configuration;
 use crate::exchange::etsi::mobile_perceived_object::MobilePerceivedObject;
-use crate::exchange::etsi::perceived_object::PerceivedObject;
+use crate::exchange::etsi::perceived_object::{ObjectClass, PerceivedObject};
+use crate::exchange::etsi::quantity::{Acceleration, Speed};
 use crate::exchange::etsi::reference_position::ReferencePosition;
-use crate::exchange::etsi::{
-    acceleration_from_etsi, heading_from_etsi, speed_from_etsi, PositionConfidence,
-};
+use crate::exchange::etsi::{heading_from_etsi_opt, PositionConfidence};
 use crate::exchange::message::content::Content;
 use crate::exchange::message::content_error::ContentError;
 use crate::exchange::message::content_error::ContentError::{
     MissingStationDataContainer, NotAMortal, RsuOriginatingMessage,
 };
 use crate::exchange::mortal::Mortal;
+use crate::mobility::geofence::Polygon;
 use crate::mobility::mobile::Mobile;
 use crate::mobility::position::Position;
 use serde::{Deserialize, Serialize};
 use std::any::type_name;
+use std::f64::consts::PI;
 
 #[serde_with::skip_serializing_none]
 #[derive(Default, Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CollectivePerceptionMessage {
+    #[serde(alias = "protocolVersion")]
     pub protocol_version: u8,
+    #[serde(alias = "stationId")]
     pub station_id: u32,
     // pub message_id: u8,
+    #[serde(alias = "generationDeltaTime")]
     pub generation_delta_time: u16,
+    #[serde(alias = "managementContainer")]
     pub management_container: ManagementContainer,
+    #[serde(alias = "stationDataContainer")]
     pub station_data_container: Option<StationDataContainer>,
     #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    #[serde(alias = "sensorInformationContainer")]
     pub sensor_information_container: Vec<SensorInformation>,
     #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    #[serde(alias = "perceivedObjectContainer")]
     pub perceived_object_container: Vec<PerceivedObject>,
     #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    #[serde(alias = "freeSpaceAddendumContainer")]
     pub free_space_addendum_container: Vec<FreeSpaceAddendum>,
 }
 
 #[serde_with::skip_serializing_none]
 #[derive(Default, Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ManagementContainer {
+    #[serde(alias = "stationType")]
     pub station_type: u8,
+    #[serde(alias = "referencePosition")]
     pub reference_position: ReferencePosition,
     pub confidence: PositionConfidence,
+    #[serde(alias = "segmentationInfo")]
+    pub segmentation_info: Option<SegmentationInfo>,
+}
+
+/// Position of a CPM among the messages its perceived objects are split across
+#[derive(Default, Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SegmentationInfo {
+    /// Number of messages of the set, from 1 to 127
+    #[serde(alias = "totalMsgNo")]
+    pub total_msg_no: u8,
+    /// Number of this message in the set, from 1 to `total_msg_no`
+    #[serde(alias = "thisMsgNo")]
+    pub this_msg_no: u8,
 }
 
 #[serde_with::skip_serializing_none]
 #[derive(Default, Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct StationDataContainer {
+    #[serde(alias = "originatingVehicleContainer")]
     pub originating_vehicle_container: Option<OriginatingVehicleContainer>,
+    #[serde(alias = "originatingRsuContainer")]
     pub originating_rsu_container: Option<OriginatingRSUContainer>,
 }
 
@@ -64,12 +90,19 @@ pub struct StationDataContainer {
 pub struct OriginatingVehicleContainer {
     pub heading: u16,
     pub speed: u16,
+    #[serde(alias = "driveDirection")]
     pub drive_direction: Option<u8>,
+    #[serde(alias = "vehicleLength")]
     pub vehicle_length: Option<u16>,
+    #[serde(alias = "vehicleWidth")]
     pub vehicle_width: Option<u8>,
+    #[serde(alias = "longitudinalAcceleration")]
     pub longitudinal_acceleration: Option<i16>,
+    #[serde(alias = "yawRate")]
     pub yaw_rate: Option<i16>,
+    #[serde(alias = "lateralAcceleration")]
     pub lateral_acceleration: Option<i16>,
+    #[serde(alias = "verticalAcceleration")]
     pub vertical_acceleration: Option<i16>,
     pub confidence: OriginatingVehicleContainerConfidence,
 }
@@ -77,14 +110,18 @@ pub struct OriginatingVehicleContainer {
 #[serde_with::skip_serializing_none]
 #[derive(Default, Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct OriginatingRSUContainer {
+    #[serde(alias = "intersectionReferenceId")]
     pub intersection_reference_id: Option<IntersectionReferenceId>,
+    #[serde(alias = "roadSegmentReferenceId")]
     pub road_segment_reference_id: Option<u32>,
 }
 
 #[serde_with::skip_serializing_none]
 #[derive(Default, Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct IntersectionReferenceId {
+    #[serde(alias = "roadRegulatorId")]
     pub road_regulator_id: Option<u32>,
+    #[serde(alias = "intersectionId")]
     pub intersection_id: u32,
 }
 
@@ -93,41 +130,59 @@ pub struct IntersectionReferenceId {
 pub struct OriginatingVehicleContainerConfidence {
     pub heading: u8,
     pub speed: u8,
+    #[serde(alias = "vehicleLength")]
     pub vehicle_length: Option<u8>,
+    #[serde(alias = "yawRate")]
     pub yaw_rate: Option<u8>,
+    #[serde(alias = "longitudinalAcceleration")]
     pub longitudinal_acceleration: Option<u8>,
+    #[serde(alias = "lateralAcceleration")]
     pub lateral_acceleration: Option<u8>,
+    #[serde(alias = "verticalAcceleration")]
     pub vertical_acceleration: Option<u8>,
 }
 
 #[serde_with::skip_serializing_none]
 #[derive(Default, Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct SensorInformation {
+    #[serde(alias = "sensorId")]
     pub sensor_id: u8,
     #[serde(rename = "type")]
     pub sensor_type: u8,
+    #[serde(alias = "detectionArea")]
     pub detection_area: DetectionArea,
 }
 
 #[serde_with::skip_serializing_none]
 #[derive(Default, Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct DetectionArea {
+    #[serde(alias = "vehicleSensor")]
     pub vehicle_sensor: Option<VehicleSensor>,
+    #[serde(alias = "stationarySensorPolygon")]
     pub stationary_sensor_polygon: Option<Vec<Offset>>,
+    #[serde(alias = "stationarySensorRadial")]
     pub stationary_sensor_radial: Option<StationarySensorRadial>,
+    #[serde(alias = "stationarySensorCircular")]
     pub stationary_sensor_circular: Option<CircularArea>,
+    #[serde(alias = "stationarySensorEllipse")]
     pub stationary_sensor_ellipse: Option<EllipticArea>,
+    #[serde(alias = "stationarySensorRectangle")]
     pub stationary_sensor_rectangle: Option<RectangleArea>,
 }
 
 #[serde_with::skip_serializing_none]
 #[derive(Default, Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct VehicleSensor {
+    #[serde(alias = "refPointId")]
     pub ref_point_id: u8,
+    #[serde(alias = "xSensorOffset")]
     pub x_sensor_offset: i16,
+    #[serde(alias = "ySensorOffset")]
     pub y_sensor_offset: i16,
+    #[serde(alias = "zSensorOffset")]
     pub z_sensor_offset: Option<u16>,
     #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    #[serde(alias = "vehicleSensorPropertyList")]
     pub vehicle_sensor_property_list: Vec<VehicleSensorProperty>,
 }
 
@@ -135,9 +190,13 @@ pub struct VehicleSensor {
 #[derive(Default, Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct VehicleSensorProperty {
     pub range: u16,
+    #[serde(alias = "horizontalOpeningAngleStart")]
     pub horizontal_opening_angle_start: u16,
+    #[serde(alias = "horizontalOpeningAngleEnd")]
     pub horizontal_opening_angle_end: u16,
+    #[serde(alias = "verticalOpeningAngleStart")]
     pub vertical_opening_angle_start: Option<u16>,
+    #[serde(alias = "verticalOpeningAngleEnd")]
     pub vertical_opening_angle_end: Option<u16>,
 }
 
@@ -145,16 +204,22 @@ pub struct VehicleSensorProperty {
 #[derive(Default, Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct StationarySensorRadial {
     pub range: u16,
+    #[serde(alias = "horizontalOpeningAngleStart")]
     pub horizontal_opening_angle_start: u16,
+    #[serde(alias = "horizontalOpeningAngleEnd")]
     pub horizontal_opening_angle_end: u16,
+    #[serde(alias = "verticalOpeningAngleStart")]
     pub vertical_opening_angle_start: Option<u16>,
+    #[serde(alias = "verticalOpeningAngleEnd")]
     pub vertical_opening_angle_end: Option<u16>,
+    #[serde(alias = "sensorPositionOffset")]
     pub sensor_position_offset: Option<Offset>,
 }
 
 #[serde_with::skip_serializing_none]
 #[derive(Default, Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CircularArea {
+    #[serde(alias = "nodeCenterPoint")]
     pub node_center_point: Option<Offset>,
     pub radius: u16,
 }
@@ -162,20 +227,30 @@ pub struct CircularArea {
 #[serde_with::skip_serializing_none]
 #[derive(Default, Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct EllipticArea {
+    #[serde(alias = "semiMajorRangeLength")]
     pub semi_major_range_length: u16,
+    #[serde(alias = "semiMinorRangeLength")]
     pub semi_minor_range_length: u16,
+    #[serde(alias = "semiMajorRangeOrientation")]
     pub semi_major_range_orientation: u16,
+    #[serde(alias = "nodeCenterPoint")]
     pub node_center_point: Option<Offset>,
+    #[serde(alias = "semiHeight")]
     pub semi_height: Option<u16>,
 }
 
 #[serde_with::skip_serializing_none]
 #[derive(Default, Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct RectangleArea {
+    #[serde(alias = "semiMajorRangeLength")]
     pub semi_major_range_length: u16,
+    #[serde(alias = "semiMinorRangeLength")]
     pub semi_minor_range_length: u16,
+    #[serde(alias = "semiMajorRangeOrientation")]
     pub semi_major_range_orientation: u16,
+    #[serde(alias = "nodeCenterPoint")]
     pub node_center_point: Option<Offset>,
+    #[serde(alias = "semiHeight")]
     pub semi_height: Option<u16>,
 }
 
@@ -190,19 +265,27 @@ pub struct Offset {
 #[serde_with::skip_serializing_none]
 #[derive(Default, Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct FreeSpaceAddendum {
+    #[serde(alias = "freeSpaceArea")]
     pub free_space_area: FreeSpaceArea,
+    #[serde(alias = "freeSpaceConfidence")]
     pub free_space_confidence: u8,
     #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    #[serde(alias = "sensorIdList")]
     pub sensor_id_list: Vec<u8>,
+    #[serde(alias = "shadowingApplies")]
     pub shadowing_applies: Option<bool>,
 }
 
 #[serde_with::skip_serializing_none]
 #[derive(Default, Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct FreeSpaceArea {
+    #[serde(alias = "freeSpacePolygon")]
     pub free_space_polygon: Option<Vec<Offset>>,
+    #[serde(alias = "freeSpaceCircular")]
     pub free_space_circular: Option<CircularArea>,
+    #[serde(alias = "freeSpaceEllipse")]
     pub free_space_ellipse: Option<EllipticArea>,
+    #[serde(alias = "freeSpaceRectangle")]
     pub free_space_rectangle: Option<RectangleArea>,
 }
 
@@ -215,11 +298,50 @@ impl CollectivePerceptionMessage {
                     //assumed clone : we store a copy into the MobilePerceivedObject container
                     // TODO use a lifetime to propage the lifecycle betwwen PerceivedObject and MobilePerceivedObject instead of clone
                     perceived_object.clone(),
-                    &self,
+                    self,
                 )
             })
             .collect()
     }
+
+    /// Returns the perceived objects having at least one classification matching the predicate
+    ///
+    /// ```
+    /// # use libits::exchange::etsi::collective_perception_message::CollectivePerceptionMessage;
+    /// # use libits::exchange::etsi::perceived_object::{ObjectClass, SingleVruClass};
+    /// # let cpm = CollectivePerceptionMessage::default();
+    /// let cyclists_and_pedestrians = cpm.perceived_objects_of_class(|class| {
+    ///     matches!(
+    ///         class,
+    ///         ObjectClass::SingleVru(SingleVruClass::Pedestrian(_))
+    ///             | ObjectClass::SingleVru(SingleVruClass::Bicyclist(_))
+    ///     )
+    /// });
+    /// ```
+    pub fn perceived_objects_of_class<P>(&self, predicate: P) -> Vec<&PerceivedObject>
+    where
+        P: Fn(&ObjectClass) -> bool,
+    {
+        self.perceived_object_container
+            .iter()
+            .filter(|perceived_object| perceived_object.has_class(&predicate))
+            .collect()
+    }
+
+    /// Returns the perceived objects measured at most `max_age_ms` milliseconds before the
+    /// message's generation
+    ///
+    /// The measurement age is the object's `time_of_measurement`, objects measured after the
+    /// generation time (negative value) are fresh; `object_age` is not used as it tells for how
+    /// long the object has been tracked, not how old its last measurement is
+    pub fn fresh_perceived_objects(&self, max_age_ms: u16) -> Vec<&PerceivedObject> {
+        self.perceived_object_container
+            .iter()
+            .filter(|perceived_object| {
+                i32::from(perceived_object.time_of_measurement) <= i32::from(max_age_ms)
+            })
+            .collect()
+    }
 }
 
 impl Mobile for CollectivePerceptionMessage {
@@ -227,6 +349,9 @@ impl Mobile for CollectivePerceptionMessage {
         self.station_id
     }
 
+    /// Returns the reference position, only checked when [seen as a mobile][1]
+    ///
+    /// [1]: Content::as_mobile
     fn position(&self) -> Position {
         self.management_container.reference_position.as_position()
     }
@@ -236,7 +361,7 @@ impl Mobile for CollectivePerceptionMessage {
             if let Some(originating_vehicle_container) =
                 &station_data_container.originating_vehicle_container
             {
-                return Some(speed_from_etsi(originating_vehicle_container.speed));
+                return Speed(originating_vehicle_container.speed).as_si();
             }
         }
         None
@@ -247,7 +372,7 @@ impl Mobile for CollectivePerceptionMessage {
             if let Some(originating_vehicle_container) =
                 &station_data_container.originating_vehicle_container
             {
-                return Some(heading_from_etsi(originating_vehicle_container.heading));
+                return heading_from_etsi_opt(originating_vehicle_container.heading);
             }
         }
         None
@@ -260,7 +385,7 @@ impl Mobile for CollectivePerceptionMessage {
             {
                 return originating_vehicle_container
                     .longitudinal_acceleration
-                    .map(acceleration_from_etsi);
+                    .and_then(|acceleration| Acceleration(acceleration).as_si());
             }
         }
         None
@@ -280,7 +405,12 @@ impl Content for CollectivePerceptionMessage {
     fn as_mobile(&self) -> Result<&dyn Mobile, ContentError> {
         match &self.station_data_container {
             Some(container) => match container.originating_vehicle_container {
-                Some(_) => Ok(self),
+                Some(_) => {
+                    self.management_container
+                        .reference_position
+                        .try_as_position()?;
+                    Ok(self)
+                }
                 None => Err(RsuOriginatingMessage(type_name::<
                     CollectivePerceptionMessage,
                 >())),
@@ -294,6 +424,44 @@ impl Content for CollectivePerceptionMessage {
     fn as_mortal(&self) -> Result<&dyn Mortal, ContentError> {
         Err(NotAMortal(type_name::<CollectivePerceptionMessage>()))
     }
+
+    /// Returns the bounding box of the station and of its perceived objects, None if there is no
+    /// perceived object or if the station's position is unavailable
+    fn covering_area(&self) -> Option<Polygon> {
+        if self.perceived_object_container.is_empty() {
+            return None;
+        }
+        let reference = self
+            .management_container
+            .reference_position
+            .try_as_position()
+            .ok()?;
+        let wrap = |longitude: f64| (longitude + PI).rem_euclid(2. * PI) - PI;
+
+        let (mut south, mut north) = (reference.latitude, reference.latitude);
+        let (mut west, mut east) = (0_f64, 0_f64);
+        for object in self.mobile_perceived_object_list() {
+            let position = object.position();
+            south = south.min(position.latitude);
+            north = north.max(position.latitude);
+            let offset = wrap(position.longitude - reference.longitude);
+            west = west.min(offset);
+            east = east.max(offset);
+        }
+
+        let corner = |latitude: f64, offset: f64| Position {
+            latitude,
+            longitude: wrap(reference.longitude + offset),
+            altitude: None,
+        };
+        Polygon::new(vec![
+            corner(north, west),
+            corner(north, east),
+            corner(south, east),
+            corner(south, west),
+        ])
+        .ok()
+    }
 }
 
 #[cfg(test)]
@@ -303,11 +471,13 @@ mod tests {
         ManagementContainer, Offset, RectangleArea, StationarySensorRadial,
     };
 
-    use crate::exchange::etsi::perceived_object::PerceivedObject;
+    use crate::exchange::etsi::perceived_object::{ObjectClass, PerceivedObject, SingleVruClass};
     use crate::exchange::etsi::reference_position::{
         altitude_from_etsi, coordinate_from_etsi, ReferencePosition,
     };
     use crate::exchange::etsi::speed_from_etsi;
+    use crate::exchange::message::content::Content;
+    use crate::mobility::mobile::Mobile;
 
     macro_rules! assert_float_eq {
         ($a:expr, $b:expr, $e:expr) => {
@@ -328,6 +498,7 @@ mod tests {
                     altitude: 900,
                 },
                 confidence: Default::default(),
+                segmentation_info: None,
             },
             perceived_object_container: vec![
                 PerceivedObject {
@@ -375,7 +546,11 @@ mod tests {
             coordinate_from_etsi(23680844),
             1e-6
         );
-        assert_float_eq!(first.position.altitude, altitude_from_etsi(900), 1e-3);
+        assert_float_eq!(
+            first.position.altitude.unwrap(),
+            altitude_from_etsi(900),
+            1e-3
+        );
         assert_float_eq!(first.speed, speed_from_etsi(389), 1e-5);
         assert_float_eq!(first.heading.to_degrees(), 86.3, 1e-1);
 
@@ -390,7 +565,11 @@ mod tests {
             coordinate_from_etsi(23679078),
             1e-6
         );
-        assert_float_eq!(second.position.altitude, altitude_from_etsi(900), 1e-3);
+        assert_float_eq!(
+            second.position.altitude.unwrap(),
+            altitude_from_etsi(900),
+            1e-3
+        );
         assert_float_eq!(second.speed, speed_from_etsi(18), 1e-5);
         assert_float_eq!(second.heading.to_degrees(), 29.3, 1e-1);
     }
@@ -856,6 +1035,22 @@ mod tests {
         match serde_json::from_str::<CollectivePerceptionMessage>(data) {
             Ok(cpm) => {
                 assert_eq!(cpm.station_id, 0);
+
+                let pedestrians = cpm.perceived_objects_of_class(|class| {
+                    matches!(class, ObjectClass::SingleVru(SingleVruClass::Pedestrian(_)))
+                });
+                assert_eq!(pedestrians.len(), 13);
+                assert!(pedestrians.iter().all(|po| po.is_pedestrian()));
+
+                let bicyclists = cpm.perceived_objects_of_class(|class| {
+                    matches!(class, ObjectClass::SingleVru(SingleVruClass::Bicyclist(_)))
+                });
+                assert_eq!(bicyclists.len(), 3);
+                assert!(bicyclists.iter().all(|po| po.is_bicyclist()));
+
+                let vehicles = cpm
+                    .perceived_objects_of_class(|class| matches!(class, ObjectClass::Vehicle(_)));
+                assert!(vehicles.is_empty());
             }
             Err(e) => {
                 panic!("Failed to deserialize CPM: '{}'", e);
@@ -1493,4 +1688,93 @@ mod tests {
             Err(e) => panic!("Failed to deserialize FreeSpaceAddendum: '{}'", e),
         }
     }
+
+    #[test]
+    fn fresh_perceived_objects_drop_stale_measurements() {
+        let object = |object_id: u8, time_of_measurement: i16, object_age: u16| PerceivedObject {
+            object_id,
+            time_of_measurement,
+            object_age,
+            ..Default::default()
+        };
+        let cpm = CollectivePerceptionMessage {
+            perceived_object_container: vec![
+                object(1, 50, 1500),
+                object(2, 200, 100),
+                object(3, 100, 0),
+                object(4, -20, 1500),
+                object(5, 1500, 1500),
+            ],
+            ..Default::default()
+        };
+
+        let fresh_ids = cpm
+            .fresh_perceived_objects(100)
+            .iter()
+            .map(|perceived_object| perceived_object.object_id)
+            .collect::<Vec<u8>>();
+
+        assert_eq!(fresh_ids, vec![1, 3, 4]);
+        assert_eq!(cpm.fresh_perceived_objects(1500).len(), 5);
+        assert_eq!(cpm.fresh_perceived_objects(0).len(), 1);
+    }
+
+    fn cpm_around_origin(
+        perceived_object_container: Vec<PerceivedObject>,
+    ) -> CollectivePerceptionMessage {
+        CollectivePerceptionMessage {
+            management_container: ManagementContainer {
+                station_type: 15,
+                reference_position: ReferencePosition {
+                    latitude: 10,
+                    longitude: 10,
+                    altitude: 0,
+                },
+                ..Default::default()
+            },
+            perceived_object_container,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn no_covering_area_without_perceived_objects() {
+        assert!(cpm_around_origin(Vec::new()).covering_area().is_none());
+    }
+
+    #[test]
+    fn covering_area_bounds_the_station_and_its_perceived_objects() {
+        let cpm = cpm_around_origin(vec![PerceivedObject {
+            object_id: 1,
+            x_distance: -500,
+            y_distance: -500,
+            ..Default::default()
+        }]);
+
+        let area = cpm
+            .covering_area()
+            .expect("CPM with objects must have an area");
+
+        let reference = cpm
+            .management_container
+            .reference_position
+            .try_as_position()
+            .unwrap();
+        let object = cpm.mobile_perceived_object_list()[0].position();
+        let (south, north) = (
+            object.latitude.min(reference.latitude),
+            object.latitude.max(reference.latitude),
+        );
+        let (west, east) = (
+            object.longitude.min(reference.longitude),
+            object.longitude.max(reference.longitude),
+        );
+        assert!(south < 0. && north > 0.);
+        assert!(west < 0. && east > 0.);
+        for vertex in area.vertices() {
+            let close = |a: f64, b: f64| (a - b).abs() < 1e-12;
+            assert!(close(vertex.latitude, south) || close(vertex.latitude, north));
+            assert!(close(vertex.longitude, west) || close(vertex.longitude, east));
+        }
+    }
 }
@@ -9,9 +9,10 @@
  * Authors: see CONTRIBUTORS.md
  */
 
+use crate::exchange::etsi::quantity::{Acceleration, Speed};
 use crate::exchange::etsi::reference_position::ReferencePosition;
 use crate::exchange::etsi::{
-    acceleration_from_etsi, heading_from_etsi, speed_from_etsi, PathHistory, PositionConfidence,
+    heading_confidence_from_etsi, heading_from_etsi_opt, PathHistory, PositionConfidence,
 };
 use crate::mobility::mobile::Mobile;
 use std::any::type_name;
@@ -23,22 +24,31 @@ use crate::exchange::message::content_error::ContentError::NotAMortal;
 use crate::exchange::mortal::Mortal;
 use crate::mobility::position::Position;
 use serde::{Deserialize, Serialize};
+use tracing::warn;
 
 #[serde_with::skip_serializing_none]
 #[derive(Default, Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CooperativeAwarenessMessage {
+    #[serde(alias = "protocolVersion")]
     pub protocol_version: u8,
+    #[serde(alias = "stationId")]
     pub station_id: u32,
+    #[serde(alias = "generationDeltaTime")]
     pub generation_delta_time: u16,
+    #[serde(alias = "basicContainer")]
     pub basic_container: BasicContainer,
+    #[serde(alias = "highFrequencyContainer")]
     pub high_frequency_container: HighFrequencyContainer,
+    #[serde(alias = "lowFrequencyContainer")]
     pub low_frequency_container: Option<LowFrequencyContainer>,
 }
 
 #[serde_with::skip_serializing_none]
 #[derive(Default, Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct BasicContainer {
+    #[serde(alias = "stationType")]
     pub station_type: Option<u8>,
+    #[serde(alias = "referencePosition")]
     pub reference_position: ReferencePosition,
     pub confidence: Option<PositionConfidence>,
 }
@@ -48,16 +58,26 @@ pub struct BasicContainer {
 pub struct HighFrequencyContainer {
     pub heading: Option<u16>,
     pub speed: Option<u16>,
+    #[serde(alias = "driveDirection")]
     pub drive_direction: Option<u8>,
+    #[serde(alias = "vehicleLength")]
     pub vehicle_length: Option<u16>,
+    #[serde(alias = "vehicleWidth")]
     pub vehicle_width: Option<u16>,
     pub curvature: Option<i16>,
+    #[serde(alias = "curvatureCalculationMode")]
     pub curvature_calculation_mode: Option<u8>,
+    #[serde(alias = "longitudinalAcceleration")]
     pub longitudinal_acceleration: Option<i16>,
+    #[serde(alias = "yawRate")]
     pub yaw_rate: Option<i16>,
+    #[serde(alias = "accelerationControl")]
     pub acceleration_control: Option<String>,
+    #[serde(alias = "lanePosition")]
     pub lane_position: Option<i8>,
+    #[serde(alias = "lateralAcceleration")]
     pub lateral_acceleration: Option<i16>,
+    #[serde(alias = "verticalAcceleration")]
     pub vertical_acceleration: Option<i16>,
     pub confidence: Option<HighFrequencyConfidence>,
 }
@@ -65,8 +85,11 @@ pub struct HighFrequencyContainer {
 #[serde_with::skip_serializing_none]
 #[derive(Default, Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct LowFrequencyContainer {
+    #[serde(alias = "vehicleRole")]
     pub vehicle_role: Option<u8>,
+    #[serde(alias = "exteriorLights")]
     pub exterior_lights: String,
+    #[serde(alias = "pathHistory")]
     pub path_history: Vec<PathHistory>,
 }
 
@@ -75,11 +98,16 @@ pub struct LowFrequencyContainer {
 pub struct HighFrequencyConfidence {
     pub heading: Option<u8>,
     pub speed: Option<u8>,
+    #[serde(alias = "vehicleLength")]
     pub vehicle_length: Option<u8>,
+    #[serde(alias = "yawRate")]
     pub yaw_rate: Option<u8>,
+    #[serde(alias = "longitudinalAcceleration")]
     pub longitudinal_acceleration: Option<u8>,
     pub curvature: Option<u8>,
+    #[serde(alias = "lateralAcceleration")]
     pub lateral_acceleration: Option<u8>,
+    #[serde(alias = "verticalAcceleration")]
     pub vertical_acceleration: Option<u8>,
 }
 
@@ -88,22 +116,53 @@ impl Mobile for CooperativeAwarenessMessage {
         self.station_id
     }
 
+    /// Returns the reference position, only checked when [seen as a mobile][1]
+    ///
+    /// [1]: Content::as_mobile
     fn position(&self) -> Position {
         self.basic_container.reference_position.as_position()
     }
 
     fn speed(&self) -> Option<f64> {
-        self.high_frequency_container.speed.map(speed_from_etsi)
+        self.high_frequency_container
+            .speed
+            .and_then(|speed| Speed(speed).as_si())
     }
 
     fn heading(&self) -> Option<f64> {
-        self.high_frequency_container.heading.map(heading_from_etsi)
+        self.high_frequency_container
+            .heading
+            .and_then(heading_from_etsi_opt)
     }
 
     fn acceleration(&self) -> Option<f64> {
         self.high_frequency_container
             .longitudinal_acceleration
-            .map(acceleration_from_etsi)
+            .and_then(|acceleration| Acceleration(acceleration).as_si())
+    }
+
+    fn speed_confidence(&self) -> Option<f64> {
+        self.high_frequency_container
+            .confidence
+            .as_ref()
+            .and_then(|confidence| confidence.speed)
+            .and_then(Speed::confidence_si)
+    }
+
+    fn heading_confidence(&self) -> Option<f64> {
+        self.high_frequency_container
+            .confidence
+            .as_ref()
+            .and_then(|confidence| confidence.heading)
+            .and_then(heading_confidence_from_etsi)
+    }
+
+    fn acceleration_confidence(&self) -> Option<f64> {
+        self.high_frequency_container
+            .confidence
+            .as_ref()
+            .and_then(|confidence| confidence.longitudinal_acceleration)
+            .and_then(Acceleration::confidence_si)
     }
 }
 
@@ -121,11 +180,18 @@ impl Content for CooperativeAwarenessMessage {
             .read()
             .unwrap()
             .station_id(Some(self.station_id));
-        self.station_id = station_id;
+        match station_id {
+            Some(station_id) => self.station_id = station_id,
+            None => warn!(
+                "No station id can be derived from {}, kept as is",
+                self.station_id
+            ),
+        }
         // TODO update the generation delta time
     }
 
     fn as_mobile(&self) -> Result<&dyn Mobile, ContentError> {
+        self.basic_container.reference_position.try_as_position()?;
         Ok(self)
     }
 
@@ -133,3 +199,211 @@ impl Content for CooperativeAwarenessMessage {
         Err(NotAMortal(type_name::<CooperativeAwarenessMessage>()))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::exchange::etsi::cooperative_awareness_message::{
+        BasicContainer, CooperativeAwarenessMessage,
+    };
+    use crate::exchange::etsi::reference_position::ReferencePosition;
+    use crate::exchange::message::content::Content;
+    use crate::mobility::mobile::{to_geojson, Mobile};
+
+    macro_rules! assert_float_eq {
+        ($a:expr, $b:expr, $e:expr) => {
+            let delta = ($a - $b).abs();
+            assert!(delta <= $e, "Actual:   {}\nExpected: {}", $a, $b)
+        };
+    }
+
+    #[test]
+    fn confidences_are_converted_to_si() {
+        let data = r#"{
+            "protocol_version": 1,
+            "station_id": 42,
+            "generation_delta_time": 3,
+            "basic_container": {
+                "reference_position": {
+                    "latitude": 486263556,
+                    "longitude": 22492123,
+                    "altitude": 20000
+                }
+            },
+            "high_frequency_container": {
+                "heading": 900,
+                "speed": 1500,
+                "longitudinal_acceleration": 12,
+                "confidence": {
+                    "heading": 20,
+                    "speed": 3,
+                    "longitudinal_acceleration": 5
+                }
+            }
+        }"#;
+
+        let cam = serde_json::from_str::<CooperativeAwarenessMessage>(data)
+            .expect("Failed to deserialize CAM");
+
+        assert_float_eq!(cam.heading_confidence().unwrap(), 2_f64.to_radians(), 1e-9);
+        assert_float_eq!(cam.speed_confidence().unwrap(), 0.03, 1e-9);
+        assert_float_eq!(cam.acceleration_confidence().unwrap(), 0.5, 1e-9);
+    }
+
+    #[test]
+    fn unavailable_confidences_are_none() {
+        let data = r#"{
+            "protocol_version": 1,
+            "station_id": 42,
+            "generation_delta_time": 3,
+            "basic_container": {
+                "reference_position": {
+                    "latitude": 486263556,
+                    "longitude": 22492123,
+                    "altitude": 20000
+                }
+            },
+            "high_frequency_container": {
+                "confidence": {
+                    "heading": 127,
+                    "speed": 127
+                }
+            }
+        }"#;
+
+        let cam = serde_json::from_str::<CooperativeAwarenessMessage>(data)
+            .expect("Failed to deserialize CAM");
+
+        assert!(cam.heading_confidence().is_none());
+        assert!(cam.speed_confidence().is_none());
+        assert!(cam.acceleration_confidence().is_none());
+    }
+
+    #[test]
+    fn unavailable_values_are_none() {
+        let data = r#"{
+            "protocol_version": 1,
+            "station_id": 42,
+            "generation_delta_time": 3,
+            "basic_container": {
+                "reference_position": {
+                    "latitude": 486263556,
+                    "longitude": 22492123,
+                    "altitude": 20000
+                }
+            },
+            "high_frequency_container": {
+                "heading": 3601,
+                "speed": 16383,
+                "longitudinal_acceleration": 161
+            }
+        }"#;
+
+        let cam = serde_json::from_str::<CooperativeAwarenessMessage>(data)
+            .expect("Failed to deserialize CAM");
+
+        assert!(cam.heading().is_none());
+        assert!(cam.speed().is_none());
+        assert!(cam.acceleration().is_none());
+    }
+
+    #[test]
+    fn missing_confidence_container_is_none() {
+        let cam = CooperativeAwarenessMessage::default();
+
+        assert!(cam.heading_confidence().is_none());
+        assert!(cam.speed_confidence().is_none());
+        assert!(cam.acceleration_confidence().is_none());
+    }
+
+    #[test]
+    fn cam_to_geojson_feature() {
+        let data = r#"{
+            "protocol_version": 1,
+            "station_id": 42,
+            "generation_delta_time": 3,
+            "basic_container": {
+                "reference_position": {
+                    "latitude": 486263556,
+                    "longitude": 22492123,
+                    "altitude": 20000
+                }
+            },
+            "high_frequency_container": {
+                "heading": 900,
+                "speed": 1500
+            }
+        }"#;
+        let cam = serde_json::from_str::<CooperativeAwarenessMessage>(data)
+            .expect("Failed to deserialize CAM");
+
+        let feature = to_geojson(&cam);
+
+        assert_eq!(feature["type"], "Feature");
+        assert_eq!(feature["geometry"]["type"], "Point");
+        let coordinates = feature["geometry"]["coordinates"].as_array().unwrap();
+        assert_float_eq!(coordinates[0].as_f64().unwrap(), 2.2492123, 1e-9);
+        assert_float_eq!(coordinates[1].as_f64().unwrap(), 48.6263556, 1e-9);
+        assert_float_eq!(coordinates[2].as_f64().unwrap(), 200., 1e-9);
+        assert_eq!(feature["properties"]["id"], 42);
+        assert_float_eq!(feature["properties"]["speed"].as_f64().unwrap(), 15., 1e-9);
+        assert_float_eq!(
+            feature["properties"]["heading"].as_f64().unwrap(),
+            90_f64.to_radians(),
+            1e-9
+        );
+    }
+
+    #[test]
+    fn camel_case_keys_are_accepted() {
+        let data = r#"{
+            "protocolVersion": 1,
+            "stationId": 42,
+            "generationDeltaTime": 3,
+            "basicContainer": {
+                "stationType": 5,
+                "referencePosition": {
+                    "latitude": 486263556,
+                    "longitude": 22492123,
+                    "altitude": 20000
+                }
+            },
+            "highFrequencyContainer": {
+                "heading": 900,
+                "speed": 1500,
+                "longitudinalAcceleration": 12
+            }
+        }"#;
+
+        let cam = serde_json::from_str::<CooperativeAwarenessMessage>(data)
+            .expect("Failed to deserialize CAM");
+
+        assert_eq!(cam.station_id, 42);
+        assert_eq!(cam.generation_delta_time, 3);
+        assert_eq!(cam.basic_container.station_type, Some(5));
+        assert_eq!(cam.basic_container.reference_position.latitude, 486263556);
+        assert_eq!(
+            cam.high_frequency_container.longitudinal_acceleration,
+            Some(12)
+        );
+        let serialized = serde_json::to_value(&cam).unwrap();
+        assert_eq!(serialized["basic_container"]["station_type"], 5);
+        assert!(serialized.get("basicContainer").is_none());
+    }
+
+    #[test]
+    fn unavailable_reference_position_is_not_a_mobile() {
+        let cam = CooperativeAwarenessMessage {
+            basic_container: BasicContainer {
+                reference_position: ReferencePosition {
+                    latitude: 900000001,
+                    longitude: 22492123,
+                    altitude: 20000,
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        assert!(cam.as_mobile().is_err());
+    }
+}
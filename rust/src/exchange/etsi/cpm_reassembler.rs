@@ -0,0 +1,266 @@
+/*
+ * Software Name : libits-client
+ * SPDX-FileCopyrightText: Copyright (c) Orange SA
+ * SPDX-License-Identifier: MIT
+ *
+ * This software is distributed under the MIT license,
+ * see the "LICENSE.txt" file for more details or https://opensource.org/license/MIT/
+ *
+ * Authors: see CONTRIBUTORS.md
+ */
+
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+
+use thiserror::Error;
+use tracing::debug;
+
+use crate::exchange::etsi::collective_perception_message::CollectivePerceptionMessage;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum SegmentError {
+    #[error("Segment number {0} is out of the [1, {1}] range")]
+    InvalidSegmentNumber(u8, u8),
+    #[error("Segment announces {0} segments where the previous ones of its set announced {1}")]
+    InconsistentTotal(u8, u8),
+}
+
+/// Segments of a CPM set received so far
+struct PendingSet {
+    first_received: u64,
+    total: u8,
+    segments: BTreeMap<u8, CollectivePerceptionMessage>,
+}
+
+/// Merges the CPMs whose [segmentation info][1] shows they are split across several messages
+///
+/// Segments of a set share their station id and generation delta time; once all of them are
+/// received they are merged into a single CPM, holding the objects, sensors and free spaces of
+/// every segment
+///
+/// [1]: crate::exchange::etsi::collective_perception_message::SegmentationInfo
+pub struct CpmReassembler {
+    timeout: Duration,
+    pending: HashMap<(u32, u16), PendingSet>,
+}
+
+impl CpmReassembler {
+    /// Creates a reassembler flushing the sets still incomplete after `timeout`
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            pending: HashMap::new(),
+        }
+    }
+
+    /// Buffers the CPM, returns the merged CPM if it completes its set
+    ///
+    /// CPMs that are not segmented are returned as is; `timestamp` is the reception time in
+    /// milliseconds, used to [flush][1] incomplete sets
+    ///
+    /// Segments numbered outside of their set, or announcing another number of segments than
+    /// the previous ones of their set, are refused
+    ///
+    /// [1]: CpmReassembler::flush_expired
+    pub fn push(
+        &mut self,
+        cpm: CollectivePerceptionMessage,
+        timestamp: u64,
+    ) -> Result<Option<CollectivePerceptionMessage>, SegmentError> {
+        let Some(segmentation_info) = cpm.management_container.segmentation_info.clone() else {
+            return Ok(Some(cpm));
+        };
+        let (this_msg_no, total_msg_no) = (
+            segmentation_info.this_msg_no,
+            segmentation_info.total_msg_no,
+        );
+        if !(1..=total_msg_no).contains(&this_msg_no) {
+            return Err(SegmentError::InvalidSegmentNumber(
+                this_msg_no,
+                total_msg_no,
+            ));
+        }
+        if total_msg_no == 1 {
+            return Ok(Some(cpm));
+        }
+
+        let key = (cpm.station_id, cpm.generation_delta_time);
+        let set = self.pending.entry(key).or_insert_with(|| PendingSet {
+            first_received: timestamp,
+            total: total_msg_no,
+            segments: BTreeMap::new(),
+        });
+        if set.total != total_msg_no {
+            return Err(SegmentError::InconsistentTotal(total_msg_no, set.total));
+        }
+        set.segments.insert(this_msg_no, cpm);
+
+        if set.segments.len() >= usize::from(set.total) {
+            let set = self.pending.remove(&key).unwrap();
+            Ok(Some(merge(set)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Removes the sets still incomplete after the timeout and returns them merged from the
+    /// segments received so far
+    pub fn flush_expired(&mut self, timestamp: u64) -> Vec<CollectivePerceptionMessage> {
+        let timeout = self.timeout.as_millis() as u64;
+        let expired = self
+            .pending
+            .iter()
+            .filter(|(_, set)| timestamp.saturating_sub(set.first_received) >= timeout)
+            .map(|(key, _)| *key)
+            .collect::<Vec<(u32, u16)>>();
+
+        expired
+            .into_iter()
+            .filter_map(|key| self.pending.remove(&key))
+            .map(|set| {
+                debug!(
+                    "CPM set flushed with {} of {} segments",
+                    set.segments.len(),
+                    set.total
+                );
+                merge(set)
+            })
+            .collect()
+    }
+
+    /// Returns the number of sets waiting for segments
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+}
+
+/// Merges the segments into the lowest numbered one, in segment order
+fn merge(set: PendingSet) -> CollectivePerceptionMessage {
+    let mut segments = set.segments.into_values();
+    let mut merged = segments
+        .next()
+        .expect("A pending set has at least one segment");
+
+    for segment in segments {
+        merged
+            .sensor_information_container
+            .extend(segment.sensor_information_container);
+        merged
+            .perceived_object_container
+            .extend(segment.perceived_object_container);
+        merged
+            .free_space_addendum_container
+            .extend(segment.free_space_addendum_container);
+    }
+    merged.management_container.segmentation_info = None;
+
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::exchange::etsi::collective_perception_message::{
+        CollectivePerceptionMessage, ManagementContainer, SegmentationInfo,
+    };
+    use crate::exchange::etsi::cpm_reassembler::{CpmReassembler, SegmentError};
+    use crate::exchange::etsi::perceived_object::PerceivedObject;
+
+    fn segment(this_msg_no: u8, total_msg_no: u8) -> CollectivePerceptionMessage {
+        CollectivePerceptionMessage {
+            station_id: 42,
+            generation_delta_time: 1000,
+            management_container: ManagementContainer {
+                segmentation_info: Some(SegmentationInfo {
+                    total_msg_no,
+                    this_msg_no,
+                }),
+                ..Default::default()
+            },
+            perceived_object_container: vec![PerceivedObject {
+                object_id: this_msg_no,
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn complete_set_is_merged() {
+        let mut reassembler = CpmReassembler::new(Duration::from_secs(1));
+
+        assert!(reassembler.push(segment(2, 2), 0).unwrap().is_none());
+        let merged = reassembler.push(segment(1, 2), 10).unwrap().unwrap();
+
+        let object_ids = merged
+            .perceived_object_container
+            .iter()
+            .map(|object| object.object_id)
+            .collect::<Vec<u8>>();
+        assert_eq!(object_ids, vec![1, 2]);
+        assert!(merged.management_container.segmentation_info.is_none());
+        assert_eq!(reassembler.pending(), 0);
+    }
+
+    #[test]
+    fn unsegmented_cpm_is_returned_as_is() {
+        let mut reassembler = CpmReassembler::new(Duration::from_secs(1));
+        let cpm = CollectivePerceptionMessage::default();
+
+        assert_eq!(reassembler.push(cpm.clone(), 0), Ok(Some(cpm)));
+    }
+
+    #[test]
+    fn sets_are_keyed_by_station() {
+        let mut reassembler = CpmReassembler::new(Duration::from_secs(1));
+        let mut other_station = segment(2, 2);
+        other_station.station_id = 43;
+
+        assert!(reassembler.push(segment(1, 2), 0).unwrap().is_none());
+        assert!(reassembler.push(other_station, 0).unwrap().is_none());
+        assert_eq!(reassembler.pending(), 2);
+    }
+
+    #[test]
+    fn incomplete_set_is_flushed_after_timeout() {
+        let mut reassembler = CpmReassembler::new(Duration::from_secs(1));
+        assert!(reassembler.push(segment(1, 3), 0).unwrap().is_none());
+        assert!(reassembler.push(segment(3, 3), 100).unwrap().is_none());
+
+        assert!(reassembler.flush_expired(999).is_empty());
+        let flushed = reassembler.flush_expired(1_000);
+
+        assert_eq!(flushed.len(), 1);
+        assert_eq!(flushed[0].perceived_object_container.len(), 2);
+        assert_eq!(reassembler.pending(), 0);
+    }
+
+    #[test]
+    fn segment_out_of_its_set_is_err() {
+        let mut reassembler = CpmReassembler::new(Duration::from_secs(1));
+
+        assert_eq!(
+            reassembler.push(segment(0, 2), 0),
+            Err(SegmentError::InvalidSegmentNumber(0, 2))
+        );
+        assert_eq!(
+            reassembler.push(segment(3, 2), 0),
+            Err(SegmentError::InvalidSegmentNumber(3, 2))
+        );
+        assert_eq!(reassembler.pending(), 0);
+    }
+
+    #[test]
+    fn inconsistent_total_is_err() {
+        let mut reassembler = CpmReassembler::new(Duration::from_secs(1));
+        assert!(reassembler.push(segment(1, 3), 0).unwrap().is_none());
+
+        assert_eq!(
+            reassembler.push(segment(2, 2), 10),
+            Err(SegmentError::InconsistentTotal(2, 3))
+        );
+        assert!(reassembler.push(segment(2, 3), 20).unwrap().is_none());
+        assert!(reassembler.push(segment(3, 3), 30).unwrap().is_some());
+    }
+}
@@ -12,13 +12,16 @@
 use std::hash;
 
 use crate::client::configuration::Configuration;
+use crate::clock::{Clock, SystemClock};
+pub use crate::exchange::etsi::action_id::ActionId;
 use crate::exchange::etsi::decentralized_environmental_notification_message::RelevanceDistance::{
     LessThan1000m, LessThan100m, LessThan10Km, LessThan200m, LessThan500m, LessThan50m,
     LessThan5Km, Over10Km,
 };
-use crate::exchange::etsi::reference_position::ReferencePosition;
+use crate::exchange::etsi::quantity::Speed;
+use crate::exchange::etsi::reference_position::{ReferencePosition, ReferencePositionError};
 use crate::exchange::etsi::{
-    etsi_now, heading_from_etsi, speed_from_etsi, PathHistory, PositionConfidence,
+    heading_from_etsi_opt, timestamp_from_etsi, timestamp_to_etsi, PathHistory, PositionConfidence,
 };
 use crate::exchange::message::content::Content;
 use crate::exchange::message::content_error::ContentError;
@@ -28,53 +31,71 @@ use crate::mobility::position::Position;
 
 use serde::{Deserialize, Serialize};
 
+/// Default validity duration of a DENM event, in seconds
+const DEFAULT_VALIDITY_DURATION: u32 = 600;
+
 #[serde_with::skip_serializing_none]
 #[derive(Default, Debug, Clone, Serialize, Deserialize)]
 pub struct DecentralizedEnvironmentalNotificationMessage {
+    #[serde(alias = "protocolVersion")]
     pub protocol_version: u8,
+    #[serde(alias = "stationId")]
     pub station_id: u32,
+    #[serde(alias = "managementContainer")]
     pub management_container: ManagementContainer,
+    #[serde(alias = "situationContainer")]
     pub situation_container: Option<SituationContainer>,
+    #[serde(alias = "locationContainer")]
     pub location_container: Option<LocationContainer>,
+    #[serde(alias = "alacarteContainer")]
     pub alacarte_container: Option<AlacarteContainer>,
 }
 
 #[serde_with::skip_serializing_none]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ManagementContainer {
+    #[serde(alias = "actionId")]
     pub action_id: ActionId,
+    #[serde(alias = "detectionTime")]
     pub detection_time: u64,
+    #[serde(alias = "referenceTime")]
     pub reference_time: u64,
     pub termination: Option<u8>,
+    #[serde(alias = "eventPosition")]
     pub event_position: ReferencePosition,
+    #[serde(alias = "relevanceDistance")]
     pub relevance_distance: Option<u8>,
+    #[serde(alias = "relevanceTrafficDirection")]
     pub relevance_traffic_direction: Option<u8>,
+    #[serde(alias = "validityDuration")]
     pub validity_duration: Option<u32>,
+    #[serde(alias = "transmissionInterval")]
     pub transmission_interval: Option<u16>,
+    #[serde(alias = "stationType")]
     pub station_type: Option<u8>,
     pub confidence: Option<PositionConfidence>,
 }
 
-#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Hash)]
-pub struct ActionId {
-    pub originating_station_id: u32,
-    pub sequence_number: u16,
-}
-
 #[serde_with::skip_serializing_none]
 #[derive(Default, Debug, Clone, Serialize, Deserialize)]
 pub struct SituationContainer {
+    #[serde(alias = "informationQuality")]
     pub information_quality: Option<u8>,
+    #[serde(alias = "eventType")]
     pub event_type: EventType,
+    #[serde(alias = "linkedCause")]
     pub linked_cause: Option<EventType>,
 }
 
 #[serde_with::skip_serializing_none]
 #[derive(Default, Debug, Clone, Serialize, Deserialize)]
 pub struct LocationContainer {
+    #[serde(alias = "eventSpeed")]
     pub event_speed: Option<u16>,
+    #[serde(alias = "eventPositionHeading")]
     pub event_position_heading: Option<u16>,
     pub traces: Vec<Trace>,
+    #[serde(alias = "roadType")]
     pub road_type: Option<u8>,
     pub confidence: Option<LocationContainerConfidence>,
 }
@@ -82,7 +103,9 @@ pub struct LocationContainer {
 #[serde_with::skip_serializing_none]
 #[derive(Default, Debug, Clone, Serialize, Deserialize)]
 pub struct AlacarteContainer {
+    #[serde(alias = "lanePosition")]
     pub lane_position: Option<i8>,
+    #[serde(alias = "positioningSolution")]
     pub positioning_solution: Option<u8>,
 }
 
@@ -95,7 +118,7 @@ pub struct EventType {
 
 #[derive(Default, Debug, Clone, Serialize, Deserialize)]
 pub struct Trace {
-    #[serde(rename = "path_history")]
+    #[serde(rename = "path_history", alias = "pathHistory")]
     pub path_history: Vec<PathHistory>,
 }
 
@@ -107,6 +130,7 @@ pub struct LocationContainerConfidence {
 }
 
 #[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum RelevanceTrafficDirection {
     AllTrafficDirection = 0,
     UpstreamTraffic,
@@ -118,8 +142,118 @@ impl From<RelevanceTrafficDirection> for u8 {
         val as u8
     }
 }
+impl TryFrom<u8> for RelevanceTrafficDirection {
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(RelevanceTrafficDirection::AllTrafficDirection),
+            1 => Ok(RelevanceTrafficDirection::UpstreamTraffic),
+            2 => Ok(RelevanceTrafficDirection::DownstreamTraffic),
+            3 => Ok(RelevanceTrafficDirection::OppositeTraffic),
+            invalid => Err(invalid),
+        }
+    }
+}
+
+/// Direct cause of a DENM event, as defined by ETSI TS 102 894-2
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CauseCode {
+    TrafficCondition,
+    Accident,
+    Roadworks,
+    ImpassabilityCondition,
+    AdverseWeatherConditionAdhesion,
+    HazardousLocationSurfaceCondition,
+    HazardousLocationObstacleOnTheRoad,
+    HazardousLocationAnimalOnTheRoad,
+    HumanPresenceOnTheRoad,
+    WrongWayDriving,
+    RescueAndRecoveryWorkInProgress,
+    AdverseWeatherConditionExtremeWeatherCondition,
+    AdverseWeatherConditionVisibility,
+    AdverseWeatherConditionPrecipitation,
+    SlowVehicle,
+    DangerousEndOfQueue,
+    VehicleBreakdown,
+    PostCrash,
+    HumanProblem,
+    StationaryVehicle,
+    EmergencyVehicleApproaching,
+    HazardousLocationDangerousCurve,
+    CollisionRisk,
+    SignalViolation,
+    DangerousSituation,
+    /// Reserved or not yet supported cause code
+    Unknown(u8),
+}
+impl From<u8> for CauseCode {
+    fn from(value: u8) -> Self {
+        match value {
+            1 => CauseCode::TrafficCondition,
+            2 => CauseCode::Accident,
+            3 => CauseCode::Roadworks,
+            5 => CauseCode::ImpassabilityCondition,
+            6 => CauseCode::AdverseWeatherConditionAdhesion,
+            9 => CauseCode::HazardousLocationSurfaceCondition,
+            10 => CauseCode::HazardousLocationObstacleOnTheRoad,
+            11 => CauseCode::HazardousLocationAnimalOnTheRoad,
+            12 => CauseCode::HumanPresenceOnTheRoad,
+            14 => CauseCode::WrongWayDriving,
+            15 => CauseCode::RescueAndRecoveryWorkInProgress,
+            17 => CauseCode::AdverseWeatherConditionExtremeWeatherCondition,
+            18 => CauseCode::AdverseWeatherConditionVisibility,
+            19 => CauseCode::AdverseWeatherConditionPrecipitation,
+            26 => CauseCode::SlowVehicle,
+            27 => CauseCode::DangerousEndOfQueue,
+            91 => CauseCode::VehicleBreakdown,
+            92 => CauseCode::PostCrash,
+            93 => CauseCode::HumanProblem,
+            94 => CauseCode::StationaryVehicle,
+            95 => CauseCode::EmergencyVehicleApproaching,
+            96 => CauseCode::HazardousLocationDangerousCurve,
+            97 => CauseCode::CollisionRisk,
+            98 => CauseCode::SignalViolation,
+            99 => CauseCode::DangerousSituation,
+            other => CauseCode::Unknown(other),
+        }
+    }
+}
+impl From<CauseCode> for u8 {
+    fn from(val: CauseCode) -> Self {
+        match val {
+            CauseCode::TrafficCondition => 1,
+            CauseCode::Accident => 2,
+            CauseCode::Roadworks => 3,
+            CauseCode::ImpassabilityCondition => 5,
+            CauseCode::AdverseWeatherConditionAdhesion => 6,
+            CauseCode::HazardousLocationSurfaceCondition => 9,
+            CauseCode::HazardousLocationObstacleOnTheRoad => 10,
+            CauseCode::HazardousLocationAnimalOnTheRoad => 11,
+            CauseCode::HumanPresenceOnTheRoad => 12,
+            CauseCode::WrongWayDriving => 14,
+            CauseCode::RescueAndRecoveryWorkInProgress => 15,
+            CauseCode::AdverseWeatherConditionExtremeWeatherCondition => 17,
+            CauseCode::AdverseWeatherConditionVisibility => 18,
+            CauseCode::AdverseWeatherConditionPrecipitation => 19,
+            CauseCode::SlowVehicle => 26,
+            CauseCode::DangerousEndOfQueue => 27,
+            CauseCode::VehicleBreakdown => 91,
+            CauseCode::PostCrash => 92,
+            CauseCode::HumanProblem => 93,
+            CauseCode::StationaryVehicle => 94,
+            CauseCode::EmergencyVehicleApproaching => 95,
+            CauseCode::HazardousLocationDangerousCurve => 96,
+            CauseCode::CollisionRisk => 97,
+            CauseCode::SignalViolation => 98,
+            CauseCode::DangerousSituation => 99,
+            CauseCode::Unknown(value) => value,
+        }
+    }
+}
 
 #[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum RelevanceDistance {
     LessThan50m = 0,
     LessThan100m,
@@ -135,6 +269,23 @@ impl From<RelevanceDistance> for u8 {
         val as u8
     }
 }
+impl TryFrom<u8> for RelevanceDistance {
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(LessThan50m),
+            1 => Ok(LessThan100m),
+            2 => Ok(LessThan200m),
+            3 => Ok(LessThan500m),
+            4 => Ok(LessThan1000m),
+            5 => Ok(LessThan5Km),
+            6 => Ok(LessThan10Km),
+            7 => Ok(Over10Km),
+            invalid => Err(invalid),
+        }
+    }
+}
 impl From<f64> for RelevanceDistance {
     fn from(value: f64) -> Self {
         match value {
@@ -286,7 +437,7 @@ impl DecentralizedEnvironmentalNotificationMessage {
                     sequence_number,
                 },
                 detection_time: etsi_timestamp,
-                reference_time: etsi_now(),
+                reference_time: etsi_timestamp,
                 event_position,
                 validity_duration,
                 transmission_interval,
@@ -325,18 +476,108 @@ impl DecentralizedEnvironmentalNotificationMessage {
     }
 
     pub fn is_stationary_vehicle(&self) -> bool {
-        self.situation_container.is_some()
-            && 94 == self.situation_container.as_ref().unwrap().event_type.cause
+        self.cause_code() == Some(CauseCode::StationaryVehicle)
     }
 
     pub fn is_traffic_condition(&self) -> bool {
-        self.situation_container.is_some()
-            && 1 == self.situation_container.as_ref().unwrap().event_type.cause
+        self.cause_code() == Some(CauseCode::TrafficCondition)
     }
 
     pub fn is_collision_risk(&self) -> bool {
-        self.situation_container.is_some()
-            && 97 == self.situation_container.as_ref().unwrap().event_type.cause
+        self.cause_code() == Some(CauseCode::CollisionRisk)
+    }
+
+    /// Returns the identifier of the event, shared by all its updates
+    pub fn action_id(&self) -> ActionId {
+        self.management_container.action_id
+    }
+
+    /// Returns the event's cause, None if the DENM has no situation container
+    pub fn cause_code(&self) -> Option<CauseCode> {
+        self.situation_container
+            .as_ref()
+            .map(|situation_container| CauseCode::from(situation_container.event_type.cause))
+    }
+
+    /// Returns the event's sub cause, whose meaning depends on the [cause][1]
+    ///
+    /// [1]: Self::cause_code
+    pub fn sub_cause_code(&self) -> Option<u8> {
+        self.situation_container
+            .as_ref()
+            .and_then(|situation_container| situation_container.event_type.subcause)
+    }
+
+    /// Returns the cause of the event which led to this one, None if not set
+    pub fn linked_cause_code(&self) -> Option<CauseCode> {
+        self.situation_container
+            .as_ref()
+            .and_then(|situation_container| situation_container.linked_cause.as_ref())
+            .map(|linked_cause| CauseCode::from(linked_cause.cause))
+    }
+
+    /// Returns the quality of the information about the event, from 1 (lowest) to 7 (highest),
+    /// None if not set or unavailable (0)
+    pub fn information_quality(&self) -> Option<u8> {
+        self.situation_container
+            .as_ref()
+            .and_then(|situation_container| situation_container.information_quality)
+            .filter(|information_quality| *information_quality > 0)
+    }
+
+    /// Returns the position of the event
+    pub fn event_position(&self) -> Position {
+        self.management_container.event_position.as_position()
+    }
+
+    /// Returns the position of the event, failing if its coordinates are out of range or
+    /// unavailable
+    pub fn try_event_position(&self) -> Result<Position, ReferencePositionError> {
+        self.management_container.event_position.try_as_position()
+    }
+
+    /// Returns the time the event was detected at, in milliseconds since the Unix epoch, None if
+    /// it does not fit in a u64
+    pub fn detection_time(&self) -> Option<u64> {
+        timestamp_from_etsi(self.management_container.detection_time)
+    }
+
+    /// Returns the distance up to which the event is relevant, None if not set or invalid
+    pub fn relevance_distance(&self) -> Option<RelevanceDistance> {
+        self.management_container
+            .relevance_distance
+            .and_then(|relevance_distance| RelevanceDistance::try_from(relevance_distance).ok())
+    }
+
+    /// Returns the traffic direction the event is relevant to, None if not set or invalid
+    pub fn relevance_traffic_direction(&self) -> Option<RelevanceTrafficDirection> {
+        self.management_container
+            .relevance_traffic_direction
+            .and_then(|direction| RelevanceTrafficDirection::try_from(direction).ok())
+    }
+
+    /// Returns the lane the event is on, from the à la carte container
+    ///
+    /// -1 is off the road, 0 the inner hard shoulder, 1 the innermost driving lane, 2 the second
+    /// lane from the inside and so on, up to 14 the outer hard shoulder
+    pub fn lane_position(&self) -> Option<i8> {
+        self.alacarte_container
+            .as_ref()
+            .and_then(|alacarte_container| alacarte_container.lane_position)
+    }
+
+    /// Returns true if the event is still valid at the ETSI timestamp, in milliseconds
+    ///
+    /// The event is valid from its detection time for its validity duration, 600 seconds if
+    /// not set
+    pub fn event_validity(&self, timestamp: u64) -> bool {
+        let validity_duration = self
+            .management_container
+            .validity_duration
+            .unwrap_or(DEFAULT_VALIDITY_DURATION);
+        let detection_time = self.management_container.detection_time;
+
+        (detection_time..detection_time + u64::from(validity_duration) * 1000).contains(&timestamp)
     }
 }
 
@@ -359,13 +600,18 @@ impl Mobile for DecentralizedEnvironmentalNotificationMessage {
         self.station_id
     }
 
+    /// Returns the event position, only checked when [seen as a mobile][1]
+    ///
+    /// [1]: Content::as_mobile
     fn position(&self) -> Position {
         self.management_container.event_position.as_position()
     }
 
     fn speed(&self) -> Option<f64> {
         if let Some(location_container) = &self.location_container {
-            location_container.event_speed.map(speed_from_etsi)
+            location_container
+                .event_speed
+                .and_then(|speed| Speed(speed).as_si())
         } else {
             None
         }
@@ -375,7 +621,7 @@ impl Mobile for DecentralizedEnvironmentalNotificationMessage {
         if let Some(location_container) = &self.location_container {
             location_container
                 .event_position_heading
-                .map(heading_from_etsi)
+                .and_then(heading_from_etsi_opt)
         } else {
             None
         }
@@ -397,6 +643,7 @@ impl Content for DecentralizedEnvironmentalNotificationMessage {
     }
 
     fn as_mobile(&self) -> Result<&dyn Mobile, ContentError> {
+        self.try_event_position()?;
         Ok(self)
     }
 
@@ -417,9 +664,13 @@ impl Mortal for DecentralizedEnvironmentalNotificationMessage {
     }
 
     fn terminate(&mut self) {
+        self.terminate_at(SystemClock.now_millis());
+    }
+
+    fn terminate_at(&mut self, now_ms: u64) {
         self.management_container.termination = Some(0);
-        self.management_container.detection_time = etsi_now();
-        self.management_container.reference_time = etsi_now();
+        self.management_container.detection_time = timestamp_to_etsi(now_ms).unwrap_or_default();
+        self.management_container.reference_time = timestamp_to_etsi(now_ms).unwrap_or_default();
         self.management_container.validity_duration = Some(10);
     }
 
@@ -427,13 +678,14 @@ impl Mortal for DecentralizedEnvironmentalNotificationMessage {
         self.management_container.termination.is_some()
     }
 
-    fn remaining_time(&self) -> u64 {
-        let now = etsi_now();
-        if self.timeout() > now {
-            (self.timeout() - now) / 1000
-        } else {
-            0
-        }
+    fn expired_at(&self, now_ms: u64) -> bool {
+        timestamp_to_etsi(now_ms).unwrap_or_default() > self.timeout()
+    }
+
+    fn remaining_time_at(&self, now_ms: u64) -> u64 {
+        self.timeout()
+            .saturating_sub(timestamp_to_etsi(now_ms).unwrap_or_default())
+            / 1000
     }
 }
 
@@ -447,7 +699,7 @@ impl Default for ManagementContainer {
             event_position: Default::default(),
             relevance_distance: Default::default(),
             relevance_traffic_direction: Default::default(),
-            validity_duration: Some(600),
+            validity_duration: Some(DEFAULT_VALIDITY_DURATION),
             transmission_interval: Default::default(),
             station_type: Default::default(),
             confidence: Default::default(),
@@ -470,12 +722,16 @@ impl hash::Hash for ManagementContainer {
 #[cfg(test)]
 mod tests {
     use crate::exchange::etsi::decentralized_environmental_notification_message::{
-        DecentralizedEnvironmentalNotificationMessage, ManagementContainer,
+        ActionId, CauseCode, DecentralizedEnvironmentalNotificationMessage, ManagementContainer,
+        RelevanceDistance, RelevanceTrafficDirection,
     };
     use crate::exchange::etsi::reference_position::ReferencePosition;
-    use crate::exchange::etsi::{etsi_now, timestamp_to_etsi};
+    use crate::exchange::etsi::{timestamp_from_etsi, timestamp_to_etsi};
     use crate::exchange::mortal::Mortal;
+    use crate::mobility::mobile::Mobile;
     use crate::now;
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
 
     #[test]
     fn create_new_stationary_vehicle() {
@@ -483,9 +739,8 @@ mod tests {
         let originating_station_id = 1230;
         let event_position = ReferencePosition::default();
         let sequence_number = 10;
-        let detection_time = etsi_now();
+        let detection_time = timestamp_to_etsi(now()).unwrap();
         let event_position_heading = Some(3000);
-        std::thread::sleep(std::time::Duration::from_secs(1));
 
         let denm = DecentralizedEnvironmentalNotificationMessage::new_stationary_vehicle(
             station_id,
@@ -508,9 +763,33 @@ mod tests {
         );
 
         assert_eq!(denm.management_container.detection_time, detection_time);
-        assert!(
-            denm.management_container.detection_time <= denm.management_container.reference_time
+        assert_eq!(denm.management_container.reference_time, detection_time);
+    }
+
+    #[test]
+    fn termination_is_timestamped_with_the_provided_time() {
+        let mut denm = DecentralizedEnvironmentalNotificationMessage::new_stationary_vehicle(
+            4567,
+            1230,
+            ReferencePosition::default(),
+            10,
+            timestamp_to_etsi(1_700_000_000_000).unwrap(),
+            None,
         );
+
+        denm.terminate_at(1_700_000_060_000);
+
+        assert!(denm.terminated());
+        assert_eq!(
+            denm.management_container.detection_time,
+            timestamp_to_etsi(1_700_000_060_000).unwrap()
+        );
+        assert_eq!(
+            denm.management_container.reference_time,
+            timestamp_to_etsi(1_700_000_060_000).unwrap()
+        );
+        assert!(!denm.expired_at(1_700_000_070_000));
+        assert!(denm.expired_at(1_700_000_070_001));
     }
 
     #[test]
@@ -530,8 +809,8 @@ mod tests {
         let now = now();
         let denm = DecentralizedEnvironmentalNotificationMessage {
             management_container: ManagementContainer {
-                reference_time: timestamp_to_etsi(now) + 500,
-                detection_time: timestamp_to_etsi(now),
+                reference_time: timestamp_to_etsi(now).unwrap() + 500,
+                detection_time: timestamp_to_etsi(now).unwrap(),
                 validity_duration: Some(10),
                 ..Default::default()
             },
@@ -539,9 +818,137 @@ mod tests {
         };
 
         assert!(denm.remaining_time() <= 10);
+        assert_eq!(denm.remaining_time_at(now), 10);
+        assert_eq!(denm.remaining_time_at(now + 11_000), 0);
+        assert!(!denm.expired_at(now + 10_000));
+        assert!(denm.expired_at(now + 11_000));
         assert_eq!(
             denm.timeout() - denm.management_container.reference_time,
             10_000
         );
     }
+
+    const DENM: &str = r#"{"protocol_version":1,"station_id":42,"management_container":{"action_id":{"originating_station_id":42,"sequence_number":1},"detection_time":503253332000,"reference_time":503253333000,"event_position":{"latitude":486263556,"longitude":22492123,"altitude":20000},"relevance_distance":2,"validity_duration":60},"situation_container":{"event_type":{"cause":3,"subcause":4}}}"#;
+
+    #[test]
+    fn situation_accessors() {
+        let denm = serde_json::from_str::<DecentralizedEnvironmentalNotificationMessage>(DENM)
+            .expect("Failed to parse DENM");
+
+        assert_eq!(denm.cause_code(), Some(CauseCode::Roadworks));
+        assert_eq!(denm.sub_cause_code(), Some(4));
+        assert_eq!(
+            denm.relevance_distance(),
+            Some(RelevanceDistance::LessThan200m)
+        );
+    }
+
+    const FULL_DENM: &str = r#"{"protocol_version":2,"station_id":42,"management_container":{"action_id":{"originating_station_id":41,"sequence_number":7},"detection_time":503253332000,"reference_time":503253333000,"termination":0,"event_position":{"latitude":486263556,"longitude":22492123,"altitude":20000},"relevance_distance":4,"relevance_traffic_direction":2,"validity_duration":600,"transmission_interval":100,"station_type":5},"situation_container":{"information_quality":3,"event_type":{"cause":94,"subcause":2},"linked_cause":{"cause":97,"subcause":1}},"location_container":{"event_speed":1500,"event_position_heading":900,"traces":[{"path_history":[]}],"road_type":1},"alacarte_container":{"lane_position":2,"positioning_solution":1}}"#;
+
+    #[test]
+    fn full_denm_accessors() {
+        let denm = serde_json::from_str::<DecentralizedEnvironmentalNotificationMessage>(FULL_DENM)
+            .expect("Failed to parse DENM");
+
+        assert_eq!(denm.cause_code(), Some(CauseCode::StationaryVehicle));
+        assert_eq!(denm.sub_cause_code(), Some(2));
+        assert_eq!(denm.linked_cause_code(), Some(CauseCode::CollisionRisk));
+        assert_eq!(denm.information_quality(), Some(3));
+        assert_eq!(denm.event_position(), denm.position());
+        assert!((denm.event_position().latitude.to_degrees() - 48.6263556).abs() < 1e-7);
+        assert_eq!(denm.try_event_position(), Ok(denm.position()));
+        assert_eq!(denm.detection_time(), timestamp_from_etsi(503253332000));
+        assert_eq!(
+            denm.relevance_distance(),
+            Some(RelevanceDistance::LessThan1000m)
+        );
+        assert_eq!(
+            denm.relevance_traffic_direction(),
+            Some(RelevanceTrafficDirection::DownstreamTraffic)
+        );
+        assert_eq!(denm.lane_position(), Some(2));
+    }
+
+    #[test]
+    fn no_situation_container_no_cause() {
+        let denm = DecentralizedEnvironmentalNotificationMessage::default();
+
+        assert!(denm.cause_code().is_none());
+        assert!(denm.sub_cause_code().is_none());
+        assert!(denm.linked_cause_code().is_none());
+        assert!(denm.information_quality().is_none());
+        assert!(denm.relevance_distance().is_none());
+        assert!(denm.relevance_traffic_direction().is_none());
+        assert!(denm.lane_position().is_none());
+    }
+
+    #[test]
+    fn overflowing_detection_time_is_none() {
+        let denm = DecentralizedEnvironmentalNotificationMessage {
+            management_container: ManagementContainer {
+                detection_time: u64::MAX,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        assert!(denm.detection_time().is_none());
+    }
+
+    fn hash_of(action_id: ActionId) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        action_id.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn update_keeps_action_id() {
+        let denm = serde_json::from_str::<DecentralizedEnvironmentalNotificationMessage>(FULL_DENM)
+            .expect("Failed to parse DENM");
+        let mut update = denm.clone();
+        update.management_container.reference_time += 1000;
+        update.update_information_quality(5);
+
+        assert_eq!(denm.action_id(), ActionId::new(41, 7));
+        assert_eq!(update.action_id(), denm.action_id());
+        assert_eq!(hash_of(update.action_id()), hash_of(denm.action_id()));
+    }
+
+    #[test]
+    fn invalid_relevance_traffic_direction_is_none() {
+        let denm = DecentralizedEnvironmentalNotificationMessage {
+            management_container: ManagementContainer {
+                relevance_traffic_direction: Some(4),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        assert!(denm.relevance_traffic_direction().is_none());
+    }
+
+    #[test]
+    fn unknown_cause_code_round_trip() {
+        assert_eq!(CauseCode::from(42), CauseCode::Unknown(42));
+        assert_eq!(u8::from(CauseCode::from(42)), 42);
+        assert_eq!(u8::from(CauseCode::from(97)), 97);
+    }
+
+    #[test]
+    fn event_still_valid() {
+        let denm = serde_json::from_str::<DecentralizedEnvironmentalNotificationMessage>(DENM)
+            .expect("Failed to parse DENM");
+
+        assert!(denm.event_validity(503253332000));
+        assert!(denm.event_validity(503253332000 + 59_999));
+    }
+
+    #[test]
+    fn event_validity_expired() {
+        let denm = serde_json::from_str::<DecentralizedEnvironmentalNotificationMessage>(DENM)
+            .expect("Failed to parse DENM");
+
+        assert!(!denm.event_validity(503253332000 + 60_000));
+        assert!(!denm.event_validity(503253331999));
+    }
 }
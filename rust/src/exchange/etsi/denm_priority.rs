@@ -0,0 +1,174 @@
+/*
+ * Software Name : libits-client
+ * SPDX-FileCopyrightText: Copyright (c) Orange SA
+ * SPDX-License-Identifier: MIT
+ *
+ * This software is distributed under the MIT license,
+ * see the "LICENSE.txt" file for more details or https://opensource.org/license/MIT/
+ *
+ * Authors: see CONTRIBUTORS.md
+ */
+
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use rumqttc::v5::mqttbytes::QoS;
+
+use crate::exchange::etsi::decentralized_environmental_notification_message::CauseCode;
+use crate::exchange::message::Message;
+use crate::exchange::Exchange;
+
+/// Publishing priority of an exchange
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Priority {
+    /// Published before any routine exchange waiting to be published
+    Urgent,
+    Routine,
+}
+
+impl Priority {
+    /// Returns the QoS to publish with: urgent exchanges are published at least once, without
+    /// the extra round trip of exactly once, routine ones keep the `qos` they were given
+    pub fn qos(&self, qos: QoS) -> QoS {
+        match self {
+            Priority::Urgent => QoS::AtLeastOnce,
+            Priority::Routine => qos,
+        }
+    }
+}
+
+/// DENM causes whose DENMs are published as [urgent][1], every other exchange being routine
+///
+/// Configured as a comma separated list of cause codes (e.g. `2,14,95,97,99`); by default,
+/// accidents, human presence on the road, wrong way driving, emergency vehicles approaching,
+/// collision risks and dangerous situations are urgent
+///
+/// [1]: Priority::Urgent
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DenmPriorities {
+    urgent_causes: HashSet<u8>,
+}
+
+impl DenmPriorities {
+    pub fn new<I: IntoIterator<Item = CauseCode>>(urgent_causes: I) -> Self {
+        Self {
+            urgent_causes: urgent_causes.into_iter().map(u8::from).collect(),
+        }
+    }
+
+    pub fn is_urgent(&self, cause_code: CauseCode) -> bool {
+        self.urgent_causes.contains(&u8::from(cause_code))
+    }
+
+    pub fn priority(&self, exchange: &Exchange) -> Priority {
+        match &exchange.message {
+            Message::DENM(denm) => match denm.cause_code() {
+                Some(cause_code) if self.is_urgent(cause_code) => Priority::Urgent,
+                _ => Priority::Routine,
+            },
+            _ => Priority::Routine,
+        }
+    }
+}
+
+impl Default for DenmPriorities {
+    fn default() -> Self {
+        Self::new([
+            CauseCode::Accident,
+            CauseCode::HumanPresenceOnTheRoad,
+            CauseCode::WrongWayDriving,
+            CauseCode::EmergencyVehicleApproaching,
+            CauseCode::CollisionRisk,
+            CauseCode::DangerousSituation,
+        ])
+    }
+}
+
+impl FromStr for DenmPriorities {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(',')
+            .map(str::trim)
+            .filter(|code| !code.is_empty())
+            .map(|code| {
+                code.parse::<u8>()
+                    .map(CauseCode::from)
+                    .map_err(|e| format!("Invalid cause code '{}': {}", code, e))
+            })
+            .collect::<Result<Vec<CauseCode>, String>>()
+            .map(Self::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use rumqttc::v5::mqttbytes::QoS;
+
+    use crate::exchange::etsi::cooperative_awareness_message::CooperativeAwarenessMessage;
+    use crate::exchange::etsi::decentralized_environmental_notification_message::{
+        CauseCode, DecentralizedEnvironmentalNotificationMessage, EventType, SituationContainer,
+    };
+    use crate::exchange::etsi::denm_priority::{DenmPriorities, Priority};
+    use crate::exchange::message::Message;
+    use crate::exchange::Exchange;
+
+    fn exchange(message: Message) -> Exchange {
+        *Exchange::new(String::from("rsu_1"), 0, Vec::new(), message)
+    }
+
+    fn denm(cause_code: CauseCode) -> Exchange {
+        exchange(Message::DENM(
+            DecentralizedEnvironmentalNotificationMessage {
+                situation_container: Some(SituationContainer {
+                    event_type: EventType {
+                        cause: u8::from(cause_code),
+                        subcause: None,
+                    },
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+        ))
+    }
+
+    #[test]
+    fn accident_is_urgent_by_default() {
+        let priorities = DenmPriorities::default();
+
+        assert_eq!(
+            priorities.priority(&denm(CauseCode::Accident)),
+            Priority::Urgent
+        );
+        assert_eq!(
+            priorities.priority(&denm(CauseCode::TrafficCondition)),
+            Priority::Routine
+        );
+        assert_eq!(Priority::Urgent.qos(QoS::AtMostOnce), QoS::AtLeastOnce);
+        assert_eq!(Priority::Urgent.qos(QoS::ExactlyOnce), QoS::AtLeastOnce);
+        assert_eq!(Priority::Routine.qos(QoS::AtMostOnce), QoS::AtMostOnce);
+    }
+
+    #[test]
+    fn other_messages_are_routine() {
+        let cam = exchange(Message::CAM(CooperativeAwarenessMessage::default()));
+
+        assert_eq!(DenmPriorities::default().priority(&cam), Priority::Routine);
+    }
+
+    #[test]
+    fn causes_are_read_from_str() {
+        let priorities = DenmPriorities::from_str("1, 3").unwrap();
+
+        assert!(priorities.is_urgent(CauseCode::TrafficCondition));
+        assert!(priorities.is_urgent(CauseCode::Roadworks));
+        assert!(!priorities.is_urgent(CauseCode::Accident));
+    }
+
+    #[test]
+    fn invalid_cause_is_err() {
+        assert!(DenmPriorities::from_str("2,accident").is_err());
+    }
+}
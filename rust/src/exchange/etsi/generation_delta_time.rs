@@ -0,0 +1,44 @@
+/*
+ * Software Name : libits-client
+ * SPDX-FileCopyrightText: Copyright (c) Orange SA
+ * SPDX-License-Identifier: MIT
+ *
+ * This software is distributed under the MIT license,
+ * see the "LICENSE.txt" file for more details or https://opensource.org/license/MIT/
+ *
+ * Authors: see CONTRIBUTORS.md
+ */
+
+//! Wrap-around safe operations on CAM/CPM `generation_delta_time`
+//!
+//! Generation delta times are ETSI timestamps modulo 65536, they wrap every ~65 seconds so they
+//! cannot be compared naively: a message generated at 2 is more recent than one generated at 65534
+
+/// Returns the signed shortest-path difference `a - b`, in milliseconds
+///
+/// The difference is computed modulo 65536 and lies within [-32768, 32767]
+pub fn delta(a: u16, b: u16) -> i32 {
+    i32::from(a.wrapping_sub(b) as i16)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::exchange::etsi::generation_delta_time::delta;
+
+    macro_rules! test_delta {
+        ($test_name:ident, $a:expr, $b:expr, $expected:expr) => {
+            #[test]
+            fn $test_name() {
+                assert_eq!(delta($a, $b), $expected);
+            }
+        };
+    }
+    test_delta!(same_time, 500, 500, 0);
+    test_delta!(newer_without_wrap, 600, 500, 100);
+    test_delta!(older_without_wrap, 500, 600, -100);
+    test_delta!(newer_across_wrap, 10, 65530, 16);
+    test_delta!(older_across_wrap, 65530, 10, -16);
+    test_delta!(zero_after_max, 0, 65535, 1);
+    test_delta!(max_before_zero, 65535, 0, -1);
+    test_delta!(half_range, 32768, 0, -32768);
+}
@@ -9,8 +9,8 @@
  * Authors: see CONTRIBUTORS.md
  */
 
-use log::warn;
 use std::any::type_name;
+use tracing::warn;
 
 use crate::client::configuration::Configuration;
 use crate::exchange::etsi::reference_position::ReferencePosition;
@@ -19,7 +19,7 @@ use crate::exchange::message::content_error::ContentError;
 use crate::exchange::message::content_error::ContentError::{NotAMobile, NotAMortal};
 use crate::exchange::mortal::Mortal;
 use crate::mobility::mobile::Mobile;
-use crate::mobility::position::{distance_to_line, position_from_degrees, Position};
+use crate::mobility::position::{distance_to_line, Position};
 use serde::{Deserialize, Serialize};
 use serde_repr::Deserialize_repr;
 use std::hash::{Hash, Hasher};
@@ -123,6 +123,24 @@ pub struct Lane {
     pub connections: Vec<Connection>,
 }
 
+impl Lane {
+    /// Returns the lane's polyline as absolute positions, in the [geometry][1] order
+    ///
+    /// The altitude of the points is unknown
+    ///
+    /// [1]: Lane::geom
+    pub fn positions(&self) -> Vec<Position> {
+        self.geom
+            .iter()
+            .map(|[longitude, latitude]| Position {
+                latitude: f64::from(*latitude).to_radians(),
+                longitude: f64::from(*longitude).to_radians(),
+                altitude: None,
+            })
+            .collect()
+    }
+}
+
 impl Hash for Lane {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.id.hash(state);
@@ -164,16 +182,20 @@ impl MAPExtendedMessage {
     pub fn get_lane_from_position(&self, reference_position: &ReferencePosition) -> Option<&Lane> {
         let mut best_lane: Option<(&Lane, f64)> = None;
 
-        let reference_position = reference_position.as_position();
+        let reference_position = match reference_position.try_as_position() {
+            Ok(position) => position,
+            Err(error) => {
+                warn!(
+                    "Cannot look for a lane from this reference position: {}",
+                    error
+                );
+                return None;
+            }
+        };
 
         for lane in &self.lanes {
-            match &lane.geom {
-                geometry if geometry.len() > 1 => {
-                    let mut positions: Vec<Position> = Vec::new();
-                    for point in geometry {
-                        positions.push(position_from_degrees(point[0].into(), point[1].into(), 0.));
-                    }
-
+            match lane.positions() {
+                positions if positions.len() > 1 => {
                     let distance_to_lane = distance_to_line(&reference_position, &positions);
                     if best_lane.is_none() || best_lane.unwrap().1 > distance_to_lane {
                         best_lane = Some((lane, distance_to_lane));
@@ -191,7 +213,16 @@ impl MAPExtendedMessage {
 
 #[cfg(test)]
 mod test {
-    use crate::exchange::etsi::map_extended_message::{Action, MAPExtendedMessage};
+    use crate::exchange::etsi::map_extended_message::{Action, Lane, MAPExtendedMessage};
+    use crate::exchange::etsi::reference_position::ReferencePosition;
+    use crate::mobility::position::position_from_degrees;
+
+    fn lane(id: u64, geom: &str) -> String {
+        format!(
+            r#"{{"id":{},"signalId":1,"left":false,"right":false,"speedLimit":50,"ingress":true,"egress":false,"geom":{}}}"#,
+            id, geom
+        )
+    }
 
     #[test]
     fn test_complete_deserialization() {
@@ -1205,4 +1236,45 @@ mod test {
             }
         }
     }
+
+    #[test]
+    fn two_node_lane_positions() {
+        let lane = serde_json::from_str::<Lane>(&lane(
+            1,
+            "[[2.37637806826, 48.8390563094], [2.37688503009, 48.8393363062]]",
+        ))
+        .unwrap();
+
+        let positions = lane.positions();
+
+        assert_eq!(positions.len(), 2);
+        for (position, (latitude, longitude)) in positions.iter().zip([
+            (48.8390563094, 2.37637806826),
+            (48.8393363062, 2.37688503009),
+        ]) {
+            let expected = position_from_degrees(latitude, longitude, 0.);
+            assert!((position.latitude - expected.latitude).abs() < 1e-7);
+            assert!((position.longitude - expected.longitude).abs() < 1e-7);
+            assert!(position.altitude.is_none());
+        }
+    }
+
+    #[test]
+    fn lane_from_position_uses_longitude_first_geometry() {
+        let data = format!(
+            r#"{{"protocolVersion":1,"id":243,"lanes":[{},{}]}}"#,
+            lane(1, "[[2.3763, 48.8390], [2.3768, 48.8393]]"),
+            lane(2, "[[2.3800, 48.8400], [2.3805, 48.8403]]"),
+        );
+        let mapem = serde_json::from_str::<MAPExtendedMessage>(&data).unwrap();
+        let reference_position = ReferencePosition {
+            latitude: 488401500,
+            longitude: 23802500,
+            altitude: 800001,
+        };
+
+        let lane = mapem.get_lane_from_position(&reference_position).unwrap();
+
+        assert_eq!(lane.id, 2);
+    }
 }
@@ -20,7 +20,7 @@ use crate::exchange::etsi::perceived_object::PerceivedObject;
 use crate::exchange::etsi::speed_from_etsi;
 use crate::mobility::mobile::Mobile;
 use crate::mobility::position::{enu_destination, haversine_destination, Position};
-use log::trace;
+use tracing::trace;
 
 const PI2: f64 = 2. * PI;
 
@@ -149,7 +149,7 @@ fn compute_position_from_mobile(
     let x_offset_meters = x_distance as f64 / 100.0;
     let y_offset_meters = y_distance as f64 / 100.0;
 
-    let intermediate = haversine_destination(&position, heading, x_offset_meters);
+    let intermediate = haversine_destination(position, heading, x_offset_meters);
     haversine_destination(
         &intermediate,
         (heading - PI / 2. + 2. * PI) % (2. * PI),
@@ -237,7 +237,6 @@ mod tests {
                     }
                     .as_position()
                 );
-                println!("PO position: {}", position);
 
                 assert!(
                     (position.latitude - $expected.latitude).abs() <= 1e-6,
@@ -252,8 +251,8 @@ mod tests {
                     $expected.longitude.to_degrees()
                 );
                 assert!(
-                    (position.altitude - $expected.altitude).abs() <= 1e-4,
-                    "Altitude: {} (expected: {})",
+                    (position.altitude.unwrap() - $expected.altitude.unwrap()).abs() <= 1e-4,
+                    "Altitude: {:?} (expected: {:?})",
                     position.altitude,
                     $expected.altitude
                 );
@@ -267,7 +266,7 @@ mod tests {
         Position {
             latitude: coordinate_from_etsi(486251958),
             longitude: coordinate_from_etsi(22417534),
-            altitude: altitude_from_etsi(900),
+            altitude: Some(altitude_from_etsi(900)),
         }
     );
     test_compute_position_from_mobile!(
@@ -277,7 +276,7 @@ mod tests {
         Position {
             latitude: coordinate_from_etsi(486252587),
             longitude: coordinate_from_etsi(22415093),
-            altitude: altitude_from_etsi(900),
+            altitude: Some(altitude_from_etsi(900)),
         }
     );
     test_compute_position_from_mobile!(
@@ -287,7 +286,7 @@ mod tests {
         Position {
             latitude: coordinate_from_etsi(486252587),
             longitude: coordinate_from_etsi(22417535),
-            altitude: altitude_from_etsi(900),
+            altitude: Some(altitude_from_etsi(900)),
         }
     );
 
@@ -314,7 +313,7 @@ mod tests {
             position: Position {
                 latitude: coordinate_from_etsi(434622516),
                 longitude: coordinate_from_etsi(1218218),
-                altitude: altitude_from_etsi(220000),
+                altitude: Some(altitude_from_etsi(220000)),
             },
             speed: 0.,
             heading: PI,
@@ -386,7 +385,7 @@ mod tests {
             position: Position {
                 latitude: coordinate_from_etsi(488415432),
                 longitude: coordinate_from_etsi(23679076),
-                altitude: altitude_from_etsi(900),
+                altitude: Some(altitude_from_etsi(900)),
             },
             speed: speed_from_etsi(591),
             heading: heading_from_etsi(1257),
@@ -437,11 +436,11 @@ mod tests {
             expected_mobile_perceived_object.position.longitude
         );
         assert!(
-            (mobile_perceived_object.position.altitude
-                - expected_mobile_perceived_object.position.altitude)
-                .abs()
+            (mobile_perceived_object.position.altitude.unwrap()
+                - expected_mobile_perceived_object.position.altitude.unwrap())
+            .abs()
                 <= 1e-4,
-            "Actual: {} (expected: {})",
+            "Actual: {:?} (expected: {:?})",
             mobile_perceived_object.position.altitude,
             expected_mobile_perceived_object.position.altitude
         );
@@ -14,48 +14,81 @@ use serde::{Deserialize, Serialize};
 #[serde_with::skip_serializing_none]
 #[derive(Default, Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PerceivedObject {
+    #[serde(alias = "objectId")]
     pub object_id: u8,
+    #[serde(alias = "timeOfMeasurement")]
     pub time_of_measurement: i16,
     pub confidence: ObjectConfidence,
+    #[serde(alias = "xDistance")]
     pub x_distance: i32,
+    #[serde(alias = "yDistance")]
     pub y_distance: i32,
+    #[serde(alias = "zDistance")]
     pub z_distance: Option<i32>,
+    #[serde(alias = "xSpeed")]
     pub x_speed: i16,
+    #[serde(alias = "ySpeed")]
     pub y_speed: i16,
+    #[serde(alias = "zSpeed")]
     pub z_speed: Option<i16>,
+    #[serde(alias = "objectAge")]
     pub object_age: u16,
+    #[serde(alias = "objectRefPoint")]
     pub object_ref_point: Option<u8>,
+    #[serde(alias = "xAcceleration")]
     pub x_acceleration: Option<i16>,
+    #[serde(alias = "yAcceleration")]
     pub y_acceleration: Option<i16>,
+    #[serde(alias = "zAcceleration")]
     pub z_acceleration: Option<i16>,
+    #[serde(alias = "rollAngle")]
     pub roll_angle: Option<u16>,
+    #[serde(alias = "pitchAngle")]
     pub pitch_angle: Option<u16>,
+    #[serde(alias = "yawAngle")]
     pub yaw_angle: Option<u16>,
+    #[serde(alias = "rollRate")]
     pub roll_rate: Option<i16>,
+    #[serde(alias = "pitchRate")]
     pub pitch_rate: Option<i16>,
+    #[serde(alias = "yawRate")]
     pub yaw_rate: Option<i16>,
+    #[serde(alias = "rollAcceleration")]
     pub roll_acceleration: Option<i16>,
+    #[serde(alias = "pitchAcceleration")]
     pub pitch_acceleration: Option<i16>,
+    #[serde(alias = "yawAcceleration")]
     pub yaw_acceleration: Option<i16>,
     #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    #[serde(alias = "lowerTriangularCorrelationMatrixColumns")]
     pub lower_triangular_correlation_matrix_columns: Vec<Vec<i8>>,
+    #[serde(alias = "planarObjectDimension1")]
     pub planar_object_dimension_1: Option<u16>,
+    #[serde(alias = "planarObjectDimension2")]
     pub planar_object_dimension_2: Option<u16>,
+    #[serde(alias = "verticalObjectDimension")]
     pub vertical_object_dimension: Option<u16>,
     #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    #[serde(alias = "sensorIdList")]
     pub sensor_id_list: Vec<u8>,
+    #[serde(alias = "dynamicStatus")]
     pub dynamic_status: Option<u8>,
     #[serde(skip_serializing_if = "Vec::is_empty", default)]
     pub classification: Vec<ObjectClassification>,
+    #[serde(alias = "matchedPosition")]
     pub matched_position: Option<MatchedPosition>,
 }
 
 #[serde_with::skip_serializing_none]
 #[derive(Default, Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ObjectConfidence {
+    #[serde(alias = "xDistance")]
     pub x_distance: u16,
+    #[serde(alias = "yDistance")]
     pub y_distance: u16,
+    #[serde(alias = "xSpeed")]
     pub x_speed: u8,
+    #[serde(alias = "ySpeed")]
     pub y_speed: u8,
     pub object: Option<u8>,
 }
@@ -63,6 +96,7 @@ pub struct ObjectConfidence {
 #[serde_with::skip_serializing_none]
 #[derive(Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ObjectClassification {
+    #[serde(alias = "objectClass")]
     pub object_class: ObjectClass,
     pub confidence: u8,
 }
@@ -90,8 +124,11 @@ pub enum SingleVruClass {
 #[serde_with::skip_serializing_none]
 #[derive(Default, Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct VruGroupClass {
+    #[serde(alias = "groupSize")]
     pub group_size: u8,
+    #[serde(alias = "groupType")]
     pub group_type: VruGroupType,
+    #[serde(alias = "clusterId")]
     pub cluster_id: Option<u8>,
 }
 
@@ -107,11 +144,83 @@ pub struct VruGroupType {
 #[serde_with::skip_serializing_none]
 #[derive(Default, Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct MatchedPosition {
+    #[serde(alias = "laneId")]
     pub lane_id: u8,
+    #[serde(alias = "longitudinalLanePosition")]
     pub longitudinal_lane_position: u16,
 }
 
+/// Simplified class of a perceived object, whatever the classification detail
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub enum ObjectCategory {
+    Vehicle,
+    Pedestrian,
+    Cyclist,
+    Motorcyclist,
+    Animal,
+    Other,
+}
+
+impl From<&ObjectClass> for ObjectCategory {
+    /// VRU groups are categorized by their first member type, pedestrians first
+    fn from(object_class: &ObjectClass) -> Self {
+        match object_class {
+            ObjectClass::Vehicle(_) => ObjectCategory::Vehicle,
+            ObjectClass::SingleVru(SingleVruClass::Pedestrian(_)) => ObjectCategory::Pedestrian,
+            ObjectClass::SingleVru(SingleVruClass::Bicyclist(_)) => ObjectCategory::Cyclist,
+            ObjectClass::SingleVru(SingleVruClass::Motorcyclist(_)) => ObjectCategory::Motorcyclist,
+            ObjectClass::SingleVru(SingleVruClass::Animal(_)) => ObjectCategory::Animal,
+            ObjectClass::VruGroup(group) => match &group.group_type {
+                group_type if group_type.pedestrian => ObjectCategory::Pedestrian,
+                group_type if group_type.bicyclist => ObjectCategory::Cyclist,
+                group_type if group_type.motorcyclist => ObjectCategory::Motorcyclist,
+                group_type if group_type.animal => ObjectCategory::Animal,
+                _ => ObjectCategory::Other,
+            },
+            ObjectClass::Other(_) => ObjectCategory::Other,
+        }
+    }
+}
+
 impl PerceivedObject {
+    /// Returns the category and confidence of the most confident classification
+    ///
+    /// Confidences are percentages from 1 to 100; unknown (0) and unavailable (101) confidences
+    /// rank below any other. On a tie, the first classification of the list is returned
+    pub fn best_classification(&self) -> Option<(ObjectCategory, u8)> {
+        let rank = |confidence: u8| match confidence {
+            1..=100 => confidence,
+            _ => 0,
+        };
+
+        self.classification
+            .iter()
+            .fold(
+                None,
+                |best: Option<&ObjectClassification>, classification| match best {
+                    Some(best) if rank(best.confidence) >= rank(classification.confidence) => {
+                        Some(best)
+                    }
+                    _ => Some(classification),
+                },
+            )
+            .map(|best| (ObjectCategory::from(&best.object_class), best.confidence))
+    }
+
+    /// Returns true if the most confident classification is a vulnerable road user
+    pub fn is_vru(&self) -> bool {
+        matches!(
+            self.best_classification(),
+            Some((
+                ObjectCategory::Pedestrian
+                    | ObjectCategory::Cyclist
+                    | ObjectCategory::Motorcyclist
+                    | ObjectCategory::Animal,
+                _
+            ))
+        )
+    }
+
     pub fn is_pedestrian(&self) -> bool {
         self.classification.iter().any(|object_classification| {
             matches!(
@@ -121,6 +230,25 @@ impl PerceivedObject {
         })
     }
 
+    pub fn is_bicyclist(&self) -> bool {
+        self.classification.iter().any(|object_classification| {
+            matches!(
+                object_classification.object_class,
+                ObjectClass::SingleVru(SingleVruClass::Bicyclist(_))
+            )
+        })
+    }
+
+    /// Returns true if any of the object's classifications matches the predicate
+    pub fn has_class<P>(&self, predicate: P) -> bool
+    where
+        P: Fn(&ObjectClass) -> bool,
+    {
+        self.classification
+            .iter()
+            .any(|object_classification| predicate(&object_classification.object_class))
+    }
+
     pub fn is_vehicle(&self) -> bool {
         self.classification.iter().any(|object_classification| {
             matches!(object_classification.object_class, ObjectClass::Vehicle(_))
@@ -130,7 +258,9 @@ impl PerceivedObject {
 
 #[cfg(test)]
 mod test {
-    use crate::exchange::etsi::perceived_object::PerceivedObject;
+    use crate::exchange::etsi::perceived_object::{
+        ObjectCategory, ObjectClass, ObjectClassification, PerceivedObject, SingleVruClass,
+    };
 
     #[test]
     fn test_deserialize() {
@@ -173,9 +303,7 @@ mod test {
         }
     }
 
-    #[test]
-    fn test_deserialize_full_po() {
-        let data = r#"{
+    const FULL_PO: &str = r#"{
             "object_id": 0,
             "time_of_measurement": 50,
             "x_distance": 400,
@@ -275,7 +403,9 @@ mod test {
             }
         }"#;
 
-        match serde_json::from_str::<PerceivedObject>(data) {
+    #[test]
+    fn test_deserialize_full_po() {
+        match serde_json::from_str::<PerceivedObject>(FULL_PO) {
             Ok(po) => {
                 assert_eq!(0, po.object_id);
             }
@@ -284,4 +414,62 @@ mod test {
             }
         }
     }
+
+    #[test]
+    fn best_classification_skips_unavailable_confidence() {
+        let po = serde_json::from_str::<PerceivedObject>(FULL_PO).unwrap();
+
+        assert_eq!(
+            po.best_classification(),
+            Some((ObjectCategory::Pedestrian, 64))
+        );
+        assert!(po.is_vru());
+    }
+
+    #[test]
+    fn first_classification_wins_a_tie() {
+        let po = PerceivedObject {
+            classification: vec![
+                ObjectClassification {
+                    object_class: ObjectClass::SingleVru(SingleVruClass::Bicyclist(1)),
+                    confidence: 50,
+                },
+                ObjectClassification {
+                    object_class: ObjectClass::Vehicle(10),
+                    confidence: 50,
+                },
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            po.best_classification(),
+            Some((ObjectCategory::Cyclist, 50))
+        );
+    }
+
+    #[test]
+    fn vehicle_is_not_vru() {
+        let po = PerceivedObject {
+            classification: vec![ObjectClassification {
+                object_class: ObjectClass::Vehicle(10),
+                confidence: 90,
+            }],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            po.best_classification(),
+            Some((ObjectCategory::Vehicle, 90))
+        );
+        assert!(!po.is_vru());
+    }
+
+    #[test]
+    fn unclassified_object_has_no_best_classification() {
+        let po = PerceivedObject::default();
+
+        assert!(po.best_classification().is_none());
+        assert!(!po.is_vru());
+    }
 }
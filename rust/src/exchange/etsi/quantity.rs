@@ -0,0 +1,258 @@
+/*
+ * Software Name : libits-client
+ * SPDX-FileCopyrightText: Copyright (c) Orange SA
+ * SPDX-License-Identifier: MIT
+ *
+ * This software is distributed under the MIT license,
+ * see the "LICENSE.txt" file for more details or https://opensource.org/license/MIT/
+ *
+ * Authors: see CONTRIBUTORS.md
+ */
+
+//! ETSI values with their conversion to SI units
+//!
+//! Each type wraps the raw value of the message field (e.g. `YawRate(cam.yaw_rate)`), its
+//! `as_si` method returns `None` for the value standing for unavailable; `confidence_si` converts
+//! the raw confidence of the value, `None` for the out of range and unavailable ones
+
+use serde::{Deserialize, Serialize};
+
+use crate::exchange::etsi::{acceleration_from_etsi, speed_from_etsi};
+
+/// Upper bounds of the yaw rate confidence classes, in degrees per second
+const YAW_RATE_CONFIDENCES: [f64; 7] = [0.01, 0.05, 0.1, 1., 5., 10., 100.];
+/// Upper bounds of the curvature confidence classes, in m⁻¹
+const CURVATURE_CONFIDENCES: [f64; 6] = [0.00002, 0.0001, 0.0005, 0.002, 0.01, 0.1];
+
+/// Speed in cm/s, 16383 standing for unavailable
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Speed(pub u16);
+
+impl Speed {
+    pub const UNAVAILABLE: Speed = Speed(16383);
+
+    /// Returns the speed in m/s
+    pub fn as_si(&self) -> Option<f64> {
+        (*self != Self::UNAVAILABLE).then(|| speed_from_etsi(self.0))
+    }
+
+    /// Converts the speed confidence, in cm/s, to m/s
+    ///
+    /// Returns `None` for the out of range (126) and unavailable (127) values
+    pub fn confidence_si(confidence: u8) -> Option<f64> {
+        match confidence {
+            126.. => None,
+            value => Some(f64::from(value) / 100.),
+        }
+    }
+}
+
+/// Acceleration in tenths of m/s², 161 standing for unavailable
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Acceleration(pub i16);
+
+impl Acceleration {
+    pub const UNAVAILABLE: Acceleration = Acceleration(161);
+
+    /// Returns the acceleration in m/s²
+    pub fn as_si(&self) -> Option<f64> {
+        (*self != Self::UNAVAILABLE).then(|| acceleration_from_etsi(self.0))
+    }
+
+    /// Converts the acceleration confidence, in tenths of m/s², to m/s²
+    ///
+    /// Returns `None` for the out of range (101) and unavailable (102) values
+    pub fn confidence_si(confidence: u8) -> Option<f64> {
+        match confidence {
+            101.. => None,
+            value => Some(f64::from(value) / 10.),
+        }
+    }
+}
+
+/// Yaw rate in hundredths of degree per second, positive counterclockwise, 32767 standing for
+/// unavailable
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct YawRate(pub i16);
+
+impl YawRate {
+    pub const UNAVAILABLE: YawRate = YawRate(32767);
+
+    /// Returns the yaw rate in rad/s, positive counterclockwise
+    pub fn as_si(&self) -> Option<f64> {
+        (*self != Self::UNAVAILABLE).then(|| (f64::from(self.0) / 100.).to_radians())
+    }
+
+    /// Converts the yaw rate confidence class, from 0 (0.01°/s) to 6 (100°/s), to the upper
+    /// bound of its class in rad/s
+    pub fn confidence_si(confidence: u8) -> Option<f64> {
+        YAW_RATE_CONFIDENCES
+            .get(usize::from(confidence))
+            .map(|degrees| degrees.to_radians())
+    }
+}
+
+/// Curvature, the inverse of the turning radius, in 1/10000 m⁻¹, positive when turning left;
+/// 1023 stands for unavailable
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Curvature(pub i16);
+
+impl Curvature {
+    pub const UNAVAILABLE: Curvature = Curvature(1023);
+
+    /// Returns the curvature in m⁻¹, positive when turning left
+    pub fn as_si(&self) -> Option<f64> {
+        (*self != Self::UNAVAILABLE).then(|| f64::from(self.0) / 10_000.)
+    }
+
+    /// Converts the curvature confidence class, from 0 (0.00002 m⁻¹) to 5 (0.1 m⁻¹), to the upper
+    /// bound of its class in m⁻¹
+    pub fn confidence_si(confidence: u8) -> Option<f64> {
+        CURVATURE_CONFIDENCES.get(usize::from(confidence)).copied()
+    }
+}
+
+/// Vehicle length in tenths of meter, 1023 standing for unavailable
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct VehicleLength(pub u16);
+
+impl VehicleLength {
+    pub const UNAVAILABLE: VehicleLength = VehicleLength(1023);
+
+    /// Returns the length in meters
+    pub fn as_si(&self) -> Option<f64> {
+        (*self != Self::UNAVAILABLE).then(|| f64::from(self.0) / 10.)
+    }
+}
+
+/// Steering wheel angle in steps of 1.5 degrees, positive counterclockwise, 512 standing for
+/// unavailable
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SteeringWheelAngle(pub i16);
+
+impl SteeringWheelAngle {
+    pub const UNAVAILABLE: SteeringWheelAngle = SteeringWheelAngle(512);
+
+    /// Returns the angle in radians, positive counterclockwise
+    pub fn as_si(&self) -> Option<f64> {
+        (*self != Self::UNAVAILABLE).then(|| (f64::from(self.0) * 1.5).to_radians())
+    }
+
+    /// Converts the angle confidence, in steps of 1.5 degrees, to radians
+    pub fn confidence_si(confidence: u8) -> Option<f64> {
+        match confidence {
+            126.. => None,
+            value => Some((f64::from(value) * 1.5).to_radians()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::exchange::etsi::quantity::{
+        Acceleration, Curvature, Speed, SteeringWheelAngle, VehicleLength, YawRate,
+    };
+
+    macro_rules! assert_si_eq {
+        ($si:expr, $expected:expr, $what:expr) => {
+            let expected: Option<f64> = $expected;
+            match ($si, expected) {
+                (Some(si), Some(expected)) => assert!(
+                    (si - expected).abs() < 1e-9,
+                    "{:?} is {} instead of {}",
+                    $what,
+                    si,
+                    expected
+                ),
+                (si, expected) => assert_eq!(si, expected),
+            }
+        };
+    }
+
+    macro_rules! test_as_si {
+        ($test_name:ident, $quantity:expr, $expected:expr) => {
+            #[test]
+            fn $test_name() {
+                assert_si_eq!($quantity.as_si(), $expected, $quantity);
+            }
+        };
+    }
+    test_as_si!(acceleration, Acceleration(12), Some(1.2));
+    test_as_si!(deceleration, Acceleration(-45), Some(-4.5));
+    test_as_si!(min_acceleration, Acceleration(-160), Some(-16.));
+    test_as_si!(unavailable_acceleration, Acceleration::UNAVAILABLE, None);
+    test_as_si!(yaw_rate, YawRate(9000), Some(std::f64::consts::FRAC_PI_2));
+    test_as_si!(
+        negative_yaw_rate,
+        YawRate(-18000),
+        Some(-std::f64::consts::PI)
+    );
+    test_as_si!(unavailable_yaw_rate, YawRate::UNAVAILABLE, None);
+    test_as_si!(curvature, Curvature(100), Some(0.01));
+    test_as_si!(straight_curvature, Curvature(0), Some(0.));
+    test_as_si!(right_curvature, Curvature(-1022), Some(-0.1022));
+    test_as_si!(unavailable_curvature, Curvature::UNAVAILABLE, None);
+    test_as_si!(speed, Speed(1389), Some(13.89));
+    test_as_si!(max_speed, Speed(16382), Some(163.82));
+    test_as_si!(unavailable_speed, Speed::UNAVAILABLE, None);
+    test_as_si!(vehicle_length, VehicleLength(45), Some(4.5));
+    test_as_si!(unavailable_vehicle_length, VehicleLength::UNAVAILABLE, None);
+    test_as_si!(
+        steering_wheel_angle,
+        SteeringWheelAngle(60),
+        Some(std::f64::consts::FRAC_PI_2)
+    );
+    test_as_si!(
+        unavailable_steering_wheel_angle,
+        SteeringWheelAngle::UNAVAILABLE,
+        None
+    );
+
+    macro_rules! test_confidence_si {
+        ($test_name:ident, $quantity:ident, $confidence:expr, $expected:expr) => {
+            #[test]
+            fn $test_name() {
+                assert_si_eq!(
+                    $quantity::confidence_si($confidence),
+                    $expected,
+                    stringify!($quantity)
+                );
+            }
+        };
+    }
+    test_confidence_si!(speed_confidence, Speed, 5, Some(0.05));
+    test_confidence_si!(max_speed_confidence, Speed, 125, Some(1.25));
+    test_confidence_si!(out_of_range_speed_confidence, Speed, 126, None);
+    test_confidence_si!(unavailable_speed_confidence, Speed, 127, None);
+    test_confidence_si!(acceleration_confidence, Acceleration, 5, Some(0.5));
+    test_confidence_si!(max_acceleration_confidence, Acceleration, 100, Some(10.));
+    test_confidence_si!(
+        out_of_range_acceleration_confidence,
+        Acceleration,
+        101,
+        None
+    );
+    test_confidence_si!(unavailable_acceleration_confidence, Acceleration, 102, None);
+    test_confidence_si!(yaw_rate_confidence, YawRate, 3, Some(1_f64.to_radians()));
+    test_confidence_si!(out_of_range_yaw_rate_confidence, YawRate, 7, None);
+    test_confidence_si!(curvature_confidence, Curvature, 1, Some(0.0001));
+    test_confidence_si!(unavailable_curvature_confidence, Curvature, 7, None);
+    test_confidence_si!(
+        steering_wheel_angle_confidence,
+        SteeringWheelAngle,
+        2,
+        Some(3_f64.to_radians())
+    );
+    test_confidence_si!(
+        unavailable_steering_wheel_angle_confidence,
+        SteeringWheelAngle,
+        127,
+        None
+    );
+}
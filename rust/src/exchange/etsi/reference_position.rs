@@ -10,12 +10,33 @@
  */
 
 use core::fmt;
+use std::f64::consts::{PI, TAU};
 
-use crate::mobility::position::Position;
+use crate::mobility::position::{enu_destination, Position};
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
 const COORDINATE_SIGNIFICANT_DIGIT: u8 = 7;
 const ALTITUDE_SIGNIFICANT_DIGIT: u8 = 2;
+const ALTITUDE_MIN: i32 = -100000;
+const ALTITUDE_MAX: i32 = 800000;
+const ALTITUDE_UNAVAILABLE: i32 = 800001;
+const LATITUDE_MAX: i32 = 900000000;
+const LATITUDE_UNAVAILABLE: i32 = 900000001;
+const LONGITUDE_MAX: i32 = 1800000000;
+const LONGITUDE_UNAVAILABLE: i32 = 1800000001;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ReferencePositionError {
+    #[error("Latitude {0} is out of the [-900000000, 900000000] range")]
+    InvalidLatitude(i32),
+    #[error("Longitude {0} is out of the [-1800000000, 1800000000] range")]
+    InvalidLongitude(i32),
+    #[error("Latitude is unavailable")]
+    UnavailableLatitude,
+    #[error("Longitude is unavailable")]
+    UnavailableLongitude,
+}
 
 #[derive(Clone, Default, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
 pub struct ReferencePosition {
@@ -23,26 +44,124 @@ pub struct ReferencePosition {
     pub latitude: i32,
     /// Longitude in tenths of microdegree
     pub longitude: i32,
-    /// Altitude in centimeters
+    /// Altitude in centimeters, 800001 if unavailable
     pub altitude: i32,
 }
 
 impl ReferencePosition {
+    /// Converts into a [Position] without checking the coordinates
+    ///
+    /// Out of range or unavailable coordinates lead to meaningless positions,
+    /// use [try_as_position][1] when the reference position comes from an untrusted message
+    ///
+    /// [1]: ReferencePosition::try_as_position
     pub fn as_position(&self) -> Position {
         Position {
             latitude: coordinate_from_etsi(self.latitude),
             longitude: coordinate_from_etsi(self.longitude),
-            altitude: altitude_from_etsi(self.altitude),
+            altitude: self.altitude_meters(),
+        }
+    }
+
+    /// Converts into a [Position], failing if a coordinate is out of range or unavailable
+    pub fn try_as_position(&self) -> Result<Position, ReferencePositionError> {
+        self.validate()?;
+        if self.latitude == LATITUDE_UNAVAILABLE {
+            Err(ReferencePositionError::UnavailableLatitude)
+        } else if self.longitude == LONGITUDE_UNAVAILABLE {
+            Err(ReferencePositionError::UnavailableLongitude)
+        } else {
+            Ok(self.as_position())
         }
     }
+
+    /// Checks the latitude and longitude are within the ETSI ranges
+    ///
+    /// The unavailable values (900000001 and 1800000001) are considered valid
+    pub fn validate(&self) -> Result<(), ReferencePositionError> {
+        if self.latitude != LATITUDE_UNAVAILABLE
+            && !(-LATITUDE_MAX..=LATITUDE_MAX).contains(&self.latitude)
+        {
+            Err(ReferencePositionError::InvalidLatitude(self.latitude))
+        } else if self.longitude != LONGITUDE_UNAVAILABLE
+            && !(-LONGITUDE_MAX..=LONGITUDE_MAX).contains(&self.longitude)
+        {
+            Err(ReferencePositionError::InvalidLongitude(self.longitude))
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.validate().is_ok()
+    }
+
+    /// Returns the reference position moved by an offset in centimeters
+    ///
+    /// `x_offset` is towards the east and `y_offset` towards the north, as the offsets of
+    /// the [perceived objects][1]; the altitude is kept as is
+    ///
+    /// [1]: crate::exchange::etsi::perceived_object::PerceivedObject
+    pub fn apply_offset(&self, x_offset: i32, y_offset: i32) -> ReferencePosition {
+        let origin = self.as_position();
+        let destination = enu_destination(
+            &origin,
+            f64::from(x_offset) / 100.,
+            f64::from(y_offset) / 100.,
+            0.,
+        );
+
+        // the coordinates are moved by the converted difference rather than replaced by the
+        // converted destination, whose truncation would shift an unchanged coordinate
+        let longitude_difference =
+            (destination.longitude - origin.longitude + PI).rem_euclid(TAU) - PI;
+        let longitude = self.longitude + coordinate_to_etsi(longitude_difference);
+        ReferencePosition {
+            latitude: self.latitude + coordinate_to_etsi(destination.latitude - origin.latitude),
+            longitude: if longitude > LONGITUDE_MAX {
+                longitude - LONGITUDE_MAX - LONGITUDE_MAX
+            } else if longitude < -LONGITUDE_MAX {
+                longitude + LONGITUDE_MAX + LONGITUDE_MAX
+            } else {
+                longitude
+            },
+            altitude: self.altitude,
+        }
+    }
+
+    /// Returns false if the altitude is the 800001 unavailable sentinel
+    pub fn is_altitude_available(&self) -> bool {
+        self.altitude != ALTITUDE_UNAVAILABLE
+    }
+
+    /// Returns the altitude in meters, or `None` if it is unavailable
+    pub fn altitude_meters(&self) -> Option<f64> {
+        self.is_altitude_available()
+            .then(|| altitude_from_etsi(self.altitude))
+    }
 }
 
+/// Out of range values are clamped to the ETSI ranges, NaN values are converted to the
+/// unavailable values
 impl From<Position> for ReferencePosition {
     fn from(position: Position) -> Self {
         ReferencePosition {
-            latitude: coordinate_to_etsi(position.latitude),
-            longitude: coordinate_to_etsi(position.longitude),
-            altitude: altitude_to_etsi(position.altitude),
+            latitude: bounded_coordinate_to_etsi(
+                position.latitude,
+                LATITUDE_MAX,
+                LATITUDE_UNAVAILABLE,
+            ),
+            longitude: bounded_coordinate_to_etsi(
+                position.longitude,
+                LONGITUDE_MAX,
+                LONGITUDE_UNAVAILABLE,
+            ),
+            altitude: position
+                .altitude
+                .filter(|altitude| !altitude.is_nan())
+                .map_or(ALTITUDE_UNAVAILABLE, |altitude| {
+                    altitude_to_etsi(altitude).clamp(ALTITUDE_MIN, ALTITUDE_MAX)
+                }),
         }
     }
 }
@@ -70,6 +189,17 @@ fn coordinate_to_etsi(radians: f64) -> i32 {
     (degrees * f64::from(10i32.pow(u32::from(COORDINATE_SIGNIFICANT_DIGIT)))) as i32
 }
 
+/// Converts a coordinate from radians to tenths of microdegree, clamped to [-max, max]
+///
+/// NaN is converted to the unavailable value
+fn bounded_coordinate_to_etsi(radians: f64, max: i32, unavailable: i32) -> i32 {
+    if radians.is_nan() {
+        unavailable
+    } else {
+        coordinate_to_etsi(radians).clamp(-max, max)
+    }
+}
+
 /// Converts altitude from centimeters to meters
 pub(crate) fn altitude_from_etsi(centimeters: i32) -> f64 {
     f64::from(centimeters) / 10f64.powf(f64::from(ALTITUDE_SIGNIFICANT_DIGIT))
@@ -84,7 +214,7 @@ fn altitude_to_etsi(meters: f64) -> i32 {
 mod tests {
     use crate::exchange::etsi::reference_position::{
         altitude_from_etsi, altitude_to_etsi, coordinate_from_etsi, coordinate_to_etsi,
-        ReferencePosition,
+        ReferencePosition, ReferencePositionError, LONGITUDE_MAX,
     };
     use crate::mobility::position::Position;
 
@@ -151,7 +281,7 @@ mod tests {
         let expected_position = Position {
             latitude: 48.8417860_f64.to_radians(),
             longitude: 2.3678940_f64.to_radians(),
-            altitude: 168.80,
+            altitude: Some(168.80),
         };
 
         let position = reference_position.as_position();
@@ -169,8 +299,8 @@ mod tests {
             expected_position.longitude,
         );
         assert!(
-            (position.altitude - expected_position.altitude).abs() <= 1e-11,
-            "Actual altitude: {} (Expected: {}",
+            (position.altitude.unwrap() - expected_position.altitude.unwrap()).abs() <= 1e-11,
+            "Actual altitude: {:?} (Expected: {:?}",
             position.altitude,
             expected_position.altitude,
         );
@@ -181,7 +311,7 @@ mod tests {
         let position = Position {
             latitude: 48.8417860_f64.to_radians(),
             longitude: 2.3678940_f64.to_radians(),
-            altitude: 168.80,
+            altitude: Some(168.80),
         };
         let expected_reference_position = ReferencePosition {
             latitude: 488417860,
@@ -204,4 +334,269 @@ mod tests {
             expected_reference_position.altitude
         );
     }
+
+    #[test]
+    fn unavailable_altitude_is_none() {
+        let reference_position = ReferencePosition {
+            latitude: 488417860,
+            longitude: 23678940,
+            altitude: 800001,
+        };
+
+        assert!(!reference_position.is_altitude_available());
+        assert!(reference_position.altitude_meters().is_none());
+        assert!(reference_position.as_position().altitude.is_none());
+    }
+
+    #[test]
+    fn zero_altitude_is_available() {
+        let reference_position = ReferencePosition {
+            latitude: 488417860,
+            longitude: 23678940,
+            altitude: 0,
+        };
+
+        assert!(reference_position.is_altitude_available());
+        assert_eq!(reference_position.altitude_meters(), Some(0.));
+        assert_eq!(reference_position.as_position().altitude, Some(0.));
+    }
+
+    macro_rules! test_from_position {
+        ($test_name:ident, $latitude:expr, $longitude:expr, $altitude:expr, $expected:expr) => {
+            #[test]
+            fn $test_name() {
+                let position = Position {
+                    latitude: $latitude,
+                    longitude: $longitude,
+                    altitude: $altitude,
+                };
+
+                let reference_position = ReferencePosition::from(position);
+
+                assert_eq!(reference_position, $expected);
+                assert!(reference_position.is_valid());
+            }
+        };
+    }
+    test_from_position!(
+        nan_is_unavailable,
+        f64::NAN,
+        f64::NAN,
+        Some(f64::NAN),
+        ReferencePosition {
+            latitude: 900000001,
+            longitude: 1800000001,
+            altitude: 800001,
+        }
+    );
+    test_from_position!(
+        out_of_range_is_clamped,
+        95_f64.to_radians(),
+        -200_f64.to_radians(),
+        Some(9000.),
+        ReferencePosition {
+            latitude: 900000000,
+            longitude: -1800000000,
+            altitude: 800000,
+        }
+    );
+    test_from_position!(
+        infinite_is_clamped,
+        f64::NEG_INFINITY,
+        f64::INFINITY,
+        Some(-2000.),
+        ReferencePosition {
+            latitude: -900000000,
+            longitude: 1800000000,
+            altitude: -100000,
+        }
+    );
+
+    #[test]
+    fn unknown_altitude_to_etsi() {
+        let position = Position {
+            latitude: 48.8417860_f64.to_radians(),
+            longitude: 2.3678940_f64.to_radians(),
+            altitude: None,
+        };
+
+        let reference_position = ReferencePosition::from(position);
+
+        assert_eq!(reference_position.altitude, 800001);
+    }
+
+    macro_rules! test_validate {
+        ($test_name:ident, $latitude:expr, $longitude:expr, $expected:expr) => {
+            #[test]
+            fn $test_name() {
+                let reference_position = ReferencePosition {
+                    latitude: $latitude,
+                    longitude: $longitude,
+                    altitude: 800001,
+                };
+
+                let expected: Result<(), ReferencePositionError> = $expected;
+
+                assert_eq!(reference_position.validate(), expected);
+                assert_eq!(reference_position.is_valid(), expected.is_ok());
+            }
+        };
+    }
+    test_validate!(valid_position, 488417860, 23678940, Ok(()));
+    test_validate!(north_pole_antimeridian, 900000000, 1800000000, Ok(()));
+    test_validate!(south_pole_antimeridian, -900000000, -1800000000, Ok(()));
+    test_validate!(unavailable_coordinates, 900000001, 1800000001, Ok(()));
+    test_validate!(
+        latitude_above_range,
+        900000002,
+        23678940,
+        Err(ReferencePositionError::InvalidLatitude(900000002))
+    );
+    test_validate!(
+        latitude_below_range,
+        -900000001,
+        23678940,
+        Err(ReferencePositionError::InvalidLatitude(-900000001))
+    );
+    test_validate!(
+        longitude_above_range,
+        488417860,
+        1800000002,
+        Err(ReferencePositionError::InvalidLongitude(1800000002))
+    );
+    test_validate!(
+        longitude_below_range,
+        488417860,
+        -1800000001,
+        Err(ReferencePositionError::InvalidLongitude(-1800000001))
+    );
+
+    #[test]
+    fn try_as_position_rejects_unavailable_coordinates() {
+        let reference_position = ReferencePosition {
+            latitude: 900000001,
+            longitude: 23678940,
+            altitude: 16880,
+        };
+        assert_eq!(
+            reference_position.try_as_position(),
+            Err(ReferencePositionError::UnavailableLatitude)
+        );
+
+        let reference_position = ReferencePosition {
+            latitude: 488417860,
+            longitude: 1800000001,
+            altitude: 16880,
+        };
+        assert_eq!(
+            reference_position.try_as_position(),
+            Err(ReferencePositionError::UnavailableLongitude)
+        );
+    }
+
+    #[test]
+    fn try_as_position_rejects_out_of_range_coordinates() {
+        let reference_position = ReferencePosition {
+            latitude: i32::MAX,
+            longitude: 23678940,
+            altitude: 16880,
+        };
+
+        assert_eq!(
+            reference_position.try_as_position(),
+            Err(ReferencePositionError::InvalidLatitude(i32::MAX))
+        );
+    }
+
+    #[test]
+    fn try_as_position_accepts_valid_coordinates() {
+        let reference_position = ReferencePosition {
+            latitude: 488417860,
+            longitude: 23678940,
+            altitude: 16880,
+        };
+
+        assert_eq!(
+            reference_position.try_as_position(),
+            Ok(reference_position.as_position())
+        );
+    }
+
+    #[test]
+    fn zero_offset_is_identity() {
+        let reference_position = ReferencePosition {
+            latitude: 488417860,
+            longitude: 23678940,
+            altitude: 16880,
+        };
+
+        assert_eq!(reference_position.apply_offset(0, 0), reference_position);
+    }
+
+    #[test]
+    fn eastward_offset_increases_longitude() {
+        let reference_position = ReferencePosition {
+            latitude: 488417860,
+            longitude: 23678940,
+            altitude: 800001,
+        };
+
+        // 10 meters east
+        let moved = reference_position.apply_offset(1000, 0);
+
+        assert!(
+            (moved.latitude - reference_position.latitude).abs() <= 1,
+            "{}",
+            moved
+        );
+        // at 48.84°N, a 1e-7° longitude step is about 7.34 mm
+        assert!(
+            (moved.longitude - reference_position.longitude - 1362).abs() <= 2,
+            "{}",
+            moved
+        );
+        assert_eq!(moved.altitude, 800001);
+    }
+
+    #[test]
+    fn northward_offset_increases_latitude() {
+        let reference_position = ReferencePosition {
+            latitude: 488417860,
+            longitude: 23678940,
+            altitude: 16880,
+        };
+
+        // 10 meters north, a 1e-7° latitude step is about 11.1 mm
+        let moved = reference_position.apply_offset(0, 1000);
+
+        assert!(
+            (moved.latitude - reference_position.latitude - 899).abs() <= 2,
+            "{}",
+            moved
+        );
+        assert!(
+            (moved.longitude - reference_position.longitude).abs() <= 1,
+            "{}",
+            moved
+        );
+        assert_eq!(moved.altitude, 16880);
+    }
+
+    #[test]
+    fn eastward_offset_wraps_around_the_antimeridian() {
+        let reference_position = ReferencePosition {
+            latitude: 0,
+            longitude: 1799999999,
+            altitude: 0,
+        };
+
+        // 10 meters east, a 1e-7° longitude step is about 11.1 mm at the equator
+        let moved = reference_position.apply_offset(1000, 0);
+
+        assert!(
+            (moved.longitude - (-LONGITUDE_MAX + 898)).abs() <= 2,
+            "{}",
+            moved
+        );
+    }
 }
@@ -0,0 +1,421 @@
+/*
+ * Software Name : libits-client
+ * SPDX-FileCopyrightText: Copyright (c) Orange SA
+ * SPDX-License-Identifier: MIT
+ *
+ * This software is distributed under the MIT license,
+ * see the "LICENSE.txt" file for more details or https://opensource.org/license/MIT/
+ *
+ * Authors: see CONTRIBUTORS.md
+ */
+
+use std::f64::consts::PI;
+
+use crate::exchange::etsi::angle::Angle;
+use crate::exchange::etsi::collective_perception_message::{
+    CircularArea, DetectionArea, EllipticArea, FreeSpaceArea, Offset, RectangleArea,
+    StationarySensorRadial,
+};
+
+/// Area described in a CPM, e.g. a sensor's detection area or a free space
+///
+/// The local frame is centred on the CPM reference position, with x towards the east and y
+/// towards the north, in meters; polygon points and centres are offsets from that position
+pub trait Shape {
+    /// Returns the area's surface in m², `None` if it cannot be known from the area alone
+    fn area_m2(&self) -> Option<f64>;
+
+    /// Returns true if the point, in the local frame, is within the area
+    fn contains_local(&self, x: f64, y: f64) -> bool;
+}
+
+/// Converts lengths from decimeters to meters
+fn length_from_etsi(decimeters: u16) -> f64 {
+    f64::from(decimeters) / 10.
+}
+
+/// Returns the offset in meters, the origin if there is none
+fn offset_from_etsi(offset: Option<&Offset>) -> (f64, f64) {
+    offset.map_or((0., 0.), |offset| {
+        (f64::from(offset.x) / 100., f64::from(offset.y) / 100.)
+    })
+}
+
+/// Returns the point's coordinates along the semi major and the semi minor axes of a shape
+/// centred on `center`, the semi major axis pointing `orientation` clockwise from north (north
+/// if unavailable)
+fn to_shape_frame(x: f64, y: f64, center: Option<&Offset>, orientation: u16) -> (f64, f64) {
+    let (center_x, center_y) = offset_from_etsi(center);
+    let (dx, dy) = (x - center_x, y - center_y);
+    let (sin, cos) = Angle(orientation).to_radians().unwrap_or(0.).sin_cos();
+
+    (dx * sin + dy * cos, dx * cos - dy * sin)
+}
+
+impl Shape for CircularArea {
+    fn area_m2(&self) -> Option<f64> {
+        Some(PI * length_from_etsi(self.radius).powi(2))
+    }
+
+    fn contains_local(&self, x: f64, y: f64) -> bool {
+        let (center_x, center_y) = offset_from_etsi(self.node_center_point.as_ref());
+        (x - center_x).hypot(y - center_y) <= length_from_etsi(self.radius)
+    }
+}
+
+impl Shape for EllipticArea {
+    fn area_m2(&self) -> Option<f64> {
+        Some(
+            PI * length_from_etsi(self.semi_major_range_length)
+                * length_from_etsi(self.semi_minor_range_length),
+        )
+    }
+
+    fn contains_local(&self, x: f64, y: f64) -> bool {
+        let (major, minor) = to_shape_frame(
+            x,
+            y,
+            self.node_center_point.as_ref(),
+            self.semi_major_range_orientation,
+        );
+        let semi_major = length_from_etsi(self.semi_major_range_length);
+        let semi_minor = length_from_etsi(self.semi_minor_range_length);
+
+        if semi_major == 0. || semi_minor == 0. {
+            return false;
+        }
+        (major / semi_major).powi(2) + (minor / semi_minor).powi(2) <= 1.
+    }
+}
+
+impl Shape for RectangleArea {
+    fn area_m2(&self) -> Option<f64> {
+        Some(
+            4. * length_from_etsi(self.semi_major_range_length)
+                * length_from_etsi(self.semi_minor_range_length),
+        )
+    }
+
+    fn contains_local(&self, x: f64, y: f64) -> bool {
+        let (major, minor) = to_shape_frame(
+            x,
+            y,
+            self.node_center_point.as_ref(),
+            self.semi_major_range_orientation,
+        );
+
+        major.abs() <= length_from_etsi(self.semi_major_range_length)
+            && minor.abs() <= length_from_etsi(self.semi_minor_range_length)
+    }
+}
+
+/// Polygon closed by linking its last point to the first one
+impl Shape for [Offset] {
+    fn area_m2(&self) -> Option<f64> {
+        if self.len() < 3 {
+            return None;
+        }
+
+        let points = self
+            .iter()
+            .map(|offset| offset_from_etsi(Some(offset)))
+            .collect::<Vec<_>>();
+        let twice_area = points
+            .iter()
+            .zip(points.iter().cycle().skip(1))
+            .map(|((x1, y1), (x2, y2))| x1 * y2 - x2 * y1)
+            .sum::<f64>();
+        Some(twice_area.abs() / 2.)
+    }
+
+    fn contains_local(&self, x: f64, y: f64) -> bool {
+        let points = self
+            .iter()
+            .map(|offset| offset_from_etsi(Some(offset)))
+            .collect::<Vec<_>>();
+
+        // ray casting towards the east
+        let mut inside = false;
+        for ((x1, y1), (x2, y2)) in points.iter().zip(points.iter().cycle().skip(1)) {
+            if (*y1 > y) != (*y2 > y) && x < x1 + (y - y1) * (x2 - x1) / (y2 - y1) {
+                inside = !inside;
+            }
+        }
+        inside
+    }
+}
+
+/// Sector swept clockwise from the start to the end opening angle
+impl Shape for StationarySensorRadial {
+    fn area_m2(&self) -> Option<f64> {
+        let opening = Angle(self.horizontal_opening_angle_start)
+            .difference(&Angle(self.horizontal_opening_angle_end))?;
+        let opening = f64::from(i32::from(opening).rem_euclid(3600)) / 10.;
+
+        Some(length_from_etsi(self.range).powi(2) * opening.to_radians() / 2.)
+    }
+
+    fn contains_local(&self, x: f64, y: f64) -> bool {
+        let (Some(start), Some(end)) = (
+            Angle(self.horizontal_opening_angle_start).to_radians(),
+            Angle(self.horizontal_opening_angle_end).to_radians(),
+        ) else {
+            return false;
+        };
+        let (sensor_x, sensor_y) = offset_from_etsi(self.sensor_position_offset.as_ref());
+        let (dx, dy) = (x - sensor_x, y - sensor_y);
+        if dx.hypot(dy) > length_from_etsi(self.range) {
+            return false;
+        }
+
+        let bearing = dx.atan2(dy);
+        (bearing - start).rem_euclid(2. * PI) <= (end - start).rem_euclid(2. * PI)
+    }
+}
+
+/// Area of the first shape set; vehicle sensors have no area, their opening angles being
+/// relative to the vehicle heading
+impl Shape for DetectionArea {
+    fn area_m2(&self) -> Option<f64> {
+        if let Some(polygon) = &self.stationary_sensor_polygon {
+            polygon.area_m2()
+        } else if let Some(radial) = &self.stationary_sensor_radial {
+            radial.area_m2()
+        } else if let Some(circle) = &self.stationary_sensor_circular {
+            circle.area_m2()
+        } else if let Some(ellipse) = &self.stationary_sensor_ellipse {
+            ellipse.area_m2()
+        } else if let Some(rectangle) = &self.stationary_sensor_rectangle {
+            rectangle.area_m2()
+        } else {
+            None
+        }
+    }
+
+    fn contains_local(&self, x: f64, y: f64) -> bool {
+        if let Some(polygon) = &self.stationary_sensor_polygon {
+            polygon.contains_local(x, y)
+        } else if let Some(radial) = &self.stationary_sensor_radial {
+            radial.contains_local(x, y)
+        } else if let Some(circle) = &self.stationary_sensor_circular {
+            circle.contains_local(x, y)
+        } else if let Some(ellipse) = &self.stationary_sensor_ellipse {
+            ellipse.contains_local(x, y)
+        } else if let Some(rectangle) = &self.stationary_sensor_rectangle {
+            rectangle.contains_local(x, y)
+        } else {
+            false
+        }
+    }
+}
+
+/// Area of the first shape set
+impl Shape for FreeSpaceArea {
+    fn area_m2(&self) -> Option<f64> {
+        if let Some(polygon) = &self.free_space_polygon {
+            polygon.area_m2()
+        } else if let Some(circle) = &self.free_space_circular {
+            circle.area_m2()
+        } else if let Some(ellipse) = &self.free_space_ellipse {
+            ellipse.area_m2()
+        } else if let Some(rectangle) = &self.free_space_rectangle {
+            rectangle.area_m2()
+        } else {
+            None
+        }
+    }
+
+    fn contains_local(&self, x: f64, y: f64) -> bool {
+        if let Some(polygon) = &self.free_space_polygon {
+            polygon.contains_local(x, y)
+        } else if let Some(circle) = &self.free_space_circular {
+            circle.contains_local(x, y)
+        } else if let Some(ellipse) = &self.free_space_ellipse {
+            ellipse.contains_local(x, y)
+        } else if let Some(rectangle) = &self.free_space_rectangle {
+            rectangle.contains_local(x, y)
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::exchange::etsi::collective_perception_message::{
+        CircularArea, DetectionArea, EllipticArea, Offset, RectangleArea, StationarySensorRadial,
+        VehicleSensor,
+    };
+    use crate::exchange::etsi::shape::Shape;
+    use std::f64::consts::PI;
+
+    fn offset(x: i32, y: i32) -> Offset {
+        Offset { x, y, z: None }
+    }
+
+    fn rectangle(orientation: u16) -> RectangleArea {
+        RectangleArea {
+            semi_major_range_length: 50,
+            semi_minor_range_length: 20,
+            semi_major_range_orientation: orientation,
+            node_center_point: None,
+            semi_height: None,
+        }
+    }
+
+    #[test]
+    fn rectangle_area() {
+        assert_eq!(rectangle(0).area_m2(), Some(40.));
+    }
+
+    #[test]
+    fn circle_area() {
+        let circle = CircularArea {
+            node_center_point: None,
+            radius: 100,
+        };
+
+        assert!((circle.area_m2().unwrap() - 100. * PI).abs() < 1e-9);
+    }
+
+    #[test]
+    fn ellipse_area() {
+        let ellipse = EllipticArea {
+            semi_major_range_length: 30,
+            semi_minor_range_length: 10,
+            ..Default::default()
+        };
+
+        assert!((ellipse.area_m2().unwrap() - 3. * PI).abs() < 1e-9);
+    }
+
+    #[test]
+    fn polygon_area() {
+        let square = [
+            offset(0, 0),
+            offset(1000, 0),
+            offset(1000, 1000),
+            offset(0, 1000),
+        ];
+
+        assert_eq!(square.area_m2(), Some(100.));
+    }
+
+    #[test]
+    fn quarter_sector_area() {
+        let radial = StationarySensorRadial {
+            range: 100,
+            horizontal_opening_angle_start: 3150,
+            horizontal_opening_angle_end: 450,
+            ..Default::default()
+        };
+
+        assert!((radial.area_m2().unwrap() - 25. * PI).abs() < 1e-9);
+    }
+
+    #[test]
+    fn vehicle_sensor_has_no_area() {
+        let detection_area = DetectionArea {
+            vehicle_sensor: Some(VehicleSensor::default()),
+            ..Default::default()
+        };
+
+        assert!(detection_area.area_m2().is_none());
+        assert!(!detection_area.contains_local(0., 0.));
+    }
+
+    macro_rules! test_contains {
+        ($test_name:ident, $shape:expr, $x:expr, $y:expr, $expected:expr) => {
+            #[test]
+            fn $test_name() {
+                assert_eq!($shape.contains_local($x, $y), $expected);
+            }
+        };
+    }
+    test_contains!(north_rectangle_length, rectangle(0), 1., 4.9, true);
+    test_contains!(north_rectangle_width, rectangle(0), 2.1, 0., false);
+    test_contains!(east_rectangle_length, rectangle(900), 4.9, 1., true);
+    test_contains!(east_rectangle_width, rectangle(900), 0., 2.1, false);
+    test_contains!(
+        offset_circle,
+        CircularArea {
+            node_center_point: Some(offset(1000, 0)),
+            radius: 20,
+        },
+        11.,
+        1.,
+        true
+    );
+    test_contains!(
+        outside_offset_circle,
+        CircularArea {
+            node_center_point: Some(offset(1000, 0)),
+            radius: 20,
+        },
+        0.,
+        0.,
+        false
+    );
+    test_contains!(
+        ellipse_along_major_axis,
+        EllipticArea {
+            semi_major_range_length: 30,
+            semi_minor_range_length: 10,
+            semi_major_range_orientation: 900,
+            ..Default::default()
+        },
+        2.9,
+        0.,
+        true
+    );
+    test_contains!(
+        ellipse_beyond_minor_axis,
+        EllipticArea {
+            semi_major_range_length: 30,
+            semi_minor_range_length: 10,
+            semi_major_range_orientation: 900,
+            ..Default::default()
+        },
+        0.,
+        1.1,
+        false
+    );
+    test_contains!(
+        inside_triangle,
+        [offset(0, 0), offset(1000, 0), offset(0, 1000)][..],
+        2.,
+        2.,
+        true
+    );
+    test_contains!(
+        outside_triangle,
+        [offset(0, 0), offset(1000, 0), offset(0, 1000)][..],
+        6.,
+        6.,
+        false
+    );
+    test_contains!(
+        within_sector_across_north,
+        StationarySensorRadial {
+            range: 100,
+            horizontal_opening_angle_start: 3150,
+            horizontal_opening_angle_end: 450,
+            ..Default::default()
+        },
+        -1.,
+        5.,
+        true
+    );
+    test_contains!(
+        beside_sector,
+        StationarySensorRadial {
+            range: 100,
+            horizontal_opening_angle_start: 3150,
+            horizontal_opening_angle_end: 450,
+            ..Default::default()
+        },
+        5.,
+        1.,
+        false
+    );
+}
@@ -241,7 +241,7 @@ mod test {
                 assert!(spat.revision.is_none());
                 assert!(spat.protocol_version.is_none());
                 assert_eq!(spat.states.len(), 1);
-                assert!(spat.states.first().is_some());
+                assert!(!spat.states.is_empty());
                 let state = spat.states.first().unwrap();
                 assert_eq!(state.id, 16);
                 assert_eq!(state.state, TrafficLightState::StopAndRemain);
@@ -295,7 +295,7 @@ mod test {
                 assert_eq!(spat.revision.unwrap(), 14);
                 assert_eq!(spat.protocol_version.unwrap(), 15);
                 assert_eq!(spat.states.len(), 1);
-                assert!(spat.states.first().is_some());
+                assert!(!spat.states.is_empty());
                 let state = spat.states.first().unwrap();
                 assert_eq!(state.id, 16);
                 assert_eq!(state.state, TrafficLightState::StopAndRemain);
@@ -343,7 +343,7 @@ mod test {
                 assert_eq!(spat.revision.unwrap(), 14);
                 assert_eq!(spat.protocol_version.unwrap(), 15);
                 assert_eq!(spat.states.len(), 1);
-                assert!(spat.states.first().is_some());
+                assert!(!spat.states.is_empty());
                 let state = spat.states.first().unwrap();
                 assert_eq!(state.id, 16);
                 assert_eq!(state.state, TrafficLightState::StopAndRemain);
@@ -389,7 +389,7 @@ mod test {
                 assert_eq!(spat.revision.unwrap(), 14);
                 assert_eq!(spat.protocol_version.unwrap(), 15);
                 assert_eq!(spat.states.len(), 1);
-                assert!(spat.states.first().is_some());
+                assert!(!spat.states.is_empty());
                 let state = spat.states.first().unwrap();
                 assert_eq!(state.id, 16);
                 assert_eq!(state.state, TrafficLightState::StopAndRemain);
@@ -441,7 +441,7 @@ mod test {
                 assert_eq!(spat.revision.unwrap(), 14);
                 assert_eq!(spat.protocol_version.unwrap(), 15);
                 assert_eq!(spat.states.len(), 1);
-                assert!(spat.states.first().is_some());
+                assert!(!spat.states.is_empty());
                 let state = spat.states.first().unwrap();
                 assert_eq!(state.id, 16);
                 assert_eq!(state.state, TrafficLightState::StopAndRemain);
@@ -502,7 +502,7 @@ mod test {
                 assert_eq!(spat.revision.unwrap(), 14);
                 assert_eq!(spat.protocol_version.unwrap(), 15);
                 assert_eq!(spat.states.len(), 1);
-                assert!(spat.states.first().is_some());
+                assert!(!spat.states.is_empty());
                 let state = spat.states.first().unwrap();
                 assert_eq!(state.id, 16);
                 assert_eq!(state.state, TrafficLightState::StopAndRemain);
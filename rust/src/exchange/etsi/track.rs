@@ -0,0 +1,150 @@
+/*
+ * Software Name : libits-client
+ * SPDX-FileCopyrightText: Copyright (c) Orange SA
+ * SPDX-License-Identifier: MIT
+ *
+ * This software is distributed under the MIT license,
+ * see the "LICENSE.txt" file for more details or https://opensource.org/license/MIT/
+ *
+ * Authors: see CONTRIBUTORS.md
+ */
+
+use std::time::Duration;
+
+use crate::exchange::etsi::mobile_perceived_object::MobilePerceivedObject;
+
+/// Confidence below which a track can be dropped
+const DROP_THRESHOLD: f64 = 0.1;
+
+/// Perceived object followed over time, whose confidence decays while it is not re-observed
+///
+/// The confidence starts from the object's confidence, or 1 if the object does not provide any,
+/// and decays exponentially: a fully confident track not re-observed becomes droppable once its
+/// time to live has elapsed, a less confident one sooner
+#[derive(Clone, Debug)]
+pub struct Track {
+    object: MobilePerceivedObject,
+    confidence: f64,
+    time_constant: f64,
+}
+
+impl Track {
+    pub fn new(object: MobilePerceivedObject, ttl: Duration) -> Self {
+        Self {
+            confidence: initial_confidence(&object),
+            object,
+            time_constant: ttl.as_millis() as f64 / (1. / DROP_THRESHOLD).ln(),
+        }
+    }
+
+    pub fn object(&self) -> &MobilePerceivedObject {
+        &self.object
+    }
+
+    /// Returns the track confidence, between 0 and 1
+    pub fn confidence(&self) -> f64 {
+        self.confidence
+    }
+
+    /// Decays the confidence for the time elapsed since the last aging or observation
+    pub fn age(&mut self, elapsed_ms: u64) {
+        if self.time_constant > 0. {
+            self.confidence *= (-(elapsed_ms as f64) / self.time_constant).exp();
+        } else {
+            self.confidence = 0.;
+        }
+    }
+
+    /// Updates the track with a new observation of the object, restoring its confidence
+    pub fn observe(&mut self, object: MobilePerceivedObject) {
+        self.confidence = initial_confidence(&object);
+        self.object = object;
+    }
+
+    /// Returns true if the confidence dropped below the threshold and the track should be removed
+    pub fn is_droppable(&self) -> bool {
+        self.confidence < DROP_THRESHOLD
+    }
+}
+
+/// Object confidence is a percentage from 1 to 100, 0 meaning unknown and 101 unavailable
+fn initial_confidence(object: &MobilePerceivedObject) -> f64 {
+    match object.perceived_object.confidence.object {
+        Some(confidence @ 1..=100) => f64::from(confidence) / 100.,
+        _ => 1.,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::exchange::etsi::collective_perception_message::CollectivePerceptionMessage;
+    use crate::exchange::etsi::mobile_perceived_object::MobilePerceivedObject;
+    use crate::exchange::etsi::perceived_object::{ObjectConfidence, PerceivedObject};
+    use crate::exchange::etsi::track::Track;
+
+    fn object(confidence: Option<u8>) -> MobilePerceivedObject {
+        MobilePerceivedObject::new(
+            PerceivedObject {
+                confidence: ObjectConfidence {
+                    object: confidence,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            &CollectivePerceptionMessage::default(),
+        )
+    }
+
+    #[test]
+    fn confidence_monotonically_decreases() {
+        let mut track = Track::new(object(None), Duration::from_secs(2));
+        let mut previous = track.confidence();
+
+        for _ in 0..20 {
+            track.age(100);
+            assert!(track.confidence() < previous);
+            previous = track.confidence();
+        }
+    }
+
+    #[test]
+    fn droppable_past_ttl() {
+        let mut track = Track::new(object(None), Duration::from_secs(2));
+
+        track.age(1_900);
+        assert!(!track.is_droppable());
+
+        track.age(200);
+        assert!(track.is_droppable());
+    }
+
+    #[test]
+    fn less_confident_object_is_dropped_sooner() {
+        let mut track = Track::new(object(Some(50)), Duration::from_secs(2));
+        assert_eq!(track.confidence(), 0.5);
+
+        track.age(1_500);
+
+        assert!(track.is_droppable());
+    }
+
+    #[test]
+    fn observation_restores_confidence() {
+        let mut track = Track::new(object(Some(80)), Duration::from_secs(2));
+        track.age(1_500);
+
+        track.observe(object(Some(90)));
+
+        assert_eq!(track.confidence(), 0.9);
+        assert!(!track.is_droppable());
+    }
+
+    #[test]
+    fn unavailable_confidence_starts_full() {
+        let track = Track::new(object(Some(101)), Duration::from_secs(2));
+
+        assert_eq!(track.confidence(), 1.);
+    }
+}
@@ -0,0 +1,20 @@
+/*
+ * Software Name : libits-client
+ * SPDX-FileCopyrightText: Copyright (c) Orange SA
+ * SPDX-License-Identifier: MIT
+ *
+ * This software is distributed under the MIT license,
+ * see the "LICENSE.txt" file for more details or https://opensource.org/license/MIT/
+ *
+ * Authors: see CONTRIBUTORS.md
+ */
+
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ExchangeError {
+    #[error("No exchange message for topic message type '{0}'")]
+    UnsupportedMessageType(String),
+    #[error("Failed to parse exchange: {0}")]
+    InvalidPayload(#[from] serde_json::Error),
+}
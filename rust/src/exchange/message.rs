@@ -23,6 +23,7 @@ use crate::exchange::message::content::Content;
 use crate::exchange::message::content_error::ContentError;
 use crate::exchange::message::information::BoxedInformation;
 use crate::exchange::mortal::Mortal;
+use crate::mobility::geofence::Polygon;
 use crate::mobility::mobile::Mobile;
 use enum_dispatch::enum_dispatch;
 use serde::{Deserialize, Serialize};
@@ -41,6 +42,30 @@ pub enum Message {
 }
 
 impl Message {
+    /// Returns the message as [Content], whatever its type
+    pub fn content(&self) -> &dyn Content {
+        match self {
+            Self::CAM(v) => v,
+            Self::CPM(v) => v,
+            Self::DENM(v) => v,
+            Self::INFO(v) => v,
+            Self::MAPEM(v) => v,
+            Self::SPATEM(v) => v,
+        }
+    }
+
+    /// Returns the id of the station which sent the message, `None` for information messages
+    pub fn station_id(&self) -> Option<u64> {
+        match self {
+            Self::CAM(cam) => Some(u64::from(cam.station_id)),
+            Self::CPM(cpm) => Some(u64::from(cpm.station_id)),
+            Self::DENM(denm) => Some(u64::from(denm.station_id)),
+            Self::INFO(_) => None,
+            Self::MAPEM(mapem) => mapem.sending_station_id,
+            Self::SPATEM(spatem) => spatem.sending_station_id,
+        }
+    }
+
     pub fn as_content(&mut self) -> &mut dyn Content {
         match self {
             Self::CAM(v) => v,
@@ -12,6 +12,7 @@
 use crate::client::configuration::Configuration;
 use crate::exchange::message::content_error::ContentError;
 use crate::exchange::mortal::Mortal;
+use crate::mobility::geofence::Polygon;
 use crate::mobility::mobile::Mobile;
 use enum_dispatch::enum_dispatch;
 
@@ -24,4 +25,10 @@ pub trait Content {
     fn as_mobile(&self) -> Result<&dyn Mobile, ContentError>;
 
     fn as_mortal(&self) -> Result<&dyn Mortal, ContentError>;
+
+    /// Returns the area the message is relevant to, on which it is published when the pipeline's
+    /// `max_covering_tiles` option is set; None by default
+    fn covering_area(&self) -> Option<Polygon> {
+        None
+    }
 }
@@ -11,6 +11,8 @@
 
 use thiserror::Error;
 
+use crate::exchange::etsi::reference_position::ReferencePositionError;
+
 #[derive(Debug, Error)]
 pub enum ContentError {
     #[error("Struct {0} does not implement Mobile trait")]
@@ -21,4 +23,6 @@ pub enum ContentError {
     MissingStationDataContainer(&'static str),
     #[error("{0} type message has been sent by a RSU station")]
     RsuOriginatingMessage(&'static str),
+    #[error("Invalid reference position: {0}")]
+    InvalidPosition(#[from] ReferencePositionError),
 }
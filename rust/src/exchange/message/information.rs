@@ -18,6 +18,7 @@ use std::ops::Deref;
 use crate::client::configuration::Configuration;
 use crate::exchange::message::content_error::ContentError;
 use crate::exchange::message::content_error::ContentError::{NotAMobile, NotAMortal};
+use crate::mobility::quadtree::quadkey::Quadkey;
 use crate::transport::payload::Payload;
 use serde::{Deserialize, Serialize};
 
@@ -88,6 +89,44 @@ pub struct Vertex {
 
 impl Information {
     pub const TYPE: &'static str = "info";
+
+    /// Creates the information of a running instance, valid for `validity_duration` seconds from
+    /// the UNIX timestamp, in milliseconds, e.g. the configuration's [clock][1] time
+    ///
+    /// The instance type is one of `local`, `edge` or `central`
+    ///
+    /// [1]: crate::client::configuration::Configuration::clock
+    pub fn new(
+        instance_id: &str,
+        instance_type: &str,
+        validity_duration: u32,
+        timestamp: u64,
+    ) -> Self {
+        Self {
+            // only value allowed by the 1.2.0 schema this struct follows
+            type_field: String::from("broker"),
+            version: String::from("1.2.0"),
+            instance_id: instance_id.to_string(),
+            instance_type: instance_type.to_string(),
+            running: true,
+            timestamp,
+            validity_duration,
+            ..Default::default()
+        }
+    }
+
+    /// Sets the service area to the tiles of the provided region of responsibility
+    pub fn with_region_of_responsibility(mut self, region_of_responsibility: &[Quadkey]) -> Self {
+        self.service_area = Some(ServiceArea {
+            type_field: String::from("tiles"),
+            quadkeys: region_of_responsibility
+                .iter()
+                .map(|quadkey| quadkey.to_string().replace('/', ""))
+                .collect(),
+            ..Default::default()
+        });
+        self
+    }
 }
 
 impl Content for Information {
@@ -118,7 +157,7 @@ impl Mortal for Information {
     }
 
     fn terminated(&self) -> bool {
-        self.expired()
+        self.validity_duration == 0
     }
 }
 
@@ -155,6 +194,34 @@ impl Content for BoxedInformation {
 #[cfg(test)]
 mod tests {
     use crate::exchange::message::information::{Information, ServiceArea};
+    use crate::exchange::mortal::Mortal;
+    use crate::mobility::quadtree::quadkey::Quadkey;
+    use std::str::FromStr;
+
+    #[test]
+    fn region_of_responsibility_is_set_as_tiles() {
+        let information = Information::new("corp_role_32", "local", 60, 1_700_000_000_000)
+            .with_region_of_responsibility(&[
+                Quadkey::from_str("12020").unwrap(),
+                Quadkey::from_str("1/2/0/2/1").unwrap(),
+            ]);
+
+        let service_area = information.service_area.expect("Service area must be set");
+        assert_eq!(service_area.type_field, "tiles");
+        assert_eq!(service_area.quadkeys, vec!["12020", "12021"]);
+    }
+
+    #[test]
+    fn new_information_is_running_and_valid() {
+        let information = Information::new("corp_role_32", "local", 60, 1_700_000_000_000);
+
+        assert!(information.running);
+        assert_eq!(information.instance_id, "corp_role_32");
+        assert_eq!(information.instance_type, "local");
+        assert_eq!(information.timestamp, 1_700_000_000_000);
+        assert!(!information.expired_at(1_700_000_060_000));
+        assert!(information.expired_at(1_700_000_060_001));
+    }
 
     // FIXME either use or remove this function in tests
     #[allow(unused)]
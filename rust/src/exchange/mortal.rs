@@ -9,7 +9,11 @@
  * Authors: see CONTRIBUTORS.md
  */
 
-use crate::now;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Arc;
+
+use crate::clock::{Clock, SystemClock};
 
 pub trait Mortal {
     /// Returns the milliseconds timestamp at which this mortal item
@@ -18,17 +22,248 @@ pub trait Mortal {
 
     fn terminate(&mut self);
 
+    /// Terminates the item at the UNIX timestamp, in milliseconds
+    ///
+    /// Items recording when they were terminated must override it, the default ignores the
+    /// timestamp
+    fn terminate_at(&mut self, _now_ms: u64) {
+        self.terminate()
+    }
+
     fn terminated(&self) -> bool;
 
+    /// Returns true if the item is expired according to the [SystemClock], see [expired_at][1]
+    ///
+    /// [1]: Mortal::expired_at
     fn expired(&self) -> bool {
-        now() > self.timeout()
+        self.expired_at(SystemClock.now_millis())
+    }
+
+    /// Returns true if the item is expired at the UNIX timestamp, in milliseconds
+    ///
+    /// Items whose timeout is not a UNIX timestamp must convert it
+    fn expired_at(&self, now_ms: u64) -> bool {
+        now_ms > self.timeout()
     }
 
+    /// Returns the remaining seconds before the item expires, see [remaining_time_at][1]
+    ///
+    /// [1]: Mortal::remaining_time_at
     fn remaining_time(&self) -> u64 {
-        if self.timeout() > now() {
-            (self.timeout() - now()) / 1000
-        } else {
-            0
+        self.remaining_time_at(SystemClock.now_millis())
+    }
+
+    /// Returns the seconds remaining from the UNIX timestamp, in milliseconds, before the item
+    /// expires, 0 if it already is
+    ///
+    /// Items whose timeout is not a UNIX timestamp must convert it
+    fn remaining_time_at(&self, now_ms: u64) -> u64 {
+        self.timeout().saturating_sub(now_ms) / 1000
+    }
+}
+
+/// Map of mortal items, forgetting them once expired or terminated
+///
+/// Expired items are never returned, and are removed by [purge][1] or when the map would
+/// otherwise grow, so that the map does not keep dead items indefinitely
+///
+/// Items are checked against the time of the [clock][2], the system one unless
+/// [provided][3] (e.g. the configuration's one)
+///
+/// [1]: MortalCache::purge
+/// [2]: Clock
+/// [3]: MortalCache::with_clock
+pub struct MortalCache<K, V: Mortal> {
+    entries: HashMap<K, V>,
+    clock: Arc<dyn Clock>,
+}
+
+impl<K: Eq + Hash, V: Mortal> MortalCache<K, V> {
+    pub fn new() -> Self {
+        Self::with_clock(Arc::new(SystemClock))
+    }
+
+    pub fn with_clock(clock: Arc<dyn Clock>) -> Self {
+        Self {
+            entries: HashMap::new(),
+            clock,
         }
     }
+
+    /// Inserts the item, returns the previous one for the key if it was still alive
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        if self.entries.len() == self.entries.capacity() {
+            self.purge();
+        }
+        let now = self.clock.now_millis();
+        self.entries
+            .insert(key, value)
+            .filter(|previous| is_alive(previous, now))
+    }
+
+    /// Returns the item of the key, None if there is none or if it is dead
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let now = self.clock.now_millis();
+        self.entries.get(key).filter(|value| is_alive(*value, now))
+    }
+
+    /// Returns the item of the key, None if there is none or if it is dead
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let now = self.clock.now_millis();
+        self.entries
+            .get_mut(key)
+            .filter(|value| is_alive(*value, now))
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        self.entries.remove(key)
+    }
+
+    /// Returns the alive items
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        let now = self.clock.now_millis();
+        self.entries
+            .values()
+            .filter(move |value| is_alive(*value, now))
+    }
+
+    /// Removes the expired or terminated items, returns how many were removed
+    pub fn purge(&mut self) -> usize {
+        let now = self.clock.now_millis();
+        let before = self.entries.len();
+        self.entries.retain(|_, value| is_alive(value, now));
+        before - self.entries.len()
+    }
+
+    /// Returns the number of items kept, dead ones included until purged
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl<K: Eq + Hash, V: Mortal> Default for MortalCache<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn is_alive<V: Mortal>(value: &V, now: u64) -> bool {
+    !value.terminated() && !value.expired_at(now)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use crate::clock::MockClock;
+    use crate::exchange::etsi::decentralized_environmental_notification_message::{
+        DecentralizedEnvironmentalNotificationMessage, ManagementContainer,
+    };
+    use crate::exchange::etsi::timestamp_to_etsi;
+    use crate::exchange::mortal::{Mortal, MortalCache};
+
+    struct Item {
+        timeout: u64,
+        terminated: bool,
+    }
+
+    impl Item {
+        fn expiring_at(timeout: u64) -> Self {
+            Self {
+                timeout,
+                terminated: false,
+            }
+        }
+    }
+
+    impl Mortal for Item {
+        fn timeout(&self) -> u64 {
+            self.timeout
+        }
+
+        fn terminate(&mut self) {
+            self.terminated = true;
+        }
+
+        fn terminated(&self) -> bool {
+            self.terminated
+        }
+    }
+
+    #[test]
+    fn expired_item_is_not_returned() {
+        let mut cache = MortalCache::new();
+        cache.insert("expired", Item::expiring_at(0));
+        cache.insert("alive", Item::expiring_at(u64::MAX));
+
+        assert!(cache.get(&"expired").is_none());
+        assert!(cache.get(&"alive").is_some());
+        assert_eq!(cache.values().count(), 1);
+    }
+
+    #[test]
+    fn terminated_item_is_not_returned() {
+        let mut cache = MortalCache::new();
+        cache.insert("terminated", Item::expiring_at(u64::MAX));
+
+        cache.get_mut(&"terminated").unwrap().terminate();
+
+        assert!(cache.get(&"terminated").is_none());
+    }
+
+    #[test]
+    fn purge_removes_expired_items() {
+        let clock = Arc::new(MockClock::new(500));
+        let mut cache = MortalCache::with_clock(clock.clone());
+        cache.insert("early", Item::expiring_at(1_000));
+        cache.insert("late", Item::expiring_at(2_000));
+
+        clock.set(1_500);
+        assert_eq!(cache.purge(), 1);
+
+        assert_eq!(cache.len(), 1);
+        assert!(cache.remove(&"early").is_none());
+        assert!(cache.remove(&"late").is_some());
+    }
+
+    #[test]
+    fn expired_items_are_purged_when_growing() {
+        let mut cache = MortalCache::new();
+        cache.insert(0, Item::expiring_at(0));
+        let capacity = cache.entries.capacity();
+        for key in 1..capacity {
+            cache.insert(key, Item::expiring_at(u64::MAX));
+        }
+
+        cache.insert(capacity, Item::expiring_at(u64::MAX));
+
+        assert_eq!(cache.len(), capacity);
+        assert!(!cache.entries.contains_key(&0));
+    }
+
+    #[test]
+    fn denm_lives_for_its_validity_duration() {
+        let now = 1_700_000_000_000;
+        let clock = Arc::new(MockClock::new(now));
+        let mut cache = MortalCache::with_clock(clock.clone());
+        let denm = DecentralizedEnvironmentalNotificationMessage {
+            management_container: ManagementContainer {
+                reference_time: timestamp_to_etsi(now).unwrap(),
+                validity_duration: Some(10),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        cache.insert("denm", denm);
+        assert!(cache.get(&"denm").is_some());
+
+        clock.advance(Duration::from_secs(11));
+        assert!(cache.get(&"denm").is_none());
+    }
 }
@@ -0,0 +1,177 @@
+/*
+ * Software Name : libits-client
+ * SPDX-FileCopyrightText: Copyright (c) Orange SA
+ * SPDX-License-Identifier: MIT
+ *
+ * This software is distributed under the MIT license,
+ * see the "LICENSE.txt" file for more details or https://opensource.org/license/MIT/
+ *
+ * Authors: see CONTRIBUTORS.md
+ */
+
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use serde_json::Value;
+
+/// Version of the JSON schema a message was produced with, e.g. 1.1.3
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SchemaVersion {
+    pub major: u8,
+    pub minor: u8,
+    pub patch: u8,
+}
+
+impl SchemaVersion {
+    pub const fn new(major: u8, minor: u8, patch: u8) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+}
+
+impl FromStr for SchemaVersion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let numbers = s
+            .split('.')
+            .map(u8::from_str)
+            .collect::<Result<Vec<u8>, _>>()
+            .map_err(|e| format!("Invalid schema version '{}': {}", s, e))?;
+        match numbers[..] {
+            [major, minor, patch] => Ok(Self::new(major, minor, patch)),
+            _ => Err(format!(
+                "Invalid schema version '{}', expected <major>.<minor>.<patch>",
+                s
+            )),
+        }
+    }
+}
+
+impl Display for SchemaVersion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// Returns the schema version of a message of type `message_type` (e.g. `cam`), given either as
+/// a whole exchange or as its bare `message` content
+///
+/// The version is read from the exchange `version` field if any; otherwise it is the oldest
+/// version having the keys found:
+/// - 2.x exchanges name their type `message_type` instead of `type`, and have no `origin`
+/// - CAMs with a `special_vehicle_container` are 2.1 or later
+/// - CPMs with a `message_id` or a `number_of_perceived_objects` are 1.0
+///
+/// The `protocol_version` of the message is the ETSI PDU header version, it does not tell the
+/// schema version
+///
+/// Defaults to 1.0.0 when nothing tells
+pub fn schema_version(message_type: &str, value: &Value) -> SchemaVersion {
+    if let Some(version) = value
+        .get("version")
+        .and_then(Value::as_str)
+        .and_then(|version| SchemaVersion::from_str(version).ok())
+    {
+        return version;
+    }
+
+    let message = value.get("message").unwrap_or(value);
+    let has_key = |key: &str| message.get(key).is_some();
+    match message_type {
+        "cam" if has_key("special_vehicle_container") => return SchemaVersion::new(2, 1, 0),
+        "cpm" if has_key("message_id") || has_key("number_of_perceived_objects") => {
+            return SchemaVersion::new(1, 0, 0)
+        }
+        _ => (),
+    }
+
+    if value.get("message_type").is_some() && value.get("type").is_none() {
+        SchemaVersion::new(2, 0, 0)
+    } else {
+        SchemaVersion::new(1, 0, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::exchange::schema::{schema_version, SchemaVersion};
+    use serde_json::json;
+    use std::str::FromStr;
+
+    macro_rules! test_schema_version {
+        ($test_name:ident, $message_type:expr, $value:expr, $expected:expr) => {
+            #[test]
+            fn $test_name() {
+                assert_eq!(
+                    schema_version($message_type, &$value),
+                    SchemaVersion::from_str($expected).unwrap()
+                );
+            }
+        };
+    }
+    test_schema_version!(
+        version_field,
+        "cam",
+        json!({"type": "cam", "origin": "self", "version": "1.1.3", "message": {"protocol_version": 1}}),
+        "1.1.3"
+    );
+    test_schema_version!(
+        version_field_wins,
+        "cam",
+        json!({"message_type": "cam", "version": "2.1.0", "message": {"protocol_version": 1}}),
+        "2.1.0"
+    );
+    test_schema_version!(
+        type_key_is_v1,
+        "denm",
+        json!({"type": "denm", "origin": "self", "message": {"protocol_version": 2}}),
+        "1.0.0"
+    );
+    test_schema_version!(
+        message_type_key_is_v2,
+        "denm",
+        json!({"message_type": "denm", "message": {"protocol_version": 1}}),
+        "2.0.0"
+    );
+    test_schema_version!(
+        cam_special_vehicle_container,
+        "cam",
+        json!({"message": {"protocol_version": 2, "special_vehicle_container": {}}}),
+        "2.1.0"
+    );
+    test_schema_version!(
+        cpm_number_of_perceived_objects,
+        "cpm",
+        json!({"type": "cpm", "message": {"number_of_perceived_objects": 0}}),
+        "1.0.0"
+    );
+    test_schema_version!(
+        protocol_version_is_not_the_schema_version,
+        "cam",
+        json!({"protocol_version": 2, "station_id": 42}),
+        "1.0.0"
+    );
+    test_schema_version!(
+        bare_message_protocol_version_1,
+        "cam",
+        json!({"protocol_version": 1, "station_id": 42}),
+        "1.0.0"
+    );
+    test_schema_version!(nothing_tells, "cam", json!({}), "1.0.0");
+
+    #[test]
+    fn invalid_version_is_err() {
+        assert!(SchemaVersion::from_str("2.1").is_err());
+        assert!(SchemaVersion::from_str("2.x.0").is_err());
+    }
+
+    #[test]
+    fn versions_are_ordered() {
+        assert!(SchemaVersion::new(1, 1, 3) < SchemaVersion::new(2, 0, 0));
+        assert_eq!(SchemaVersion::new(2, 1, 0).to_string(), "2.1.0");
+    }
+}
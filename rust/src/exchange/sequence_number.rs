@@ -12,7 +12,7 @@
 use std::fmt::Formatter;
 use std::sync::Mutex;
 
-#[derive(Default)]
+#[derive(Debug, Default)]
 pub struct SequenceNumber {
     current: Mutex<u128>,
     max: u128,
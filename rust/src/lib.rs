@@ -12,6 +12,7 @@
 use std::time::{SystemTime, UNIX_EPOCH};
 
 pub mod client;
+pub mod clock;
 #[cfg(feature = "mobility")]
 pub mod exchange;
 #[cfg(feature = "mobility")]
@@ -9,6 +9,11 @@
  * Authors: see CONTRIBUTORS.md
  */
 
+pub mod angle;
+pub mod bounds;
+pub mod derive;
+pub mod filter;
+pub mod geofence;
 pub mod mobile;
 pub mod position;
 pub mod quadtree;
@@ -0,0 +1,113 @@
+/*
+ * Software Name : libits-client
+ * SPDX-FileCopyrightText: Copyright (c) Orange SA
+ * SPDX-License-Identifier: MIT
+ *
+ * This software is distributed under the MIT license,
+ * see the "LICENSE.txt" file for more details or https://opensource.org/license/MIT/
+ *
+ * Authors: see CONTRIBUTORS.md
+ */
+
+use std::f64::consts::{FRAC_PI_4, PI, TAU};
+
+/// Direction of a target relatively to a heading
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// Within 45° of the heading
+    Ahead,
+    /// Between 45° and 135° clockwise from the heading
+    Right,
+    /// More than 135° away from the heading
+    Behind,
+    /// Between 45° and 135° counterclockwise from the heading
+    Left,
+}
+
+/// Returns the signed angle to turn clockwise from `from` to reach `to`, normalized to (−π, π]
+///
+/// Both angles are in radians, clockwise from north like headings and [bearings][1]
+///
+/// [1]: crate::mobility::position::bearing
+pub fn bearing_difference(from: f64, to: f64) -> f64 {
+    let difference = (to - from) % TAU;
+
+    if difference <= -PI {
+        difference + TAU
+    } else if difference > PI {
+        difference - TAU
+    } else {
+        difference
+    }
+}
+
+/// Returns where a target stands relatively to the ego heading, given the bearing to the target
+///
+/// Both angles are in radians, clockwise from north
+pub fn relative_direction(ego_heading: f64, target_bearing: f64) -> Direction {
+    let difference = bearing_difference(ego_heading, target_bearing);
+
+    if difference.abs() <= FRAC_PI_4 {
+        Direction::Ahead
+    } else if difference.abs() >= 3. * FRAC_PI_4 {
+        Direction::Behind
+    } else if difference > 0. {
+        Direction::Right
+    } else {
+        Direction::Left
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mobility::angle::{bearing_difference, relative_direction, Direction};
+    use std::f64::consts::PI;
+
+    macro_rules! test_bearing_difference {
+        ($test_name:ident, $from:expr, $to:expr, $expected:expr) => {
+            #[test]
+            fn $test_name() {
+                let difference =
+                    bearing_difference(f64::to_radians($from), f64::to_radians($to)).to_degrees();
+
+                assert!(
+                    (difference - $expected).abs() < 1e-9,
+                    "{} != {}",
+                    difference,
+                    $expected
+                );
+            }
+        };
+    }
+    test_bearing_difference!(same_bearing, 42., 42., 0.);
+    test_bearing_difference!(small_clockwise, 10., 30., 20.);
+    test_bearing_difference!(small_counterclockwise, 30., 10., -20.);
+    test_bearing_difference!(clockwise_across_north, 350., 10., 20.);
+    test_bearing_difference!(counterclockwise_across_north, 10., 350., -20.);
+    test_bearing_difference!(full_turn_is_no_turn, 0., 360., 0.);
+    test_bearing_difference!(negative_bearing, 170., -170., 20.);
+    test_bearing_difference!(several_turns, 0., 730., 10.);
+
+    #[test]
+    fn opposite_bearings_are_normalized_to_pi() {
+        assert_eq!(bearing_difference(0., PI), PI);
+        assert_eq!(bearing_difference(PI, 0.), PI);
+    }
+
+    macro_rules! test_relative_direction {
+        ($test_name:ident, $heading:expr, $bearing:expr, $expected:expr) => {
+            #[test]
+            fn $test_name() {
+                assert_eq!(
+                    relative_direction(f64::to_radians($heading), f64::to_radians($bearing)),
+                    $expected
+                );
+            }
+        };
+    }
+    test_relative_direction!(ahead_across_north, 355., 5., Direction::Ahead);
+    test_relative_direction!(right_across_north, 350., 80., Direction::Right);
+    test_relative_direction!(left_across_north, 10., 290., Direction::Left);
+    test_relative_direction!(behind, 90., 265., Direction::Behind);
+    test_relative_direction!(behind_across_north, 0., 180., Direction::Behind);
+}
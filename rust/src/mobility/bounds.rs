@@ -0,0 +1,245 @@
+/*
+ * Software Name : libits-client
+ * SPDX-FileCopyrightText: Copyright (c) Orange SA
+ * SPDX-License-Identifier: MIT
+ *
+ * This software is distributed under the MIT license,
+ * see the "LICENSE.txt" file for more details or https://opensource.org/license/MIT/
+ *
+ * Authors: see CONTRIBUTORS.md
+ */
+
+use std::f64::consts::{PI, TAU};
+
+use crate::mobility::position::Position;
+
+/// Latitude/longitude bounds of a set of positions, in radians
+///
+/// A box whose west bound is greater than its east bound crosses the antimeridian (180°
+/// longitude), e.g. from 179° to -179°
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BoundingBox {
+    pub south: f64,
+    pub west: f64,
+    pub north: f64,
+    pub east: f64,
+}
+
+impl BoundingBox {
+    /// Returns the smallest box containing all the positions, None if there is none
+    ///
+    /// Longitudes are bounded by the shortest arc containing them all, so that a cluster spread
+    /// on both sides of the antimeridian gets a box crossing it rather than one spanning the
+    /// whole globe
+    pub fn from_positions<'a, I>(positions: I) -> Option<Self>
+    where
+        I: IntoIterator<Item = &'a Position>,
+    {
+        let mut south = f64::INFINITY;
+        let mut north = f64::NEG_INFINITY;
+        let mut longitudes = Vec::new();
+        for position in positions {
+            south = south.min(position.latitude);
+            north = north.max(position.latitude);
+            longitudes.push(normalize_longitude(position.longitude));
+        }
+        if longitudes.is_empty() {
+            return None;
+        }
+        longitudes.sort_by(f64::total_cmp);
+
+        // the box spans everything but the widest gap between two consecutive longitudes
+        let last = longitudes.len() - 1;
+        let (mut west, mut east) = (longitudes[0], longitudes[last]);
+        let mut widest_gap = longitudes[0] + TAU - longitudes[last];
+        for (index, pair) in longitudes.windows(2).enumerate() {
+            if pair[1] - pair[0] > widest_gap {
+                widest_gap = pair[1] - pair[0];
+                west = longitudes[index + 1];
+                east = longitudes[index];
+            }
+        }
+
+        Some(Self {
+            south,
+            west,
+            north,
+            east,
+        })
+    }
+
+    /// Returns true if the box crosses the antimeridian
+    pub fn crosses_antimeridian(&self) -> bool {
+        self.west > self.east
+    }
+
+    /// Returns the longitude span of the box in radians
+    pub fn width(&self) -> f64 {
+        (self.east - self.west).rem_euclid(TAU)
+    }
+
+    /// Returns the center of the box
+    pub fn center(&self) -> Position {
+        Position {
+            latitude: (self.south + self.north) / 2.,
+            longitude: normalize_longitude(self.west + self.width() / 2.),
+            altitude: None,
+        }
+    }
+
+    pub fn contains(&self, position: &Position) -> bool {
+        let longitude = normalize_longitude(position.longitude);
+        let within_longitudes = if self.crosses_antimeridian() {
+            longitude >= self.west || longitude <= self.east
+        } else {
+            longitude >= self.west && longitude <= self.east
+        };
+
+        within_longitudes && position.latitude >= self.south && position.latitude <= self.north
+    }
+
+    /// Extends the box to contain the position
+    ///
+    /// Longitudes are extended on the side requiring the smallest extension, possibly across the
+    /// antimeridian
+    pub fn expand(&mut self, position: &Position) {
+        self.south = self.south.min(position.latitude);
+        self.north = self.north.max(position.latitude);
+        if self.contains(&Position {
+            latitude: self.south,
+            ..*position
+        }) {
+            return;
+        }
+
+        let longitude = normalize_longitude(position.longitude);
+        let eastward = (longitude - self.east).rem_euclid(TAU);
+        let westward = (self.west - longitude).rem_euclid(TAU);
+        if eastward <= westward {
+            self.east = longitude;
+        } else {
+            self.west = longitude;
+        }
+    }
+}
+
+/// Returns the geographic centroid of the positions, None if there is none
+///
+/// Positions are averaged as vectors from the earth's center, which handles clusters crossing the
+/// antimeridian or near a pole
+pub fn centroid<'a, I>(positions: I) -> Option<Position>
+where
+    I: IntoIterator<Item = &'a Position>,
+{
+    let (mut x, mut y, mut z, mut count) = (0., 0., 0., 0);
+    for position in positions {
+        x += position.latitude.cos() * position.longitude.cos();
+        y += position.latitude.cos() * position.longitude.sin();
+        z += position.latitude.sin();
+        count += 1;
+    }
+    if count == 0 {
+        return None;
+    }
+
+    Some(Position {
+        latitude: z.atan2(x.hypot(y)),
+        longitude: y.atan2(x),
+        altitude: None,
+    })
+}
+
+/// Normalizes a longitude to [-π, π)
+pub(crate) fn normalize_longitude(longitude: f64) -> f64 {
+    (longitude + PI).rem_euclid(TAU) - PI
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mobility::bounds::{centroid, BoundingBox};
+    use crate::mobility::position::{position_from_degrees, Position};
+
+    fn positions(coordinates: &[(f64, f64)]) -> Vec<Position> {
+        coordinates
+            .iter()
+            .map(|(lat, lon)| position_from_degrees(*lat, *lon, 0.))
+            .collect()
+    }
+
+    fn assert_degrees(actual: f64, expected: f64) {
+        assert!(
+            (actual.to_degrees() - expected).abs() < 1e-9,
+            "{}° instead of {}°",
+            actual.to_degrees(),
+            expected
+        );
+    }
+
+    #[test]
+    fn empty_set_has_no_box() {
+        assert!(BoundingBox::from_positions(&Vec::new()).is_none());
+        assert!(centroid(&Vec::new()).is_none());
+    }
+
+    #[test]
+    fn cluster_box() {
+        let cluster = positions(&[(48.62, 2.24), (48.64, 2.26), (48.63, 2.20)]);
+
+        let bounding_box = BoundingBox::from_positions(&cluster).unwrap();
+
+        assert_degrees(bounding_box.south, 48.62);
+        assert_degrees(bounding_box.north, 48.64);
+        assert_degrees(bounding_box.west, 2.20);
+        assert_degrees(bounding_box.east, 2.26);
+        assert!(!bounding_box.crosses_antimeridian());
+        assert_degrees(bounding_box.center().latitude, 48.63);
+        assert_degrees(bounding_box.center().longitude, 2.23);
+        assert!(bounding_box.contains(&position_from_degrees(48.63, 2.25, 0.)));
+        assert!(!bounding_box.contains(&position_from_degrees(48.63, 2.30, 0.)));
+    }
+
+    #[test]
+    fn cluster_across_antimeridian() {
+        let cluster = positions(&[(-17., 179.), (-18., -179.5), (-17.5, 179.5)]);
+
+        let bounding_box = BoundingBox::from_positions(&cluster).unwrap();
+
+        // naive min/max would give a box from -179.5° to 179°, around the whole globe
+        assert!(bounding_box.crosses_antimeridian());
+        assert_degrees(bounding_box.west, 179.);
+        assert_degrees(bounding_box.east, -179.5);
+        assert_degrees(bounding_box.width(), 1.5);
+        assert_degrees(bounding_box.center().longitude, 179.75);
+        assert!(bounding_box.contains(&position_from_degrees(-17.5, 180., 0.)));
+        assert!(bounding_box.contains(&position_from_degrees(-17.5, -179.8, 0.)));
+        assert!(!bounding_box.contains(&position_from_degrees(-17.5, 0., 0.)));
+        let centroid = centroid(&cluster).unwrap();
+        assert!(bounding_box.contains(&centroid));
+        assert!(centroid.longitude.to_degrees().abs() > 179.5);
+    }
+
+    #[test]
+    fn expansion_takes_shortest_side() {
+        let mut bounding_box =
+            BoundingBox::from_positions(&positions(&[(0., 170.), (1., 178.)])).unwrap();
+
+        bounding_box.expand(&position_from_degrees(2., -178., 0.));
+
+        assert!(bounding_box.crosses_antimeridian());
+        assert_degrees(bounding_box.west, 170.);
+        assert_degrees(bounding_box.east, -178.);
+        assert_degrees(bounding_box.north, 2.);
+    }
+
+    #[test]
+    fn expansion_inside_box_keeps_longitudes() {
+        let mut bounding_box =
+            BoundingBox::from_positions(&positions(&[(0., 10.), (1., 20.)])).unwrap();
+
+        bounding_box.expand(&position_from_degrees(-1., 15., 0.));
+
+        assert_degrees(bounding_box.west, 10.);
+        assert_degrees(bounding_box.east, 20.);
+        assert_degrees(bounding_box.south, -1.);
+    }
+}
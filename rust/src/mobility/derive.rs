@@ -0,0 +1,80 @@
+/*
+ * Software Name : libits-client
+ * SPDX-FileCopyrightText: Copyright (c) Orange SA
+ * SPDX-License-Identifier: MIT
+ *
+ * This software is distributed under the MIT license,
+ * see the "LICENSE.txt" file for more details or https://opensource.org/license/MIT/
+ *
+ * Authors: see CONTRIBUTORS.md
+ */
+
+use crate::mobility::position::{bearing, haversine_distance, Position};
+use std::f64::consts::TAU;
+
+/// Returns the heading and the speed of a mobile that moved from `prev` to `cur` in `dt_s` seconds
+///
+/// Fills the kinematics a message lacks (e.g. some CPM perceived objects) from two consecutive
+/// positions; the heading is in radians in [0, 2π), clockwise from north, and the speed in m/s
+///
+/// The heading is 0 if both positions are the same; returns None if `dt_s` is not strictly
+/// positive, e.g. for two positions of the same time or received out of order
+pub fn heading_speed(prev: &Position, cur: &Position, dt_s: f64) -> Option<(f64, f64)> {
+    if dt_s.is_nan() || dt_s <= 0. {
+        return None;
+    }
+    let heading = bearing(prev, cur).rem_euclid(TAU);
+    let speed = haversine_distance(prev, cur) / dt_s;
+
+    Some((heading, speed))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mobility::derive::heading_speed;
+    use crate::mobility::position::{haversine_distance, position_from_degrees};
+    use std::f64::consts::FRAC_PI_2;
+
+    #[test]
+    fn due_north_movement() {
+        let prev = position_from_degrees(48.62, 2.24, 0.);
+        let cur = position_from_degrees(48.6201, 2.24, 0.);
+
+        let (heading, speed) = heading_speed(&prev, &cur, 0.5).unwrap();
+
+        assert!(heading.abs() < 1e-9, "heading is {}", heading);
+        assert!((speed - 22.239).abs() < 1e-2, "speed is {}", speed);
+        assert!((speed - haversine_distance(&prev, &cur) / 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn due_west_movement() {
+        let prev = position_from_degrees(0., 2.24, 0.);
+        let cur = position_from_degrees(0., 2.2399, 0.);
+
+        let (heading, _) = heading_speed(&prev, &cur, 1.).unwrap();
+
+        assert!(
+            (heading - 3. * FRAC_PI_2).abs() < 1e-9,
+            "heading is {}",
+            heading
+        );
+    }
+
+    #[test]
+    fn standing_still() {
+        let position = position_from_degrees(48.62, 2.24, 0.);
+
+        assert_eq!(heading_speed(&position, &position, 1.), Some((0., 0.)));
+    }
+
+    #[test]
+    fn no_elapsed_time_is_none() {
+        let prev = position_from_degrees(48.62, 2.24, 0.);
+        let cur = position_from_degrees(48.6201, 2.24, 0.);
+
+        assert!(heading_speed(&prev, &cur, 0.).is_none());
+        assert!(heading_speed(&prev, &cur, -1.).is_none());
+        assert!(heading_speed(&prev, &cur, f64::NAN).is_none());
+    }
+}
@@ -0,0 +1,325 @@
+/*
+ * Software Name : libits-client
+ * SPDX-FileCopyrightText: Copyright (c) Orange SA
+ * SPDX-License-Identifier: MIT
+ *
+ * This software is distributed under the MIT license,
+ * see the "LICENSE.txt" file for more details or https://opensource.org/license/MIT/
+ *
+ * Authors: see CONTRIBUTORS.md
+ */
+
+//! Exponential moving average filters smoothing the jitter of mobiles' raw values
+//!
+//! Each new value moves the smoothed value by `alpha` times their difference: a `alpha` close to
+//! 1 follows the raw values closely, a `alpha` close to 0 smooths more but lags behind changes
+
+use std::collections::HashMap;
+use std::f64::consts::{PI, TAU};
+use std::time::Duration;
+
+use crate::mobility::angle::bearing_difference;
+use crate::mobility::mobile::Mobile;
+use crate::mobility::position::Position;
+
+/// Exponential moving average of a scalar value
+#[derive(Clone, Debug)]
+pub struct Ema {
+    alpha: f64,
+    value: Option<f64>,
+}
+
+impl Ema {
+    /// Creates a filter with a smoothing factor `alpha`, clamped to [0, 1]
+    pub fn new(alpha: f64) -> Self {
+        Self {
+            alpha: alpha.clamp(0., 1.),
+            value: None,
+        }
+    }
+
+    /// Returns the smoothed value, None if no value has been filtered yet
+    pub fn value(&self) -> Option<f64> {
+        self.value
+    }
+
+    /// Filters the new raw value and returns the smoothed value
+    ///
+    /// The first value is returned as is
+    pub fn update(&mut self, raw: f64) -> f64 {
+        let value = match self.value {
+            Some(value) => value + self.alpha * (raw - value),
+            None => raw,
+        };
+        self.value = Some(value);
+        value
+    }
+}
+
+/// Exponential moving average of an angle in radians, taking the shortest way around the circle
+///
+/// Smoothed values are normalized to [0, 2π), like headings
+#[derive(Clone, Debug)]
+pub struct AngleEma {
+    alpha: f64,
+    value: Option<f64>,
+}
+
+impl AngleEma {
+    /// Creates a filter with a smoothing factor `alpha`, clamped to [0, 1]
+    pub fn new(alpha: f64) -> Self {
+        Self {
+            alpha: alpha.clamp(0., 1.),
+            value: None,
+        }
+    }
+
+    /// Returns the smoothed angle, None if no angle has been filtered yet
+    pub fn value(&self) -> Option<f64> {
+        self.value
+    }
+
+    /// Filters the new raw angle and returns the smoothed angle
+    pub fn update(&mut self, raw: f64) -> f64 {
+        let value = match self.value {
+            Some(value) => value + self.alpha * bearing_difference(value, raw),
+            None => raw,
+        }
+        .rem_euclid(TAU);
+        self.value = Some(value);
+        value
+    }
+}
+
+/// Smoothed state of a mobile, as returned by the [StationFilter]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SmoothedState {
+    pub position: Position,
+    /// Speed in m/s, None if the mobile never provided it
+    pub speed: Option<f64>,
+    /// Heading in radians, None if the mobile never provided it
+    pub heading: Option<f64>,
+}
+
+#[derive(Clone, Debug)]
+struct MobileEma {
+    latitude: Ema,
+    longitude: AngleEma,
+    altitude: Ema,
+    speed: Ema,
+    heading: AngleEma,
+}
+
+impl MobileEma {
+    fn new(alpha: f64) -> Self {
+        Self {
+            latitude: Ema::new(alpha),
+            longitude: AngleEma::new(alpha),
+            altitude: Ema::new(alpha),
+            speed: Ema::new(alpha),
+            heading: AngleEma::new(alpha),
+        }
+    }
+
+    fn update(&mut self, mobile: &dyn Mobile) -> SmoothedState {
+        let position = mobile.position();
+        let longitude = self.longitude.update(position.longitude);
+
+        SmoothedState {
+            position: Position {
+                latitude: self.latitude.update(position.latitude),
+                longitude: if longitude > PI {
+                    longitude - TAU
+                } else {
+                    longitude
+                },
+                altitude: position
+                    .altitude
+                    .map(|altitude| self.altitude.update(altitude))
+                    .or(self.altitude.value()),
+            },
+            speed: mobile
+                .speed()
+                .map(|speed| self.speed.update(speed))
+                .or(self.speed.value()),
+            heading: mobile
+                .heading()
+                .map(|heading| self.heading.update(heading))
+                .or(self.heading.value()),
+        }
+    }
+}
+
+/// Smooths the position, speed and heading of several mobiles, keyed by their station id
+///
+/// A value the mobile does not provide leaves the smoothed one unchanged.
+/// A station not updated for `ttl` is forgotten, its next state being used as is
+#[derive(Clone, Debug)]
+pub struct StationFilter {
+    alpha: f64,
+    ttl: Duration,
+    /// Smoothed values of each station, along with the time of its last update
+    stations: HashMap<u32, (MobileEma, u64)>,
+    next_purge: u64,
+}
+
+impl StationFilter {
+    /// Creates a filter with a smoothing factor `alpha`, clamped to [0, 1], forgetting the
+    /// stations not updated for `ttl`
+    pub fn new(alpha: f64, ttl: Duration) -> Self {
+        Self {
+            alpha,
+            ttl,
+            stations: HashMap::new(),
+            next_purge: 0,
+        }
+    }
+
+    /// Filters the mobile's new state and returns its smoothed state
+    ///
+    /// `timestamp` is the time of the state in milliseconds
+    pub fn update(&mut self, mobile: &dyn Mobile, timestamp: u64) -> SmoothedState {
+        let ttl = self.ttl.as_millis() as u64;
+        if timestamp >= self.next_purge {
+            self.stations
+                .retain(|_, (_, last_update)| last_update.saturating_add(ttl) > timestamp);
+            self.next_purge = timestamp.saturating_add(ttl);
+        }
+
+        let alpha = self.alpha;
+        let (ema, last_update) = self
+            .stations
+            .entry(mobile.id())
+            .or_insert_with(|| (MobileEma::new(alpha), timestamp));
+        if last_update.saturating_add(ttl) <= timestamp {
+            *ema = MobileEma::new(alpha);
+        }
+        *last_update = timestamp;
+        ema.update(mobile)
+    }
+
+    /// Forgets the station, its next state will be used as is
+    pub fn remove(&mut self, station_id: u32) {
+        self.stations.remove(&station_id);
+    }
+
+    /// Returns the number of stations filtered
+    pub fn len(&self) -> usize {
+        self.stations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stations.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mobility::filter::{AngleEma, Ema, StationFilter};
+    use crate::mobility::mobile::MobileSnapshot;
+    use crate::mobility::position::position_from_degrees;
+    use std::time::Duration;
+
+    fn state(id: u32, speed: Option<f64>) -> MobileSnapshot {
+        MobileSnapshot {
+            id,
+            position: position_from_degrees(48.6263556, 2.2492123, 0.),
+            speed,
+            heading: Some(90_f64.to_radians()),
+            acceleration: None,
+            timestamp: None,
+        }
+    }
+
+    #[test]
+    fn constant_input_is_unchanged() {
+        let mut ema = Ema::new(0.3);
+
+        for _ in 0..10 {
+            assert_eq!(ema.update(12.5), 12.5);
+        }
+    }
+
+    #[test]
+    fn step_input_lags_then_converges() {
+        let mut ema = Ema::new(0.5);
+        ema.update(0.);
+
+        assert_eq!(ema.update(10.), 5.);
+        assert_eq!(ema.update(10.), 7.5);
+        assert_eq!(ema.update(10.), 8.75);
+        for _ in 0..30 {
+            ema.update(10.);
+        }
+        assert!((ema.value().unwrap() - 10.).abs() < 1e-6);
+    }
+
+    #[test]
+    fn alpha_is_clamped() {
+        let mut ema = Ema::new(2.);
+        ema.update(0.);
+
+        assert_eq!(ema.update(10.), 10.);
+    }
+
+    #[test]
+    fn angle_takes_shortest_way_across_north() {
+        let mut ema = AngleEma::new(0.5);
+        ema.update(350_f64.to_radians());
+
+        let smoothed = ema.update(10_f64.to_radians());
+
+        assert!((smoothed - 0.).abs() < 1e-9 || (smoothed - std::f64::consts::TAU).abs() < 1e-9);
+    }
+
+    #[test]
+    fn stations_are_filtered_separately() {
+        let mut filter = StationFilter::new(0.5, Duration::from_secs(10));
+        filter.update(&state(1, Some(0.)), 0);
+        filter.update(&state(2, Some(20.)), 0);
+
+        assert_eq!(filter.update(&state(1, Some(10.)), 0).speed, Some(5.));
+        assert_eq!(filter.update(&state(2, Some(20.)), 0).speed, Some(20.));
+        assert_eq!(filter.len(), 2);
+    }
+
+    #[test]
+    fn missing_value_keeps_smoothed_one() {
+        let mut filter = StationFilter::new(0.5, Duration::from_secs(10));
+        filter.update(&state(1, Some(10.)), 0);
+
+        let smoothed = filter.update(&state(1, None), 0);
+
+        assert_eq!(smoothed.speed, Some(10.));
+        assert_eq!(smoothed.position, state(1, None).position);
+    }
+
+    #[test]
+    fn removed_station_restarts_from_raw_values() {
+        let mut filter = StationFilter::new(0.5, Duration::from_secs(10));
+        filter.update(&state(1, Some(0.)), 0);
+
+        filter.remove(1);
+
+        assert_eq!(filter.update(&state(1, Some(10.)), 0).speed, Some(10.));
+    }
+
+    #[test]
+    fn stale_station_restarts_from_raw_values() {
+        let mut filter = StationFilter::new(0.5, Duration::from_secs(10));
+        filter.update(&state(1, Some(0.)), 0);
+
+        assert_eq!(filter.update(&state(1, Some(10.)), 10_000).speed, Some(10.));
+    }
+
+    #[test]
+    fn stale_stations_are_forgotten() {
+        let mut filter = StationFilter::new(0.5, Duration::from_secs(10));
+        filter.update(&state(1, Some(0.)), 0);
+        filter.update(&state(2, Some(0.)), 5_000);
+
+        filter.update(&state(3, Some(0.)), 12_000);
+
+        assert_eq!(filter.len(), 2);
+    }
+}
@@ -0,0 +1,159 @@
+/*
+ * Software Name : libits-client
+ * SPDX-FileCopyrightText: Copyright (c) Orange SA
+ * SPDX-License-Identifier: MIT
+ *
+ * This software is distributed under the MIT license,
+ * see the "LICENSE.txt" file for more details or https://opensource.org/license/MIT/
+ *
+ * Authors: see CONTRIBUTORS.md
+ */
+
+use std::f64::consts::PI;
+use std::str::FromStr;
+
+use thiserror::Error;
+
+use crate::mobility::position::{position_from_degrees, Position};
+
+#[derive(Error, Debug, PartialEq)]
+pub enum PolygonError {
+    #[error("Invalid polygon vertex '{0}', expected 'latitude,longitude' in degrees")]
+    InvalidVertex(String),
+    #[error("A polygon requires at least 3 vertices, {0} provided")]
+    TooFewVertices(usize),
+}
+
+/// Geofence area delimited by a closed polygon
+///
+/// The edges are the segments joining consecutive vertices, the last vertex being joined to the
+/// first one
+#[derive(Clone, Debug, PartialEq)]
+pub struct Polygon {
+    vertices: Vec<Position>,
+}
+
+impl Polygon {
+    pub fn new(vertices: Vec<Position>) -> Result<Self, PolygonError> {
+        if vertices.len() < 3 {
+            return Err(PolygonError::TooFewVertices(vertices.len()));
+        }
+        Ok(Self { vertices })
+    }
+
+    pub fn vertices(&self) -> &[Position] {
+        &self.vertices
+    }
+
+    /// Returns true if the position is inside the polygon
+    ///
+    /// Uses ray-casting along the position's meridian; longitudes are taken relative to the
+    /// position and edges follow the shortest longitude difference, so that polygons crossing
+    /// the antimeridian are handled
+    pub fn contains(&self, position: &Position) -> bool {
+        let wrap = |longitude: f64| (longitude + PI).rem_euclid(2. * PI) - PI;
+
+        let mut inside = false;
+        let mut previous = self.vertices.last().unwrap();
+        for current in &self.vertices {
+            let x1 = wrap(previous.longitude - position.longitude);
+            let x2 = x1 + wrap(current.longitude - previous.longitude);
+            let (y1, y2) = (previous.latitude, current.latitude);
+
+            if (x1 > 0.) != (x2 > 0.) {
+                let crossing_latitude = y1 - x1 * (y2 - y1) / (x2 - x1);
+                if position.latitude < crossing_latitude {
+                    inside = !inside;
+                }
+            }
+            previous = current;
+        }
+        inside
+    }
+}
+
+/// Parses a polygon from `;` separated `latitude,longitude` vertices in degrees
+///
+/// e.g. `48.62,2.24;48.63,2.24;48.63,2.25`
+impl FromStr for Polygon {
+    type Err = PolygonError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let vertices = s
+            .split(';')
+            .map(str::trim)
+            .filter(|vertex| !vertex.is_empty())
+            .map(|vertex| {
+                let invalid = || PolygonError::InvalidVertex(vertex.to_string());
+                let (latitude, longitude) = vertex.split_once(',').ok_or_else(invalid)?;
+                let latitude = latitude.trim().parse::<f64>().map_err(|_| invalid())?;
+                let longitude = longitude.trim().parse::<f64>().map_err(|_| invalid())?;
+                Ok(Position {
+                    altitude: None,
+                    ..position_from_degrees(latitude, longitude, 0.)
+                })
+            })
+            .collect::<Result<Vec<Position>, PolygonError>>()?;
+
+        Polygon::new(vertices)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::mobility::geofence::{Polygon, PolygonError};
+    use crate::mobility::position::position_from_degrees;
+
+    fn l_shape() -> Polygon {
+        Polygon::from_str("48.60,2.20;48.70,2.20;48.70,2.25;48.65,2.25;48.65,2.30;48.60,2.30")
+            .unwrap()
+    }
+
+    macro_rules! test_contains {
+        ($test_name:ident, $polygon:expr, $lat:expr, $lon:expr, $expected:expr) => {
+            #[test]
+            fn $test_name() {
+                let position = position_from_degrees($lat, $lon, 0.);
+
+                assert_eq!($polygon.contains(&position), $expected);
+            }
+        };
+    }
+    test_contains!(inside_the_foot, l_shape(), 48.62, 2.28, true);
+    test_contains!(inside_the_leg, l_shape(), 48.68, 2.22, true);
+    test_contains!(in_the_notch, l_shape(), 48.68, 2.28, false);
+    test_contains!(north_of_the_polygon, l_shape(), 48.75, 2.22, false);
+    test_contains!(west_of_the_polygon, l_shape(), 48.62, 2.10, false);
+    test_contains!(
+        across_the_antimeridian,
+        Polygon::from_str("-10,179;10,179;10,-179;-10,-179").unwrap(),
+        0.,
+        180.,
+        true
+    );
+    test_contains!(
+        outside_across_the_antimeridian,
+        Polygon::from_str("-10,179;10,179;10,-179;-10,-179").unwrap(),
+        0.,
+        0.,
+        false
+    );
+
+    #[test]
+    fn too_few_vertices_is_err() {
+        assert_eq!(
+            Polygon::from_str("48.60,2.20;48.70,2.20"),
+            Err(PolygonError::TooFewVertices(2))
+        );
+    }
+
+    #[test]
+    fn invalid_vertex_is_err() {
+        assert_eq!(
+            Polygon::from_str("48.60,2.20;48.70;48.70,2.25"),
+            Err(PolygonError::InvalidVertex("48.70".to_string()))
+        );
+    }
+}
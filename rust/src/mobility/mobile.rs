@@ -9,7 +9,8 @@
  * Authors: see CONTRIBUTORS.md
  */
 
-use crate::mobility::position::Position;
+use crate::mobility::angle::bearing_difference;
+use crate::mobility::position::{bearing, haversine_distance, Position};
 
 /// Describes a mobile at a moment in time
 ///
@@ -28,4 +29,223 @@ pub trait Mobile {
 
     /// Returns ths mobile's acceleration in m/s²
     fn acceleration(&self) -> Option<f64>;
+
+    /// Returns the mobile's speed confidence in m/s
+    ///
+    /// The confidence is the half-width of the interval around the [speed][1] containing the true
+    /// value with a 95% probability
+    ///
+    /// [1]: Mobile::speed
+    fn speed_confidence(&self) -> Option<f64> {
+        None
+    }
+
+    /// Returns the mobile's heading confidence in radians
+    ///
+    /// The confidence is the half-width of the interval around the [heading][1] containing the
+    /// true value with a 95% probability
+    ///
+    /// [1]: Mobile::heading
+    fn heading_confidence(&self) -> Option<f64> {
+        None
+    }
+
+    /// Returns the mobile's acceleration confidence in m/s²
+    ///
+    /// The confidence is the half-width of the interval around the [acceleration][1] containing
+    /// the true value with a 95% probability
+    ///
+    /// [1]: Mobile::acceleration
+    fn acceleration_confidence(&self) -> Option<f64> {
+        None
+    }
+}
+
+/// Copy of a mobile's state, detached from the message it was read from
+///
+/// Cheaper to keep than the whole message, e.g. to track many stations
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MobileSnapshot {
+    pub id: u32,
+    pub position: Position,
+    /// Speed in m/s
+    pub speed: Option<f64>,
+    /// Heading in radians
+    pub heading: Option<f64>,
+    /// Acceleration in m/s²
+    pub acceleration: Option<f64>,
+    /// Time of the state in milliseconds, the [Mobile] trait does not provide it
+    pub timestamp: Option<u64>,
+}
+
+impl MobileSnapshot {
+    /// Returns the snapshot of the mobile's state at `timestamp`, in milliseconds
+    pub fn at(mobile: &dyn Mobile, timestamp: u64) -> Self {
+        Self {
+            timestamp: Some(timestamp),
+            ..Self::from(mobile)
+        }
+    }
+}
+
+impl From<&dyn Mobile> for MobileSnapshot {
+    fn from(mobile: &dyn Mobile) -> Self {
+        Self {
+            id: mobile.id(),
+            position: mobile.position(),
+            speed: mobile.speed(),
+            heading: mobile.heading(),
+            acceleration: mobile.acceleration(),
+            timestamp: None,
+        }
+    }
+}
+
+impl Mobile for MobileSnapshot {
+    fn id(&self) -> u32 {
+        self.id
+    }
+
+    fn position(&self) -> Position {
+        self.position
+    }
+
+    fn speed(&self) -> Option<f64> {
+        self.speed
+    }
+
+    fn heading(&self) -> Option<f64> {
+        self.heading
+    }
+
+    fn acceleration(&self) -> Option<f64> {
+        self.acceleration
+    }
+}
+
+/// Range and bearing from an ego mobile to another mobile
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RelativePosition {
+    /// Distance in meters
+    pub distance: f64,
+    /// Bearing in degrees
+    ///
+    /// If [relative][1], measured clockwise from the ego heading in (-180, 180], 0 being dead
+    /// ahead; otherwise measured clockwise from north in [0, 360)
+    ///
+    /// [1]: RelativePosition::relative
+    pub bearing: f64,
+    /// False if the ego heading is unavailable and the bearing is absolute
+    pub relative: bool,
+}
+
+/// Returns the range and bearing from `ego` to `object`, relatively to the ego heading if known
+pub fn relative_position(ego: &dyn Mobile, object: &dyn Mobile) -> RelativePosition {
+    let ego_position = ego.position();
+    let object_position = object.position();
+    let absolute_bearing = bearing(&ego_position, &object_position);
+
+    let (bearing, relative) = match ego.heading() {
+        Some(heading) => (bearing_difference(heading, absolute_bearing), true),
+        None => (absolute_bearing.rem_euclid(std::f64::consts::TAU), false),
+    };
+
+    RelativePosition {
+        distance: haversine_distance(&ego_position, &object_position),
+        bearing: bearing.to_degrees(),
+        relative,
+    }
+}
+
+/// Returns the mobile as a GeoJSON Feature with a Point geometry
+///
+/// Coordinates are `[longitude, latitude]` in degrees, followed by the altitude in meters if known;
+/// the `id`, `speed` (m/s) and `heading` (radians) properties are null when unavailable
+pub fn to_geojson(mobile: &dyn Mobile) -> serde_json::Value {
+    let position = mobile.position();
+    let mut coordinates = vec![
+        serde_json::json!(position.longitude.to_degrees()),
+        serde_json::json!(position.latitude.to_degrees()),
+    ];
+    if let Some(altitude) = position.altitude {
+        coordinates.push(serde_json::json!(altitude));
+    }
+
+    serde_json::json!({
+        "type": "Feature",
+        "geometry": {
+            "type": "Point",
+            "coordinates": coordinates,
+        },
+        "properties": {
+            "id": mobile.id(),
+            "speed": mobile.speed(),
+            "heading": mobile.heading(),
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mobility::mobile::{relative_position, Mobile, MobileSnapshot};
+    use crate::mobility::position::{haversine_destination, position_from_degrees};
+
+    fn ego(heading: Option<f64>) -> MobileSnapshot {
+        MobileSnapshot {
+            id: 1,
+            position: position_from_degrees(48.6263556, 2.2492123, 0.),
+            speed: None,
+            heading: heading.map(f64::to_radians),
+            acceleration: None,
+            timestamp: None,
+        }
+    }
+
+    fn object_at(bearing: f64, distance: f64) -> MobileSnapshot {
+        MobileSnapshot {
+            id: 2,
+            position: haversine_destination(&ego(None).position, bearing.to_radians(), distance),
+            heading: None,
+            ..ego(None)
+        }
+    }
+
+    macro_rules! test_relative_position {
+        ($test_name:ident, $ego_heading:expr, $object_bearing:expr, $expected_bearing:expr) => {
+            #[test]
+            fn $test_name() {
+                let relative =
+                    relative_position(&ego($ego_heading), &object_at($object_bearing, 50.));
+
+                assert!((relative.distance - 50.).abs() < 1e-3);
+                assert!(
+                    (relative.bearing - $expected_bearing).abs() < 1e-3,
+                    "bearing {} instead of {}",
+                    relative.bearing,
+                    $expected_bearing
+                );
+                assert_eq!(relative.relative, $ego_heading.is_some());
+            }
+        };
+    }
+    test_relative_position!(dead_ahead, Some(90.), 90., 0.);
+    test_relative_position!(behind, Some(90.), 270., 180.);
+    test_relative_position!(on_the_right, Some(90.), 180., 90.);
+    test_relative_position!(on_the_left, Some(0.), 270., -90.);
+    test_relative_position!(ahead_across_north, Some(350.), 10., 20.);
+    test_relative_position!(unknown_heading_is_absolute, None::<f64>, 270., 270.);
+
+    #[test]
+    fn snapshot_matches_mobile() {
+        let mobile = ego(Some(90.));
+
+        let snapshot = MobileSnapshot::at(&mobile, 1_000);
+
+        assert_eq!(snapshot.id(), mobile.id());
+        assert_eq!(snapshot.position(), mobile.position());
+        assert_eq!(snapshot.speed(), mobile.speed());
+        assert_eq!(snapshot.heading(), mobile.heading());
+        assert_eq!(snapshot.acceleration(), mobile.acceleration());
+        assert_eq!(snapshot.timestamp, Some(1_000));
+    }
 }
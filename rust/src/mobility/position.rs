@@ -12,10 +12,11 @@
 use geo::EuclideanDistance;
 
 use serde::{Deserialize, Serialize};
-use std::fmt::{Display, Formatter, Result};
+use std::fmt::{self, Display, Formatter};
 use std::hash::{Hash, Hasher};
+use thiserror::Error;
 
-const EARTH_RADIUS: f64 = 6_371_000.;
+pub(crate) const EARTH_RADIUS: f64 = 6_371_000.;
 const EARTH_FLATTENING: f64 = 1. / 298.257223563;
 const EQUATORIAL_RADIUS: f64 = 6_378_137.0;
 const POLAR_RADIUS: f64 = 6_356_752.3;
@@ -27,12 +28,54 @@ pub struct Position {
     pub latitude: f64,
     /// Longitude in radians
     pub longitude: f64,
-    /// Altitude in meters
-    pub altitude: f64,
+    /// Altitude in meters, `None` if unknown
+    pub altitude: Option<f64>,
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum PositionError {
+    #[error("Latitude {0}° out of the [-90°, 90°] range")]
+    LatitudeOutOfRange(f64),
+    #[error("Longitude {0}° out of the [-180°, 180°] range")]
+    LongitudeOutOfRange(f64),
+}
+
+impl Position {
+    /// Creates a position from decimal degrees, and an altitude in meters
+    ///
+    /// Fails if the latitude or the longitude are out of range, e.g. if ETSI tenths of
+    /// microdegrees are provided instead of degrees
+    pub fn from_degrees(
+        latitude: f64,
+        longitude: f64,
+        altitude: Option<f64>,
+    ) -> Result<Self, PositionError> {
+        if !(-90. ..=90.).contains(&latitude) {
+            return Err(PositionError::LatitudeOutOfRange(latitude));
+        }
+        if !(-180. ..=180.).contains(&longitude) {
+            return Err(PositionError::LongitudeOutOfRange(longitude));
+        }
+
+        Ok(Self {
+            latitude: latitude.to_radians(),
+            longitude: longitude.to_radians(),
+            altitude,
+        })
+    }
+
+    /// Returns the latitude and longitude in decimal degrees, and the altitude in meters
+    pub fn to_degrees(&self) -> (f64, f64, Option<f64>) {
+        (
+            self.latitude.to_degrees(),
+            self.longitude.to_degrees(),
+            self.altitude,
+        )
+    }
 }
 
 impl Display for Position {
-    fn fmt(&self, f: &mut Formatter) -> Result {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         write!(
             f,
             "{{lat:{} ({}°), lon:{} ({}°), alt:{}}}",
@@ -40,7 +83,8 @@ impl Display for Position {
             self.latitude.to_degrees(),
             self.longitude,
             self.longitude.to_degrees(),
-            self.altitude,
+            self.altitude
+                .map_or(String::from("unknown"), |altitude| altitude.to_string()),
         )
     }
 }
@@ -59,7 +103,7 @@ pub fn position_from_degrees(lat: f64, lon: f64, alt: f64) -> Position {
     Position {
         latitude: lat.to_radians(),
         longitude: lon.to_radians(),
-        altitude: alt,
+        altitude: Some(alt),
     }
 }
 
@@ -170,6 +214,8 @@ pub fn vincenty_destination(anchor: &Position, bearing: f64, distance: f64) -> P
 }
 
 /// Returns the relative position of ENU coordinates from an anchor position
+///
+/// The destination altitude is unknown if the anchor's one is
 pub fn enu_destination(
     anchor: &Position,
     easting_offset: f64,
@@ -182,14 +228,14 @@ pub fn enu_destination(
         up_offset,
         anchor.latitude,
         anchor.longitude,
-        anchor.altitude,
+        anchor.altitude.unwrap_or_default(),
         map_3d::Ellipsoid::WGS84,
     );
 
     Position {
         latitude,
         longitude,
-        altitude,
+        altitude: anchor.altitude.map(|_| altitude),
     }
 }
 
@@ -221,7 +267,7 @@ pub fn distance_to_line(position: &Position, line: &[Position]) -> f64 {
 mod tests {
     use crate::mobility::position::{
         bearing, enu_destination, haversine_destination, haversine_distance, position_from_degrees,
-        vincenty_destination,
+        vincenty_destination, Position, PositionError,
     };
 
     macro_rules! test_haversine_distance {
@@ -286,6 +332,16 @@ mod tests {
         position_from_degrees(43.63816914950018, 1.40442743, 0.)
     );
 
+    #[test]
+    fn enu_destination_keeps_unknown_altitude() {
+        let mut anchor = position_from_degrees(43.63816914950018, 1.4031882, 0.);
+        anchor.altitude = None;
+
+        let destination = enu_destination(&anchor, 0., 100., 10.);
+
+        assert!(destination.altitude.is_none());
+    }
+
     macro_rules! test_bearing {
         ($test_name:ident, $dst:expr, $exp_bearing:expr) => {
             #[test]
@@ -490,4 +546,51 @@ mod tests {
         100.,
         position_from_degrees(48.62429656659, 2.24150940001, 0.)
     );
+
+    #[test]
+    fn position_from_degrees_round_trips() {
+        let position = Position::from_degrees(48.6263556, 2.2492123, Some(20.)).unwrap();
+
+        assert_eq!(position, position_from_degrees(48.6263556, 2.2492123, 20.));
+        let (latitude, longitude, altitude) = position.to_degrees();
+        assert!((latitude - 48.6263556).abs() < 1e-12);
+        assert!((longitude - 2.2492123).abs() < 1e-12);
+        assert_eq!(altitude, Some(20.));
+    }
+
+    #[test]
+    fn position_bounds_are_valid() {
+        assert!(Position::from_degrees(90., 180., None).is_ok());
+        assert!(Position::from_degrees(-90., -180., None).is_ok());
+    }
+
+    macro_rules! test_out_of_range_degrees {
+        ($test_name:ident, $latitude:expr, $longitude:expr, $expected:expr) => {
+            #[test]
+            fn $test_name() {
+                assert_eq!(
+                    Position::from_degrees($latitude, $longitude, None),
+                    Err($expected)
+                );
+            }
+        };
+    }
+    test_out_of_range_degrees!(
+        latitude_above_90,
+        90.5,
+        2.,
+        PositionError::LatitudeOutOfRange(90.5)
+    );
+    test_out_of_range_degrees!(
+        longitude_below_minus_180,
+        48.,
+        -181.,
+        PositionError::LongitudeOutOfRange(-181.)
+    );
+    test_out_of_range_degrees!(
+        etsi_microdegrees,
+        486263556.,
+        22492123.,
+        PositionError::LatitudeOutOfRange(486263556.)
+    );
 }
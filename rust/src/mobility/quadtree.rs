@@ -14,6 +14,7 @@ use std::f64::consts::PI;
 
 pub mod parse_error;
 pub mod quadkey;
+pub mod spatial_index;
 pub mod tile;
 
 const MIN_LATITUDE: f64 = -85.05112878;
@@ -68,7 +69,8 @@ fn clip(n: f64, min_value: f64, max_value: f64) -> f64 {
     }
 }
 
-fn coordinates_to_pixel_xy(latitude: f64, longitude: f64, level_of_detail: u16) -> PixelXY {
+/// Returns the Web Mercator coordinates of a position, both in `[0, 1]` from the north-west corner
+fn coordinates_to_world_xy(latitude: f64, longitude: f64) -> (f64, f64) {
     let latitude = clip(latitude, MIN_LATITUDE, MAX_LATITUDE);
     let longitude = clip(longitude, MIN_LONGITUDE, MAX_LONGITUDE);
 
@@ -76,6 +78,18 @@ fn coordinates_to_pixel_xy(latitude: f64, longitude: f64, level_of_detail: u16)
     let sin_latitude = (latitude * PI / 180.).sin();
     let basis = (1. + sin_latitude) / (1. - sin_latitude);
     let y = 0.5 - basis.ln() / (4. * PI);
+    (x, y)
+}
+
+/// Returns the latitude and longitude, in degrees, of Web Mercator coordinates from the
+/// north-west corner; `x` is not bounded so that longitudes beyond the antimeridian are kept
+fn world_xy_to_coordinates(x: f64, y: f64) -> (f64, f64) {
+    let latitude = 90. - 360. * (-(0.5 - y) * 2. * PI).exp().atan() / PI;
+    (latitude, x * 360. - 180.)
+}
+
+fn coordinates_to_pixel_xy(latitude: f64, longitude: f64, level_of_detail: u16) -> PixelXY {
+    let (x, y) = coordinates_to_world_xy(latitude, longitude);
 
     let map_size = compute_map_size(level_of_detail);
     let pixel_x = clip(x * map_size as f64 + 0.5, 0., (map_size - 1) as f64) as i64;
@@ -9,15 +9,29 @@
  * Authors: see CONTRIBUTORS.md
  */
 
-use crate::mobility::position::Position;
+use crate::mobility::geofence::Polygon;
+use crate::mobility::position::{position_from_degrees, Position};
 use crate::mobility::quadtree::parse_error::ParseError;
 use crate::mobility::quadtree::tile::Tile;
-use crate::mobility::quadtree::{coordinates_to_quadkey, DEFAULT_DEPTH};
+use crate::mobility::quadtree::{
+    coordinates_to_quadkey, coordinates_to_world_xy, world_xy_to_coordinates, DEFAULT_DEPTH,
+};
 use core::fmt;
 use std::cmp::Ordering;
 use std::str;
 use std::str::FromStr;
 
+/// Deepest zoom level [covering tiles][1] can be computed at, the Web Mercator coordinates being
+/// no more precise beyond
+///
+/// [1]: Quadkey::covering_tiles
+pub const MAX_COVERING_ZOOM: u8 = 52;
+
+/// Deepest quadkey [neighbors][1] can be computed for, the tile coordinates being stored in an i64
+///
+/// [1]: Quadkey::neighbors
+pub const MAX_NEIGHBORS_DEPTH: usize = 62;
+
 #[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
 pub struct Quadkey {
     pub(crate) tiles: Vec<Tile>,
@@ -39,6 +53,173 @@ impl Quadkey {
             tiles: truncated_tiles,
         }
     }
+
+    /// Returns true if the other quadkey is within this one's region, i.e. this one's tiles are a
+    /// prefix of the other's, whatever their depths
+    ///
+    /// Unlike equality, a coarse quadkey contains all the finer ones inside it; a wildcard tile
+    /// (`#`) contains any tile from its level down
+    pub fn contains(&self, other: &Quadkey) -> bool {
+        for (level, tile) in self.tiles.iter().enumerate() {
+            match (tile, other.tiles.get(level)) {
+                (Tile::All, _) => return true,
+                (tile, Some(other_tile)) if tile == other_tile => (),
+                _ => return false,
+            }
+        }
+        true
+    }
+
+    /// Returns the adjacent quadkeys at the same depth, from north-west to south-east
+    ///
+    /// Tiles wrap around the antimeridian, but not around the poles: a tile on the northern or
+    /// southern edge of the map has no neighbour beyond it.
+    /// Returns an empty list for the root quadkey, a quadkey containing a wildcard or a quadkey
+    /// deeper than [MAX_NEIGHBORS_DEPTH]
+    pub fn neighbors(&self) -> Vec<Quadkey> {
+        let depth = self.tiles.len();
+        if depth == 0 || depth > MAX_NEIGHBORS_DEPTH || self.tiles.contains(&Tile::All) {
+            return Vec::new();
+        }
+
+        let size: i64 = 1 << depth;
+        let (x, y) = self.tiles.iter().fold((0_i64, 0_i64), |(x, y), &tile| {
+            let digit = tile as i64;
+            ((x << 1) | (digit & 1), (y << 1) | (digit >> 1))
+        });
+
+        let mut neighbors = Vec::with_capacity(8);
+        for neighbor_y in (y - 1)..=(y + 1) {
+            if !(0..size).contains(&neighbor_y) {
+                continue;
+            }
+            for delta_x in -1..=1 {
+                let neighbor_x = (x + delta_x).rem_euclid(size);
+                if neighbor_x == x && neighbor_y == y {
+                    continue;
+                }
+                let neighbor = Quadkey::from_tile_xy(neighbor_x, neighbor_y, depth);
+                if !neighbors.contains(&neighbor) {
+                    neighbors.push(neighbor);
+                }
+            }
+        }
+
+        neighbors
+    }
+
+    /// Returns the quadkeys at the given zoom level overlapping the polygon, from north-west to
+    /// south-east
+    ///
+    /// A tile is kept if one of the polygon's vertices lies in it, if one of its corners is
+    /// [inside the polygon][1] or if one of the polygon's edges crosses it; each tile is returned
+    /// once. Edges are taken as straight lines on the Web Mercator projection, following the
+    /// shortest longitude difference so that polygons crossing the antimeridian are handled
+    ///
+    /// Returns None if the zoom is deeper than [MAX_COVERING_ZOOM], or if the polygon's bounding
+    /// box spans more than `max_tiles` tiles, before enumerating them
+    ///
+    /// [1]: Polygon::contains
+    pub fn covering_tiles(polygon: &Polygon, zoom: u8, max_tiles: usize) -> Option<Vec<Quadkey>> {
+        if zoom > MAX_COVERING_ZOOM {
+            return None;
+        }
+        let size = 1_i64 << zoom;
+        let world_size = size as f64;
+
+        let mut vertices: Vec<(f64, f64)> = Vec::with_capacity(polygon.vertices().len());
+        for vertex in polygon.vertices() {
+            let (x, y) = coordinates_to_world_xy(
+                vertex.latitude.to_degrees(),
+                vertex.longitude.to_degrees(),
+            );
+            let mut x = x * world_size;
+            if let Some((previous_x, _)) = vertices.last() {
+                x -= ((x - previous_x) / world_size).round() * world_size;
+            }
+            vertices.push((x, y * world_size));
+        }
+
+        let tile_range = |coordinate: fn(&(f64, f64)) -> f64| {
+            let min = vertices
+                .iter()
+                .map(coordinate)
+                .fold(f64::INFINITY, f64::min);
+            let max = vertices
+                .iter()
+                .map(coordinate)
+                .fold(f64::NEG_INFINITY, f64::max);
+            (min.floor() as i64, max.floor() as i64)
+        };
+        let (min_x, max_x) = tile_range(|vertex| vertex.0);
+        let max_x = max_x.min(min_x.saturating_add(size - 1));
+        let (min_y, max_y) = tile_range(|vertex| vertex.1);
+        let (min_y, max_y) = (min_y.max(0), max_y.min(size - 1));
+
+        let span = |min: i64, max: i64| {
+            u64::try_from(max.saturating_sub(min).saturating_add(1)).unwrap_or(0)
+        };
+        if span(min_x, max_x).saturating_mul(span(min_y, max_y))
+            > u64::try_from(max_tiles).unwrap_or(u64::MAX)
+        {
+            return None;
+        }
+
+        let mut tiles = Vec::new();
+        for y in min_y..=max_y {
+            for x in min_x..=max_x {
+                if overlaps(polygon, &vertices, x, y, world_size) {
+                    tiles.push(Quadkey::from_tile_xy(x.rem_euclid(size), y, zoom as usize));
+                }
+            }
+        }
+        Some(tiles)
+    }
+
+    fn from_tile_xy(x: i64, y: i64, depth: usize) -> Self {
+        Quadkey {
+            tiles: (0..depth)
+                .rev()
+                .map(|bit| Tile::from((((x >> bit) & 1) | (((y >> bit) & 1) << 1)) as u8))
+                .collect(),
+        }
+    }
+}
+
+/// Returns true if the polygon overlaps the tile whose north-west corner is (x, y)
+///
+/// `vertices` are the polygon's ones in tile units on a map of `world_size` tiles wide
+fn overlaps(polygon: &Polygon, vertices: &[(f64, f64)], x: i64, y: i64, world_size: f64) -> bool {
+    let (x, y) = (x as f64, y as f64);
+    let square = [(x, y), (x + 1., y), (x + 1., y + 1.), (x, y + 1.)];
+    let in_square = |&(vertex_x, vertex_y): &(f64, f64)| {
+        (x..x + 1.).contains(&vertex_x) && (y..y + 1.).contains(&vertex_y)
+    };
+    let corner_inside = |&(corner_x, corner_y): &(f64, f64)| {
+        let (latitude, longitude) =
+            world_xy_to_coordinates(corner_x / world_size, corner_y / world_size);
+        polygon.contains(&position_from_degrees(latitude, longitude, 0.))
+    };
+
+    vertices.iter().any(in_square)
+        || square.iter().any(corner_inside)
+        || edges(vertices).any(|edge| edges(&square).any(|side| cross(edge, side)))
+}
+
+fn edges(polygon: &[(f64, f64)]) -> impl Iterator<Item = ((f64, f64), (f64, f64))> + '_ {
+    polygon
+        .iter()
+        .copied()
+        .zip(polygon.iter().copied().cycle().skip(1))
+}
+
+/// Returns true if both segments properly cross each other
+fn cross((a, b): ((f64, f64), (f64, f64)), (c, d): ((f64, f64), (f64, f64))) -> bool {
+    let orientation = |(px, py): (f64, f64), (qx, qy): (f64, f64), (rx, ry): (f64, f64)| {
+        ((qx - px) * (ry - py) - (qy - py) * (rx - px)).signum()
+    };
+    orientation(a, b, c) * orientation(a, b, d) < 0.
+        && orientation(c, d, a) * orientation(c, d, b) < 0.
 }
 
 impl From<Position> for Quadkey {
@@ -141,7 +322,8 @@ impl PartialOrd for Quadkey {
 
 #[cfg(test)]
 mod tests {
-    use crate::mobility::quadtree::quadkey::Quadkey;
+    use crate::mobility::geofence::Polygon;
+    use crate::mobility::quadtree::quadkey::{Quadkey, MAX_COVERING_ZOOM, MAX_NEIGHBORS_DEPTH};
     use crate::mobility::quadtree::tile::Tile;
     use std::cmp::Ordering::{Equal, Greater, Less};
     use std::str::FromStr;
@@ -152,6 +334,30 @@ mod tests {
         quadkey_result.unwrap()
     }
 
+    #[test]
+    fn coarse_quadkey_contains_finer_one_inside() {
+        assert!(create_quadkey("0123").contains(&create_quadkey("0123012")));
+        assert!(Quadkey::default().contains(&create_quadkey("0123")));
+    }
+
+    #[test]
+    fn quadkey_does_not_contain_one_outside() {
+        assert!(!create_quadkey("0123").contains(&create_quadkey("0122012")));
+        assert!(!create_quadkey("0123012").contains(&create_quadkey("0123")));
+    }
+
+    #[test]
+    fn quadkey_contains_itself_only_at_same_depth() {
+        assert!(create_quadkey("0123").contains(&create_quadkey("0123")));
+        assert!(!create_quadkey("0123").contains(&create_quadkey("0132")));
+    }
+
+    #[test]
+    fn wildcard_contains_any_tile() {
+        assert!(create_quadkey("01#").contains(&create_quadkey("0132")));
+        assert!(!create_quadkey("01#").contains(&create_quadkey("0232")));
+    }
+
     #[test]
     fn test_create_quadkey_with_slash() {
         let quadkey = create_quadkey("0/1/2/3");
@@ -480,4 +686,137 @@ mod tests {
         30,
         "0/1/2/3/1/3/2/0/3/1"
     );
+
+    macro_rules! test_neighbors {
+        ($test_name:ident, $k:expr, $e:expr) => {
+            #[test]
+            fn $test_name() {
+                let quadkey = Quadkey::from_str($k).expect("Failed to convert '{}' to quadkey");
+                let expected: Vec<Quadkey> = $e
+                    .iter()
+                    .map(|k| Quadkey::from_str(k).expect("Failed to convert '{}' to quadkey"))
+                    .collect();
+
+                assert_eq!(quadkey.neighbors(), expected);
+            }
+        };
+    }
+    test_neighbors!(
+        neighbors_of_central_tile,
+        "0/3",
+        ["0/0", "0/1", "1/0", "0/2", "1/2", "2/0", "2/1", "3/0"]
+    );
+    test_neighbors!(
+        neighbors_of_north_west_corner,
+        "0/0",
+        ["1/1", "0/1", "1/3", "0/2", "0/3"]
+    );
+    test_neighbors!(
+        neighbors_of_north_east_corner,
+        "1/1",
+        ["1/0", "0/0", "1/2", "1/3", "0/2"]
+    );
+    test_neighbors!(
+        neighbors_of_south_west_corner,
+        "2/2",
+        ["3/1", "2/0", "2/1", "3/3", "2/3"]
+    );
+    test_neighbors!(
+        neighbors_of_south_east_corner,
+        "3/3",
+        ["3/0", "3/1", "2/0", "3/2", "2/2"]
+    );
+    test_neighbors!(neighbors_of_first_level_tile, "0", ["1", "3", "2"]);
+    test_neighbors!(no_neighbors_with_wildcard, "0/#", [] as [&str; 0]);
+
+    #[test]
+    fn no_neighbors_for_root() {
+        assert!(Quadkey::default().neighbors().is_empty());
+    }
+
+    #[test]
+    fn neighbors_at_max_depth() {
+        let quadkey = Quadkey::from_str(&["3"; MAX_NEIGHBORS_DEPTH].join("/")).unwrap();
+
+        assert_eq!(quadkey.neighbors().len(), 5);
+    }
+
+    #[test]
+    fn no_neighbors_beyond_max_depth() {
+        let quadkey = Quadkey::from_str(&["3"; MAX_NEIGHBORS_DEPTH + 1].join("/")).unwrap();
+
+        assert!(quadkey.neighbors().is_empty());
+    }
+
+    macro_rules! test_covering_tiles {
+        ($test_name:ident, $polygon:expr, $zoom:expr, $expected:expr) => {
+            #[test]
+            fn $test_name() {
+                let polygon = Polygon::from_str($polygon).unwrap();
+
+                let tiles = Quadkey::covering_tiles(&polygon, $zoom, usize::MAX);
+
+                assert_eq!(
+                    tiles.unwrap(),
+                    $expected
+                        .iter()
+                        .map(|quadkey| create_quadkey(quadkey))
+                        .collect::<Vec<Quadkey>>()
+                );
+            }
+        };
+    }
+    test_covering_tiles!(
+        square_straddling_four_tiles,
+        "1,-1;1,1;-1,1;-1,-1",
+        1,
+        ["0", "1", "2", "3"]
+    );
+    test_covering_tiles!(
+        square_within_one_tile,
+        "48.62,2.24;48.63,2.24;48.63,2.25;48.62,2.25",
+        4,
+        ["1202"]
+    );
+    test_covering_tiles!(
+        edge_crossing_a_tile_without_vertex,
+        "10,-10;9,-10;-10,9",
+        1,
+        ["0", "2", "3"]
+    );
+    test_covering_tiles!(
+        polygon_enclosing_tiles,
+        "50,-100;50,0;50,100;-50,100;-50,0;-50,-100",
+        2,
+        ["02", "03", "12", "13", "20", "21", "30", "31"]
+    );
+    test_covering_tiles!(
+        polygon_across_the_antimeridian,
+        "-10,179;10,179;10,-179;-10,-179",
+        1,
+        ["1", "0", "3", "2"]
+    );
+
+    #[test]
+    fn bounding_box_above_the_maximum_is_none() {
+        let polygon = Polygon::from_str("1,-1;1,1;-1,1;-1,-1").unwrap();
+
+        assert!(Quadkey::covering_tiles(&polygon, 1, 3).is_none());
+        assert_eq!(Quadkey::covering_tiles(&polygon, 1, 4).unwrap().len(), 4);
+    }
+
+    #[test]
+    fn large_polygon_at_max_zoom_is_none() {
+        let polygon = Polygon::from_str("50,-100;50,100;-50,100;-50,-100").unwrap();
+
+        assert!(Quadkey::covering_tiles(&polygon, MAX_COVERING_ZOOM, 1_000).is_none());
+    }
+
+    #[test]
+    fn too_deep_covering_zoom_is_none() {
+        let polygon = Polygon::from_str("1,-1;1,1;-1,1;-1,-1").unwrap();
+
+        assert!(Quadkey::covering_tiles(&polygon, MAX_COVERING_ZOOM + 1, 4).is_none());
+        assert!(Quadkey::covering_tiles(&polygon, u8::MAX, 4).is_none());
+    }
 }
@@ -0,0 +1,325 @@
+/*
+ * Software Name : libits-client
+ * SPDX-FileCopyrightText: Copyright (c) Orange SA
+ * SPDX-License-Identifier: MIT
+ *
+ * This software is distributed under the MIT license,
+ * see the "LICENSE.txt" file for more details or https://opensource.org/license/MIT/
+ *
+ * Authors: see CONTRIBUTORS.md
+ */
+
+use std::f64::consts::{FRAC_PI_2, PI};
+
+use crate::mobility::bounds::{normalize_longitude, BoundingBox};
+use crate::mobility::position::{haversine_distance, Position, EARTH_RADIUS};
+
+/// Number of items a node holds before being split into four
+const NODE_CAPACITY: usize = 8;
+/// Depth from which nodes are not split anymore, about 5 meters wide
+const MAX_DEPTH: usize = 22;
+
+/// Spatial index of items located by a [Position], to find the ones in an area
+///
+/// Items are stored in a quadtree whose nodes split the latitude/longitude space in four once
+/// they hold too many items
+pub struct SpatialIndex<T> {
+    root: Node<T>,
+    len: usize,
+}
+
+struct Node<T> {
+    bounds: BoundingBox,
+    depth: usize,
+    items: Vec<(Position, T)>,
+    children: Vec<Node<T>>,
+}
+
+impl<T> SpatialIndex<T> {
+    pub fn new() -> Self {
+        Self {
+            root: Node::new(
+                BoundingBox {
+                    south: -FRAC_PI_2,
+                    west: -PI,
+                    north: FRAC_PI_2,
+                    east: PI,
+                },
+                0,
+            ),
+            len: 0,
+        }
+    }
+
+    pub fn insert(&mut self, position: Position, item: T) {
+        let position = Position {
+            longitude: normalize_longitude(position.longitude),
+            ..position
+        };
+        self.root.insert(position, item);
+        self.len += 1;
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the items inside the box, which can cross the antimeridian
+    pub fn query_box(&self, area: &BoundingBox) -> Vec<(&Position, &T)> {
+        let mut found = Vec::new();
+        if area.crosses_antimeridian() {
+            self.root
+                .collect(&BoundingBox { east: PI, ..*area }, &mut found);
+            self.root
+                .collect(&BoundingBox { west: -PI, ..*area }, &mut found);
+        } else {
+            self.root.collect(area, &mut found);
+        }
+        found
+    }
+
+    /// Returns the items at most `radius` meters away from the center
+    pub fn query_radius(&self, center: &Position, radius: f64) -> Vec<(&Position, &T)> {
+        let mut found = self.query_box(&radius_bounds(center, radius));
+        found.retain(|(position, _)| haversine_distance(center, position) <= radius);
+        found
+    }
+}
+
+impl<T> Default for SpatialIndex<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Node<T> {
+    fn new(bounds: BoundingBox, depth: usize) -> Self {
+        Self {
+            bounds,
+            depth,
+            items: Vec::new(),
+            children: Vec::new(),
+        }
+    }
+
+    fn insert(&mut self, position: Position, item: T) {
+        if !self.children.is_empty() {
+            let index = self.child_index(&position);
+            self.children[index].insert(position, item);
+            return;
+        }
+
+        self.items.push((position, item));
+        if self.items.len() > NODE_CAPACITY && self.depth < MAX_DEPTH {
+            self.split();
+        }
+    }
+
+    /// Creates the four children, south-west, south-east, north-west and north-east, and moves
+    /// the items into them
+    fn split(&mut self) {
+        let (middle_latitude, middle_longitude) = self.middle();
+        let BoundingBox {
+            south,
+            west,
+            north,
+            east,
+        } = self.bounds;
+        self.children = [
+            (south, west, middle_latitude, middle_longitude),
+            (south, middle_longitude, middle_latitude, east),
+            (middle_latitude, west, north, middle_longitude),
+            (middle_latitude, middle_longitude, north, east),
+        ]
+        .into_iter()
+        .map(|(south, west, north, east)| {
+            Node::new(
+                BoundingBox {
+                    south,
+                    west,
+                    north,
+                    east,
+                },
+                self.depth + 1,
+            )
+        })
+        .collect();
+
+        for (position, item) in std::mem::take(&mut self.items) {
+            let index = self.child_index(&position);
+            self.children[index].insert(position, item);
+        }
+    }
+
+    fn middle(&self) -> (f64, f64) {
+        (
+            (self.bounds.south + self.bounds.north) / 2.,
+            (self.bounds.west + self.bounds.east) / 2.,
+        )
+    }
+
+    fn child_index(&self, position: &Position) -> usize {
+        let (middle_latitude, middle_longitude) = self.middle();
+        let eastern = usize::from(position.longitude >= middle_longitude);
+        let northern = usize::from(position.latitude >= middle_latitude);
+        northern * 2 + eastern
+    }
+
+    /// Collects the items inside the area, which must not cross the antimeridian
+    fn collect<'a>(&'a self, area: &BoundingBox, found: &mut Vec<(&'a Position, &'a T)>) {
+        let overlaps = area.south <= self.bounds.north
+            && area.north >= self.bounds.south
+            && area.west <= self.bounds.east
+            && area.east >= self.bounds.west;
+        if !overlaps {
+            return;
+        }
+
+        found.extend(
+            self.items
+                .iter()
+                .filter(|(position, _)| area.contains(position))
+                .map(|(position, item)| (position, item)),
+        );
+        for child in &self.children {
+            child.collect(area, found);
+        }
+    }
+}
+
+/// Returns a box containing the circle, spanning all the longitudes if it contains a pole
+fn radius_bounds(center: &Position, radius: f64) -> BoundingBox {
+    let angular_radius = radius / EARTH_RADIUS;
+    let south = center.latitude - angular_radius;
+    let north = center.latitude + angular_radius;
+    if south <= -FRAC_PI_2 || north >= FRAC_PI_2 {
+        return BoundingBox {
+            south: south.max(-FRAC_PI_2),
+            west: -PI,
+            north: north.min(FRAC_PI_2),
+            east: PI,
+        };
+    }
+
+    let longitude_radius = (angular_radius.sin() / center.latitude.cos())
+        .min(1.)
+        .asin();
+    if longitude_radius >= PI / 2. {
+        return BoundingBox {
+            south,
+            west: -PI,
+            north,
+            east: PI,
+        };
+    }
+    BoundingBox {
+        south,
+        west: normalize_longitude(center.longitude - longitude_radius),
+        north,
+        east: normalize_longitude(center.longitude + longitude_radius),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mobility::bounds::BoundingBox;
+    use crate::mobility::position::{position_from_degrees, Position};
+    use crate::mobility::quadtree::spatial_index::SpatialIndex;
+
+    fn at(latitude: f64, longitude: f64) -> Position {
+        position_from_degrees(latitude, longitude, 0.)
+    }
+
+    fn sorted_ids(found: Vec<(&Position, &u32)>) -> Vec<u32> {
+        let mut ids = found.into_iter().map(|(_, id)| *id).collect::<Vec<_>>();
+        ids.sort();
+        ids
+    }
+
+    /// Index holding a grid of 20x20 items spaced by 0.001° (about 110 meters) around the
+    /// position, numbered row by row from the south-west corner
+    fn grid_around(latitude: f64, longitude: f64) -> SpatialIndex<u32> {
+        let mut index = SpatialIndex::new();
+        for row in 0..20 {
+            for column in 0..20 {
+                index.insert(
+                    at(
+                        latitude + f64::from(row as i32 - 10) * 0.001,
+                        longitude + f64::from(column as i32 - 10) * 0.001,
+                    ),
+                    row * 20 + column,
+                );
+            }
+        }
+        index
+    }
+
+    #[test]
+    fn all_items_are_indexed() {
+        let index = grid_around(48.62, 2.24);
+
+        assert_eq!(index.len(), 400);
+        assert_eq!(
+            index
+                .query_box(&BoundingBox {
+                    south: -1.6,
+                    west: -3.2,
+                    north: 1.6,
+                    east: 3.2,
+                })
+                .len(),
+            400
+        );
+    }
+
+    #[test]
+    fn radius_query_returns_only_items_in_range() {
+        let index = grid_around(48.62, 2.24);
+        let center = at(48.62, 2.24);
+
+        let found = index.query_radius(&center, 140.);
+
+        // the center, its four direct neighbours about 74 and 111 meters away and the four
+        // diagonal ones about 133 meters away, but not the ones two columns away at 147 meters
+        assert_eq!(
+            sorted_ids(found),
+            vec![189, 190, 191, 209, 210, 211, 229, 230, 231]
+        );
+    }
+
+    #[test]
+    fn radius_query_far_away_is_empty() {
+        let index = grid_around(48.62, 2.24);
+
+        assert!(index.query_radius(&at(-33.86, 151.21), 10_000.).is_empty());
+    }
+
+    #[test]
+    fn radius_query_spans_the_antimeridian() {
+        let mut index = SpatialIndex::new();
+        index.insert(at(0., 179.9995), 1);
+        index.insert(at(0., -179.9995), 2);
+        index.insert(at(0., -179.99), 3);
+        index.insert(at(0., 179.99), 4);
+
+        let found = index.query_radius(&at(0., 180.), 100.);
+
+        assert_eq!(sorted_ids(found), vec![1, 2]);
+    }
+
+    #[test]
+    fn radius_query_around_a_pole_spans_all_longitudes() {
+        let mut index = SpatialIndex::new();
+        index.insert(at(89.9995, 0.), 1);
+        index.insert(at(89.9995, 180.), 2);
+        index.insert(at(89.99, 90.), 3);
+
+        let found = index.query_radius(&at(90., 0.), 100.);
+
+        assert_eq!(sorted_ids(found), vec![1, 2]);
+    }
+}
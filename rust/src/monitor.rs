@@ -17,7 +17,6 @@ use crate::exchange::etsi::map_extended_message::MAPExtendedMessage;
 use crate::exchange::etsi::signal_phase_and_timing_extended_message::SignalPhaseAndTimingExtendedMessage;
 use crate::exchange::message::Message;
 use crate::exchange::Exchange;
-use crate::now;
 
 pub fn trace_exchange(
     exchange: &Exchange,
@@ -25,6 +24,7 @@ pub fn trace_exchange(
     direction: &str,
     component: String,
     partner: String,
+    timestamp: u64,
 ) {
     let message_part = match &exchange.message {
         Message::CAM(cam) => format_cam_trace(cam),
@@ -36,12 +36,7 @@ pub fn trace_exchange(
     };
     println!(
         "{} {} {} {} {} at {}",
-        component,
-        exchange.type_field,
-        direction,
-        partner,
-        message_part,
-        now()
+        component, exchange.type_field, direction, partner, message_part, timestamp
     );
 }
 
@@ -9,8 +9,16 @@
  * Authors: see CONTRIBUTORS.md
  */
 
+pub mod compression;
+#[cfg(feature = "mobility")]
+pub mod dedup;
+pub mod lazy_item;
+#[cfg(feature = "metrics")]
+pub mod metrics;
 pub mod mqtt;
 pub mod packet;
 pub mod payload;
+pub mod pipe;
+pub mod serialization;
 #[cfg(feature = "telemetry")]
 pub mod telemetry;
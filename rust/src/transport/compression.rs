@@ -0,0 +1,144 @@
+/*
+ * Software Name : libits-client
+ * SPDX-FileCopyrightText: Copyright (c) Orange SA
+ * SPDX-License-Identifier: MIT
+ *
+ * This software is distributed under the MIT license,
+ * see the "LICENSE.txt" file for more details or https://opensource.org/license/MIT/
+ *
+ * Authors: see CONTRIBUTORS.md
+ */
+
+use std::io;
+use std::io::{Read, Write};
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+/// MQTTv5 user property key carrying the payload encoding
+pub const CONTENT_ENCODING: &str = "content-encoding";
+/// [CONTENT_ENCODING] value of gzip compressed payloads
+pub const GZIP: &str = "gzip";
+
+/// Compresses the payload using gzip
+pub fn gzip(payload: &[u8]) -> io::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(payload)?;
+    encoder.finish()
+}
+
+/// Decompresses a gzip payload
+///
+/// With a `max_size`, the decompression stops as soon as the output goes beyond it and an
+/// [InvalidData][1] error is returned, so that a small payload cannot expand without bound
+///
+/// [1]: io::ErrorKind::InvalidData
+pub fn gunzip(payload: &[u8], max_size: Option<usize>) -> io::Result<Vec<u8>> {
+    let mut decompressed = Vec::new();
+    let limit = max_size.map_or(u64::MAX, |max| max as u64 + 1);
+    GzDecoder::new(payload)
+        .take(limit)
+        .read_to_end(&mut decompressed)?;
+    if let Some(max) = max_size.filter(|max| decompressed.len() > *max) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("decompressed payload above the {} bytes limit", max),
+        ));
+    }
+    Ok(decompressed)
+}
+
+/// Returns true if the user properties declare a gzip encoded payload
+///
+/// Payloads without [CONTENT_ENCODING] property are considered as not compressed
+pub fn is_gzip_encoded(user_properties: &[(String, String)]) -> bool {
+    user_properties
+        .iter()
+        .any(|(key, value)| key == CONTENT_ENCODING && value == GZIP)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::transport::compression::{gunzip, gzip, is_gzip_encoded};
+
+    #[test]
+    fn gzip_property_is_detected() {
+        assert!(is_gzip_encoded(&[
+            ("traceparent".to_string(), "00-0-0-01".to_string()),
+            ("content-encoding".to_string(), "gzip".to_string()),
+        ]));
+    }
+
+    #[test]
+    fn missing_property_is_not_compressed() {
+        assert!(!is_gzip_encoded(&[]));
+        assert!(!is_gzip_encoded(&[(
+            "content-encoding".to_string(),
+            "identity".to_string()
+        )]));
+    }
+
+    #[test]
+    fn payload_round_trip() {
+        let payload = b"{\"type\":\"cpm\",\"origin\":\"self\"}";
+
+        let decompressed = gunzip(&gzip(payload).unwrap(), None).unwrap();
+
+        assert_eq!(decompressed, payload);
+    }
+
+    #[test]
+    fn invalid_gzip_is_err() {
+        assert!(gunzip(b"{\"type\":\"cpm\"}", None).is_err());
+    }
+
+    #[test]
+    fn decompression_stops_above_the_limit() {
+        let compressed = gzip(&[b' '; 1_000_000]).unwrap();
+
+        let error = gunzip(&compressed, Some(1024)).unwrap_err();
+
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(
+            gunzip(&compressed, Some(1_000_000)).unwrap().len(),
+            1_000_000
+        );
+    }
+
+    #[cfg(feature = "mobility")]
+    #[test]
+    fn large_cpm_round_trip() {
+        use crate::exchange::etsi::collective_perception_message::CollectivePerceptionMessage;
+        use crate::exchange::etsi::perceived_object::PerceivedObject;
+
+        let cpm = CollectivePerceptionMessage {
+            station_id: 42,
+            perceived_object_container: (0..=u8::MAX)
+                .map(|object_id| PerceivedObject {
+                    object_id,
+                    x_distance: 1000 + i32::from(object_id),
+                    y_distance: -500,
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        };
+        let payload = serde_json::to_vec(&cpm).unwrap();
+
+        let compressed = gzip(&payload).unwrap();
+        assert!(
+            compressed.len() < payload.len(),
+            "compressed: {}, original: {}",
+            compressed.len(),
+            payload.len()
+        );
+
+        let decompressed = gunzip(&compressed, None).unwrap();
+        assert_eq!(decompressed, payload);
+        assert_eq!(
+            serde_json::from_slice::<CollectivePerceptionMessage>(&decompressed).unwrap(),
+            cpm
+        );
+    }
+}
@@ -0,0 +1,295 @@
+/*
+ * Software Name : libits-client
+ * SPDX-FileCopyrightText: Copyright (c) Orange SA
+ * SPDX-License-Identifier: MIT
+ *
+ * This software is distributed under the MIT license,
+ * see the "LICENSE.txt" file for more details or https://opensource.org/license/MIT/
+ *
+ * Authors: see CONTRIBUTORS.md
+ */
+
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+
+use crate::exchange::etsi::action_id::ActionId;
+use crate::exchange::message::Message;
+use crate::exchange::Exchange;
+
+/// Period after which the generation delta time of CAMs and CPMs wraps, in milliseconds
+const GENERATION_DELTA_TIME_PERIOD: u64 = 65_536;
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+struct MessageKey {
+    station_id: u64,
+    message_type: String,
+    action_id: Option<ActionId>,
+    time: u64,
+}
+
+/// Drops the messages already received, e.g. through overlapping subscriptions or relayed by
+/// several brokers
+///
+/// Messages are identified by their station id, type, and generation delta time for CAMs and
+/// CPMs, action id and reference time for DENMs, or timestamp for the other types; only the
+/// message is compared, not its envelope, so that a copy received from another source is
+/// recognized even if its source uuid or path differs
+///
+/// A message is a duplicate if the same one was received less than `ttl` ago, the whole
+/// message being compared so that a distinct message sharing the identifiers of a remembered
+/// one replaces it instead of being dropped; as the generation delta time wraps every 65.536
+/// seconds, CAMs and CPMs are never remembered longer than that so that a message generated one
+/// period later is not mistaken for a duplicate.
+/// At most `capacity` messages are remembered, the first ones to expire being forgotten first
+pub struct MessageDeduplicator {
+    capacity: usize,
+    ttl: Duration,
+    messages: HashMap<MessageKey, Remembered>,
+    /// Remembered messages ordered by expiration, then by reception
+    order: BTreeMap<(u64, u64), MessageKey>,
+    receptions: u64,
+}
+
+/// Message remembered with its position in the expiration order
+struct Remembered {
+    message: Message,
+    order: (u64, u64),
+}
+
+impl MessageDeduplicator {
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            capacity,
+            ttl,
+            messages: HashMap::with_capacity(capacity),
+            order: BTreeMap::new(),
+            receptions: 0,
+        }
+    }
+
+    /// Returns true if the exchange was already received, remembers it otherwise
+    ///
+    /// `timestamp` is the reception time in milliseconds
+    pub fn is_duplicate(&mut self, exchange: &Exchange, timestamp: u64) -> bool {
+        self.forget_expired(timestamp);
+
+        let (key, wrapping) = key(exchange);
+        if let Some(remembered) = self.messages.get(&key) {
+            if remembered.message == exchange.message {
+                return true;
+            }
+            self.order.remove(&remembered.order);
+            self.messages.remove(&key);
+        }
+        if self.capacity == 0 {
+            return false;
+        }
+
+        let mut ttl = self.ttl.as_millis() as u64;
+        if wrapping {
+            ttl = ttl.min(GENERATION_DELTA_TIME_PERIOD);
+        }
+        if self.order.len() >= self.capacity {
+            if let Some((_, oldest)) = self.order.pop_first() {
+                self.messages.remove(&oldest);
+            }
+        }
+        let order = (timestamp.saturating_add(ttl), self.receptions);
+        self.messages.insert(
+            key.clone(),
+            Remembered {
+                message: exchange.message.clone(),
+                order,
+            },
+        );
+        self.order.insert(order, key);
+        self.receptions = self.receptions.wrapping_add(1);
+
+        false
+    }
+
+    /// Returns the number of messages remembered
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+
+    fn forget_expired(&mut self, timestamp: u64) {
+        while let Some(entry) = self.order.first_entry() {
+            if entry.key().0 > timestamp {
+                break;
+            }
+            self.messages.remove(&entry.remove());
+        }
+    }
+}
+
+/// Returns the key identifying the exchange, and whether its time wraps
+fn key(exchange: &Exchange) -> (MessageKey, bool) {
+    let (time, wrapping) = match &exchange.message {
+        Message::CAM(cam) => (u64::from(cam.generation_delta_time), true),
+        Message::CPM(cpm) => (u64::from(cpm.generation_delta_time), true),
+        Message::DENM(denm) => (denm.management_container.reference_time, false),
+        Message::MAPEM(map) => (map.timestamp.unwrap_or(exchange.timestamp), false),
+        Message::SPATEM(spat) => (spat.timestamp.unwrap_or(exchange.timestamp), false),
+        Message::INFO(_) => (exchange.timestamp, false),
+    };
+
+    (
+        MessageKey {
+            station_id: exchange.message.station_id().unwrap_or_default(),
+            message_type: exchange.type_field.clone(),
+            action_id: match &exchange.message {
+                Message::DENM(denm) => Some(denm.action_id()),
+                _ => None,
+            },
+            time,
+        },
+        wrapping,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::exchange::etsi::action_id::ActionId;
+    use crate::exchange::etsi::cooperative_awareness_message::CooperativeAwarenessMessage;
+    use crate::exchange::etsi::decentralized_environmental_notification_message::{
+        DecentralizedEnvironmentalNotificationMessage, ManagementContainer,
+    };
+    use crate::exchange::message::Message;
+    use crate::exchange::Exchange;
+    use crate::transport::dedup::MessageDeduplicator;
+
+    fn cam(station_id: u32, generation_delta_time: u16) -> Exchange {
+        *Exchange::new(
+            String::from("car_1"),
+            0,
+            Vec::new(),
+            Message::CAM(CooperativeAwarenessMessage {
+                station_id,
+                generation_delta_time,
+                ..Default::default()
+            }),
+        )
+    }
+
+    fn denm(originating_station_id: u32, sequence_number: u16, reference_time: u64) -> Exchange {
+        *Exchange::new(
+            String::from("rsu_1"),
+            0,
+            Vec::new(),
+            Message::DENM(DecentralizedEnvironmentalNotificationMessage {
+                station_id: 42,
+                management_container: ManagementContainer {
+                    action_id: ActionId::new(originating_station_id, sequence_number),
+                    reference_time,
+                    ..Default::default()
+                },
+                ..Default::default()
+            }),
+        )
+    }
+
+    #[test]
+    fn denms_are_keyed_on_action_id() {
+        let mut deduplicator = MessageDeduplicator::new(10, Duration::from_secs(1));
+
+        assert!(!deduplicator.is_duplicate(&denm(1, 1, 1000), 0));
+        assert!(deduplicator.is_duplicate(&denm(1, 1, 1000), 10));
+        assert!(!deduplicator.is_duplicate(&denm(1, 2, 1000), 10));
+        assert!(!deduplicator.is_duplicate(&denm(2, 1, 1000), 10));
+        assert!(!deduplicator.is_duplicate(&denm(1, 1, 2000), 10));
+    }
+
+    #[test]
+    fn duplicate_within_ttl_is_dropped() {
+        let mut deduplicator = MessageDeduplicator::new(10, Duration::from_secs(1));
+
+        assert!(!deduplicator.is_duplicate(&cam(42, 1000), 0));
+        assert!(deduplicator.is_duplicate(&cam(42, 1000), 999));
+    }
+
+    #[test]
+    fn distinct_messages_pass() {
+        let mut deduplicator = MessageDeduplicator::new(10, Duration::from_secs(1));
+
+        assert!(!deduplicator.is_duplicate(&cam(42, 1000), 0));
+        assert!(!deduplicator.is_duplicate(&cam(42, 1100), 100));
+        assert!(!deduplicator.is_duplicate(&cam(43, 1000), 100));
+        assert_eq!(deduplicator.len(), 3);
+    }
+
+    #[test]
+    fn duplicate_after_ttl_passes() {
+        let mut deduplicator = MessageDeduplicator::new(10, Duration::from_secs(1));
+
+        assert!(!deduplicator.is_duplicate(&cam(42, 1000), 0));
+        assert!(!deduplicator.is_duplicate(&cam(42, 1000), 1_000));
+    }
+
+    #[test]
+    fn wrapped_generation_delta_time_is_not_duplicate() {
+        let mut deduplicator = MessageDeduplicator::new(10, Duration::from_secs(120));
+
+        assert!(!deduplicator.is_duplicate(&cam(42, 1000), 0));
+        assert!(deduplicator.is_duplicate(&cam(42, 1000), 60_000));
+        assert!(!deduplicator.is_duplicate(&cam(42, 1000), 65_536));
+    }
+
+    #[test]
+    fn oldest_message_is_forgotten_at_capacity() {
+        let mut deduplicator = MessageDeduplicator::new(2, Duration::from_secs(1));
+
+        assert!(!deduplicator.is_duplicate(&cam(1, 1000), 0));
+        assert!(!deduplicator.is_duplicate(&cam(2, 1000), 0));
+        assert!(!deduplicator.is_duplicate(&cam(3, 1000), 0));
+
+        assert_eq!(deduplicator.len(), 2);
+        assert!(!deduplicator.is_duplicate(&cam(1, 1000), 0));
+    }
+
+    #[test]
+    fn copy_from_another_source_is_dropped() {
+        let mut deduplicator = MessageDeduplicator::new(10, Duration::from_secs(1));
+        let mut copy = cam(42, 1000);
+        copy.source_uuid = String::from("broker_2");
+        copy.timestamp = 10;
+
+        assert!(!deduplicator.is_duplicate(&cam(42, 1000), 0));
+        assert!(deduplicator.is_duplicate(&copy, 10));
+    }
+
+    #[test]
+    fn distinct_message_with_the_same_key_passes() {
+        let mut deduplicator = MessageDeduplicator::new(10, Duration::from_secs(1));
+        let mut other = cam(42, 1000);
+        if let Message::CAM(cam) = &mut other.message {
+            cam.basic_container.station_type = Some(15);
+        }
+
+        assert!(!deduplicator.is_duplicate(&cam(42, 1000), 0));
+        assert!(!deduplicator.is_duplicate(&other, 10));
+        assert!(deduplicator.is_duplicate(&other, 20));
+        assert_eq!(deduplicator.len(), 1);
+    }
+
+    #[test]
+    fn messages_are_forgotten_in_expiration_order() {
+        let mut deduplicator = MessageDeduplicator::new(10, Duration::from_secs(120));
+
+        assert!(!deduplicator.is_duplicate(&denm(1, 1, 1000), 0));
+        assert!(!deduplicator.is_duplicate(&cam(42, 1000), 10));
+        assert_eq!(deduplicator.len(), 2);
+
+        assert!(!deduplicator.is_duplicate(&cam(42, 1000), 65_546));
+        assert!(deduplicator.is_duplicate(&denm(1, 1, 1000), 65_546));
+        assert_eq!(deduplicator.len(), 2);
+
+        assert!(!deduplicator.is_duplicate(&denm(1, 1, 1000), 120_000));
+    }
+}
@@ -0,0 +1,162 @@
+/*
+ * Software Name : libits-client
+ * SPDX-FileCopyrightText: Copyright (c) Orange SA
+ * SPDX-License-Identifier: MIT
+ *
+ * This software is distributed under the MIT license,
+ * see the "LICENSE.txt" file for more details or https://opensource.org/license/MIT/
+ *
+ * Authors: see CONTRIBUTORS.md
+ */
+
+use bytes::Bytes;
+use rumqttc::v5::mqttbytes::v5::{Publish, PublishProperties};
+use serde::de::DeserializeOwned;
+
+use crate::transport::compression::{gunzip, is_gzip_encoded};
+use crate::transport::mqtt::mqtt_router::BoxedReception;
+
+/// Received payload kept as raw bytes until it is explicitly [parsed][1]
+///
+/// Using the [lazy] callback when [adding a route][2], the router dispatches publishes on their
+/// topic only and the payload is deserialized on demand; items dropped after a topic based
+/// filtering are then never parsed
+///
+/// [1]: LazyItem::parse
+/// [2]: crate::transport::mqtt::mqtt_router::MqttRouter::add_route
+#[derive(Clone, Debug, Default)]
+pub struct LazyItem {
+    payload: Bytes,
+    properties: PublishProperties,
+    max_payload_size: Option<usize>,
+}
+
+impl LazyItem {
+    pub fn new(payload: Bytes, properties: PublishProperties) -> Self {
+        Self {
+            payload,
+            properties,
+            max_payload_size: None,
+        }
+    }
+
+    /// Refuses to parse compressed payloads whose decompressed size goes beyond the limit
+    pub fn with_max_payload_size(self, max_payload_size: Option<usize>) -> Self {
+        Self {
+            max_payload_size,
+            ..self
+        }
+    }
+
+    /// Returns the payload as received, still compressed if it was sent compressed
+    pub fn bytes(&self) -> &Bytes {
+        &self.payload
+    }
+
+    pub fn properties(&self) -> &PublishProperties {
+        &self.properties
+    }
+
+    /// Deserializes the JSON payload, decompressing it first if needed
+    ///
+    /// The decompression fails as soon as the output goes beyond the [maximum payload size][1]
+    ///
+    /// [1]: LazyItem::with_max_payload_size
+    pub fn parse<T: DeserializeOwned>(&self) -> Result<T, serde_json::Error> {
+        if is_gzip_encoded(&self.properties.user_properties) {
+            let decompressed =
+                gunzip(&self.payload, self.max_payload_size).map_err(serde_json::Error::io)?;
+            serde_json::from_slice(&decompressed)
+        } else {
+            serde_json::from_slice(&self.payload)
+        }
+    }
+}
+
+impl From<Publish> for LazyItem {
+    fn from(publish: Publish) -> Self {
+        Self::new(publish.payload, publish.properties.unwrap_or_default())
+    }
+}
+
+/// Route callback boxing the publish into a [LazyItem] without parsing it
+pub fn lazy(publish: Publish) -> Option<BoxedReception> {
+    bounded_lazy(None)(publish)
+}
+
+/// Returns a [lazy] route callback whose items refuse to decompress beyond `max_payload_size`
+///
+/// Pass the pipeline's configured `max_payload_size` so that a compressed payload cannot bypass it
+pub fn bounded_lazy(max_payload_size: Option<usize>) -> impl Fn(Publish) -> Option<BoxedReception> {
+    move |publish| {
+        let properties = publish.properties.clone().unwrap_or_default();
+        let item = LazyItem::from(publish).with_max_payload_size(max_payload_size);
+        Some((Box::new(item), properties))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use rumqttc::v5::mqttbytes::v5::{Publish, PublishProperties};
+    use rumqttc::v5::mqttbytes::QoS;
+    use serde_json::Value;
+
+    use crate::transport::compression::{gzip, CONTENT_ENCODING, GZIP};
+    use crate::transport::lazy_item::{bounded_lazy, lazy, LazyItem};
+
+    #[test]
+    fn payload_is_parsed_on_demand() {
+        let item = LazyItem::from(Publish::new(
+            "test",
+            QoS::AtMostOnce,
+            r#"{"type":"cam"}"#,
+            None,
+        ));
+
+        assert_eq!(item.bytes(), &Bytes::from(r#"{"type":"cam"}"#));
+        let value = item.parse::<Value>().unwrap();
+        assert_eq!(value["type"], "cam");
+    }
+
+    #[test]
+    fn garbage_is_only_an_error_when_parsed() {
+        let reception = lazy(Publish::new("test", QoS::AtMostOnce, "garbage", None));
+
+        let (boxed, _) = reception.expect("lazy route must always dispatch");
+        let item = boxed.downcast::<LazyItem>().unwrap();
+        assert!(item.parse::<Value>().is_err());
+    }
+
+    #[test]
+    fn compressed_payload_is_decompressed_on_parse() {
+        let properties = PublishProperties {
+            user_properties: vec![(CONTENT_ENCODING.to_string(), GZIP.to_string())],
+            ..Default::default()
+        };
+        let compressed = gzip(br#"{"type":"denm"}"#).unwrap();
+
+        let item = LazyItem::new(Bytes::from(compressed.clone()), properties);
+
+        assert_eq!(item.bytes(), &Bytes::from(compressed));
+        assert_eq!(item.parse::<Value>().unwrap()["type"], "denm");
+    }
+
+    #[test]
+    fn decompression_beyond_the_limit_is_an_error() {
+        let properties = PublishProperties {
+            user_properties: vec![(CONTENT_ENCODING.to_string(), GZIP.to_string())],
+            ..Default::default()
+        };
+        let publish = Publish::new(
+            "test",
+            QoS::AtMostOnce,
+            gzip(br#"{"type":"denm"}"#).unwrap(),
+            Some(properties),
+        );
+
+        let (boxed, _) = bounded_lazy(Some(8))(publish).unwrap();
+        let item = boxed.downcast::<LazyItem>().unwrap();
+        assert!(item.parse::<Value>().is_err());
+    }
+}
@@ -0,0 +1,348 @@
+/*
+ * Software Name : libits-client
+ * SPDX-FileCopyrightText: Copyright (c) Orange SA
+ * SPDX-License-Identifier: MIT
+ *
+ * This software is distributed under the MIT license,
+ * see the "LICENSE.txt" file for more details or https://opensource.org/license/MIT/
+ *
+ * Authors: see CONTRIBUTORS.md
+ */
+
+use std::fmt::Write;
+use std::io;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream, ToSocketAddrs};
+use tokio::time::{sleep, timeout};
+use tracing::{debug, info, warn};
+
+use crate::transport::mqtt::connection_state::{ConnectionState, ConnectionTracker};
+use crate::transport::mqtt::router_stats::RouterStats;
+
+/// Size above which a request head is not read further
+const MAX_REQUEST_SIZE: usize = 8192;
+/// Time after which a connection whose request head is not complete is closed
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+/// Delay before accepting connections again after an error, e.g. too many open files
+const ACCEPT_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// Serves the [router counters][1] and the connection state at `/metrics`, in the Prometheus
+/// text format
+///
+/// Lighter than the telemetry feature, it lets the clients be scraped without any collector
+///
+/// ```no_run
+/// # use libits::transport::metrics::PrometheusServer;
+/// # use libits::transport::mqtt::mqtt_client::MqttClient;
+/// # use libits::transport::mqtt::mqtt_router::MqttRouter;
+/// # async fn start(router: &MqttRouter, client: &MqttClient) {
+/// let server = PrometheusServer::new(router.stats()).with_connection(client.connection_tracker());
+/// tokio::spawn(server.serve("0.0.0.0:9100"));
+/// # }
+/// ```
+///
+/// [1]: RouterStats
+pub struct PrometheusServer {
+    stats: Arc<RouterStats>,
+    connection: Option<Arc<ConnectionTracker>>,
+}
+
+impl PrometheusServer {
+    pub fn new(stats: Arc<RouterStats>) -> Self {
+        Self {
+            stats,
+            connection: None,
+        }
+    }
+
+    /// Also exposes the state of the connection to the broker
+    pub fn with_connection(self, connection: Arc<ConnectionTracker>) -> Self {
+        Self {
+            connection: Some(connection),
+            ..self
+        }
+    }
+
+    /// Returns the metrics in the Prometheus text format
+    pub fn render(&self) -> String {
+        let snapshot = self.stats.snapshot();
+        let mut metrics = String::new();
+
+        for (name, help, value) in [
+            (
+                "its_router_received_total",
+                "Publish received on a routed topic",
+                snapshot.received,
+            ),
+            (
+                "its_router_parsed_total",
+                "Publish successfully parsed",
+                snapshot.parsed,
+            ),
+            (
+                "its_router_parse_failed_total",
+                "Publish that failed to be parsed",
+                snapshot.parse_failed,
+            ),
+            (
+                "its_router_truncated_total",
+                "Parse failures looking like a truncated payload",
+                snapshot.truncated,
+            ),
+            (
+                "its_router_rejected_total",
+                "Publish rejected before parsing",
+                snapshot.rejected,
+            ),
+            (
+                "its_router_duplicates_total",
+                "Messages dropped as duplicates",
+                snapshot.duplicates,
+            ),
+        ] {
+            write_metric(
+                &mut metrics,
+                name,
+                help,
+                "counter",
+                &[(String::new(), value)],
+            );
+        }
+
+        let mut per_message_type = snapshot
+            .per_message_type
+            .into_iter()
+            .map(|(message_type, count)| {
+                (
+                    format!("{{message_type=\"{}\"}}", escape_label_value(&message_type)),
+                    count,
+                )
+            })
+            .collect::<Vec<_>>();
+        per_message_type.sort();
+        write_metric(
+            &mut metrics,
+            "its_router_messages_total",
+            "Messages parsed by message type",
+            "counter",
+            &per_message_type,
+        );
+
+        if let Some(connection) = &self.connection {
+            let connected = u64::from(connection.state() == ConnectionState::Connected);
+            write_metric(
+                &mut metrics,
+                "its_mqtt_connected",
+                "1 if the broker accepted the connection, 0 otherwise",
+                "gauge",
+                &[(String::new(), connected)],
+            );
+        }
+
+        metrics
+    }
+
+    /// Listens on the bind address (e.g. `0.0.0.0:9100`) and answers the scrapes until dropped
+    ///
+    /// Only binding errors are returned, the failures to accept a connection are logged.
+    /// Any other path than `/metrics` is answered with a 404
+    pub async fn serve<A: ToSocketAddrs>(self, bind_address: A) -> io::Result<()> {
+        self.serve_listener(TcpListener::bind(bind_address).await?)
+            .await
+    }
+
+    async fn serve_listener(self, listener: TcpListener) -> io::Result<()> {
+        info!("Serving metrics on {}", listener.local_addr()?);
+
+        let server = Arc::new(self);
+        loop {
+            let (stream, peer) = match listener.accept().await {
+                Ok(connection) => connection,
+                Err(e) => {
+                    warn!("Failed to accept metrics connection: {}", e);
+                    sleep(ACCEPT_RETRY_DELAY).await;
+                    continue;
+                }
+            };
+            let server = server.clone();
+            tokio::spawn(async move {
+                if let Err(e) = server.answer(stream).await {
+                    warn!("Failed to answer metrics request from {}: {}", peer, e);
+                }
+            });
+        }
+    }
+
+    async fn answer(&self, mut stream: TcpStream) -> io::Result<()> {
+        let request = timeout(READ_TIMEOUT, read_request_head(&mut stream))
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "request head not received"))??;
+
+        let request = String::from_utf8_lossy(&request);
+        let request_line = request.lines().next().unwrap_or_default();
+        debug!("Metrics request: {}", request_line);
+
+        let mut request_line = request_line.split_whitespace();
+        let response = match (request_line.next(), request_line.next()) {
+            (Some("GET"), Some("/metrics")) => {
+                response("200 OK", "text/plain; version=0.0.4", &self.render())
+            }
+            _ => response("404 Not Found", "text/plain", "Not Found\n"),
+        };
+
+        stream.write_all(response.as_bytes()).await?;
+        stream.shutdown().await
+    }
+}
+
+/// Reads the request until the end of its head, the connection end or [MAX_REQUEST_SIZE]
+async fn read_request_head(stream: &mut TcpStream) -> io::Result<Vec<u8>> {
+    let mut request = Vec::new();
+    let mut buffer = [0; 1024];
+    while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+        let read = stream.read(&mut buffer).await?;
+        if read == 0 || request.len() + read > MAX_REQUEST_SIZE {
+            break;
+        }
+        request.extend_from_slice(&buffer[..read]);
+    }
+    Ok(request)
+}
+
+/// Escapes the backslashes, double quotes and line feeds as the text format requires for label
+/// values
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn write_metric(
+    metrics: &mut String,
+    name: &str,
+    help: &str,
+    kind: &str,
+    samples: &[(String, u64)],
+) {
+    let _ = writeln!(metrics, "# HELP {} {}", name, help);
+    let _ = writeln!(metrics, "# TYPE {} {}", name, kind);
+    for (labels, value) in samples {
+        let _ = writeln!(metrics, "{}{} {}", name, labels, value);
+    }
+}
+
+fn response(status: &str, content_type: &str, body: &str) -> String {
+    format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    use crate::transport::metrics::{escape_label_value, PrometheusServer};
+    use crate::transport::mqtt::router_stats::RouterStats;
+
+    fn server() -> PrometheusServer {
+        let stats = Arc::new(RouterStats::default());
+        stats.count_received();
+        stats.count_parsed();
+        stats.count_message_type("denm");
+        stats.count_message_type("cam");
+        stats.count_message_type("cam");
+
+        PrometheusServer::new(stats)
+    }
+
+    #[test]
+    fn metrics_are_rendered() {
+        let metrics = server().render();
+
+        for line in [
+            "# TYPE its_router_received_total counter",
+            "its_router_received_total 1",
+            "its_router_parsed_total 1",
+            "its_router_parse_failed_total 0",
+            "its_router_truncated_total 0",
+            "its_router_rejected_total 0",
+            "its_router_duplicates_total 0",
+            "its_router_messages_total{message_type=\"cam\"} 2",
+            "its_router_messages_total{message_type=\"denm\"} 1",
+        ] {
+            assert!(metrics.lines().any(|l| l == line), "missing '{}'", line);
+        }
+        assert!(!metrics.contains("its_mqtt_connected"));
+    }
+
+    #[test]
+    fn crafted_message_type_is_counted_as_other() {
+        let stats = Arc::new(RouterStats::default());
+        stats.count_message_type("cam\"} 1\nits_mqtt_connected 1\n#");
+
+        let metrics = PrometheusServer::new(stats).render();
+
+        assert!(metrics
+            .lines()
+            .any(|l| l == "its_router_messages_total{message_type=\"other\"} 1"));
+        assert!(!metrics.contains("its_mqtt_connected"));
+    }
+
+    #[test]
+    fn label_values_are_escaped() {
+        assert_eq!(escape_label_value("a\\b\"c\nd"), "a\\\\b\\\"c\\nd");
+    }
+
+    async fn get(path: &str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(server().serve_listener(listener));
+
+        let mut stream = TcpStream::connect(address).await.unwrap();
+        stream
+            .write_all(format!("GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path).as_bytes())
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    #[tokio::test]
+    async fn endpoint_serves_metrics() {
+        let response = get("/metrics").await;
+
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains("\r\n\r\n# HELP its_router_received_total"));
+        assert!(response.contains("its_router_messages_total{message_type=\"cam\"} 2\n"));
+    }
+
+    #[tokio::test]
+    async fn other_paths_are_not_found() {
+        assert!(get("/").await.starts_with("HTTP/1.1 404 Not Found\r\n"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn idle_connection_is_closed() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(server().serve_listener(listener));
+
+        let mut stream = TcpStream::connect(address).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+
+        assert!(response.is_empty());
+    }
+}
@@ -9,34 +9,209 @@
  * Authors: see CONTRIBUTORS.md
  */
 
+use http::header::HOST;
+use http::uri::Authority;
+use http::{HeaderValue, Request, Uri};
 use rumqttc::v5::MqttOptions;
 use rumqttc::{TlsConfiguration, Transport};
+use tracing::{info, warn};
 
+pub mod connection_state;
 pub mod mqtt_client;
+pub mod mqtt_error;
 pub mod mqtt_router;
+pub mod parse_error_reporter;
+pub(crate) mod publish_ack;
+pub(crate) mod resend_queue;
+pub mod router_stats;
+pub mod subscription;
 pub mod topic;
 
 #[cfg(feature = "geo_routing")]
 pub mod geo_topic;
 
+/// Returns the address to connect the broker at
+///
+/// For standard MQTT over TLS, rumqttc takes both the TLS server name (SNI) and the name the
+/// broker certificate is checked against from the broker address: the server name then replaces
+/// the host, and the broker is reached at the address the server name resolves to. Over
+/// WebSocket, the host is kept and the server name set on the upgrade request instead
+pub(crate) fn broker_address(
+    host: String,
+    use_tls: bool,
+    use_websocket: bool,
+    tls_server_name: Option<&str>,
+) -> String {
+    match tls_server_name {
+        Some(server_name) if use_tls && !use_websocket => {
+            if server_name != host {
+                info!(
+                    "Connecting to '{}' instead of '{}' to use it as TLS server name",
+                    server_name, host
+                );
+            }
+            server_name.to_string()
+        }
+        _ => host,
+    }
+}
+
 pub(crate) fn configure_transport(
     use_tls: bool,
     use_websocket: bool,
+    tls_server_name: Option<String>,
     mqtt_options: &mut MqttOptions,
 ) {
     match (use_tls, use_websocket) {
         (true, true) => {
-            println!("Transport: MQTT over WebSocket; TLS enabled");
+            info!("Transport: MQTT over WebSocket; TLS enabled");
             mqtt_options.set_transport(Transport::Wss(TlsConfiguration::default()));
         }
         (true, false) => {
-            println!("Transport: standard MQTT; TLS enabled");
+            info!("Transport: standard MQTT; TLS enabled");
             mqtt_options.set_transport(Transport::Tls(TlsConfiguration::default()));
         }
         (false, true) => {
-            println!("Transport: MQTT over WebSocket; TLS disabled");
+            info!("Transport: MQTT over WebSocket; TLS disabled");
             mqtt_options.set_transport(Transport::Ws);
         }
-        (false, false) => println!("Transport: standard MQTT; TLS disabled"),
+        (false, false) => info!("Transport: standard MQTT; TLS disabled"),
+    }
+
+    if let Some(server_name) = tls_server_name {
+        match (use_tls, use_websocket) {
+            (_, true) => {
+                info!("Server name: {}", server_name);
+                set_server_name(server_name, mqtt_options);
+            }
+            (true, false) => info!("Server name: {}", server_name),
+            (false, false) => warn!("Server name '{}' ignored without TLS", server_name),
+        }
+    }
+}
+
+/// Overrides the host of the WebSocket upgrade request, and thus the TLS server name (SNI)
+/// and the `Host` header, while still connecting to the configured host
+fn set_server_name(server_name: String, mqtt_options: &mut MqttOptions) {
+    mqtt_options.set_request_modifier(move |request: Request<()>| {
+        let server_name = server_name.clone();
+        async move { with_server_name(request, &server_name) }
+    });
+}
+
+fn with_server_name(mut request: Request<()>, server_name: &str) -> Request<()> {
+    let authority = match request.uri().port_u16() {
+        Some(port) => format!("{}:{}", server_name, port),
+        None => server_name.to_string(),
+    };
+
+    let mut parts = request.uri().clone().into_parts();
+    match Authority::try_from(authority.as_str()) {
+        Ok(authority) => parts.authority = Some(authority),
+        Err(e) => {
+            warn!("Invalid server name '{}': {}", server_name, e);
+            return request;
+        }
+    }
+    if let Ok(uri) = Uri::from_parts(parts) {
+        *request.uri_mut() = uri;
+    }
+    if let Ok(host) = HeaderValue::from_str(&authority) {
+        request.headers_mut().insert(HOST, host);
+    }
+
+    request
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::transport::mqtt::{broker_address, configure_transport};
+    use http::header::HOST;
+    use http::Request;
+    use rumqttc::v5::MqttOptions;
+    use rumqttc::Transport;
+
+    #[tokio::test]
+    async fn server_name_overrides_websocket_request_host() {
+        let mut mqtt_options = MqttOptions::new("client", "ws://10.0.0.1:8080/mqtt", 8080);
+
+        configure_transport(
+            false,
+            true,
+            Some("its.example.com".to_string()),
+            &mut mqtt_options,
+        );
+
+        let request_modifier = mqtt_options
+            .request_modifier()
+            .expect("Request modifier should be set");
+        let request = Request::builder()
+            .uri("ws://10.0.0.1:8080/mqtt")
+            .header(HOST, "10.0.0.1:8080")
+            .body(())
+            .unwrap();
+
+        let request = request_modifier(request).await;
+
+        assert_eq!(request.uri().host(), Some("its.example.com"));
+        assert_eq!(request.uri().port_u16(), Some(8080));
+        assert_eq!(request.uri().path(), "/mqtt");
+        assert_eq!(request.headers()[HOST], "its.example.com:8080");
+    }
+
+    #[test]
+    fn server_name_is_ignored_without_tls() {
+        let mut mqtt_options = MqttOptions::new("client", "10.0.0.1", 1883);
+
+        configure_transport(
+            false,
+            false,
+            Some("its.example.com".to_string()),
+            &mut mqtt_options,
+        );
+
+        assert!(mqtt_options.request_modifier().is_none());
+        assert!(matches!(mqtt_options.transport(), Transport::Tcp));
+    }
+
+    #[test]
+    fn server_name_replaces_host_for_tls() {
+        assert_eq!(
+            broker_address(
+                String::from("10.0.0.1"),
+                true,
+                false,
+                Some("its.example.com")
+            ),
+            "its.example.com"
+        );
+    }
+
+    #[test]
+    fn host_is_kept_without_tls_or_over_websocket() {
+        for (use_tls, use_websocket) in [(false, false), (false, true), (true, true)] {
+            assert_eq!(
+                broker_address(
+                    String::from("10.0.0.1"),
+                    use_tls,
+                    use_websocket,
+                    Some("its.example.com")
+                ),
+                "10.0.0.1"
+            );
+        }
+        assert_eq!(
+            broker_address(String::from("10.0.0.1"), true, false, None),
+            "10.0.0.1"
+        );
+    }
+
+    #[test]
+    fn no_request_modifier_by_default() {
+        let mut mqtt_options = MqttOptions::new("client", "ws://10.0.0.1:8080/mqtt", 8080);
+
+        configure_transport(false, true, None, &mut mqtt_options);
+
+        assert!(mqtt_options.request_modifier().is_none());
     }
 }
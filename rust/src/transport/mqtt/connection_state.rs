@@ -0,0 +1,235 @@
+/*
+ * Software Name : libits-client
+ * SPDX-FileCopyrightText: Copyright (c) Orange SA
+ * SPDX-License-Identifier: MIT
+ *
+ * This software is distributed under the MIT license,
+ * see the "LICENSE.txt" file for more details or https://opensource.org/license/MIT/
+ *
+ * Authors: see CONTRIBUTORS.md
+ */
+
+use std::sync::{Arc, RwLock};
+
+use rumqttc::v5::mqttbytes::v5::ConnectReturnCode;
+use rumqttc::v5::{Event, Incoming};
+use rumqttc::Outgoing;
+use tokio::sync::watch;
+use tracing::info;
+
+use crate::transport::mqtt::publish_ack::AckTracker;
+
+type StateChangeCallback = Arc<dyn Fn(ConnectionState) + Send + Sync>;
+
+/// State of the link between the [client][1] and the broker
+///
+/// [1]: crate::transport::mqtt::mqtt_client::MqttClient
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ConnectionState {
+    /// Waiting for the broker to acknowledge the connection
+    #[default]
+    Connecting,
+    /// The broker accepted the connection
+    Connected,
+    /// The connection was refused, closed or lost
+    Disconnected,
+}
+
+impl ConnectionState {
+    /// Returns the state the event leads to, None if the event does not change the state
+    pub fn from_event(event: &Event) -> Option<Self> {
+        match event {
+            Event::Incoming(Incoming::ConnAck(conn_ack)) => {
+                if conn_ack.code == ConnectReturnCode::Success {
+                    Some(ConnectionState::Connected)
+                } else {
+                    Some(ConnectionState::Disconnected)
+                }
+            }
+            Event::Incoming(Incoming::Disconnect(_)) | Event::Outgoing(Outgoing::Disconnect) => {
+                Some(ConnectionState::Disconnected)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Keeps the connection state up to date from the event loop's events
+///
+/// Changes are published to [watchers][1] and to the optional [callback][2]; publish
+/// acknowledgements are matched with the publishes waiting for them along the way
+///
+/// [1]: ConnectionTracker::subscribe
+/// [2]: ConnectionTracker::on_state_change
+pub struct ConnectionTracker {
+    state: Arc<watch::Sender<ConnectionState>>,
+    callback: RwLock<Option<StateChangeCallback>>,
+    acks: AckTracker,
+}
+
+impl ConnectionTracker {
+    pub fn state(&self) -> ConnectionState {
+        *self.state.borrow()
+    }
+
+    /// Returns a receiver notified at each state change
+    pub fn subscribe(&self) -> watch::Receiver<ConnectionState> {
+        self.state.subscribe()
+    }
+
+    /// Sets the function called with the new state at each state change
+    pub fn on_state_change<F>(&self, callback: F)
+    where
+        F: Fn(ConnectionState) + Send + Sync + 'static,
+    {
+        *self.callback.write().unwrap() = Some(Arc::new(callback));
+    }
+
+    /// Updates the state from the event, if it is a connection or disconnection event
+    pub fn handle_event(&self, event: &Event) {
+        if let Some(state) = ConnectionState::from_event(event) {
+            self.update(state);
+        }
+        self.acks.handle_event(event);
+    }
+
+    /// Returns the sender of the state, updating it directly skips the callback and the
+    /// acknowledgements tracking
+    pub(crate) fn sender(&self) -> Arc<watch::Sender<ConnectionState>> {
+        self.state.clone()
+    }
+
+    pub(crate) fn acks(&self) -> &AckTracker {
+        &self.acks
+    }
+
+    pub fn update(&self, state: ConnectionState) {
+        if state == ConnectionState::Disconnected {
+            self.acks.connection_lost();
+        }
+        if self.state.send_replace(state) != state {
+            info!("connection state: {:?}", state);
+            // the guard is released before the call so that the callback can replace itself
+            let callback = self.callback.read().unwrap().clone();
+            if let Some(callback) = callback {
+                callback(state);
+            }
+        }
+    }
+}
+
+impl Default for ConnectionTracker {
+    fn default() -> Self {
+        Self {
+            state: Arc::new(watch::channel(ConnectionState::default()).0),
+            callback: RwLock::new(None),
+            acks: AckTracker::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::transport::mqtt::connection_state::{ConnectionState, ConnectionTracker};
+    use rumqttc::v5::mqttbytes::v5::{ConnAck, ConnectReturnCode, Disconnect};
+    use rumqttc::v5::{Event, Incoming};
+    use rumqttc::Outgoing;
+    use std::sync::{Arc, Mutex};
+
+    fn conn_ack(code: ConnectReturnCode) -> Event {
+        Event::Incoming(Incoming::ConnAck(ConnAck {
+            session_present: false,
+            code,
+            properties: None,
+        }))
+    }
+
+    macro_rules! test_from_event {
+        ($test_name:ident, $event:expr, $expected:expr) => {
+            #[test]
+            fn $test_name() {
+                assert_eq!(ConnectionState::from_event(&$event), $expected);
+            }
+        };
+    }
+    test_from_event!(
+        accepted_conn_ack_connects,
+        conn_ack(ConnectReturnCode::Success),
+        Some(ConnectionState::Connected)
+    );
+    test_from_event!(
+        refused_conn_ack_disconnects,
+        conn_ack(ConnectReturnCode::NotAuthorized),
+        Some(ConnectionState::Disconnected)
+    );
+    test_from_event!(
+        broker_disconnection,
+        Event::Incoming(Incoming::Disconnect(Disconnect::new(
+            rumqttc::v5::mqttbytes::v5::DisconnectReasonCode::ServerShuttingDown
+        ))),
+        Some(ConnectionState::Disconnected)
+    );
+    test_from_event!(
+        client_disconnection,
+        Event::Outgoing(Outgoing::Disconnect),
+        Some(ConnectionState::Disconnected)
+    );
+    test_from_event!(
+        ping_does_not_change_state,
+        Event::Outgoing(Outgoing::PingReq),
+        None
+    );
+
+    #[test]
+    fn tracker_follows_connection_events() {
+        let tracker = ConnectionTracker::default();
+        assert_eq!(tracker.state(), ConnectionState::Connecting);
+
+        tracker.handle_event(&conn_ack(ConnectReturnCode::Success));
+        assert_eq!(tracker.state(), ConnectionState::Connected);
+
+        tracker.handle_event(&Event::Outgoing(Outgoing::PingReq));
+        assert_eq!(tracker.state(), ConnectionState::Connected);
+
+        tracker.handle_event(&Event::Outgoing(Outgoing::Disconnect));
+        assert_eq!(tracker.state(), ConnectionState::Disconnected);
+    }
+
+    #[test]
+    fn callback_is_called_on_changes_only() {
+        let tracker = ConnectionTracker::default();
+        let changes = Arc::new(Mutex::new(Vec::new()));
+        let recorded = changes.clone();
+        tracker.on_state_change(move |state| recorded.lock().unwrap().push(state));
+
+        tracker.update(ConnectionState::Connected);
+        tracker.update(ConnectionState::Connected);
+        tracker.update(ConnectionState::Disconnected);
+
+        assert_eq!(
+            *changes.lock().unwrap(),
+            vec![ConnectionState::Connected, ConnectionState::Disconnected]
+        );
+    }
+
+    #[test]
+    fn callback_can_replace_itself() {
+        let tracker = Arc::new(ConnectionTracker::default());
+        let changes = Arc::new(Mutex::new(Vec::new()));
+        let recorded = changes.clone();
+        let callback_tracker = Arc::downgrade(&tracker);
+        tracker.on_state_change(move |state| {
+            recorded.lock().unwrap().push(state);
+            let recorded = recorded.clone();
+            if let Some(tracker) = callback_tracker.upgrade() {
+                tracker.on_state_change(move |_| recorded.lock().unwrap().clear());
+            }
+        });
+
+        tracker.update(ConnectionState::Connected);
+        assert_eq!(*changes.lock().unwrap(), vec![ConnectionState::Connected]);
+
+        tracker.update(ConnectionState::Disconnected);
+        assert!(changes.lock().unwrap().is_empty());
+    }
+}
@@ -9,23 +9,28 @@
  * Authors: see CONTRIBUTORS.md
  */
 
+use crate::mobility::position::Position;
 use crate::mobility::quadtree::quadkey::Quadkey;
 use crate::mobility::quadtree::tile::Tile;
-use crate::transport::mqtt::topic::Topic;
-use log::{error, warn};
+use crate::transport::mqtt::topic::{topic_from_publish, ParseError, Topic};
+use rumqttc::v5::mqttbytes::v5::Publish;
+use std::any::type_name;
 use std::fmt;
 use std::fmt::{Debug, Display};
 use std::hash::{Hash, Hasher};
-use std::str::FromStr;
+use std::str::{from_utf8, FromStr};
+use tracing::{error, warn};
 
 use crate::client::configuration::geo_configuration::GeoConfiguration;
 use crate::client::configuration::Configuration;
 use crate::transport::mqtt::geo_topic::message_type::MessageType;
 use crate::transport::mqtt::geo_topic::queue::Queue;
+use crate::transport::mqtt::geo_topic::topic_template::TopicTemplate;
 use thiserror::Error;
 
 mod message_type;
 mod queue;
+pub mod topic_template;
 
 /// An error which can be returned when parsing a Topic string.
 #[derive(Error, Debug)]
@@ -36,10 +41,19 @@ pub enum GeoTopicError {
     UnknownMessageType(String),
     #[error("Cannot parse topic with invalid tile '{0}'")]
     InvalidTile(String),
+    #[error("Cannot parse topic with unexpected level '{0}'")]
+    UnexpectedLevel(String),
+    #[error("Invalid topic template '{0}'")]
+    InvalidTemplate(String),
 }
 
 /// Orange V2X platform implementation of [Topic]
 ///
+/// Topics are laid out following the [template][1] they were created or parsed with, the default
+/// layout if none
+///
+/// [1]: TopicTemplate
+///
 /// FIXME info messages does not contains the `suffix` part and it requires if/else management
 #[derive(Clone, Debug, Default)]
 pub struct GeoTopic {
@@ -49,11 +63,15 @@ pub struct GeoTopic {
     message_type: MessageType,
     uuid: String,
     pub geo_extension: Quadkey,
+    template: Option<TopicTemplate>,
 }
 
 impl GeoTopic {
-    pub fn denm(
+    /// Creates the topic on which a component publishes a message of the given type
+    /// (e.g. `cam`, `denm`)
+    pub fn new(
         configuration: &GeoConfiguration,
+        message_type: &str,
         component_name: &str,
         geo_extension: &Quadkey,
     ) -> Self {
@@ -61,21 +79,154 @@ impl GeoTopic {
             prefix: String::from(&configuration.prefix),
             queue: Queue::In,
             suffix: String::from(&configuration.suffix),
-            message_type: MessageType::DENM,
+            message_type: MessageType::from(message_type),
             uuid: component_name.to_string(),
             geo_extension: Quadkey::from(geo_extension),
+            template: configuration.topic_template.clone(),
+        }
+    }
+
+    pub fn cam(
+        configuration: &GeoConfiguration,
+        component_name: &str,
+        geo_extension: &Quadkey,
+    ) -> Self {
+        Self::new(configuration, "cam", component_name, geo_extension)
+    }
+
+    pub fn denm(
+        configuration: &GeoConfiguration,
+        component_name: &str,
+        geo_extension: &Quadkey,
+    ) -> Self {
+        Self::new(configuration, "denm", component_name, geo_extension)
+    }
+
+    /// Creates the topic on which a component announces its own [Information][1]
+    ///
+    /// [1]: crate::exchange::message::information::Information
+    pub fn info(configuration: &GeoConfiguration, component_name: &str) -> Self {
+        Self {
+            prefix: String::from(&configuration.prefix),
+            queue: Queue::In,
+            suffix: String::from(&configuration.suffix),
+            message_type: MessageType::INFO,
+            uuid: component_name.to_string(),
+            geo_extension: Quadkey::default(),
+            template: configuration.topic_template.clone(),
+        }
+    }
+
+    /// Returns the project, queue and server levels of the topic (e.g. `5GCroCo/inQueue/v2x`),
+    /// shared by the topics of a project whatever the message type
+    ///
+    /// The levels are laid out following the [template][1] if any
+    ///
+    /// [1]: TopicTemplate::project_base
+    pub fn project_base(&self) -> String {
+        match self.template.as_ref() {
+            Some(template) => template.project_base(self),
+            None => format!("{}/{}/{}", self.prefix, self.queue, self.suffix),
+        }
+    }
+
+    /// Returns the message type level of the topic (e.g. `cam`, `denm`, `info`)
+    pub fn message_type(&self) -> String {
+        self.message_type.to_string()
+    }
+
+    /// Returns the depth of the geo extension, i.e. its number of tiles
+    pub fn zoom(&self) -> usize {
+        self.geo_extension.tiles.len()
+    }
+
+    /// Returns the tile of the geo extension at the level, starting from 0, None if the geo
+    /// extension is not that deep
+    pub fn tile_at(&self, level: usize) -> Option<Tile> {
+        self.geo_extension.tiles.get(level).copied()
+    }
+
+    /// Sets the geo extension to the tile containing the position at the zoom level, e.g. the
+    /// reference position of the message published on this topic
+    ///
+    /// Zoom levels deeper than the quadkeys computed from positions (26) are capped
+    pub fn with_position(&mut self, position: &Position, zoom: u8) {
+        self.geo_extension = Quadkey::from(position).as_reduced(usize::from(zoom));
+    }
+
+    /// Returns the geo extension as a quadkey digit string (e.g. `0123`), without separators
+    pub fn quadkey_string(&self) -> String {
+        self.geo_extension
+            .tiles
+            .iter()
+            .map(|tile| tile.to_string())
+            .collect()
+    }
+
+    /// Returns the topic laid out following the [template][1]
+    ///
+    /// `None` restores the default layout, `{project}/{queue}/{suffix}/{type}/{uuid}/{geo}`
+    /// (without suffix for info topics)
+    ///
+    /// [1]: TopicTemplate
+    pub fn with_template(self, template: Option<TopicTemplate>) -> Self {
+        Self { template, ..self }
+    }
+
+    /// Parses a topic laid out following the [template][1], the topic keeps it to be formatted
+    ///
+    /// [1]: TopicTemplate
+    pub fn from_template(topic: &str, template: &TopicTemplate) -> Result<Self, GeoTopicError> {
+        template.parse(topic)
+    }
+
+    /// Extracts the topic of a [Publish] and parses it following the [template][1]
+    ///
+    /// [1]: TopicTemplate
+    pub fn from_templated_publish(
+        publish: &Publish,
+        template: &TopicTemplate,
+    ) -> Result<Self, ParseError> {
+        let str_topic = from_utf8(&publish.topic)?;
+        Self::from_template(str_topic, template)
+            .map_err(|_| ParseError::InvalidTopic(str_topic.to_string(), type_name::<Self>()))
+    }
+
+    /// Returns the topic on which the component publishes a message like the one of this topic
+    ///
+    /// The queue is set to `inQueue` and the uuid to the component name, the other levels
+    /// (including the geo extension) are kept
+    pub fn to_in_queue(&self, component_name: &str) -> Self {
+        Self {
+            queue: Queue::In,
+            uuid: component_name.to_string(),
+            ..self.clone()
+        }
+    }
+
+    /// Returns the topic on which the message published on this topic is delivered to consumers
+    ///
+    /// The queue is set to `outQueue`, the other levels (including the sender's uuid and the geo
+    /// extension) are kept
+    pub fn to_out_queue(&self) -> Self {
+        Self {
+            queue: Queue::Out,
+            ..self.clone()
         }
     }
 
     // TODO find a better way to appropriate
     pub fn appropriate(&mut self, configuration: &Configuration) {
-        self.uuid = configuration.component_name(None);
-        self.queue = Queue::In;
+        *self = self.to_in_queue(&configuration.component_name(None));
     }
 }
 
 impl Topic for GeoTopic {
     fn as_route(&self) -> String {
+        if let Some(template) = self.template.as_ref() {
+            return template.route(self);
+        }
+
         if self.message_type == MessageType::INFO {
             format!("{}/{}/{}", self.prefix, self.queue, self.message_type)
         } else {
@@ -85,6 +236,17 @@ impl Topic for GeoTopic {
             )
         }
     }
+
+    fn geo_extension(&self) -> Option<&Quadkey> {
+        Some(&self.geo_extension)
+    }
+
+    fn with_geo_extension(&self, geo_extension: Quadkey) -> Option<Self> {
+        Some(Self {
+            geo_extension,
+            ..self.clone()
+        })
+    }
 }
 
 impl Hash for GeoTopic {
@@ -205,15 +367,35 @@ impl FromStr for GeoTopic {
 
 impl Display for GeoTopic {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(template) = self.template.as_ref() {
+            return write!(f, "{}", template.format(self));
+        }
+
         let s = format!("{}/{}{}", self.as_route(), self.uuid, self.geo_extension);
         write!(f, "{}", s.trim_matches('/'))
     }
 }
 
+impl TryFrom<&Publish> for GeoTopic {
+    type Error = ParseError;
+
+    fn try_from(publish: &Publish) -> Result<Self, Self::Error> {
+        topic_from_publish(publish)
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use crate::client::configuration::geo_configuration::GeoConfiguration;
+    use crate::mobility::position::position_from_degrees;
+    use crate::mobility::quadtree::quadkey::Quadkey;
     use crate::mobility::quadtree::tile::Tile;
+    use crate::transport::mqtt::geo_topic::topic_template::TopicTemplate;
     use crate::transport::mqtt::geo_topic::GeoTopic;
+    use crate::transport::mqtt::topic::{ParseError, Topic};
+    use bytes::Bytes;
+    use rumqttc::v5::mqttbytes::v5::Publish;
+    use rumqttc::v5::mqttbytes::QoS;
     use std::str::FromStr;
 
     use crate::transport::mqtt::geo_topic::message_type::MessageType;
@@ -257,6 +439,58 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_cam_topic_to_string() {
+        let configuration = GeoConfiguration {
+            prefix: String::from("default"),
+            suffix: String::from("v2x"),
+            topic_template: None,
+        };
+
+        let topic = GeoTopic::cam(
+            &configuration,
+            "com_myapplication_1",
+            &Quadkey::from_str("0123").unwrap(),
+        );
+
+        assert_eq!(
+            topic.to_string(),
+            "default/inQueue/v2x/cam/com_myapplication_1/0/1/2/3"
+        );
+    }
+
+    #[test]
+    fn cam_topic_geo_accessors() {
+        let topic = GeoTopic::from_str("5GCroCo/outQueue/v2x/cam/car_1/0/1/2/3").unwrap();
+
+        assert_eq!(topic.zoom(), 4);
+        assert_eq!(topic.tile_at(0), Some(Tile::Zero));
+        assert_eq!(topic.tile_at(3), Some(Tile::Three));
+        assert_eq!(topic.tile_at(4), None);
+        assert_eq!(topic.quadkey_string(), "0123");
+    }
+
+    #[test]
+    fn denm_topic_geo_accessors() {
+        let topic = GeoTopic::from_str(
+            "5GCroCo/outQueue/v2x/denm/wse_app_bcn1/1/2/0/2/2/2/2/3/3/0/0/3/2/0/2/0/1/0/1/0/3/1/",
+        )
+        .unwrap();
+
+        assert_eq!(topic.zoom(), 22);
+        assert_eq!(topic.tile_at(21), Some(Tile::One));
+        assert_eq!(topic.quadkey_string(), "1202222330032020101031");
+    }
+
+    #[test]
+    fn info_topic_has_no_geo_extension() {
+        let topic = GeoTopic::from_str("5GCroCo/outQueue/info/broker").unwrap();
+
+        assert_eq!(topic.zoom(), 0);
+        assert_eq!(topic.tile_at(0), None);
+        assert!(topic.quadkey_string().is_empty());
+    }
+
     #[test]
     fn test_info_topic_from_str() {
         let topic_string = "5GCroCo/outQueue/info/broker";
@@ -274,6 +508,32 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_vam_topic_round_trip() {
+        let topic_string = "5GCroCo/outQueue/v2x/vam/vru_1/0/1/2/3";
+
+        match GeoTopic::from_str(topic_string) {
+            Ok(topic) => {
+                assert_eq!(topic.message_type, MessageType::VAM);
+                assert_eq!(topic.to_string(), topic_string);
+            }
+            Err(e) => panic!("Failed to create GeoTopic from string: {}", e),
+        }
+    }
+
+    #[test]
+    fn test_unknown_type_topic_round_trip() {
+        let topic_string = "5GCroCo/outQueue/v2x/srem/rsu_1/0/1/2/3";
+
+        match GeoTopic::from_str(topic_string) {
+            Ok(topic) => {
+                assert_eq!(topic.message_type, MessageType::Other("srem".to_string()));
+                assert_eq!(topic.to_string(), topic_string);
+            }
+            Err(e) => panic!("Failed to create GeoTopic from string: {}", e),
+        }
+    }
+
     #[test]
     fn test_in_queue_cam_topic_from_str() {
         let topic_string = "5GCroCo/inQueue/v2x/cam/car_1/0/1/2/3";
@@ -293,4 +553,119 @@ mod tests {
             Err(e) => panic!("Failed to create GeoTopic from string: {}", e),
         }
     }
+
+    #[test]
+    fn topic_from_publish() {
+        let publish = Publish::new(
+            "5GCroCo/outQueue/v2x/cam/car_1/0/1/2/3",
+            QoS::AtMostOnce,
+            "{}",
+            None,
+        );
+
+        let topic = GeoTopic::try_from(&publish).unwrap();
+
+        assert_eq!(topic.message_type, MessageType::CAM);
+        assert_eq!(topic.uuid, "car_1");
+    }
+
+    #[test]
+    fn non_utf8_topic_from_publish_is_err() {
+        let mut publish = Publish::new("", QoS::AtMostOnce, "{}", None);
+        publish.topic = Bytes::from_static(&[0xc3, 0x28]);
+
+        assert!(matches!(
+            GeoTopic::try_from(&publish),
+            Err(ParseError::InvalidUtf8(_))
+        ));
+    }
+
+    #[test]
+    fn out_queue_topic_to_in_queue() {
+        let topic = GeoTopic::from_str("5GCroCo/outQueue/v2x/cam/car_1/0/1/2/3").unwrap();
+
+        let in_topic = topic.to_in_queue("com_myapplication_1");
+
+        assert_eq!(
+            in_topic.to_string(),
+            "5GCroCo/inQueue/v2x/cam/com_myapplication_1/0/1/2/3"
+        );
+    }
+
+    #[test]
+    fn in_queue_topic_to_out_queue() {
+        let topic = GeoTopic::from_str("5GCroCo/inQueue/v2x/denm/rsu_2/1/2/0").unwrap();
+
+        let out_topic = topic.to_out_queue();
+
+        assert_eq!(
+            out_topic.to_string(),
+            "5GCroCo/outQueue/v2x/denm/rsu_2/1/2/0"
+        );
+        assert_eq!(out_topic.to_out_queue(), out_topic);
+    }
+
+    #[test]
+    fn geo_extension_is_set_from_position() {
+        let mut topic = GeoTopic::from_str("default/inQueue/v2x/cam/car_1/3/3/3").unwrap();
+
+        topic.with_position(&position_from_degrees(48.6250, 2.2412, 150.), 4);
+
+        assert_eq!(topic.quadkey_string(), "1202");
+        assert_eq!(topic.to_string(), "default/inQueue/v2x/cam/car_1/1/2/0/2");
+    }
+
+    #[test]
+    fn deep_zoom_is_capped() {
+        let mut topic = GeoTopic::from_str("default/inQueue/v2x/cam/car_1").unwrap();
+
+        topic.with_position(&position_from_degrees(48.6250, 2.2412, 150.), 30);
+
+        assert_eq!(topic.zoom(), 26);
+        assert!(topic.quadkey_string().starts_with("1202"));
+    }
+
+    #[test]
+    fn topics_follow_their_own_template() {
+        let configuration = GeoConfiguration {
+            prefix: String::from("default"),
+            suffix: String::from("v2x"),
+            topic_template: Some(
+                TopicTemplate::from_str("{project}/{type}/{queue}/{uuid}/{geo}").unwrap(),
+            ),
+        };
+        let quadkey = Quadkey::from_str("12").unwrap();
+
+        let templated = GeoTopic::cam(&configuration, "car_1", &quadkey);
+        let default = GeoTopic::cam(
+            &GeoConfiguration {
+                topic_template: None,
+                ..configuration
+            },
+            "car_1",
+            &quadkey,
+        );
+
+        assert_eq!(templated.to_string(), "default/cam/inQueue/car_1/1/2");
+        assert_eq!(templated.as_route(), "default/cam/inQueue");
+        assert_eq!(
+            templated.to_out_queue().to_string(),
+            "default/cam/outQueue/car_1/1/2"
+        );
+        assert_eq!(default.to_string(), "default/inQueue/v2x/cam/car_1/1/2");
+        assert_eq!(templated, default);
+    }
+
+    #[test]
+    fn parsed_topic_keeps_its_template() {
+        let template = TopicTemplate::from_str("{project}/{type}/{queue}/{uuid}/{geo}").unwrap();
+
+        let topic = GeoTopic::from_template("default/denm/outQueue/rsu_1/3", &template).unwrap();
+
+        assert_eq!(topic.to_string(), "default/denm/outQueue/rsu_1/3");
+        assert_eq!(
+            topic.to_in_queue("app").to_string(),
+            "default/denm/inQueue/app/3"
+        );
+    }
 }
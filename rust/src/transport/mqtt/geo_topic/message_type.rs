@@ -23,6 +23,12 @@ pub(crate) enum MessageType {
     INFO,
     MAP,
     SPAT,
+    MAPEM,
+    SPATEM,
+    IVIM,
+    VAM,
+    /// Any other message type, kept as is so that the topic round-trips
+    Other(String),
 }
 
 impl fmt::Display for MessageType {
@@ -38,6 +44,11 @@ impl fmt::Display for MessageType {
                 MessageType::INFO => "info".to_string(),
                 MessageType::MAP => "map".to_string(),
                 MessageType::SPAT => "spat".to_string(),
+                MessageType::MAPEM => "mapem".to_string(),
+                MessageType::SPATEM => "spatem".to_string(),
+                MessageType::IVIM => "ivim".to_string(),
+                MessageType::VAM => "vam".to_string(),
+                MessageType::Other(message_type) => message_type.clone(),
             }
         )
     }
@@ -59,10 +70,11 @@ impl From<&str> for MessageType {
             "info" => MessageType::INFO,
             "map" => MessageType::MAP,
             "spat" => MessageType::SPAT,
-            element => panic!(
-                "Unable to convert from the element {} as a MessageType, use from_str instead",
-                element
-            ),
+            "mapem" => MessageType::MAPEM,
+            "spatem" => MessageType::SPATEM,
+            "ivim" => MessageType::IVIM,
+            "vam" => MessageType::VAM,
+            element => MessageType::Other(element.to_string()),
         }
     }
 }
@@ -83,9 +95,49 @@ impl str::FromStr for MessageType {
     type Err = GeoTopicError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "+" | "cam" | "denm" | "cpm" | "info" | "map" | "spat" => Ok(MessageType::from(s)),
-            element => Err(GeoTopicError::UnknownMessageType(element.to_string())),
+        if s.is_empty() || s.contains(['/', '#']) {
+            Err(GeoTopicError::UnknownMessageType(s.to_string()))
+        } else {
+            Ok(MessageType::from(s))
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::transport::mqtt::geo_topic::message_type::MessageType;
+    use std::str::FromStr;
+
+    macro_rules! test_message_type_round_trip {
+        ($test_name:ident, $s:expr, $expected:expr) => {
+            #[test]
+            fn $test_name() {
+                let message_type = MessageType::from_str($s).expect("Failed to parse message type");
+
+                assert_eq!(message_type, $expected);
+                assert_eq!(message_type.to_string(), $s);
+            }
+        };
+    }
+    test_message_type_round_trip!(any_round_trip, "+", MessageType::Any);
+    test_message_type_round_trip!(cam_round_trip, "cam", MessageType::CAM);
+    test_message_type_round_trip!(mapem_round_trip, "mapem", MessageType::MAPEM);
+    test_message_type_round_trip!(spatem_round_trip, "spatem", MessageType::SPATEM);
+    test_message_type_round_trip!(ivim_round_trip, "ivim", MessageType::IVIM);
+    test_message_type_round_trip!(vam_round_trip, "vam", MessageType::VAM);
+    test_message_type_round_trip!(
+        unknown_type_round_trip,
+        "srem",
+        MessageType::Other("srem".to_string())
+    );
+
+    #[test]
+    fn empty_message_type_is_err() {
+        assert!(MessageType::from_str("").is_err());
+    }
+
+    #[test]
+    fn wildcard_in_message_type_is_err() {
+        assert!(MessageType::from_str("#").is_err());
+    }
+}
@@ -0,0 +1,319 @@
+/*
+ * Software Name : libits-client
+ * SPDX-FileCopyrightText: Copyright (c) Orange SA
+ * SPDX-License-Identifier: MIT
+ *
+ * This software is distributed under the MIT license,
+ * see the "LICENSE.txt" file for more details or https://opensource.org/license/MIT/
+ *
+ * Authors: see CONTRIBUTORS.md
+ */
+
+use crate::mobility::quadtree::tile::Tile;
+use crate::transport::mqtt::geo_topic::message_type::MessageType;
+use crate::transport::mqtt::geo_topic::queue::Queue;
+use crate::transport::mqtt::geo_topic::{GeoTopic, GeoTopicError};
+use std::fmt;
+use std::str::FromStr;
+
+/// Layout of the [GeoTopic] levels
+///
+/// A template is a `/` separated list of levels, each being either a placeholder or a literal
+/// the topic level must be equal to:
+/// - `{project}`: the topic prefix
+/// - `{queue}`: `inQueue` or `outQueue`
+/// - `{suffix}`: the topic suffix
+/// - `{type}`: the message type
+/// - `{uuid}`: the sender's component name
+/// - `{geo}`: the quadkey, one level per tile; it can only be the last level
+///
+/// The default layout is `{project}/{queue}/{suffix}/{type}/{uuid}/{geo}`, an equivalent
+/// template with a fixed suffix would be `{project}/{queue}/v2x/{type}/{uuid}/{geo}`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TopicTemplate {
+    levels: Vec<Level>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Level {
+    Literal(String),
+    Project,
+    Queue,
+    Suffix,
+    Type,
+    Uuid,
+    Geo,
+}
+
+impl TopicTemplate {
+    /// Parses a topic following this template, the topic keeps the template to be formatted
+    ///
+    /// Topics can stop before the end of the template (e.g. routes without uuid nor quadkey)
+    pub fn parse(&self, topic: &str) -> Result<GeoTopic, GeoTopicError> {
+        let mut geo_topic = GeoTopic::default().with_template(Some(self.clone()));
+        let mut elements = topic.trim_matches('/').split('/');
+
+        for level in &self.levels {
+            if level == &Level::Geo {
+                for element in elements.by_ref() {
+                    match Tile::from_str(element) {
+                        Ok(tile) => geo_topic.geo_extension.push(tile),
+                        Err(_) => return Err(GeoTopicError::InvalidTile(element.to_string())),
+                    }
+                }
+                break;
+            }
+
+            let Some(element) = elements.next() else {
+                break;
+            };
+            match level {
+                Level::Literal(literal) if literal != element => {
+                    return Err(GeoTopicError::UnexpectedLevel(element.to_string()))
+                }
+                Level::Literal(_) => (),
+                Level::Project => geo_topic.prefix = element.to_string(),
+                Level::Queue => geo_topic.queue = Queue::from_str(element)?,
+                Level::Suffix => geo_topic.suffix = element.to_string(),
+                Level::Type => geo_topic.message_type = MessageType::from_str(element)?,
+                Level::Uuid => geo_topic.uuid = element.to_string(),
+                Level::Geo => unreachable!(),
+            }
+        }
+
+        match elements.next() {
+            Some(element) => Err(GeoTopicError::UnexpectedLevel(element.to_string())),
+            None => Ok(geo_topic),
+        }
+    }
+
+    /// Formats the topic following this template, empty levels are omitted
+    pub fn format(&self, topic: &GeoTopic) -> String {
+        self.format_levels(topic, self.levels.len())
+    }
+
+    /// Formats the topic levels before the uuid, to be used as [route][1]
+    ///
+    /// [1]: crate::transport::mqtt::topic::Topic::as_route
+    pub fn route(&self, topic: &GeoTopic) -> String {
+        let route_length = self
+            .levels
+            .iter()
+            .position(|level| matches!(level, Level::Uuid | Level::Geo))
+            .unwrap_or(self.levels.len());
+        self.format_levels(topic, route_length)
+    }
+
+    /// Formats the topic levels shared by the topics of a project whatever the message type,
+    /// i.e. all but the type, uuid and geo ones
+    pub fn project_base(&self, topic: &GeoTopic) -> String {
+        self.levels
+            .iter()
+            .filter(|level| !matches!(level, Level::Type | Level::Uuid | Level::Geo))
+            .map(|level| level.format(topic))
+            .filter(|element| !element.is_empty())
+            .collect::<Vec<String>>()
+            .join("/")
+    }
+
+    /// Returns the topic filter subscribing to the messages laid out following this template,
+    /// whose uuid and geo levels match the `uuid` and `geo` filters (e.g. `+`, `1/2/#`), the
+    /// other levels being the topic's ones; empty levels are omitted
+    pub fn subscription(&self, topic: &GeoTopic, uuid: &str, geo: &str) -> String {
+        self.levels
+            .iter()
+            .map(|level| match level {
+                Level::Uuid => uuid.to_string(),
+                Level::Geo => geo.to_string(),
+                level => level.format(topic),
+            })
+            .filter(|element| !element.is_empty())
+            .collect::<Vec<String>>()
+            .join("/")
+    }
+
+    fn format_levels(&self, topic: &GeoTopic, length: usize) -> String {
+        self.levels
+            .iter()
+            .take(length)
+            .map(|level| level.format(topic))
+            .filter(|element| !element.is_empty())
+            .collect::<Vec<String>>()
+            .join("/")
+    }
+}
+
+impl Level {
+    fn format(&self, topic: &GeoTopic) -> String {
+        match self {
+            Level::Literal(literal) => literal.clone(),
+            Level::Project => topic.prefix.clone(),
+            Level::Queue => topic.queue.to_string(),
+            Level::Suffix => topic.suffix.clone(),
+            Level::Type => topic.message_type.to_string(),
+            Level::Uuid => topic.uuid.clone(),
+            Level::Geo => topic
+                .geo_extension
+                .to_string()
+                .trim_matches('/')
+                .to_string(),
+        }
+    }
+}
+
+impl FromStr for TopicTemplate {
+    type Err = GeoTopicError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let levels = s
+            .trim_matches('/')
+            .split('/')
+            .map(|element| match element {
+                "{project}" => Ok(Level::Project),
+                "{queue}" => Ok(Level::Queue),
+                "{suffix}" => Ok(Level::Suffix),
+                "{type}" => Ok(Level::Type),
+                "{uuid}" => Ok(Level::Uuid),
+                "{geo}" => Ok(Level::Geo),
+                placeholder
+                    if placeholder.contains(['{', '}', '+', '#']) || placeholder.is_empty() =>
+                {
+                    Err(GeoTopicError::InvalidTemplate(s.to_string()))
+                }
+                literal => Ok(Level::Literal(literal.to_string())),
+            })
+            .collect::<Result<Vec<Level>, GeoTopicError>>()?;
+
+        match levels.iter().position(|level| level == &Level::Geo) {
+            Some(position) if position != levels.len() - 1 => {
+                Err(GeoTopicError::InvalidTemplate(s.to_string()))
+            }
+            _ => Ok(Self { levels }),
+        }
+    }
+}
+
+impl fmt::Display for TopicTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let levels = self
+            .levels
+            .iter()
+            .map(|level| match level {
+                Level::Literal(literal) => literal.as_str(),
+                Level::Project => "{project}",
+                Level::Queue => "{queue}",
+                Level::Suffix => "{suffix}",
+                Level::Type => "{type}",
+                Level::Uuid => "{uuid}",
+                Level::Geo => "{geo}",
+            })
+            .collect::<Vec<&str>>();
+        write!(f, "{}", levels.join("/"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mobility::quadtree::quadkey::Quadkey;
+    use crate::transport::mqtt::geo_topic::message_type::MessageType;
+    use crate::transport::mqtt::geo_topic::queue::Queue;
+    use crate::transport::mqtt::geo_topic::topic_template::TopicTemplate;
+    use std::str::FromStr;
+
+    #[test]
+    fn partner_layout_round_trip() {
+        let template = TopicTemplate::from_str("{project}/{type}/{queue}/{uuid}/{geo}").unwrap();
+        let topic_string = "project/cpm/outQueue/rsu_1/1/2/0/2";
+
+        let topic = template.parse(topic_string).unwrap();
+
+        assert_eq!(topic.prefix, "project");
+        assert_eq!(topic.message_type, MessageType::CPM);
+        assert_eq!(topic.queue, Queue::Out);
+        assert_eq!(topic.uuid, "rsu_1");
+        assert_eq!(topic.geo_extension, Quadkey::from_str("1202").unwrap());
+        assert_eq!(template.format(&topic), topic_string);
+        assert_eq!(template.route(&topic), "project/cpm/outQueue");
+    }
+
+    #[test]
+    fn literal_levels_are_matched() {
+        let template =
+            TopicTemplate::from_str("{project}/{queue}/v2x/{type}/{uuid}/{geo}").unwrap();
+
+        let topic = template
+            .parse("5GCroCo/outQueue/v2x/cam/car_1/0/1")
+            .unwrap();
+        assert_eq!(topic.message_type, MessageType::CAM);
+        assert!(topic.suffix.is_empty());
+        assert_eq!(
+            template.format(&topic),
+            "5GCroCo/outQueue/v2x/cam/car_1/0/1"
+        );
+
+        assert!(template
+            .parse("5GCroCo/outQueue/its/cam/car_1/0/1")
+            .is_err());
+    }
+
+    #[test]
+    fn subscription_follows_the_template() {
+        let template = TopicTemplate::from_str("{project}/{type}/{queue}/{uuid}/{geo}").unwrap();
+        let route = template.parse("project/cam/outQueue").unwrap();
+
+        assert_eq!(
+            template.subscription(&route, "+", "1/2/#"),
+            "project/cam/outQueue/+/1/2/#"
+        );
+        assert_eq!(
+            template.subscription(&route, "broker", ""),
+            "project/cam/outQueue/broker"
+        );
+    }
+
+    #[test]
+    fn project_base_skips_type_uuid_and_geo() {
+        let template =
+            TopicTemplate::from_str("{project}/{type}/{queue}/v2x/{uuid}/{geo}").unwrap();
+        let topic = template.parse("project/cam/inQueue/v2x/car_1/1/2").unwrap();
+
+        assert_eq!(template.project_base(&topic), "project/inQueue/v2x");
+    }
+
+    #[test]
+    fn route_can_be_parsed() {
+        let template = TopicTemplate::from_str("{project}/{type}/{queue}/{uuid}/{geo}").unwrap();
+
+        let topic = template.parse("project/denm/inQueue").unwrap();
+
+        assert_eq!(topic.message_type, MessageType::DENM);
+        assert_eq!(topic.queue, Queue::In);
+        assert!(topic.uuid.is_empty());
+    }
+
+    #[test]
+    fn extra_levels_are_err() {
+        let template = TopicTemplate::from_str("{project}/{queue}/{type}/{uuid}").unwrap();
+
+        assert!(template.parse("project/outQueue/cam/car_1/0/1").is_err());
+    }
+
+    #[test]
+    fn geo_must_be_last() {
+        assert!(TopicTemplate::from_str("{project}/{geo}/{type}").is_err());
+    }
+
+    #[test]
+    fn unknown_placeholder_is_err() {
+        assert!(TopicTemplate::from_str("{project}/{queue}/{kind}").is_err());
+    }
+
+    #[test]
+    fn template_display() {
+        let template_string = "{project}/{queue}/v2x/{type}/{uuid}/{geo}";
+
+        let template = TopicTemplate::from_str(template_string).unwrap();
+
+        assert_eq!(template.to_string(), template_string);
+    }
+}
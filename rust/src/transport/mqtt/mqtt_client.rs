@@ -9,15 +9,36 @@
  * Authors: see CONTRIBUTORS.md
  */
 
+use crate::transport::compression::{CONTENT_ENCODING, GZIP};
+use crate::transport::mqtt::connection_state::{ConnectionState, ConnectionTracker};
+use crate::transport::mqtt::mqtt_error::{MqttError, PublishError};
+use crate::transport::mqtt::publish_ack::AckSender;
+use crate::transport::mqtt::resend_queue::ResendQueue;
 use crate::transport::mqtt::topic::Topic;
 use crate::transport::packet::Packet;
 use crate::transport::payload::Payload;
+use crate::transport::pipe::PipeSender;
+use crate::transport::serialization::Serialization;
 
-use crossbeam_channel::Sender;
-use log::{debug, error, info, trace, warn};
-use rumqttc::v5::mqttbytes::v5::Filter;
-use rumqttc::v5::mqttbytes::QoS;
+use rumqttc::v5::mqttbytes::v5::{Filter, PublishProperties};
+use rumqttc::v5::mqttbytes::{valid_filter, valid_topic, QoS};
 use rumqttc::v5::{AsyncClient, Event, EventLoop, MqttOptions};
+use rumqttc::Outgoing;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use tokio::sync::{oneshot, watch};
+use tokio_util::sync::{CancellationToken, DropGuard};
+use tracing::{debug, error, info, trace, warn};
+
+#[cfg(feature = "geo_routing")]
+use {
+    crate::client::configuration::geo_configuration::GeoConfiguration,
+    crate::clock::{Clock, SystemClock},
+    crate::exchange::message::Message,
+    crate::exchange::Exchange,
+    crate::mobility::quadtree::quadkey::Quadkey,
+    crate::transport::mqtt::geo_topic::GeoTopic,
+};
 
 #[cfg(feature = "telemetry")]
 use {
@@ -28,18 +49,175 @@ use {
     opentelemetry_sdk::propagation::TraceContextPropagator,
 };
 
+/// Delay before the event loop is polled again after a connection error
+const RECONNECTION_DELAY: Duration = Duration::from_secs(1);
+
 pub struct MqttClient {
+    compress_payloads: bool,
+    serialization: Serialization,
+    #[cfg(feature = "geo_routing")]
+    clock: Arc<dyn Clock>,
+    outbox: Arc<Outbox>,
+    /// Stops the task resending the queued publishes on reconnection once the client is dropped
+    resender: OnceLock<DropGuard>,
+}
+
+/// Sending side of the client, shared with the task resending the queued publishes
+struct Outbox {
     client: AsyncClient,
+    connection: Arc<ConnectionTracker>,
+    resend_queue: Mutex<ResendQueue<PreparedPublish>>,
+    /// Keeps the publishes in the same order in the event loop and in the acknowledgement tracking
+    send_lock: tokio::sync::Mutex<()>,
+}
+
+/// Publish checked and serialized, ready to be queued to the event loop
+#[derive(Clone)]
+struct PreparedPublish {
+    topic: String,
+    retain: bool,
+    qos: QoS,
+    payload: Vec<u8>,
+    properties: PublishProperties,
 }
 
 impl MqttClient {
     pub fn new(options: &MqttOptions) -> (Self, EventLoop) {
         let (client, event_loop) = AsyncClient::new(options.clone(), 1000);
-        (MqttClient { client }, event_loop)
+        (Self::from_client(client), event_loop)
+    }
+
+    pub(crate) fn from_client(client: AsyncClient) -> Self {
+        MqttClient {
+            compress_payloads: false,
+            serialization: Serialization::default(),
+            #[cfg(feature = "geo_routing")]
+            clock: Arc::new(SystemClock),
+            outbox: Arc::new(Outbox {
+                client,
+                connection: Arc::default(),
+                resend_queue: Mutex::default(),
+                send_lock: tokio::sync::Mutex::default(),
+            }),
+            resender: OnceLock::new(),
+        }
+    }
+
+    /// Enables or disables the gzip compression of the published payloads
+    ///
+    /// Compressed payloads are flagged with a `content-encoding: gzip` user property
+    pub fn set_payload_compression(&mut self, enabled: bool) {
+        self.compress_payloads = enabled;
+    }
+
+    /// Sets the encoding of the published payloads, JSON by default
+    ///
+    /// Binary encoded payloads are flagged with their MQTTv5 content type, and must only be
+    /// published to stations able to decode them
+    pub fn set_serialization(&mut self, serialization: Serialization) {
+        self.serialization = serialization;
+    }
+
+    /// Sets the clock timestamping the [published messages][1], the system time by default
+    ///
+    /// [1]: MqttClient::publish_message
+    #[cfg(feature = "geo_routing")]
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+
+    /// Keeps up to `capacity` QoS 1 and 2 publishes made while disconnected, to resend them once
+    /// the connection is restored; 0, the default, disables the queue
+    ///
+    /// Queued publishes are resent, in order, as soon as the broker acknowledges the connection
+    /// again, which requires the [event loop to be listened to][1]; they are otherwise resent
+    /// before the next publish made once connected or by [resend_pending][2]. Publishes already
+    /// handed to the event loop when the connection drops are resent by the event loop itself on
+    /// reconnection if the session is resumed (see the `clean_session` option)
+    ///
+    /// Queued publishes are dropped once the event loop is dropped, as they cannot be sent anymore
+    ///
+    /// [1]: listen
+    /// [2]: MqttClient::resend_pending
+    pub fn set_resend_queue_capacity(&mut self, capacity: usize) {
+        self.outbox
+            .resend_queue
+            .lock()
+            .unwrap()
+            .set_capacity(capacity);
+    }
+
+    /// Returns the number of publishes waiting for the connection to be restored
+    pub fn pending_publishes(&self) -> usize {
+        self.outbox.resend_queue.lock().unwrap().len()
+    }
+
+    /// Resends the publishes queued while disconnected, returns how many were resent
+    ///
+    /// Does nothing while the client is not connected
+    pub async fn resend_pending(&self) -> Result<usize, MqttError> {
+        self.outbox.resend_pending().await
+    }
+
+    /// Returns the current state of the link with the broker
+    pub fn connection_state(&self) -> ConnectionState {
+        self.outbox.connection.state()
+    }
+
+    /// Returns true if the broker acknowledged the connection and it has not been lost since
+    pub fn is_connected(&self) -> bool {
+        self.connection_state() == ConnectionState::Connected
     }
 
-    pub async fn subscribe(&mut self, topic_list: &[String]) {
-        match self
+    /// Returns a receiver notified at each change of the [connection state][1]
+    ///
+    /// [1]: MqttClient::connection_state
+    pub fn watch_connection_state(&self) -> watch::Receiver<ConnectionState> {
+        self.outbox.connection.subscribe()
+    }
+
+    /// Sets the function called with the new [connection state][1] at each change
+    ///
+    /// [1]: MqttClient::connection_state
+    pub fn on_state_change<F>(&self, callback: F)
+    where
+        F: Fn(ConnectionState) + Send + Sync + 'static,
+    {
+        self.outbox.connection.on_state_change(callback);
+    }
+
+    /// Returns the tracker to give to [listen] so that it updates the [connection state][1]
+    ///
+    /// [1]: MqttClient::connection_state
+    pub fn connection_tracker(&self) -> Arc<ConnectionTracker> {
+        self.outbox.connection.clone()
+    }
+
+    /// Returns the sender of the [connection state][1]
+    ///
+    /// Sending a state through it does not call the [state change callback][2] nor fails the
+    /// publishes waiting for an acknowledgement on disconnection
+    ///
+    /// [1]: MqttClient::connection_state
+    /// [2]: MqttClient::on_state_change
+    #[deprecated(note = "use connection_tracker, whose update also runs the callback")]
+    pub fn connection_state_sender(&self) -> Arc<watch::Sender<ConnectionState>> {
+        self.outbox.connection.sender()
+    }
+
+    /// Sends the subscriptions to the event loop
+    ///
+    /// Fails with [MqttError::SendFailed] if a topic is not a valid filter,
+    /// or with [MqttError::ClientClosed] if the event loop has been dropped
+    pub async fn subscribe(&mut self, topic_list: &[String]) -> Result<(), MqttError> {
+        if let Some(invalid) = topic_list.iter().find(|topic| !valid_filter(topic)) {
+            return Err(MqttError::SendFailed(format!(
+                "subscription to invalid filter '{}'",
+                invalid
+            )));
+        }
+
+        self.outbox
             .client
             .subscribe_many(
                 topic_list
@@ -48,24 +226,116 @@ impl MqttClient {
                     .collect::<Vec<Filter>>(),
             )
             .await
-        {
-            Ok(()) => debug!("sent subscriptions"),
-            Err(e) => error!(
-                "failed to send subscriptions, is the connection close? \nError: {:?}",
-                e
-            ),
-        };
+            .map_err(|_| MqttError::ClientClosed)?;
+        debug!("sent subscriptions");
+        Ok(())
+    }
+
+    pub async fn publish<T: Topic, P: Payload>(
+        &self,
+        packet: Packet<T, P>,
+    ) -> Result<(), MqttError> {
+        let publish = self.prepare(packet)?;
+        self.send(publish).await
+    }
+
+    /// Publishes the packet and waits for the broker to acknowledge it, with a PubAck for QoS 1
+    /// or a PubComp for QoS 2, at most `timeout`
+    ///
+    /// QoS 0 packets are considered acknowledged once sent. The packet skips the [resend
+    /// queue][1]: a publish made while disconnected is sent, and acknowledged, once the
+    /// event loop reconnects if that happens within `timeout`
+    ///
+    /// The acknowledgement is only received while the [event loop is listened to][2]; the publish
+    /// fails with [PublishError::ConnectionLost] if the connection, or the session, is lost while
+    /// it is in flight
+    ///
+    /// [1]: MqttClient::set_resend_queue_capacity
+    /// [2]: listen
+    pub async fn publish_with_ack<T: Topic, P: Payload>(
+        &self,
+        packet: Packet<T, P>,
+        timeout: Duration,
+    ) -> Result<(), PublishError> {
+        let publish = self.prepare(packet)?;
+        let (sender, receiver) = oneshot::channel();
+        self.outbox
+            .queue_to_event_loop(publish, Some(sender))
+            .await?;
+
+        match tokio::time::timeout(timeout, receiver).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(_)) => Err(PublishError::Abandoned),
+            Err(_) => {
+                self.outbox.connection.acks().forget_closed();
+                Err(PublishError::Timeout(timeout))
+            }
+        }
+    }
+
+    /// Publishes a message wrapped into an [Exchange][1] from the component
+    ///
+    /// The topic is built from the message's type, the component name and the quadkey, so that
+    /// the message type level always matches the payload; trace context is injected as for any
+    /// other [publish][2]
+    ///
+    /// [1]: crate::exchange::Exchange
+    /// [2]: MqttClient::publish
+    #[cfg(feature = "geo_routing")]
+    pub async fn publish_message<M: Into<Message>>(
+        &self,
+        message: M,
+        configuration: &GeoConfiguration,
+        component_name: &str,
+        geo_extension: &Quadkey,
+    ) -> Result<(), MqttError> {
+        let exchange = Exchange::new(
+            component_name.to_string(),
+            self.clock.now_millis(),
+            Vec::new(),
+            message.into(),
+        );
+        let topic = GeoTopic::new(
+            configuration,
+            &exchange.type_field,
+            component_name,
+            geo_extension,
+        );
+        self.publish(Packet::new(topic, *exchange)).await
+    }
+
+    /// Queues all the packets to the event loop at once
+    ///
+    /// Every packet is checked and serialized before the first one is queued, so that an invalid
+    /// packet fails the whole batch instead of leaving it partially published
+    pub async fn publish_batch<T: Topic, P: Payload>(
+        &self,
+        packets: Vec<Packet<T, P>>,
+    ) -> Result<(), MqttError> {
+        debug!("Publish batch of {} packets", packets.len());
+        let publishes = packets
+            .into_iter()
+            .map(|packet| self.prepare(packet))
+            .collect::<Result<Vec<PreparedPublish>, MqttError>>()?;
+
+        for publish in publishes {
+            self.send(publish).await?;
+        }
+        Ok(())
     }
 
     #[cfg(feature = "telemetry")]
-    pub async fn publish<T: Topic, P: Payload>(&self, mut packet: Packet<T, P>) {
+    fn prepare<T: Topic, P: Payload>(
+        &self,
+        mut packet: Packet<T, P>,
+    ) -> Result<PreparedPublish, MqttError> {
         debug!("Publish with context");
-        let payload = serde_json::to_string(&packet.payload).unwrap();
+        let payload = serde_json::to_string(&packet.payload)?;
 
         let span = get_mqtt_span(
             SpanKind::Producer,
             &packet.topic.to_string(),
-            payload.as_bytes().len() as i64,
+            payload.len() as i64,
         );
 
         let cx = Context::current().with_span(span);
@@ -74,57 +344,756 @@ impl MqttClient {
         let propagator = TraceContextPropagator::new();
         propagator.inject(&mut packet);
 
-        self.do_publish(packet).await
+        self.do_prepare(packet)
     }
 
     #[cfg(not(feature = "telemetry"))]
-    pub async fn publish<T: Topic, P: Payload>(&self, packet: Packet<T, P>) {
+    fn prepare<T: Topic, P: Payload>(
+        &self,
+        packet: Packet<T, P>,
+    ) -> Result<PreparedPublish, MqttError> {
         debug!("Publish without context");
-        self.do_publish(packet).await
+        self.do_prepare(packet)
+    }
+
+    fn do_prepare<T: Topic, P: Payload>(
+        &self,
+        mut packet: Packet<T, P>,
+    ) -> Result<PreparedPublish, MqttError> {
+        let (topic, payload) = packet.to_publish(self.serialization, self.compress_payloads)?;
+        if !valid_topic(&topic) {
+            return Err(MqttError::SendFailed(format!(
+                "publish on invalid topic '{}'",
+                topic
+            )));
+        }
+
+        if self.serialization != Serialization::Json {
+            packet.properties.content_type = Some(self.serialization.content_type().to_string());
+        }
+        if self.compress_payloads {
+            packet
+                .properties
+                .user_properties
+                .push((CONTENT_ENCODING.to_string(), GZIP.to_string()));
+        }
+
+        Ok(PreparedPublish {
+            topic,
+            retain: packet.retain,
+            qos: packet.qos,
+            payload,
+            properties: packet.properties,
+        })
     }
 
-    async fn do_publish<T: Topic, P: Payload>(&self, packet: Packet<T, P>) {
-        let payload = serde_json::to_string(&packet.payload).unwrap();
+    async fn send(&self, publish: PreparedPublish) -> Result<(), MqttError> {
+        let resendable =
+            publish.qos != QoS::AtMostOnce && self.outbox.resend_queue.lock().unwrap().is_enabled();
+        if !resendable {
+            return self.outbox.queue_to_event_loop(publish, None).await;
+        }
 
-        match self
+        if self.connection_state() == ConnectionState::Disconnected {
+            debug!("disconnected, publish queued for resending");
+            self.outbox.resend_queue.lock().unwrap().push(publish);
+            self.watch_reconnections();
+            return Ok(());
+        }
+        self.outbox.resend_pending().await?;
+        self.outbox.queue_to_event_loop(publish, None).await
+    }
+
+    /// Spawns, once, the task resending the queued publishes each time the connection is restored
+    fn watch_reconnections(&self) {
+        self.resender.get_or_init(|| {
+            let outbox = self.outbox.clone();
+            let stop = CancellationToken::new();
+            let stopped = stop.clone();
+            tokio::spawn(async move {
+                let mut state = outbox.connection.subscribe();
+                loop {
+                    let connected = *state.borrow_and_update() == ConnectionState::Connected;
+                    if connected {
+                        if let Err(e) = outbox.resend_pending().await {
+                            warn!("Failed to resend pending publishes: {}", e);
+                            break;
+                        }
+                    }
+                    tokio::select! {
+                        _ = stopped.cancelled() => break,
+                        changed = state.changed() => if changed.is_err() {
+                            break;
+                        },
+                    }
+                }
+                trace!("resending task done");
+            });
+            stop.drop_guard()
+        });
+    }
+
+    /// Queues a disconnection, sent after every publish already handed to the client
+    pub async fn disconnect(&self) -> Result<(), MqttError> {
+        self.outbox
+            .client
+            .disconnect()
+            .await
+            .map_err(|_| MqttError::ClientClosed)?;
+        debug!("sent disconnection");
+        Ok(())
+    }
+}
+
+impl Outbox {
+    async fn resend_pending(&self) -> Result<usize, MqttError> {
+        let mut resent = 0;
+        while self.connection.state() == ConnectionState::Connected {
+            let Some(publish) = self.resend_queue.lock().unwrap().pop() else {
+                break;
+            };
+            if let Err(e) = self.queue_to_event_loop(publish, None).await {
+                let dropped = self.resend_queue.lock().unwrap().clear() + 1;
+                warn!("{} pending publishes dropped: {}", dropped, e);
+                return Err(e);
+            }
+            resent += 1;
+        }
+        if resent > 0 {
+            info!("{} pending publishes resent", resent);
+        }
+        Ok(resent)
+    }
+
+    async fn queue_to_event_loop(
+        &self,
+        publish: PreparedPublish,
+        waiter: Option<AckSender>,
+    ) -> Result<(), MqttError> {
+        let _order = self.send_lock.lock().await;
+        let acks = self.connection.acks();
+        acks.queued(waiter);
+        if self
             .client
             .publish_with_properties(
-                packet.topic.to_string(),
-                QoS::ExactlyOnce,
-                false,
-                payload,
-                packet.properties,
+                publish.topic,
+                publish.qos,
+                publish.retain,
+                publish.payload,
+                publish.properties,
             )
             .await
+            .is_err()
         {
-            Ok(()) => {
-                trace!("sent publish");
-            }
-            Err(e) => error!(
-                "Failed to send publish, is the connection close? \nError: {:?}",
-                e
-            ),
+            acks.unqueue_last();
+            return Err(MqttError::ClientClosed);
         }
+        trace!("sent publish");
+        Ok(())
     }
 }
 
-pub async fn listen(mut event_loop: EventLoop, sender: Sender<Event>) {
+/// Polls the event loop and forwards the received events to the sender
+///
+/// Connection errors do not stop the listening: the event loop is polled again after a short
+/// delay, which makes it reconnect
+///
+/// Once the shutdown token is cancelled, received events are no longer forwarded but the event
+/// loop keeps being polled so that pending publishes are flushed, until the client
+/// [disconnects][1] or the connection fails
+///
+/// The [connection state][2] is updated from the connection acknowledgements and disconnections
+///
+/// [1]: MqttClient::disconnect
+/// [2]: MqttClient::connection_tracker
+pub async fn listen(
+    mut event_loop: EventLoop,
+    sender: PipeSender<Event>,
+    shutdown: CancellationToken,
+    connection: Arc<ConnectionTracker>,
+) {
     info!("listening started");
-    let mut listening = true;
-    while listening {
-        match event_loop.poll().await {
-            Ok(event) => match sender.send(event) {
-                Ok(()) => trace!("item sent"),
+    let mut sender = Some(sender);
+    loop {
+        tokio::select! {
+            _ = shutdown.cancelled(), if sender.is_some() => {
+                info!("shutdown requested, listening stops forwarding events");
+                sender = None;
+            }
+            polled = event_loop.poll() => match polled {
+                Ok(Event::Outgoing(Outgoing::Disconnect)) => {
+                    info!("disconnection sent");
+                    connection.update(ConnectionState::Disconnected);
+                    break;
+                }
+                Ok(event) => {
+                    connection.handle_event(&event);
+                    if let Some(forward) = &sender {
+                        match forward.send(event) {
+                            Ok(()) => trace!("item sent"),
+                            Err(error) => {
+                                error!("stopped to send item: {}", error);
+                                break;
+                            }
+                        }
+                    }
+                }
                 Err(error) => {
-                    error!("stopped to send item: {}", error);
-                    listening = false;
+                    connection.update(ConnectionState::Disconnected);
+                    if sender.is_none() {
+                        error!("stopped to receive event: {:?}", error);
+                        break;
+                    }
+                    warn!(
+                        "connection error, reconnecting in {:?}: {:?}",
+                        RECONNECTION_DELAY, error
+                    );
+                    tokio::select! {
+                        _ = shutdown.cancelled() => {}
+                        _ = tokio::time::sleep(RECONNECTION_DELAY) => {}
+                    }
                 }
             },
-            Err(error) => {
-                error!("stopped to receive event: {:?}", error);
-                listening = false;
-            }
         }
     }
     warn!("listening done");
 }
+
+#[cfg(all(test, feature = "geo_routing"))]
+mod tests {
+    use crate::client::configuration::geo_configuration::GeoConfiguration;
+    use crate::clock::MockClock;
+    use crate::exchange::etsi::decentralized_environmental_notification_message::DecentralizedEnvironmentalNotificationMessage;
+    use crate::exchange::etsi::reference_position::ReferencePosition;
+    use crate::exchange::message::information::Information;
+    use crate::exchange::message::Message;
+    use crate::exchange::Exchange;
+    use crate::mobility::quadtree::quadkey::Quadkey;
+    use crate::now;
+    use crate::transport::compression::gunzip;
+    use crate::transport::mqtt::connection_state::ConnectionState;
+    use crate::transport::mqtt::geo_topic::GeoTopic;
+    use crate::transport::mqtt::mqtt_client::{listen, MqttClient};
+    use crate::transport::mqtt::mqtt_error::{MqttError, PublishError};
+    use crate::transport::packet::Packet;
+    use crate::transport::pipe::{pipe, OverflowPolicy};
+    use crate::transport::serialization::Serialization;
+    use rumqttc::v5::mqttbytes::v5::{
+        ConnAck, ConnectReturnCode, Disconnect, DisconnectReasonCode, PubAck,
+    };
+    use rumqttc::v5::mqttbytes::QoS;
+    use rumqttc::v5::{AsyncClient, Event, Incoming, MqttOptions, Request};
+    use rumqttc::Outgoing;
+    use std::str::FromStr;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio_util::sync::CancellationToken;
+
+    /// Creates a client whose requests are sent to the returned receiver instead of an event loop
+    fn client_with_receiver() -> (MqttClient, flume::Receiver<Request>) {
+        let (sender, receiver) = flume::bounded(10);
+        (
+            MqttClient::from_client(AsyncClient::from_senders(sender)),
+            receiver,
+        )
+    }
+
+    #[tokio::test]
+    async fn information_is_published_retained_on_its_own_topic() {
+        let (client, receiver) = client_with_receiver();
+        let geo_configuration = GeoConfiguration {
+            prefix: String::from("default"),
+            suffix: String::from("v2x"),
+            topic_template: None,
+        };
+
+        client
+            .publish(Packet::retained(
+                GeoTopic::info(&geo_configuration, "com_myapplication_1"),
+                Information::new("com_myapplication_1", "local", 60, now()),
+            ))
+            .await
+            .unwrap();
+
+        match receiver.try_recv() {
+            Ok(Request::Publish(publish)) => {
+                let topic = String::from_utf8(publish.topic.to_vec()).unwrap();
+                assert!(topic.ends_with("/info/com_myapplication_1"), "{}", topic);
+                assert!(publish.retain);
+            }
+            other => panic!("Expected a publish request, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn message_is_published_on_the_topic_of_its_type() {
+        let (mut client, receiver) = client_with_receiver();
+        client.set_clock(Arc::new(MockClock::new(1_700_000_000_000)));
+        let geo_configuration = GeoConfiguration {
+            prefix: String::from("default"),
+            suffix: String::from("v2x"),
+            topic_template: None,
+        };
+        let denm = DecentralizedEnvironmentalNotificationMessage::new_stationary_vehicle(
+            42,
+            42,
+            ReferencePosition::default(),
+            1,
+            0,
+            None,
+        );
+
+        client
+            .publish_message(
+                denm.clone(),
+                &geo_configuration,
+                "com_myapplication_1",
+                &Quadkey::from_str("1202").unwrap(),
+            )
+            .await
+            .unwrap();
+
+        match receiver.try_recv() {
+            Ok(Request::Publish(publish)) => {
+                let topic = String::from_utf8(publish.topic.to_vec()).unwrap();
+                assert_eq!(
+                    topic,
+                    "default/inQueue/v2x/denm/com_myapplication_1/1/2/0/2"
+                );
+                let exchange = serde_json::from_slice::<Exchange>(&publish.payload).unwrap();
+                assert_eq!(exchange.type_field, "denm");
+                assert_eq!(exchange.source_uuid, "com_myapplication_1");
+                assert_eq!(exchange.timestamp, 1_700_000_000_000);
+                assert_eq!(exchange.message, Message::DENM(denm));
+            }
+            other => panic!("Expected a publish request, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn compressed_publish_is_flagged() {
+        let (mut client, receiver) = client_with_receiver();
+        client.set_payload_compression(true);
+        let geo_configuration = GeoConfiguration {
+            prefix: String::from("default"),
+            suffix: String::from("v2x"),
+            topic_template: None,
+        };
+        let information = Information::new("com_myapplication_1", "local", 60, now());
+
+        client
+            .publish(Packet::new(
+                GeoTopic::info(&geo_configuration, "com_myapplication_1"),
+                information.clone(),
+            ))
+            .await
+            .unwrap();
+
+        match receiver.try_recv() {
+            Ok(Request::Publish(publish)) => {
+                let properties = publish.properties.unwrap_or_default();
+                assert!(properties
+                    .user_properties
+                    .contains(&("content-encoding".to_string(), "gzip".to_string())));
+                let payload = gunzip(&publish.payload, None).unwrap();
+                assert_eq!(payload, serde_json::to_vec(&information).unwrap());
+            }
+            other => panic!("Expected a publish request, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn cbor_publish_is_flagged_with_its_content_type() {
+        let (mut client, receiver) = client_with_receiver();
+        client.set_serialization(Serialization::Cbor);
+        let packet = information_packet("com_myapplication_1", QoS::AtMostOnce);
+        let information = packet.payload.clone();
+
+        client.publish(packet).await.unwrap();
+
+        match receiver.try_recv() {
+            Ok(Request::Publish(publish)) => {
+                let properties = publish.properties.unwrap_or_default();
+                assert_eq!(properties.content_type.as_deref(), Some("application/cbor"));
+                assert_eq!(
+                    Serialization::Cbor
+                        .deserialize::<Information>(&publish.payload)
+                        .unwrap(),
+                    information
+                );
+            }
+            other => panic!("Expected a publish request, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn publish_on_invalid_topic_is_err() {
+        let (client, receiver) = client_with_receiver();
+        let geo_configuration = GeoConfiguration {
+            prefix: String::from("default"),
+            suffix: String::from("v2x"),
+            topic_template: None,
+        };
+
+        let result = client
+            .publish(Packet::new(
+                GeoTopic::info(&geo_configuration, "com_myapplication_+"),
+                Information::new("com_myapplication_1", "local", 60, now()),
+            ))
+            .await;
+
+        assert!(matches!(result, Err(MqttError::SendFailed(_))));
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn subscribe_to_invalid_filter_is_err() {
+        let (mut client, receiver) = client_with_receiver();
+
+        let result = client
+            .subscribe(&["default/outQueue/#/cam".to_string()])
+            .await;
+
+        assert!(matches!(result, Err(MqttError::SendFailed(_))));
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[test]
+    fn new_client_is_connecting() {
+        let (client, _) = MqttClient::new(&MqttOptions::new("client", "localhost", 1883));
+
+        assert_eq!(client.connection_state(), ConnectionState::Connecting);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn connection_state_sender_updates_the_state() {
+        let (client, _) = MqttClient::new(&MqttOptions::new("client", "localhost", 1883));
+        let watcher = client.watch_connection_state();
+
+        client
+            .connection_state_sender()
+            .send_replace(ConnectionState::Connected);
+
+        assert!(watcher.has_changed().unwrap());
+        assert_eq!(client.connection_state(), ConnectionState::Connected);
+    }
+
+    #[test]
+    fn connection_state_changes_are_watched() {
+        let (client, _) = client_with_receiver();
+        let mut watcher = client.watch_connection_state();
+
+        client
+            .connection_tracker()
+            .update(ConnectionState::Connected);
+
+        assert!(watcher.has_changed().unwrap());
+        assert_eq!(*watcher.borrow_and_update(), ConnectionState::Connected);
+        assert_eq!(client.connection_state(), ConnectionState::Connected);
+    }
+
+    #[test]
+    fn connection_events_flip_is_connected() {
+        let (client, _) = client_with_receiver();
+        let tracker = client.connection_tracker();
+        assert!(!client.is_connected());
+
+        tracker.handle_event(&Event::Incoming(Incoming::ConnAck(ConnAck {
+            session_present: false,
+            code: ConnectReturnCode::Success,
+            properties: None,
+        })));
+        assert!(client.is_connected());
+
+        tracker.handle_event(&Event::Incoming(Incoming::Disconnect(Disconnect::new(
+            DisconnectReasonCode::ServerShuttingDown,
+        ))));
+        assert!(!client.is_connected());
+    }
+
+    #[tokio::test]
+    async fn closed_client_is_err() {
+        let (mut client, receiver) = client_with_receiver();
+        drop(receiver);
+
+        let result = client.subscribe(&["default/outQueue/#".to_string()]).await;
+
+        assert!(matches!(result, Err(MqttError::ClientClosed)));
+    }
+
+    #[tokio::test]
+    async fn batch_is_queued_as_many_publishes() {
+        let (client, receiver) = client_with_receiver();
+        let geo_configuration = GeoConfiguration {
+            prefix: String::from("default"),
+            suffix: String::from("v2x"),
+            topic_template: None,
+        };
+        let packets = (1..=5)
+            .map(|i| {
+                let component = format!("com_myapplication_{}", i);
+                Packet::new(
+                    GeoTopic::info(&geo_configuration, &component),
+                    Information::new(&component, "local", 60, now()),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        client.publish_batch(packets).await.unwrap();
+
+        let publishes = receiver.drain().collect::<Vec<Request>>();
+        assert_eq!(publishes.len(), 5);
+        assert!(publishes
+            .iter()
+            .all(|request| matches!(request, Request::Publish(_))));
+    }
+
+    #[tokio::test]
+    async fn batch_is_queued_in_order() {
+        let (client, receiver) = client_with_receiver();
+        let components = (1..=5)
+            .map(|i| format!("com_myapplication_{}", i))
+            .collect::<Vec<_>>();
+        let packets = components
+            .iter()
+            .map(|component| information_packet(component, QoS::AtMostOnce))
+            .collect::<Vec<_>>();
+
+        client.publish_batch(packets).await.unwrap();
+
+        let topics = published_topics(&receiver);
+        assert_eq!(topics.len(), components.len());
+        for (topic, component) in topics.iter().zip(&components) {
+            assert!(topic.ends_with(&format!("/{}", component)), "{}", topic);
+        }
+    }
+
+    #[tokio::test]
+    async fn invalid_packet_fails_the_whole_batch() {
+        let (client, receiver) = client_with_receiver();
+        let geo_configuration = GeoConfiguration {
+            prefix: String::from("default"),
+            suffix: String::from("v2x"),
+            topic_template: None,
+        };
+        let packets = ["com_myapplication_1", "com_myapplication_+"]
+            .iter()
+            .map(|component| {
+                Packet::new(
+                    GeoTopic::info(&geo_configuration, component),
+                    Information::new(component, "local", 60, now()),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let result = client.publish_batch(packets).await;
+
+        assert!(matches!(result, Err(MqttError::SendFailed(_))));
+        assert!(receiver.is_empty());
+    }
+
+    fn information_packet(component: &str, qos: QoS) -> Packet<GeoTopic, Information> {
+        let geo_configuration = GeoConfiguration {
+            prefix: String::from("default"),
+            suffix: String::from("v2x"),
+            topic_template: None,
+        };
+        Packet {
+            qos,
+            ..Packet::new(
+                GeoTopic::info(&geo_configuration, component),
+                Information::new(component, "local", 60, now()),
+            )
+        }
+    }
+
+    fn published_topics(receiver: &flume::Receiver<Request>) -> Vec<String> {
+        receiver
+            .drain()
+            .filter_map(|request| match request {
+                Request::Publish(publish) => {
+                    Some(String::from_utf8(publish.topic.to_vec()).unwrap())
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn publishes_made_while_disconnected_are_resent_on_reconnection() {
+        let (mut client, receiver) = client_with_receiver();
+        client.set_resend_queue_capacity(10);
+        let tracker = client.connection_tracker();
+        tracker.update(ConnectionState::Disconnected);
+
+        for component in ["com_myapplication_1", "com_myapplication_2"] {
+            client
+                .publish(information_packet(component, QoS::AtLeastOnce))
+                .await
+                .unwrap();
+        }
+        assert!(receiver.is_empty());
+        assert_eq!(client.pending_publishes(), 2);
+
+        tracker.update(ConnectionState::Connected);
+        client
+            .publish(information_packet("com_myapplication_3", QoS::AtLeastOnce))
+            .await
+            .unwrap();
+
+        let topics = published_topics(&receiver);
+        assert_eq!(topics.len(), 3);
+        assert!(topics[0].ends_with("/com_myapplication_1"), "{:?}", topics);
+        assert!(topics[1].ends_with("/com_myapplication_2"), "{:?}", topics);
+        assert!(topics[2].ends_with("/com_myapplication_3"), "{:?}", topics);
+        assert_eq!(client.pending_publishes(), 0);
+    }
+
+    #[tokio::test]
+    async fn pending_publishes_are_resent_once_connected() {
+        let (mut client, receiver) = client_with_receiver();
+        client.set_resend_queue_capacity(10);
+        let tracker = client.connection_tracker();
+        tracker.update(ConnectionState::Disconnected);
+        client
+            .publish(information_packet("com_myapplication_1", QoS::ExactlyOnce))
+            .await
+            .unwrap();
+
+        assert_eq!(client.resend_pending().await.unwrap(), 0);
+        tracker.update(ConnectionState::Connected);
+        assert_eq!(client.resend_pending().await.unwrap(), 1);
+
+        assert_eq!(published_topics(&receiver).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn pending_publishes_are_resent_on_conn_ack() {
+        let (mut client, receiver) = client_with_receiver();
+        client.set_resend_queue_capacity(10);
+        let tracker = client.connection_tracker();
+        tracker.update(ConnectionState::Disconnected);
+        client
+            .publish(information_packet("com_myapplication_1", QoS::AtLeastOnce))
+            .await
+            .unwrap();
+        assert!(receiver.is_empty());
+
+        tracker.handle_event(&Event::Incoming(Incoming::ConnAck(ConnAck {
+            session_present: false,
+            code: ConnectReturnCode::Success,
+            properties: None,
+        })));
+
+        assert!(matches!(
+            receiver.recv_async().await.unwrap(),
+            Request::Publish(_)
+        ));
+        assert_eq!(client.pending_publishes(), 0);
+    }
+
+    #[tokio::test]
+    async fn publish_on_closed_client_is_err_and_not_queued() {
+        let (mut client, receiver) = client_with_receiver();
+        client.set_resend_queue_capacity(10);
+        let tracker = client.connection_tracker();
+        tracker.update(ConnectionState::Disconnected);
+        client
+            .publish(information_packet("com_myapplication_1", QoS::AtLeastOnce))
+            .await
+            .unwrap();
+        drop(receiver);
+
+        tracker.update(ConnectionState::Connected);
+        let result = client
+            .publish(information_packet("com_myapplication_2", QoS::AtLeastOnce))
+            .await;
+
+        assert!(matches!(result, Err(MqttError::ClientClosed)));
+        assert_eq!(client.pending_publishes(), 0);
+    }
+
+    #[tokio::test]
+    async fn listening_goes_on_after_a_connection_error() {
+        let mut options = MqttOptions::new("client", "127.0.0.1", 1);
+        options.set_connection_timeout(1);
+        let (client, event_loop) = MqttClient::new(&options);
+        let (sender, _receiver) = pipe(None, OverflowPolicy::default());
+        let shutdown = CancellationToken::new();
+        let mut state = client.watch_connection_state();
+        let listening = tokio::spawn(listen(
+            event_loop,
+            sender,
+            shutdown.clone(),
+            client.connection_tracker(),
+        ));
+
+        state
+            .wait_for(|state| *state == ConnectionState::Disconnected)
+            .await
+            .unwrap();
+        tokio::task::yield_now().await;
+        assert!(!listening.is_finished());
+
+        shutdown.cancel();
+        tokio::time::timeout(Duration::from_secs(5), listening)
+            .await
+            .expect("listening must stop once shut down")
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn publish_without_resend_queue_is_not_queued() {
+        let (client, receiver) = client_with_receiver();
+        client
+            .connection_tracker()
+            .update(ConnectionState::Disconnected);
+
+        client
+            .publish(information_packet("com_myapplication_1", QoS::AtLeastOnce))
+            .await
+            .unwrap();
+
+        assert_eq!(client.pending_publishes(), 0);
+        assert_eq!(published_topics(&receiver).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn publish_with_ack_resolves_on_pub_ack() {
+        let (client, receiver) = client_with_receiver();
+        let connection = client.connection_tracker();
+        let publishing = tokio::spawn(async move {
+            client
+                .publish_with_ack(
+                    information_packet("com_1", QoS::AtLeastOnce),
+                    Duration::from_secs(5),
+                )
+                .await
+        });
+
+        assert!(matches!(
+            receiver.recv_async().await.unwrap(),
+            Request::Publish(_)
+        ));
+        connection.handle_event(&Event::Outgoing(Outgoing::Publish(1)));
+        connection.handle_event(&Event::Incoming(Incoming::PubAck(PubAck::new(1, None))));
+
+        assert!(publishing.await.unwrap().is_ok());
+    }
+
+    #[tokio::test]
+    async fn unacknowledged_publish_times_out() {
+        let (client, receiver) = client_with_receiver();
+
+        let result = client
+            .publish_with_ack(
+                information_packet("com_1", QoS::AtLeastOnce),
+                Duration::from_millis(10),
+            )
+            .await;
+
+        assert!(matches!(result, Err(PublishError::Timeout(_))));
+        assert_eq!(receiver.len(), 1);
+    }
+}
@@ -0,0 +1,40 @@
+/*
+ * Software Name : libits-client
+ * SPDX-FileCopyrightText: Copyright (c) Orange SA
+ * SPDX-License-Identifier: MIT
+ *
+ * This software is distributed under the MIT license,
+ * see the "LICENSE.txt" file for more details or https://opensource.org/license/MIT/
+ *
+ * Authors: see CONTRIBUTORS.md
+ */
+
+use crate::transport::serialization::SerializationError;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum MqttError {
+    #[error("MQTT client is closed, the event loop has been dropped")]
+    ClientClosed,
+    #[error("Failed to send {0}")]
+    SendFailed(String),
+    #[error("Failed to serialize payload: {0}")]
+    SerializationFailed(#[from] serde_json::Error),
+    #[error("Failed to encode payload: {0}")]
+    EncodingFailed(#[from] SerializationError),
+}
+
+/// Failure of a publish waiting for the broker's acknowledgement
+#[derive(Debug, Error)]
+pub enum PublishError {
+    #[error(transparent)]
+    Mqtt(#[from] MqttError),
+    #[error("Publish refused by the broker: {0}")]
+    Refused(String),
+    #[error("Publish not acknowledged within {0:?}")]
+    Timeout(std::time::Duration),
+    #[error("Publish acknowledgement abandoned, the event loop stopped")]
+    Abandoned,
+    #[error("Publish acknowledgement lost along with the connection or the session")]
+    ConnectionLost,
+}
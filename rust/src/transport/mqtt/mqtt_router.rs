@@ -11,13 +11,21 @@
 
 use std::collections::HashMap;
 
-use log::{error, info, trace, warn};
 use rumqttc::v5::mqttbytes::v5::{Publish, PublishProperties};
 use rumqttc::v5::{Event, Incoming};
+use tracing::{debug, error, info, trace, warn};
 
-use crate::transport::mqtt::topic::Topic;
-use std::any::{type_name, Any};
-use std::str::from_utf8;
+#[cfg(feature = "geo_routing")]
+use crate::transport::mqtt::geo_topic::topic_template::TopicTemplate;
+#[cfg(feature = "geo_routing")]
+use crate::transport::mqtt::geo_topic::GeoTopic;
+use crate::transport::mqtt::parse_error_reporter::ParseErrorReporter;
+use crate::transport::mqtt::router_stats::RouterStats;
+use crate::transport::mqtt::topic::{topic_from_publish, ParseError, Topic};
+use std::any::Any;
+#[cfg(feature = "geo_routing")]
+use std::any::TypeId;
+use std::sync::Arc;
 
 pub type BoxedReception = (Box<dyn Any + 'static + Send>, PublishProperties);
 
@@ -26,12 +34,87 @@ type BoxedCallback = Box<dyn Fn(Publish) -> Option<BoxedReception>>;
 #[cfg(feature = "telemetry")]
 use crate::transport::telemetry::get_reception_mqtt_span;
 
+/// Route callback handing the payload over unchanged, as a `Vec<u8>`, for binary payloads that
+/// are not UTF-8 (e.g. CBOR)
+///
+/// The decoding is left to the receiver, which downcasts the reception to `Vec<u8>`:
+/// ```
+/// # use libits::transport::mqtt::mqtt_router::{bytes_route, MqttRouter};
+/// # use libits::transport::mqtt::topic::Topic;
+/// fn route_binary<T: Topic>(router: &mut MqttRouter, topic: T) {
+///     router.add_route(topic, bytes_route);
+/// }
+///
+/// fn handle<T: Topic + 'static>(router: &mut MqttRouter, event: rumqttc::v5::Event) {
+///     if let Some((topic, (reception, _properties))) = router.handle_event::<T>(event) {
+///         if let Ok(payload) = reception.downcast::<Vec<u8>>() {
+///             println!("{} bytes received on {}", payload.len(), topic);
+///         }
+///     }
+/// }
+/// ```
+pub fn bytes_route(publish: Publish) -> Option<BoxedReception> {
+    Some((
+        Box::new(publish.payload.to_vec()),
+        publish.properties.unwrap_or_default(),
+    ))
+}
+
 #[derive(Default)]
 pub struct MqttRouter {
     route_map: HashMap<String, BoxedCallback>,
+    default_route: Option<BoxedCallback>,
+    stats: Arc<RouterStats>,
+    max_payload_size: Option<usize>,
+    parse_error_reporter: Option<ParseErrorReporter>,
+    #[cfg(feature = "geo_routing")]
+    topic_template: Option<TopicTemplate>,
 }
 
 impl MqttRouter {
+    /// Sets the size in bytes above which payloads are rejected without being handed to the route
+    /// callback
+    pub fn set_max_payload_size(&mut self, max_payload_size: Option<usize>) {
+        self.max_payload_size = max_payload_size;
+    }
+
+    /// Aggregates the parse failures into periodic summaries instead of one warning each
+    pub fn set_parse_error_reporter(&mut self, reporter: ParseErrorReporter) {
+        self.parse_error_reporter = Some(reporter);
+    }
+
+    /// Logs the summary of the parse failures if its interval elapsed, without waiting for the
+    /// next publish; does nothing without [reporter][1]
+    ///
+    /// [1]: MqttRouter::set_parse_error_reporter
+    pub fn report_parse_errors(&mut self) {
+        if let Some(reporter) = self.parse_error_reporter.as_mut() {
+            reporter.report();
+        }
+    }
+
+    /// Sets the [template][1] the received topics are parsed with when they are [GeoTopic]s,
+    /// [FromStr][2] is used if none and for the other topic types
+    ///
+    /// [1]: TopicTemplate
+    /// [2]: std::str::FromStr
+    #[cfg(feature = "geo_routing")]
+    pub fn set_topic_template(&mut self, topic_template: Option<TopicTemplate>) {
+        self.topic_template = topic_template;
+    }
+
+    /// Replaces the reception counters, e.g. by ones already shared with a [metrics server][1]
+    ///
+    /// [1]: crate::transport::metrics::PrometheusServer
+    pub fn set_stats(&mut self, stats: Arc<RouterStats>) {
+        self.stats = stats;
+    }
+
+    /// Returns the reception counters of this router, shareable with other threads
+    pub fn stats(&self) -> Arc<RouterStats> {
+        self.stats.clone()
+    }
+
     pub fn add_route<T, C>(&mut self, topic: T, callback: C)
     where
         T: Topic,
@@ -41,41 +124,95 @@ impl MqttRouter {
         info!("Registered route for topic: {}", topic.as_route());
     }
 
-    pub fn handle_event<T: Topic>(&mut self, event: Event) -> Option<(T, BoxedReception)> {
+    /// Sets the callback receiving the publishes whose topic matches no route
+    pub fn add_default_route<C>(&mut self, callback: C)
+    where
+        C: Fn(Publish) -> Option<BoxedReception> + 'static,
+    {
+        self.default_route = Some(Box::new(callback));
+        info!("Registered default route");
+    }
+
+    /// Returns the route matching the topic, None if the topic would go to the default route or
+    /// be dropped
+    pub fn matched_route<T: Topic>(&self, topic: &T) -> Option<&str> {
+        self.route_map
+            .get_key_value(&topic.as_route())
+            .map(|(route, _)| route.as_str())
+    }
+
+    fn parse_topic<T: Topic + 'static>(&self, publish: &Publish) -> Result<T, ParseError> {
+        #[cfg(feature = "geo_routing")]
+        if let Some(template) = self.topic_template.as_ref() {
+            if TypeId::of::<T>() == TypeId::of::<GeoTopic>() {
+                let topic: Box<dyn Any> =
+                    Box::new(GeoTopic::from_templated_publish(publish, template)?);
+                if let Ok(topic) = topic.downcast::<T>() {
+                    return Ok(*topic);
+                }
+            }
+        }
+        topic_from_publish(publish)
+    }
+
+    pub fn handle_event<T: Topic + 'static>(
+        &mut self,
+        event: Event,
+    ) -> Option<(T, BoxedReception)> {
         match event {
             Event::Incoming(incoming) => match incoming {
                 Incoming::Publish(publish) => {
-                    match from_utf8(&publish.topic) {
-                        Ok(str_topic) => {
-                            #[cfg(feature = "telemetry")]
-                            let _span = get_reception_mqtt_span(&publish);
+                    self.report_parse_errors();
+
+                    #[cfg(feature = "telemetry")]
+                    let _span = get_reception_mqtt_span(&publish);
 
+                    match self.parse_topic::<T>(&publish) {
+                        Ok(topic) => {
                             trace!(
                                 "Publish received for the packet {:?} on the topic {}",
                                 publish.pkid,
-                                str_topic,
+                                topic,
                             );
 
-                            match T::from_str(str_topic) {
-                                Ok(topic) => match self.route_map.get(&topic.as_route()) {
-                                    Some(callback) => {
-                                        if let Some(reception) = callback(publish) {
-                                            return Some((topic, reception));
+                            let callback = self
+                                .route_map
+                                .get(&topic.as_route())
+                                .or(self.default_route.as_ref());
+                            match callback {
+                                Some(callback) => {
+                                    self.stats.count_received();
+                                    if let Some(max) = self.max_payload_size {
+                                        if publish.payload.len() > max {
+                                            warn!(
+                                                "Payload of {} bytes rejected on topic '{}', above the {} bytes limit",
+                                                publish.payload.len(),
+                                                topic,
+                                                max
+                                            );
+                                            self.stats.count_rejection();
+                                            return None;
                                         }
                                     }
-                                    None => {
-                                        warn!("No route found for topic '{}'", topic);
+                                    if let Some(reception) = callback(publish) {
+                                        self.stats.count_parsed();
+                                        return Some((topic, reception));
+                                    }
+                                    self.stats.count_parse_failure();
+                                    match self.parse_error_reporter.as_mut() {
+                                        Some(reporter) => reporter.record(&topic.as_route()),
+                                        // the route callback logs the failure details
+                                        None => {
+                                            debug!("Failed to parse publish on topic '{}'", topic)
+                                        }
                                     }
-                                },
-                                // FIXME how to print this error ?
-                                Err(_error) => {
-                                    error!("Failed to create {} from string", type_name::<T>(),)
                                 }
-                            };
-                        }
-                        Err(e) => {
-                            warn!("Failed to parse topic as UTF-8: {:?}", e);
+                                None => {
+                                    warn!("No route found for topic '{}'", topic);
+                                }
+                            }
                         }
+                        Err(e) => error!("{}", e),
                     }
                 }
                 Incoming::PubAck(packet) => {
@@ -126,3 +263,225 @@ impl MqttRouter {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "geo_routing")]
+    use crate::transport::mqtt::geo_topic::topic_template::TopicTemplate;
+    #[cfg(feature = "geo_routing")]
+    use crate::transport::mqtt::geo_topic::GeoTopic;
+    use crate::transport::mqtt::mqtt_router::{bytes_route, BoxedReception, MqttRouter};
+    use crate::transport::mqtt::topic::tests::RoutedTopic;
+    use crate::transport::mqtt::topic::Topic;
+    use rumqttc::v5::mqttbytes::v5::{PingResp, Publish};
+    use rumqttc::v5::mqttbytes::QoS;
+    use rumqttc::v5::{Event, Incoming};
+    use std::cell::Cell;
+    use std::fmt::{Display, Formatter};
+    use std::rc::Rc;
+    use std::str::FromStr;
+
+    #[derive(Clone, Debug, Default, Hash, PartialEq, Eq)]
+    struct TestTopic(String);
+    impl Display for TestTopic {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{}", self.0)
+        }
+    }
+    impl FromStr for TestTopic {
+        type Err = ();
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            Ok(TestTopic(s.to_string()))
+        }
+    }
+    impl Topic for TestTopic {
+        fn as_route(&self) -> String {
+            String::from("test")
+        }
+    }
+
+    fn parse_json(publish: Publish) -> Option<BoxedReception> {
+        serde_json::from_slice::<serde_json::Value>(&publish.payload)
+            .ok()
+            .map(|value| {
+                (
+                    Box::new(value) as Box<dyn std::any::Any + Send>,
+                    publish.properties.unwrap_or_default(),
+                )
+            })
+    }
+
+    fn publish_event(payload: &'static str) -> Event {
+        publish_event_on("test", payload)
+    }
+
+    fn publish_event_on(topic: &'static str, payload: &'static str) -> Event {
+        Event::Incoming(Incoming::Publish(Publish::new(
+            topic,
+            QoS::AtMostOnce,
+            payload,
+            None,
+        )))
+    }
+
+    fn counting_router(default_hits: Rc<Cell<u32>>) -> MqttRouter {
+        let mut router = MqttRouter::default();
+        router.add_route(RoutedTopic(String::from("cam")), parse_json);
+        router.add_default_route(move |publish| {
+            default_hits.set(default_hits.get() + 1);
+            parse_json(publish)
+        });
+        router
+    }
+
+    #[test]
+    fn valid_and_garbage_payloads_are_counted() {
+        let mut router = MqttRouter::default();
+        router.add_route(TestTopic::default(), parse_json);
+        let stats = router.stats();
+
+        assert!(router
+            .handle_event::<TestTopic>(publish_event(r#"{"type":"cam"}"#))
+            .is_some());
+        assert!(router
+            .handle_event::<TestTopic>(publish_event(r#"{"type":"denm"}"#))
+            .is_some());
+        assert!(router
+            .handle_event::<TestTopic>(publish_event("garbage"))
+            .is_none());
+
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot.received, 3);
+        assert_eq!(snapshot.parsed, 2);
+        assert_eq!(snapshot.parse_failed, 1);
+    }
+
+    #[test]
+    fn oversized_payloads_are_rejected() {
+        let mut router = MqttRouter::default();
+        router.add_route(TestTopic::default(), parse_json);
+        router.set_max_payload_size(Some(16));
+
+        assert!(router
+            .handle_event::<TestTopic>(publish_event(r#"{"type":"cam"}"#))
+            .is_some());
+        assert!(router
+            .handle_event::<TestTopic>(publish_event(r#"{"type":"cam","padding":"0000000000"}"#))
+            .is_none());
+
+        let snapshot = router.stats().snapshot();
+        assert_eq!(snapshot.received, 2);
+        assert_eq!(snapshot.parsed, 1);
+        assert_eq!(snapshot.parse_failed, 0);
+        assert_eq!(snapshot.rejected, 1);
+    }
+
+    #[test]
+    fn binary_payload_goes_through_bytes_route_unchanged() {
+        let mut router = MqttRouter::default();
+        router.add_route(TestTopic::default(), bytes_route);
+        let payload = (0..=u8::MAX).rev().collect::<Vec<u8>>();
+
+        let (_, (reception, _)) = router
+            .handle_event::<TestTopic>(Event::Incoming(Incoming::Publish(Publish::new(
+                "test",
+                QoS::AtMostOnce,
+                payload.clone(),
+                None,
+            ))))
+            .unwrap();
+
+        assert_eq!(*reception.downcast::<Vec<u8>>().unwrap(), payload);
+    }
+
+    #[test]
+    fn non_publish_events_are_not_counted() {
+        let mut router = MqttRouter::default();
+        router.add_route(TestTopic::default(), parse_json);
+
+        router.handle_event::<TestTopic>(Event::Incoming(Incoming::PingResp(PingResp)));
+
+        assert_eq!(router.stats().snapshot().received, 0);
+    }
+
+    #[test]
+    fn unmatched_topic_hits_default_route() {
+        let default_hits = Rc::new(Cell::new(0));
+        let mut router = counting_router(default_hits.clone());
+
+        let reception = router.handle_event::<RoutedTopic>(publish_event_on("other", "{}"));
+
+        assert_eq!(reception.unwrap().0, RoutedTopic(String::from("other")));
+        assert_eq!(default_hits.get(), 1);
+        assert_eq!(router.stats().snapshot().received, 1);
+    }
+
+    #[test]
+    fn matched_topic_does_not_hit_default_route() {
+        let default_hits = Rc::new(Cell::new(0));
+        let mut router = counting_router(default_hits.clone());
+
+        assert!(router
+            .handle_event::<RoutedTopic>(publish_event_on("cam", "{}"))
+            .is_some());
+
+        assert_eq!(default_hits.get(), 0);
+    }
+
+    #[test]
+    fn unmatched_topic_without_default_route_is_dropped() {
+        let mut router = MqttRouter::default();
+        router.add_route(RoutedTopic(String::from("cam")), parse_json);
+
+        assert!(router
+            .handle_event::<RoutedTopic>(publish_event_on("other", "{}"))
+            .is_none());
+        assert_eq!(router.stats().snapshot().received, 0);
+    }
+
+    #[test]
+    fn matched_route_is_returned() {
+        let router = counting_router(Rc::new(Cell::new(0)));
+
+        assert_eq!(
+            router.matched_route(&RoutedTopic(String::from("cam"))),
+            Some("cam")
+        );
+        assert_eq!(
+            router.matched_route(&RoutedTopic(String::from("other"))),
+            None
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "geo_routing")]
+    fn received_topics_are_parsed_with_the_router_template() {
+        let template = TopicTemplate::from_str("{project}/{type}/{queue}/{uuid}/{geo}").unwrap();
+        let mut router = MqttRouter::default();
+        router.set_topic_template(Some(template.clone()));
+        router.add_route(template.parse("default/cam/outQueue").unwrap(), parse_json);
+
+        let (topic, _) = router
+            .handle_event::<GeoTopic>(publish_event_on("default/cam/outQueue/car_1/1/2", "{}"))
+            .expect("templated topic must be routed");
+
+        assert_eq!(topic.to_string(), "default/cam/outQueue/car_1/1/2");
+        assert_eq!(topic.quadkey_string(), "12");
+    }
+
+    #[test]
+    #[cfg(feature = "geo_routing")]
+    fn template_does_not_apply_to_other_topic_types() {
+        let template = TopicTemplate::from_str("{project}/{type}/{queue}/{uuid}/{geo}").unwrap();
+        let mut router = MqttRouter::default();
+        router.set_topic_template(Some(template));
+        router.add_route(TestTopic(String::from("any")), parse_json);
+
+        let (topic, _) = router
+            .handle_event::<TestTopic>(publish_event_on("not/a/templated/topic", "{}"))
+            .expect("topic must be parsed with FromStr");
+
+        assert_eq!(topic, TestTopic(String::from("not/a/templated/topic")));
+    }
+}
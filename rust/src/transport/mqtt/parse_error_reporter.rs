@@ -0,0 +1,150 @@
+/*
+ * Software Name : libits-client
+ * SPDX-FileCopyrightText: Copyright (c) Orange SA
+ * SPDX-License-Identifier: MIT
+ *
+ * This software is distributed under the MIT license,
+ * see the "LICENSE.txt" file for more details or https://opensource.org/license/MIT/
+ *
+ * Authors: see CONTRIBUTORS.md
+ */
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tracing::warn;
+
+use crate::clock::Clock;
+
+/// Number of routes whose failures are counted on their own within an interval
+const MAX_ROUTES: usize = 64;
+/// Key under which the failures on the routes beyond [MAX_ROUTES] are counted
+pub const OTHER_ROUTES: &str = "other";
+
+/// Aggregates parse failures per route and logs a summary once per interval
+///
+/// Used by the [MqttRouter][1] so that a misbehaving peer produces one line per route and per
+/// interval instead of one line per malformed message; at most [MAX_ROUTES] routes are counted
+/// on their own, the failures on any other one being counted as [OTHER_ROUTES]
+///
+/// [1]: crate::transport::mqtt::mqtt_router::MqttRouter
+pub struct ParseErrorReporter {
+    interval: Duration,
+    clock: Arc<dyn Clock>,
+    window_start: u64,
+    errors: HashMap<String, u64>,
+}
+
+impl ParseErrorReporter {
+    pub fn new(interval: Duration, clock: Arc<dyn Clock>) -> Self {
+        let window_start = clock.now_millis();
+        Self {
+            interval,
+            clock,
+            window_start,
+            errors: HashMap::new(),
+        }
+    }
+
+    /// Counts a parse failure on the route
+    pub fn record(&mut self, route: &str) {
+        if let Some(count) = self.errors.get_mut(route) {
+            *count += 1;
+            return;
+        }
+        let route = if self.errors.len() < MAX_ROUTES {
+            route
+        } else {
+            OTHER_ROUTES
+        };
+        *self.errors.entry(route.to_string()).or_default() += 1;
+    }
+
+    /// Returns the failure count per route and starts a new interval if the current one elapsed
+    pub fn due_report(&mut self) -> Option<HashMap<String, u64>> {
+        let now = self.clock.now_millis();
+        if now < self.window_start + self.interval.as_millis() as u64 {
+            return None;
+        }
+
+        self.window_start = now;
+        Some(std::mem::take(&mut self.errors))
+    }
+
+    /// Logs the failure count of each route if the current interval elapsed
+    pub fn report(&mut self) {
+        if let Some(errors) = self.due_report() {
+            for (route, count) in errors {
+                warn!(
+                    "{} parse errors on route {} in last {}s",
+                    count,
+                    route,
+                    self.interval.as_secs()
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use crate::clock::MockClock;
+    use crate::transport::mqtt::parse_error_reporter::{
+        ParseErrorReporter, MAX_ROUTES, OTHER_ROUTES,
+    };
+
+    #[test]
+    fn routes_beyond_the_limit_are_counted_together() {
+        let clock = Arc::new(MockClock::new(0));
+        let mut reporter = ParseErrorReporter::new(Duration::from_secs(10), clock.clone());
+
+        for route in 0..MAX_ROUTES + 2 {
+            reporter.record(&route.to_string());
+        }
+        reporter.record("0");
+        clock.advance(Duration::from_secs(10));
+        let report = reporter.due_report().unwrap();
+
+        assert_eq!(report.len(), MAX_ROUTES + 1);
+        assert_eq!(report.get("0"), Some(&2));
+        assert_eq!(report.get(OTHER_ROUTES), Some(&2));
+    }
+
+    #[test]
+    fn errors_are_aggregated_per_route_until_the_interval_elapses() {
+        let clock = Arc::new(MockClock::new(0));
+        let mut reporter = ParseErrorReporter::new(Duration::from_secs(60), clock.clone());
+
+        for _ in 0..1423 {
+            reporter.record("default/outQueue/v2x/cam");
+        }
+        reporter.record("default/outQueue/v2x/denm");
+        clock.advance(Duration::from_secs(59));
+        assert!(reporter.due_report().is_none());
+
+        clock.advance(Duration::from_secs(1));
+        let report = reporter.due_report().unwrap();
+
+        assert_eq!(report.get("default/outQueue/v2x/cam"), Some(&1423));
+        assert_eq!(report.get("default/outQueue/v2x/denm"), Some(&1));
+    }
+
+    #[test]
+    fn counts_are_reset_after_a_report() {
+        let clock = Arc::new(MockClock::new(0));
+        let mut reporter = ParseErrorReporter::new(Duration::from_secs(10), clock.clone());
+        reporter.record("topic");
+        clock.advance(Duration::from_secs(10));
+        assert!(reporter.due_report().is_some());
+
+        reporter.record("topic");
+        assert!(reporter.due_report().is_none());
+        clock.advance(Duration::from_secs(10));
+
+        assert_eq!(reporter.due_report().unwrap().get("topic"), Some(&1));
+    }
+}
@@ -0,0 +1,360 @@
+/*
+ * Software Name : libits-client
+ * SPDX-FileCopyrightText: Copyright (c) Orange SA
+ * SPDX-License-Identifier: MIT
+ *
+ * This software is distributed under the MIT license,
+ * see the "LICENSE.txt" file for more details or https://opensource.org/license/MIT/
+ *
+ * Authors: see CONTRIBUTORS.md
+ */
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+use rumqttc::v5::mqttbytes::v5::{PubAckReason, PubCompReason, PubRecReason};
+use rumqttc::v5::{Event, Incoming};
+use rumqttc::Outgoing;
+use tokio::sync::oneshot;
+use tracing::trace;
+
+use crate::transport::mqtt::mqtt_error::PublishError;
+
+pub(crate) type AckSender = oneshot::Sender<Result<(), PublishError>>;
+
+/// Matches the publishes waiting for an acknowledgement with the broker's PubAck and PubComp
+///
+/// The event loop only reports the packet id of a publish once it is sent, so every publish
+/// handed to the event loop must be [queued][1], in order, to be matched with its packet id;
+/// only the waiting ones are kept, the others are counted.
+/// Publishes the event loop resends on reconnection reuse a packet id already in flight and are
+/// not matched again
+///
+/// Once the [connection is lost][2], or if the broker did not keep the session, the publishes in
+/// flight are failed: their packet ids are kept until resent and acknowledged, without waiter.
+/// Waiters which stop waiting, e.g. on timeout, are [forgotten][3]
+///
+/// [1]: AckTracker::queued
+/// [2]: AckTracker::connection_lost
+/// [3]: AckTracker::forget_closed
+#[derive(Default)]
+pub(crate) struct AckTracker {
+    acks: Mutex<Acks>,
+}
+
+#[derive(Default)]
+struct Acks {
+    /// Waiting publishes, each with the number of publishes queued before it and after the
+    /// previous waiting one
+    queued: VecDeque<(u64, AckSender)>,
+    /// Number of publishes queued after the last waiting one
+    untracked: u64,
+    in_flight: HashMap<u16, Option<AckSender>>,
+}
+
+impl Acks {
+    /// Returns the sender of the next publish sent by the event loop, if someone waits for it
+    fn next_sent(&mut self) -> Option<AckSender> {
+        match self.queued.front_mut() {
+            Some((0, _)) => self.queued.pop_front().map(|(_, waiter)| waiter),
+            Some((before, _)) => {
+                *before -= 1;
+                None
+            }
+            None => {
+                self.untracked = self.untracked.saturating_sub(1);
+                None
+            }
+        }
+    }
+}
+
+impl AckTracker {
+    /// Records a publish handed to the event loop, with the sender of its acknowledgement if
+    /// someone waits for it
+    pub(crate) fn queued(&self, waiter: Option<AckSender>) {
+        let mut acks = self.acks.lock().unwrap();
+        match waiter {
+            Some(waiter) => {
+                let before = std::mem::take(&mut acks.untracked);
+                acks.queued.push_back((before, waiter));
+            }
+            None => acks.untracked += 1,
+        }
+    }
+
+    /// Fails the publishes in flight, which may be lost along with the connection or the session
+    pub(crate) fn connection_lost(&self) {
+        let mut acks = self.acks.lock().unwrap();
+        let lost = acks
+            .in_flight
+            .values_mut()
+            .filter_map(Option::take)
+            .collect::<Vec<AckSender>>();
+        if !lost.is_empty() {
+            trace!("{} publishes in flight failed", lost.len());
+        }
+        for waiter in lost {
+            resolve(Some(waiter), Err(PublishError::ConnectionLost));
+        }
+    }
+
+    /// Forgets the waiters which stopped waiting, the publishes being counted as untracked ones
+    pub(crate) fn forget_closed(&self) {
+        let mut acks = self.acks.lock().unwrap();
+        for waiter in acks.in_flight.values_mut() {
+            if waiter.as_ref().is_some_and(AckSender::is_closed) {
+                *waiter = None;
+            }
+        }
+
+        let mut untracked = 0;
+        let mut kept = VecDeque::new();
+        for (before, waiter) in std::mem::take(&mut acks.queued) {
+            if waiter.is_closed() {
+                untracked += before + 1;
+            } else {
+                kept.push_back((before + std::mem::take(&mut untracked), waiter));
+            }
+        }
+        acks.queued = kept;
+        acks.untracked += untracked;
+    }
+
+    /// Forgets the last queued publish, the event loop having refused it
+    pub(crate) fn unqueue_last(&self) {
+        let mut acks = self.acks.lock().unwrap();
+        if acks.untracked > 0 {
+            acks.untracked -= 1;
+        } else if let Some((before, _)) = acks.queued.pop_back() {
+            acks.untracked = before;
+        }
+    }
+
+    pub(crate) fn handle_event(&self, event: &Event) {
+        if let Event::Incoming(Incoming::ConnAck(conn_ack)) = event {
+            if !conn_ack.session_present {
+                self.connection_lost();
+            }
+            return;
+        }
+
+        let mut acks = self.acks.lock().unwrap();
+        match event {
+            Event::Outgoing(Outgoing::Publish(pkid)) => {
+                if *pkid != 0 && acks.in_flight.contains_key(pkid) {
+                    trace!("publish {} resent", pkid);
+                    return;
+                }
+                let waiter = acks.next_sent();
+                if *pkid == 0 {
+                    // QoS 0, nothing more to wait for once on the wire
+                    resolve(waiter, Ok(()));
+                } else {
+                    acks.in_flight.insert(*pkid, waiter);
+                }
+            }
+            Event::Incoming(Incoming::PubAck(pub_ack)) => {
+                let result = match pub_ack.reason {
+                    PubAckReason::Success | PubAckReason::NoMatchingSubscribers => Ok(()),
+                    reason => Err(PublishError::Refused(format!("{:?}", reason))),
+                };
+                if let Some(waiter) = acks.in_flight.remove(&pub_ack.pkid) {
+                    resolve(waiter, result);
+                }
+            }
+            Event::Incoming(Incoming::PubRec(pub_rec)) => match pub_rec.reason {
+                PubRecReason::Success | PubRecReason::NoMatchingSubscribers => (),
+                reason => {
+                    if let Some(waiter) = acks.in_flight.remove(&pub_rec.pkid) {
+                        resolve(waiter, Err(PublishError::Refused(format!("{:?}", reason))));
+                    }
+                }
+            },
+            Event::Incoming(Incoming::PubComp(pub_comp)) => {
+                let result = match pub_comp.reason {
+                    PubCompReason::Success => Ok(()),
+                    reason => Err(PublishError::Refused(format!("{:?}", reason))),
+                };
+                if let Some(waiter) = acks.in_flight.remove(&pub_comp.pkid) {
+                    resolve(waiter, result);
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+fn resolve(waiter: Option<AckSender>, result: Result<(), PublishError>) {
+    if let Some(sender) = waiter {
+        // the receiver is gone if the publisher stopped waiting
+        let _ = sender.send(result);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::transport::mqtt::mqtt_error::PublishError;
+    use crate::transport::mqtt::publish_ack::AckTracker;
+    use rumqttc::v5::mqttbytes::v5::{ConnAck, ConnectReturnCode, PubAck, PubAckReason, PubComp};
+    use rumqttc::v5::{Event, Incoming};
+    use rumqttc::Outgoing;
+    use tokio::sync::oneshot;
+
+    fn sent(pkid: u16) -> Event {
+        Event::Outgoing(Outgoing::Publish(pkid))
+    }
+
+    fn pub_ack(pkid: u16, reason: PubAckReason) -> Event {
+        Event::Incoming(Incoming::PubAck(PubAck {
+            pkid,
+            reason,
+            properties: None,
+        }))
+    }
+
+    #[test]
+    fn pub_ack_resolves_the_matching_publish() {
+        let tracker = AckTracker::default();
+        let (first, mut first_ack) = oneshot::channel();
+        let (second, mut second_ack) = oneshot::channel();
+        tracker.queued(Some(first));
+        tracker.queued(None);
+        tracker.queued(Some(second));
+
+        tracker.handle_event(&sent(1));
+        tracker.handle_event(&sent(2));
+        tracker.handle_event(&sent(3));
+        tracker.handle_event(&pub_ack(3, PubAckReason::Success));
+
+        assert!(first_ack.try_recv().is_err());
+        assert!(matches!(second_ack.try_recv(), Ok(Ok(()))));
+    }
+
+    #[test]
+    fn pub_comp_resolves_the_matching_publish() {
+        let tracker = AckTracker::default();
+        let (sender, mut ack) = oneshot::channel();
+        tracker.queued(Some(sender));
+
+        tracker.handle_event(&sent(7));
+        tracker.handle_event(&Event::Incoming(Incoming::PubComp(PubComp::new(7, None))));
+
+        assert!(matches!(ack.try_recv(), Ok(Ok(()))));
+    }
+
+    #[test]
+    fn refused_publish_is_err() {
+        let tracker = AckTracker::default();
+        let (sender, mut ack) = oneshot::channel();
+        tracker.queued(Some(sender));
+
+        tracker.handle_event(&sent(1));
+        tracker.handle_event(&pub_ack(1, PubAckReason::NotAuthorized));
+
+        assert!(matches!(ack.try_recv(), Ok(Err(PublishError::Refused(_)))));
+    }
+
+    #[test]
+    fn resent_publish_is_not_matched_again() {
+        let tracker = AckTracker::default();
+        let (first, mut first_ack) = oneshot::channel();
+        let (second, mut second_ack) = oneshot::channel();
+        tracker.queued(Some(first));
+        tracker.handle_event(&sent(1));
+        tracker.queued(Some(second));
+
+        tracker.handle_event(&sent(1));
+        tracker.handle_event(&sent(2));
+        tracker.handle_event(&pub_ack(1, PubAckReason::Success));
+
+        assert!(matches!(first_ack.try_recv(), Ok(Ok(()))));
+        assert!(second_ack.try_recv().is_err());
+    }
+
+    #[test]
+    fn qos_0_is_resolved_once_sent() {
+        let tracker = AckTracker::default();
+        let (sender, mut ack) = oneshot::channel();
+        tracker.queued(Some(sender));
+
+        tracker.handle_event(&sent(0));
+
+        assert!(matches!(ack.try_recv(), Ok(Ok(()))));
+    }
+
+    #[test]
+    fn publishes_in_flight_fail_once_the_connection_is_lost() {
+        let tracker = AckTracker::default();
+        let (first, mut first_ack) = oneshot::channel();
+        let (second, mut second_ack) = oneshot::channel();
+        tracker.queued(Some(first));
+        tracker.handle_event(&sent(1));
+
+        tracker.connection_lost();
+        assert!(matches!(
+            first_ack.try_recv(),
+            Ok(Err(PublishError::ConnectionLost))
+        ));
+
+        tracker.queued(Some(second));
+        tracker.handle_event(&sent(1));
+        tracker.handle_event(&sent(2));
+        tracker.handle_event(&pub_ack(2, PubAckReason::Success));
+        assert!(matches!(second_ack.try_recv(), Ok(Ok(()))));
+    }
+
+    #[test]
+    fn new_session_fails_the_publishes_in_flight() {
+        let tracker = AckTracker::default();
+        let (sender, mut ack) = oneshot::channel();
+        tracker.queued(Some(sender));
+        tracker.handle_event(&sent(1));
+
+        tracker.handle_event(&Event::Incoming(Incoming::ConnAck(ConnAck {
+            session_present: true,
+            code: ConnectReturnCode::Success,
+            properties: None,
+        })));
+        assert!(ack.try_recv().is_err());
+
+        tracker.handle_event(&Event::Incoming(Incoming::ConnAck(ConnAck {
+            session_present: false,
+            code: ConnectReturnCode::Success,
+            properties: None,
+        })));
+        assert!(matches!(
+            ack.try_recv(),
+            Ok(Err(PublishError::ConnectionLost))
+        ));
+    }
+
+    #[test]
+    fn closed_waiters_are_forgotten() {
+        let tracker = AckTracker::default();
+        let (in_flight, in_flight_ack) = oneshot::channel();
+        let (timed_out, timed_out_ack) = oneshot::channel();
+        let (waiting, mut waiting_ack) = oneshot::channel();
+        tracker.queued(Some(in_flight));
+        tracker.handle_event(&sent(1));
+        tracker.queued(None);
+        tracker.queued(Some(timed_out));
+        tracker.queued(Some(waiting));
+        drop(in_flight_ack);
+        drop(timed_out_ack);
+
+        tracker.forget_closed();
+        {
+            let acks = tracker.acks.lock().unwrap();
+            assert!(acks.in_flight[&1].is_none());
+            assert_eq!(acks.queued.len(), 1);
+            assert_eq!(acks.queued[0].0, 2);
+        }
+
+        tracker.handle_event(&sent(2));
+        tracker.handle_event(&sent(3));
+        tracker.handle_event(&sent(4));
+        tracker.handle_event(&pub_ack(4, PubAckReason::Success));
+        assert!(matches!(waiting_ack.try_recv(), Ok(Ok(()))));
+    }
+}
@@ -0,0 +1,114 @@
+/*
+ * Software Name : libits-client
+ * SPDX-FileCopyrightText: Copyright (c) Orange SA
+ * SPDX-License-Identifier: MIT
+ *
+ * This software is distributed under the MIT license,
+ * see the "LICENSE.txt" file for more details or https://opensource.org/license/MIT/
+ *
+ * Authors: see CONTRIBUTORS.md
+ */
+
+use std::collections::VecDeque;
+
+use tracing::warn;
+
+/// Bounded queue of the publishes waiting for the connection to be restored
+///
+/// Once full, the oldest publish is dropped to make room for the new one
+pub(crate) struct ResendQueue<P> {
+    capacity: usize,
+    pending: VecDeque<P>,
+}
+
+impl<P> ResendQueue<P> {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            pending: VecDeque::new(),
+        }
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    pub(crate) fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        while self.pending.len() > capacity {
+            self.pending.pop_front();
+        }
+    }
+
+    /// Queues the publish at the back, dropping the oldest one if the queue is full
+    pub(crate) fn push(&mut self, publish: P) {
+        if !self.is_enabled() {
+            return;
+        }
+        if self.pending.len() >= self.capacity {
+            self.pending.pop_front();
+            warn!(
+                "Resend queue full ({} publishes), oldest publish dropped",
+                self.capacity
+            );
+        }
+        self.pending.push_back(publish);
+    }
+
+    pub(crate) fn pop(&mut self) -> Option<P> {
+        self.pending.pop_front()
+    }
+
+    /// Drops every queued publish, returns how many were dropped
+    pub(crate) fn clear(&mut self) -> usize {
+        let dropped = self.pending.len();
+        self.pending.clear();
+        dropped
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.pending.len()
+    }
+}
+
+impl<P> Default for ResendQueue<P> {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::transport::mqtt::resend_queue::ResendQueue;
+
+    #[test]
+    fn publishes_are_resent_in_order() {
+        let mut queue = ResendQueue::new(3);
+        queue.push(1);
+        queue.push(2);
+
+        assert_eq!(queue.pop(), Some(1));
+        assert_eq!(queue.pop(), Some(2));
+        assert_eq!(queue.pop(), None);
+    }
+
+    #[test]
+    fn oldest_publish_is_dropped_when_full() {
+        let mut queue = ResendQueue::new(2);
+        queue.push(1);
+        queue.push(2);
+        queue.push(3);
+
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.pop(), Some(2));
+    }
+
+    #[test]
+    fn disabled_queue_keeps_nothing() {
+        let mut queue = ResendQueue::default();
+        queue.push(1);
+
+        assert!(!queue.is_enabled());
+        assert_eq!(queue.len(), 0);
+    }
+}
@@ -0,0 +1,169 @@
+/*
+ * Software Name : libits-client
+ * SPDX-FileCopyrightText: Copyright (c) Orange SA
+ * SPDX-License-Identifier: MIT
+ *
+ * This software is distributed under the MIT license,
+ * see the "LICENSE.txt" file for more details or https://opensource.org/license/MIT/
+ *
+ * Authors: see CONTRIBUTORS.md
+ */
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// Message types counted on their own, as laid out in the topics
+const KNOWN_MESSAGE_TYPES: [&str; 10] = [
+    "cam", "cpm", "denm", "info", "map", "spat", "mapem", "spatem", "ivim", "vam",
+];
+/// Message type under which any unknown message type is counted
+pub const OTHER_MESSAGE_TYPE: &str = "other";
+
+/// Reception counters of an [MqttRouter][1]
+///
+/// Counters are updated by the router and the dispatching code, and can be read from any thread
+/// through a [snapshot][2] (e.g. to be logged periodically or exposed on a metrics endpoint)
+///
+/// [1]: crate::transport::mqtt::mqtt_router::MqttRouter
+/// [2]: RouterStats::snapshot
+#[derive(Debug, Default)]
+pub struct RouterStats {
+    received: AtomicU64,
+    parsed: AtomicU64,
+    parse_failed: AtomicU64,
+    truncated: AtomicU64,
+    rejected: AtomicU64,
+    duplicates: AtomicU64,
+    per_message_type: Mutex<HashMap<&'static str, u64>>,
+}
+
+/// Point in time copy of the [RouterStats] counters
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RouterStatsSnapshot {
+    /// Number of publish received on a routed topic
+    pub received: u64,
+    /// Number of publish the route callback successfully parsed
+    pub parsed: u64,
+    /// Number of publish the route callback failed to parse
+    pub parse_failed: u64,
+    /// Number of parse failures which look like a truncated payload, also counted in
+    /// `parse_failed`
+    pub truncated: u64,
+    /// Number of publish rejected before parsing (e.g. oversized payload)
+    pub rejected: u64,
+    /// Number of parsed messages dropped as duplicates of already received ones
+    pub duplicates: u64,
+    /// Number of parsed messages by message type, the unknown ones being counted as
+    /// [OTHER_MESSAGE_TYPE]
+    pub per_message_type: HashMap<String, u64>,
+}
+
+impl RouterStats {
+    pub(crate) fn count_received(&self) {
+        self.received.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn count_parsed(&self) {
+        self.parsed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn count_parse_failure(&self) {
+        self.parse_failed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[cfg(feature = "mobility")]
+    pub(crate) fn count_truncation(&self) {
+        self.truncated.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn count_rejection(&self) {
+        self.rejected.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[cfg(feature = "mobility")]
+    pub(crate) fn count_duplicate(&self) {
+        self.duplicates.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Increments the counter of the provided message type (e.g. `cam`, `denm`, `info`)
+    ///
+    /// As the type comes from the received messages, any unknown one is counted as
+    /// [OTHER_MESSAGE_TYPE] so that the counters do not grow with each garbage type
+    pub fn count_message_type(&self, message_type: &str) {
+        let message_type = KNOWN_MESSAGE_TYPES
+            .into_iter()
+            .find(|known| *known == message_type)
+            .unwrap_or(OTHER_MESSAGE_TYPE);
+        *self
+            .per_message_type
+            .lock()
+            .unwrap()
+            .entry(message_type)
+            .or_default() += 1;
+    }
+
+    pub fn snapshot(&self) -> RouterStatsSnapshot {
+        RouterStatsSnapshot {
+            received: self.received.load(Ordering::Relaxed),
+            parsed: self.parsed.load(Ordering::Relaxed),
+            parse_failed: self.parse_failed.load(Ordering::Relaxed),
+            truncated: self.truncated.load(Ordering::Relaxed),
+            rejected: self.rejected.load(Ordering::Relaxed),
+            duplicates: self.duplicates.load(Ordering::Relaxed),
+            per_message_type: self
+                .per_message_type
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(message_type, count)| (message_type.to_string(), *count))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::transport::mqtt::router_stats::RouterStats;
+
+    #[test]
+    fn new_stats_are_zeroed() {
+        let snapshot = RouterStats::default().snapshot();
+
+        assert_eq!(snapshot.received, 0);
+        assert_eq!(snapshot.parsed, 0);
+        assert_eq!(snapshot.parse_failed, 0);
+        assert_eq!(snapshot.truncated, 0);
+        assert_eq!(snapshot.rejected, 0);
+        assert_eq!(snapshot.duplicates, 0);
+        assert!(snapshot.per_message_type.is_empty());
+    }
+
+    #[test]
+    fn message_types_are_counted_separately() {
+        let stats = RouterStats::default();
+
+        stats.count_message_type("cam");
+        stats.count_message_type("cam");
+        stats.count_message_type("denm");
+
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot.per_message_type.get("cam"), Some(&2));
+        assert_eq!(snapshot.per_message_type.get("denm"), Some(&1));
+        assert_eq!(snapshot.per_message_type.get("cpm"), None);
+    }
+
+    #[test]
+    fn unknown_message_types_are_counted_as_other() {
+        let stats = RouterStats::default();
+
+        stats.count_message_type("garbage");
+        stats.count_message_type("other garbage");
+        stats.count_message_type("cam");
+
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot.per_message_type.len(), 2);
+        assert_eq!(snapshot.per_message_type.get("cam"), Some(&1));
+        assert_eq!(snapshot.per_message_type.get("other"), Some(&2));
+    }
+}
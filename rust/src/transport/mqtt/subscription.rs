@@ -0,0 +1,148 @@
+/*
+ * Software Name : libits-client
+ * SPDX-FileCopyrightText: Copyright (c) Orange SA
+ * SPDX-License-Identifier: MIT
+ *
+ * This software is distributed under the MIT license,
+ * see the "LICENSE.txt" file for more details or https://opensource.org/license/MIT/
+ *
+ * Authors: see CONTRIBUTORS.md
+ */
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Geo levels filter subscribing to every tile
+const ALL_TILES: &str = "#";
+
+/// Deepest quadkey published on
+const MAX_DEPTH: usize = 32;
+
+/// Tiles to subscribe to, per message type
+///
+/// Configured as a comma separated list of `<message type>:<tile prefix>[:<depth>]`, e.g.
+/// `cam:1202:18,denm:12`: the message type is only received in the tiles under the prefix and,
+/// if a depth is set, only when published at that exact depth. Message types without filter
+/// are received in every tile
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SubscriptionFilters {
+    filters: HashMap<String, TileFilter>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct TileFilter {
+    prefix: String,
+    depth: Option<usize>,
+}
+
+impl SubscriptionFilters {
+    /// Returns the filter of the geo levels to subscribe to the message type, `#` if there is no
+    /// filter for this message type
+    pub fn geo_filter(&self, message_type: &str) -> String {
+        let Some(filter) = self.filters.get(message_type) else {
+            return ALL_TILES.to_string();
+        };
+
+        let mut levels = filter
+            .prefix
+            .chars()
+            .map(String::from)
+            .collect::<Vec<String>>();
+        match filter.depth {
+            Some(depth) => levels.extend(vec![String::from("+"); depth - filter.prefix.len()]),
+            None => levels.push(ALL_TILES.to_string()),
+        }
+        levels.join("/")
+    }
+}
+
+impl FromStr for SubscriptionFilters {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut filters = HashMap::new();
+        for filter in s.split(',').map(str::trim).filter(|f| !f.is_empty()) {
+            let mut parts = filter.split(':').map(str::trim);
+            let (Some(message_type), Some(prefix)) = (parts.next(), parts.next()) else {
+                return Err(format!(
+                    "Invalid filter '{}', expected <message type>:<tile prefix>[:<depth>]",
+                    filter
+                ));
+            };
+            if prefix.chars().any(|tile| !('0'..='3').contains(&tile)) {
+                return Err(format!("Invalid tile prefix '{}'", prefix));
+            }
+            let depth = parts
+                .next()
+                .map(|depth| match depth.parse::<usize>() {
+                    Ok(depth) if (prefix.len()..=MAX_DEPTH).contains(&depth) => Ok(depth),
+                    _ => Err(format!(
+                        "Invalid depth '{}', expected {} to {}",
+                        depth,
+                        prefix.len(),
+                        MAX_DEPTH
+                    )),
+                })
+                .transpose()?;
+            if parts.next().is_some() {
+                return Err(format!("Invalid filter '{}', too many parts", filter));
+            }
+
+            filters.insert(
+                message_type.to_string(),
+                TileFilter {
+                    prefix: prefix.to_string(),
+                    depth,
+                },
+            );
+        }
+        Ok(Self { filters })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::transport::mqtt::subscription::SubscriptionFilters;
+    use std::str::FromStr;
+
+    macro_rules! test_geo_filter {
+        ($test_name:ident, $filters:expr, $message_type:expr, $expected:expr) => {
+            #[test]
+            fn $test_name() {
+                let filters = SubscriptionFilters::from_str($filters).unwrap();
+
+                assert_eq!(filters.geo_filter($message_type), $expected);
+            }
+        };
+    }
+    test_geo_filter!(no_filter_subscribes_everywhere, "", "cam", "#");
+    test_geo_filter!(
+        other_type_filter_subscribes_everywhere,
+        "denm:12",
+        "cam",
+        "#"
+    );
+    test_geo_filter!(tile_prefix, "cam:1202", "cam", "1/2/0/2/#");
+    test_geo_filter!(
+        tile_prefix_and_depth,
+        "cam:1202:6,denm:3",
+        "cam",
+        "1/2/0/2/+/+"
+    );
+    test_geo_filter!(filter_among_others, "cam:1202:6, denm:3", "denm", "3/#");
+    test_geo_filter!(depth_only, "cpm::2", "cpm", "+/+");
+
+    macro_rules! test_invalid_filters {
+        ($test_name:ident, $filters:expr) => {
+            #[test]
+            fn $test_name() {
+                assert!(SubscriptionFilters::from_str($filters).is_err());
+            }
+        };
+    }
+    test_invalid_filters!(missing_prefix, "cam");
+    test_invalid_filters!(invalid_tile, "cam:1204");
+    test_invalid_filters!(depth_above_prefix, "cam:1202:3");
+    test_invalid_filters!(depth_too_deep, "cam:1202:33");
+    test_invalid_filters!(too_many_parts, "cam:1202:6:1");
+}
@@ -9,9 +9,25 @@
  * Authors: see CONTRIBUTORS.md
  */
 
+use std::any::type_name;
 use std::fmt::{Debug, Display};
 use std::hash::Hash;
-use std::str::FromStr;
+use std::str::{from_utf8, FromStr, Utf8Error};
+
+use rumqttc::v5::mqttbytes::v5::Publish;
+use thiserror::Error;
+
+#[cfg(feature = "mobility")]
+use crate::mobility::quadtree::quadkey::Quadkey;
+
+/// An error which can be returned when extracting a [Topic] from a [Publish]
+#[derive(Error, Debug)]
+pub enum ParseError {
+    #[error("Topic is not valid UTF-8: {0}")]
+    InvalidUtf8(#[from] Utf8Error),
+    #[error("Failed to create {1} from topic '{0}'")]
+    InvalidTopic(String, &'static str),
+}
 
 pub trait Topic:
     Default + Debug + Display + Clone + FromStr + ToString + Hash + PartialEq + Eq + Send + Sync
@@ -25,4 +41,53 @@ pub trait Topic:
     /// If you want to route the message using the message type this method should return `/root/cam`
     /// If you want to route the messages using the client this method should return `/root/cam/client_1`
     fn as_route(&self) -> String;
+
+    /// Returns the quadkey the topic is located on, None for topics without location (default)
+    #[cfg(feature = "mobility")]
+    fn geo_extension(&self) -> Option<&Quadkey> {
+        None
+    }
+
+    /// Returns a copy of the topic located on another quadkey, None for topics without location
+    /// (default)
+    #[cfg(feature = "mobility")]
+    fn with_geo_extension(&self, _geo_extension: Quadkey) -> Option<Self> {
+        None
+    }
+}
+
+/// Extracts the topic of a [Publish] and parses it as `T`
+pub fn topic_from_publish<T: Topic>(publish: &Publish) -> Result<T, ParseError> {
+    let str_topic = from_utf8(&publish.topic)?;
+    T::from_str(str_topic)
+        .map_err(|_| ParseError::InvalidTopic(str_topic.to_string(), type_name::<T>()))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::fmt::{Display, Formatter};
+    use std::str::FromStr;
+
+    use crate::transport::mqtt::topic::Topic;
+
+    /// Topic routed on its whole value
+    #[derive(Clone, Debug, Default, Hash, PartialEq, Eq)]
+    pub(crate) struct RoutedTopic(pub(crate) String);
+    impl Display for RoutedTopic {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{}", self.0)
+        }
+    }
+    impl FromStr for RoutedTopic {
+        type Err = ();
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            Ok(RoutedTopic(s.to_string()))
+        }
+    }
+    impl Topic for RoutedTopic {
+        fn as_route(&self) -> String {
+            self.0.clone()
+        }
+    }
 }
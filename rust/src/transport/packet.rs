@@ -11,10 +11,14 @@
 
 use opentelemetry::propagation::{Extractor, Injector};
 use rumqttc::v5::mqttbytes::v5::PublishProperties;
+use rumqttc::v5::mqttbytes::QoS;
+use std::collections::HashMap;
 use std::fmt::Debug;
 
+use crate::transport::compression::gzip;
 use crate::transport::mqtt::topic::Topic;
 use crate::transport::payload::Payload;
+use crate::transport::serialization::{Serialization, SerializationError};
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Packet<T, P>
@@ -25,6 +29,10 @@ where
     pub topic: T,
     pub payload: P,
     pub properties: PublishProperties,
+    /// Whether the broker must keep the packet as the last known value of its topic
+    pub retain: bool,
+    /// Quality of service the packet is published with, exactly once by default
+    pub qos: QoS,
 }
 
 impl<T: Topic, P: Payload> Packet<T, P> {
@@ -33,8 +41,47 @@ impl<T: Topic, P: Payload> Packet<T, P> {
             topic,
             payload,
             properties: PublishProperties::default(),
+            retain: false,
+            qos: QoS::ExactlyOnce,
         }
     }
+
+    /// Creates a packet the broker will retain and deliver to every new subscriber of the topic
+    pub fn retained(topic: T, payload: P) -> Self {
+        Self {
+            retain: true,
+            ..Self::new(topic, payload)
+        }
+    }
+
+    /// Returns the MQTTv5 user properties the packet was received with, e.g. the radio link
+    /// metadata some gateways add
+    ///
+    /// The last value wins when a key is repeated
+    pub fn user_properties(&self) -> HashMap<String, String> {
+        self.properties.user_properties.iter().cloned().collect()
+    }
+
+    /// Returns the topic string and the payload this packet is published with, encoded with the
+    /// [serialization][1] and gzipped if `compress` is set
+    ///
+    /// The [client][2] publishes packets this way, announcing the encoding and the compression
+    /// in the publish properties
+    ///
+    /// [1]: Serialization
+    /// [2]: crate::transport::mqtt::mqtt_client::MqttClient
+    pub fn to_publish(
+        &self,
+        serialization: Serialization,
+        compress: bool,
+    ) -> Result<(String, Vec<u8>), SerializationError> {
+        let payload = serialization.serialize(&self.payload)?;
+        let payload = match compress {
+            true => gzip(&payload)?,
+            false => payload,
+        };
+        Ok((self.topic.to_string(), payload))
+    }
 }
 
 impl<T: Topic, P: Payload> Injector for Packet<T, P> {
@@ -62,3 +109,78 @@ impl<T: Topic, P: Payload> Extractor for Packet<T, P> {
             .collect::<Vec<&str>>()
     }
 }
+
+#[cfg(all(test, feature = "geo_routing"))]
+mod tests {
+    use crate::client::application::create_cam;
+    use crate::client::configuration::geo_configuration::GeoConfiguration;
+    use crate::exchange::message::Message;
+    use crate::exchange::Exchange;
+    use crate::mobility::position::position_from_degrees;
+    use crate::mobility::quadtree::quadkey::Quadkey;
+    use crate::transport::compression::gunzip;
+    use crate::transport::mqtt::geo_topic::GeoTopic;
+    use crate::transport::packet::Packet;
+    use crate::transport::serialization::Serialization;
+    use std::str::FromStr;
+
+    #[test]
+    fn copied_cam_to_publish() {
+        let geo_configuration = GeoConfiguration {
+            prefix: String::from("default"),
+            suffix: String::from("v2x"),
+            topic_template: None,
+        };
+        let position = position_from_degrees(48.6250, 2.2412, 150.);
+        let quadkey = Quadkey::from(&position).as_reduced(4);
+        let exchange = Exchange::new(
+            String::from("com_myapplication_1"),
+            1_700_000_000_000,
+            Vec::new(),
+            Message::CAM(create_cam(42, 5, position, 13.9, 1.57)),
+        );
+        let packet = Packet::new(
+            GeoTopic::cam(&geo_configuration, "com_myapplication_1", &quadkey),
+            *exchange.clone(),
+        );
+
+        let (topic, payload) = packet.to_publish(Serialization::Json, false).unwrap();
+
+        assert_eq!(topic, "default/inQueue/v2x/cam/com_myapplication_1/1/2/0/2");
+        let published = serde_json::from_slice::<serde_json::Value>(&payload).unwrap();
+        assert_eq!(published["type"], "cam");
+        assert_eq!(published["source_uuid"], "com_myapplication_1");
+        assert_eq!(published["message"]["station_id"], 42);
+        assert_eq!(
+            published,
+            serde_json::to_value(&*exchange).expect("Failed to serialize exchange")
+        );
+    }
+
+    #[test]
+    fn packet_is_published_with_the_configured_encoding() {
+        let exchange = Exchange::new(
+            String::from("com_myapplication_1"),
+            1_700_000_000_000,
+            Vec::new(),
+            Message::CAM(create_cam(
+                42,
+                5,
+                position_from_degrees(48.6250, 2.2412, 150.),
+                13.9,
+                1.57,
+            )),
+        );
+        let packet = Packet::new(
+            GeoTopic::from_str("default/inQueue/v2x/cam/com_myapplication_1/1").unwrap(),
+            *exchange,
+        );
+
+        let (_, payload) = packet.to_publish(Serialization::Cbor, true).unwrap();
+
+        let decoded = Serialization::Cbor
+            .deserialize::<Exchange>(&gunzip(&payload, None).unwrap())
+            .unwrap();
+        assert_eq!(decoded, packet.payload);
+    }
+}
@@ -9,7 +9,228 @@
  * Authors: see CONTRIBUTORS.md
  */
 
+use crate::transport::serialization::SerializationError;
+use serde::de::DeserializeOwned;
 use serde::Serialize;
+use serde_json::Value;
 use std::fmt::Debug;
+use std::string::FromUtf8Error;
+use thiserror::Error;
 
 pub trait Payload: Clone + Debug + PartialEq + Serialize {}
+
+#[derive(Error, Debug)]
+pub enum StrictParseError {
+    #[error("Failed to parse payload: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Unknown fields in payload: {}", .0.join(", "))]
+    UnknownFields(Vec<String>),
+}
+
+/// Failure to turn a received payload into a message
+#[derive(Error, Debug)]
+pub enum DeserializeError {
+    #[error("Failed to decompress payload: {0}")]
+    Decompression(#[from] std::io::Error),
+    #[error("Payload is not valid UTF-8: {0}")]
+    Encoding(#[from] FromUtf8Error),
+    #[error("Failed to parse payload: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error(transparent)]
+    Strict(#[from] StrictParseError),
+    #[error("Failed to decode payload: {0}")]
+    Decoding(#[from] SerializationError),
+}
+
+impl DeserializeError {
+    /// Returns true if the payload looks cut short rather than corrupted, i.e. it ended in the
+    /// middle of the JSON document (e.g. unbalanced braces), of the gzip stream or of a UTF-8
+    /// character
+    pub fn is_truncation(&self) -> bool {
+        match self {
+            DeserializeError::Decompression(e) => e.kind() == std::io::ErrorKind::UnexpectedEof,
+            DeserializeError::Encoding(e) => e.utf8_error().error_len().is_none(),
+            DeserializeError::Json(e) | DeserializeError::Strict(StrictParseError::Json(e)) => {
+                e.is_eof()
+            }
+            DeserializeError::Strict(StrictParseError::UnknownFields(_)) => false,
+            DeserializeError::Decoding(e) => e.is_eof(),
+        }
+    }
+}
+
+/// Parses a JSON payload, failing if it contains fields `T` does not know
+///
+/// Unknown fields are found by comparing the payload with `T` serialized back, their path is
+/// reported using `.` between fields and `[index]` for array items (e.g. `message.extra`).
+/// Null and empty array values are not reported, they are not serialized back; nor are camelCase
+/// keys of snake_case fields, accepted as aliases
+pub fn parse_strict<T: DeserializeOwned + Serialize>(
+    payload: &[u8],
+) -> Result<T, StrictParseError> {
+    let original = serde_json::from_slice::<Value>(payload)?;
+    let parsed = serde_json::from_value::<T>(original.clone())?;
+
+    let mut unknown_fields = Vec::new();
+    collect_unknown_fields(
+        &original,
+        &serde_json::to_value(&parsed)?,
+        "",
+        &mut unknown_fields,
+    );
+
+    if unknown_fields.is_empty() {
+        Ok(parsed)
+    } else {
+        Err(StrictParseError::UnknownFields(unknown_fields))
+    }
+}
+
+fn collect_unknown_fields(original: &Value, parsed: &Value, path: &str, unknown: &mut Vec<String>) {
+    match (original, parsed) {
+        (Value::Object(original), Value::Object(parsed)) => {
+            for (key, value) in original {
+                let field_path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                match parsed.get(key).or_else(|| parsed.get(&snake_case(key))) {
+                    Some(parsed_value) => {
+                        collect_unknown_fields(value, parsed_value, &field_path, unknown)
+                    }
+                    None if is_empty(value) => (),
+                    None => unknown.push(field_path),
+                }
+            }
+        }
+        (Value::Array(original), Value::Array(parsed)) => {
+            for (index, (value, parsed_value)) in original.iter().zip(parsed).enumerate() {
+                collect_unknown_fields(
+                    value,
+                    parsed_value,
+                    &format!("{}[{}]", path, index),
+                    unknown,
+                );
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Returns the camelCase key in snake_case, as the fields it may be an alias of
+fn snake_case(key: &str) -> String {
+    let mut snake_case = String::with_capacity(key.len() + 4);
+    for c in key.chars() {
+        if c.is_ascii_uppercase() {
+            snake_case.push('_');
+            snake_case.push(c.to_ascii_lowercase());
+        } else {
+            snake_case.push(c);
+        }
+    }
+    snake_case
+}
+
+fn is_empty(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::Array(items) => items.is_empty(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::transport::payload::{parse_strict, DeserializeError, StrictParseError};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Inner {
+        value: u8,
+    }
+
+    #[serde_with::skip_serializing_none]
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Outer {
+        name: String,
+        optional: Option<u8>,
+        #[serde(skip_serializing_if = "Vec::is_empty", default)]
+        items: Vec<Inner>,
+    }
+
+    #[test]
+    fn known_fields_are_parsed() {
+        let outer = parse_strict::<Outer>(br#"{"name":"a","optional":null,"items":[{"value":1}]}"#)
+            .unwrap();
+
+        assert_eq!(outer.name, "a");
+        assert_eq!(outer.items, vec![Inner { value: 1 }]);
+    }
+
+    #[test]
+    fn skipped_empty_fields_are_not_unknown() {
+        assert!(parse_strict::<Outer>(br#"{"name":"a","items":[]}"#).is_ok());
+    }
+
+    #[test]
+    fn unknown_fields_are_reported() {
+        let result = parse_strict::<Outer>(
+            br#"{"name":"a","extra":true,"items":[{"value":1},{"value":2,"unit":"m"}]}"#,
+        );
+
+        match result {
+            Err(StrictParseError::UnknownFields(fields)) => {
+                assert_eq!(fields, vec!["extra", "items[1].unit"])
+            }
+            other => panic!("Expected unknown fields, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn camel_case_alias_is_not_unknown() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Aliased {
+            #[serde(alias = "stationId")]
+            station_id: u32,
+        }
+
+        assert_eq!(
+            parse_strict::<Aliased>(br#"{"stationId":42}"#).unwrap(),
+            Aliased { station_id: 42 }
+        );
+    }
+
+    #[test]
+    fn invalid_json_is_err() {
+        assert!(matches!(
+            parse_strict::<Outer>(b"{\"name\":"),
+            Err(StrictParseError::Json(_))
+        ));
+    }
+
+    #[test]
+    fn unbalanced_braces_are_truncation() {
+        let error = DeserializeError::from(
+            serde_json::from_slice::<Outer>(br#"{"name":"a","items":[{"value":1"#).unwrap_err(),
+        );
+
+        assert!(error.is_truncation());
+    }
+
+    #[test]
+    fn syntax_error_is_not_truncation() {
+        let error = DeserializeError::from(
+            serde_json::from_slice::<Outer>(br#"{"name":"a"}}"#).unwrap_err(),
+        );
+
+        assert!(!error.is_truncation());
+    }
+
+    #[test]
+    fn cut_utf8_character_is_truncation() {
+        let error = DeserializeError::from(String::from_utf8(vec![b'a', 0xC3]).unwrap_err());
+
+        assert!(error.is_truncation());
+    }
+}
@@ -0,0 +1,190 @@
+/*
+ * Software Name : libits-client
+ * SPDX-FileCopyrightText: Copyright (c) Orange SA
+ * SPDX-License-Identifier: MIT
+ *
+ * This software is distributed under the MIT license,
+ * see the "LICENSE.txt" file for more details or https://opensource.org/license/MIT/
+ *
+ * Authors: see CONTRIBUTORS.md
+ */
+
+use crossbeam_channel::{bounded, unbounded, Receiver, SendError, Sender, TrySendError};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tracing::{trace, warn};
+
+/// Behaviour of a bounded [pipe] when an item is sent while it is full
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum OverflowPolicy {
+    /// The sender waits until the receiving side makes room
+    #[default]
+    Block,
+    /// The oldest item of the pipe is discarded to make room for the new one
+    DropOldest,
+}
+
+impl FromStr for OverflowPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "block" => Ok(OverflowPolicy::Block),
+            "drop_oldest" => Ok(OverflowPolicy::DropOldest),
+            other => Err(format!("Unknown overflow policy '{}'", other)),
+        }
+    }
+}
+
+/// Sending half of a [pipe]
+///
+/// Clones share the same dropped items counter
+pub struct PipeSender<T> {
+    sender: Sender<T>,
+    /// Only set with the [DropOldest][1] policy, used to pop the oldest item out of a full pipe
+    ///
+    /// [1]: OverflowPolicy::DropOldest
+    eviction_receiver: Option<Receiver<T>>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl<T> Clone for PipeSender<T> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+            eviction_receiver: self.eviction_receiver.clone(),
+            dropped: self.dropped.clone(),
+        }
+    }
+}
+
+impl<T> PipeSender<T> {
+    /// Sends an item, applying the overflow policy if the pipe is full
+    ///
+    /// **Note: with the [DropOldest][1] policy the sender holds a receiver on its own pipe, so
+    /// sending only fails once every sender has been dropped**
+    ///
+    /// [1]: OverflowPolicy::DropOldest
+    pub fn send(&self, item: T) -> Result<(), SendError<T>> {
+        match &self.eviction_receiver {
+            None => self.sender.send(item),
+            Some(eviction_receiver) => {
+                let mut item = item;
+                loop {
+                    match self.sender.try_send(item) {
+                        Ok(()) => return Ok(()),
+                        Err(TrySendError::Full(rejected)) => {
+                            if eviction_receiver.try_recv().is_ok() {
+                                let dropped = self.dropped.fetch_add(1, Ordering::Relaxed) + 1;
+                                trace!("pipe full, oldest item dropped");
+                                if dropped % 1000 == 1 {
+                                    warn!("pipe full: {} item(s) dropped so far", dropped);
+                                }
+                            }
+                            item = rejected;
+                        }
+                        Err(TrySendError::Disconnected(rejected)) => {
+                            return Err(SendError(rejected))
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the number of items dropped by the overflow policy since the pipe creation
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+/// Creates a channel to link two stages of a pipeline
+///
+/// The channel is unbounded if no capacity is provided; otherwise the overflow policy applies
+/// when the channel is full
+pub fn pipe<T>(capacity: Option<usize>, policy: OverflowPolicy) -> (PipeSender<T>, Receiver<T>) {
+    let (sender, receiver) = match capacity {
+        Some(capacity) => bounded(capacity),
+        None => unbounded(),
+    };
+    let eviction_receiver = match (capacity, policy) {
+        (Some(_), OverflowPolicy::DropOldest) => Some(receiver.clone()),
+        _ => None,
+    };
+
+    (
+        PipeSender {
+            sender,
+            eviction_receiver,
+            dropped: Arc::new(AtomicU64::new(0)),
+        },
+        receiver,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::transport::pipe::{pipe, OverflowPolicy};
+    use std::str::FromStr;
+
+    #[test]
+    fn drop_oldest_keeps_the_pipe_bounded() {
+        let (sender, receiver) = pipe(Some(1), OverflowPolicy::DropOldest);
+
+        for i in 0..100 {
+            sender
+                .send(i)
+                .expect("Sending to a drop oldest pipe must not fail");
+        }
+
+        assert_eq!(receiver.len(), 1);
+        assert_eq!(sender.dropped(), 99);
+        assert_eq!(receiver.recv().unwrap(), 99);
+    }
+
+    #[test]
+    fn clones_share_the_dropped_counter() {
+        let (sender, _receiver) = pipe(Some(1), OverflowPolicy::DropOldest);
+        let clone = sender.clone();
+
+        sender.send(1).unwrap();
+        clone.send(2).unwrap();
+
+        assert_eq!(sender.dropped(), 1);
+        assert_eq!(clone.dropped(), 1);
+    }
+
+    #[test]
+    fn unbounded_pipe_never_drops() {
+        let (sender, receiver) = pipe(None, OverflowPolicy::DropOldest);
+
+        for i in 0..100 {
+            sender.send(i).unwrap();
+        }
+
+        assert_eq!(receiver.len(), 100);
+        assert_eq!(sender.dropped(), 0);
+    }
+
+    #[test]
+    fn block_pipe_fails_when_disconnected() {
+        let (sender, receiver) = pipe(Some(1), OverflowPolicy::Block);
+        drop(receiver);
+
+        assert!(sender.send(1).is_err());
+    }
+
+    #[test]
+    fn overflow_policy_from_str() {
+        assert_eq!(
+            OverflowPolicy::from_str("block").unwrap(),
+            OverflowPolicy::Block
+        );
+        assert_eq!(
+            OverflowPolicy::from_str("drop_oldest").unwrap(),
+            OverflowPolicy::DropOldest
+        );
+        assert!(OverflowPolicy::from_str("drop_newest").is_err());
+    }
+}
@@ -0,0 +1,195 @@
+/*
+ * Software Name : libits-client
+ * SPDX-FileCopyrightText: Copyright (c) Orange SA
+ * SPDX-License-Identifier: MIT
+ *
+ * This software is distributed under the MIT license,
+ * see the "LICENSE.txt" file for more details or https://opensource.org/license/MIT/
+ *
+ * Authors: see CONTRIBUTORS.md
+ */
+
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use thiserror::Error;
+
+/// MQTTv5 content type of the JSON payloads
+pub const JSON_CONTENT_TYPE: &str = "application/json";
+/// MQTTv5 content type of the CBOR payloads
+pub const CBOR_CONTENT_TYPE: &str = "application/cbor";
+/// MQTTv5 content type of the MessagePack payloads
+pub const MESSAGE_PACK_CONTENT_TYPE: &str = "application/msgpack";
+
+#[derive(Error, Debug)]
+pub enum SerializationError {
+    #[error("JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("CBOR: {0}")]
+    CborEncoding(#[from] ciborium::ser::Error<io::Error>),
+    #[error("CBOR: {0}")]
+    CborDecoding(#[from] ciborium::de::Error<io::Error>),
+    #[error("MessagePack: {0}")]
+    MessagePackEncoding(#[from] rmp_serde::encode::Error),
+    #[error("MessagePack: {0}")]
+    MessagePackDecoding(#[from] rmp_serde::decode::Error),
+    #[error("Unknown serialization '{0}', expected json, cbor or msgpack")]
+    Unknown(String),
+    #[error("gzip: {0}")]
+    Compression(#[from] io::Error),
+}
+
+impl SerializationError {
+    /// Returns true if decoding failed because the payload ended too early
+    pub fn is_eof(&self) -> bool {
+        match self {
+            SerializationError::Json(e) => e.is_eof(),
+            SerializationError::CborDecoding(ciborium::de::Error::Io(e))
+            | SerializationError::MessagePackDecoding(
+                rmp_serde::decode::Error::InvalidMarkerRead(e)
+                | rmp_serde::decode::Error::InvalidDataRead(e),
+            ) => e.kind() == io::ErrorKind::UnexpectedEof,
+            _ => false,
+        }
+    }
+}
+
+/// Encoding of the exchanged payloads
+///
+/// JSON is the default and what every station understands; the binary encodings are more
+/// compact but must only be used between stations supporting them. The encoding is announced
+/// by the MQTTv5 content type property, payloads without content type being JSON
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Serialization {
+    #[default]
+    Json,
+    Cbor,
+    MessagePack,
+}
+
+impl Serialization {
+    /// Returns the MQTTv5 content type announcing this encoding
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Serialization::Json => JSON_CONTENT_TYPE,
+            Serialization::Cbor => CBOR_CONTENT_TYPE,
+            Serialization::MessagePack => MESSAGE_PACK_CONTENT_TYPE,
+        }
+    }
+
+    /// Returns the encoding announced by the content type, JSON if there is none or if it is
+    /// unknown
+    pub fn from_content_type(content_type: Option<&str>) -> Self {
+        match content_type {
+            Some(CBOR_CONTENT_TYPE) => Serialization::Cbor,
+            Some(MESSAGE_PACK_CONTENT_TYPE) => Serialization::MessagePack,
+            _ => Serialization::Json,
+        }
+    }
+
+    pub fn serialize<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, SerializationError> {
+        match self {
+            Serialization::Json => Ok(serde_json::to_vec(value)?),
+            Serialization::Cbor => {
+                let mut bytes = Vec::new();
+                ciborium::into_writer(value, &mut bytes)?;
+                Ok(bytes)
+            }
+            // fields are named so that optional fields can be skipped
+            Serialization::MessagePack => Ok(rmp_serde::to_vec_named(value)?),
+        }
+    }
+
+    pub fn deserialize<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, SerializationError> {
+        match self {
+            Serialization::Json => Ok(serde_json::from_slice(bytes)?),
+            Serialization::Cbor => Ok(ciborium::from_reader(bytes)?),
+            Serialization::MessagePack => Ok(rmp_serde::from_slice(bytes)?),
+        }
+    }
+}
+
+impl FromStr for Serialization {
+    type Err = SerializationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(Serialization::Json),
+            "cbor" => Ok(Serialization::Cbor),
+            "msgpack" | "messagepack" => Ok(Serialization::MessagePack),
+            _ => Err(SerializationError::Unknown(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for Serialization {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Serialization::Json => write!(f, "json"),
+            Serialization::Cbor => write!(f, "cbor"),
+            Serialization::MessagePack => write!(f, "msgpack"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::transport::serialization::Serialization;
+    use std::str::FromStr;
+
+    macro_rules! test_content_type {
+        ($test_name:ident, $content_type:expr, $expected:expr) => {
+            #[test]
+            fn $test_name() {
+                assert_eq!(Serialization::from_content_type($content_type), $expected);
+            }
+        };
+    }
+    test_content_type!(no_content_type_is_json, None, Serialization::Json);
+    test_content_type!(
+        unknown_content_type_is_json,
+        Some("text/plain"),
+        Serialization::Json
+    );
+    test_content_type!(
+        cbor_content_type,
+        Some("application/cbor"),
+        Serialization::Cbor
+    );
+    test_content_type!(
+        message_pack_content_type,
+        Some("application/msgpack"),
+        Serialization::MessagePack
+    );
+
+    #[test]
+    fn serialization_is_parsed_from_its_name() {
+        for serialization in [
+            Serialization::Json,
+            Serialization::Cbor,
+            Serialization::MessagePack,
+        ] {
+            assert_eq!(
+                Serialization::from_str(&serialization.to_string()).unwrap(),
+                serialization
+            );
+        }
+        assert!(Serialization::from_str("xml").is_err());
+    }
+
+    #[test]
+    fn truncated_cbor_is_eof() {
+        let bytes = Serialization::Cbor
+            .serialize(&vec![String::from("cam"); 4])
+            .unwrap();
+
+        let error = Serialization::Cbor
+            .deserialize::<Vec<String>>(&bytes[..bytes.len() - 2])
+            .unwrap_err();
+
+        assert!(error.is_eof(), "{:?}", error);
+    }
+}
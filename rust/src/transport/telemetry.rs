@@ -9,9 +9,9 @@
  * Authors: see CONTRIBUTORS.md
  */
 
-use log::debug;
 use std::str::from_utf8;
 use std::time::Duration;
+use tracing::debug;
 
 use opentelemetry::global::BoxedSpan;
 use opentelemetry::propagation::{Extractor, TextMapPropagator};